@@ -0,0 +1,280 @@
+//! Matching of the contacts of a target against the caller preferences of a request.
+//!
+//! A proxy or a registrar forking a request to the registered contacts of a user uses the
+//! `CallerPreferences` of the request to discard the contacts the caller does not want to reach,
+//! and to order the remaining ones.
+//!
+//! [[RFC3841, Section 7](https://datatracker.ietf.org/doc/html/rfc3841#section-7)]
+
+use std::cmp::Ordering;
+
+use crate::{
+    CallerPreference, CallerPreferenceParameter, Contact, FeatureTag, FeatureValue, Header, Request,
+};
+
+/// Representation of the caller preferences of a request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CallerPreferences {
+    accept: Vec<CallerPreference>,
+    reject: Vec<CallerPreference>,
+    implicit: CallerPreference,
+}
+
+impl CallerPreferences {
+    /// Get the caller preferences of the given request, from its Accept-Contact and
+    /// Reject-Contact headers.
+    ///
+    /// The implicit preference for the contacts supporting the method of the request is added.
+    ///
+    /// [[RFC3841, Section 7.2](https://datatracker.ietf.org/doc/html/rfc3841#section-7.2)]
+    pub fn new(request: &Request) -> Self {
+        let mut accept = vec![];
+        let mut reject = vec![];
+        for header in request.headers() {
+            match header {
+                Header::AcceptContact(header) => accept.extend(header.preferences().clone()),
+                Header::RejectContact(header) => reject.extend(header.preferences().clone()),
+                _ => (),
+            }
+        }
+        let implicit = CallerPreference::new(vec![
+            CallerPreferenceParameter::Feature(FeatureTag::new(
+                "methods",
+                vec![FeatureValue::Token {
+                    value: request.method().to_string(),
+                    negated: false,
+                }],
+            )),
+            CallerPreferenceParameter::Require,
+        ]);
+        Self {
+            accept,
+            reject,
+            implicit,
+        }
+    }
+
+    /// Get a reference to the explicit preferences of the Accept-Contact headers.
+    pub fn accept(&self) -> &Vec<CallerPreference> {
+        &self.accept
+    }
+
+    /// Get a reference to the preferences of the Reject-Contact headers.
+    pub fn reject(&self) -> &Vec<CallerPreference> {
+        &self.reject
+    }
+
+    /// Compute the score of the given contact against the caller preferences, between 0 and 1.
+    ///
+    /// It returns None if the contact is to be discarded, because it matches a Reject-Contact
+    /// predicate or does not match a required Accept-Contact predicate. A contact without any
+    /// feature parameter is immune to the caller preferences and gets a score of 1.
+    ///
+    /// [[RFC3841, Section 7.4](https://datatracker.ietf.org/doc/html/rfc3841#section-7.4)]
+    pub fn score(&self, contact: &Contact) -> Option<f32> {
+        let features = contact.feature_tags();
+        if features.is_empty() {
+            return Some(1.0);
+        }
+
+        if self.reject.iter().any(|preference| {
+            preference
+                .feature_tags()
+                .all(|tag| features.iter().any(|feature| tag.is_satisfied_by(feature)))
+        }) {
+            return None;
+        }
+
+        if !Self::is_acceptable(&self.implicit, &features) {
+            return None;
+        }
+        let mut scores = vec![];
+        for preference in &self.accept {
+            if !Self::is_acceptable(preference, &features) {
+                return None;
+            }
+            scores.push(Self::predicate_score(preference, &features));
+        }
+        if scores.is_empty() {
+            Some(1.0)
+        } else {
+            Some(scores.iter().sum::<f32>() / scores.len() as f32)
+        }
+    }
+
+    /// Get the contacts that are not discarded, ordered by decreasing q-value and then by
+    /// decreasing score.
+    ///
+    /// The contacts with the same q-value and score keep their relative order.
+    ///
+    /// [[RFC3841, Section 7.4](https://datatracker.ietf.org/doc/html/rfc3841#section-7.4)]
+    pub fn rank<'a, I>(&self, contacts: I) -> Vec<Contact>
+    where
+        I: IntoIterator<Item = &'a Contact>,
+    {
+        let mut scored = contacts
+            .into_iter()
+            .filter_map(|contact| {
+                self.score(contact)
+                    .map(|score| (contact.q().unwrap_or(1.0), score, contact.clone()))
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|(a_q, a_score, _), (b_q, b_score, _)| {
+            b_q.partial_cmp(a_q)
+                .unwrap_or(Ordering::Equal)
+                .then(b_score.partial_cmp(a_score).unwrap_or(Ordering::Equal))
+        });
+        scored.into_iter().map(|(_, _, contact)| contact).collect()
+    }
+
+    /// Tell whether the contact is acceptable with regard to the given preference.
+    ///
+    /// A required preference discards the contacts declaring a feature not matching it and, if
+    /// it is also explicit, the contacts not declaring all its features.
+    fn is_acceptable(preference: &CallerPreference, features: &[FeatureTag]) -> bool {
+        if !preference.is_required() {
+            return true;
+        }
+        preference.feature_tags().all(|tag| {
+            match features.iter().find(|feature| feature.name() == tag.name()) {
+                Some(feature) => tag.is_satisfied_by(feature),
+                None => !preference.is_explicit(),
+            }
+        })
+    }
+
+    /// Compute the ratio of the features of the predicate of the preference that are matched by
+    /// the declared features of a contact.
+    fn predicate_score(preference: &CallerPreference, features: &[FeatureTag]) -> f32 {
+        let (matched, total) = preference
+            .feature_tags()
+            .fold((0, 0), |(matched, total), tag| {
+                if features.iter().any(|feature| tag.is_satisfied_by(feature)) {
+                    (matched + 1, total + 1)
+                } else {
+                    (matched, total + 1)
+                }
+            });
+        if total == 0 {
+            1.0
+        } else {
+            matched as f32 / total as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_some_eq};
+
+    fn request(headers: &str) -> Request {
+        Request::try_from(
+            format!(
+                "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+                 To: Bob <sip:bob@biloxi.com>\r\n\
+                 Call-ID: a84b4c76e66710\r\n\
+                 CSeq: 1 INVITE\r\n\
+                 {headers}\r\n"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    fn parse_contacts(value: &str) -> Vec<Contact> {
+        match Header::try_from(format!("Contact: {value}").as_str()).unwrap() {
+            Header::Contact(header) => header.contacts().to_vec(),
+            _ => panic!("Not a Contact header"),
+        }
+    }
+
+    #[test]
+    fn test_contact_feature_tags() {
+        let contacts = parse_contacts(
+            r#"<sip:bob@pc.biloxi.com>;audio;video;methods="INVITE,BYE";+sip.instance="<urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>";expires=3600"#,
+        );
+        let features = contacts[0].feature_tags();
+        assert_eq!(
+            features.iter().map(|tag| tag.name()).collect::<Vec<_>>(),
+            vec!["audio", "video", "methods", "+sip.instance"]
+        );
+    }
+
+    #[test]
+    fn test_score_without_preferences() {
+        let preferences = CallerPreferences::new(&request(""));
+        let contacts = parse_contacts("<sip:bob@pc.biloxi.com>;audio, <sip:bob@phone.biloxi.com>");
+        assert_some_eq!(preferences.score(&contacts[0]), 1.0);
+        assert_some_eq!(preferences.score(&contacts[1]), 1.0);
+    }
+
+    #[test]
+    fn test_implicit_method_preference() {
+        let preferences = CallerPreferences::new(&request(""));
+        let contacts = parse_contacts(r#"<sip:bob@pc.biloxi.com>;methods="MESSAGE""#);
+        assert_none!(preferences.score(&contacts[0]));
+    }
+
+    #[test]
+    fn test_reject_contact() {
+        let preferences =
+            CallerPreferences::new(&request("Reject-Contact: *;actor=\"msg-taker\"\r\n"));
+        let contacts = parse_contacts(
+            r#"<sip:vm@biloxi.com>;actor="msg-taker";audio, <sip:bob@pc.biloxi.com>;actor="principal""#,
+        );
+        assert_none!(preferences.score(&contacts[0]));
+        assert_some_eq!(preferences.score(&contacts[1]), 1.0);
+    }
+
+    #[test]
+    fn test_required_accept_contact() {
+        let preferences = CallerPreferences::new(&request("Accept-Contact: *;video;require\r\n"));
+        let contacts =
+            parse_contacts("<sip:bob@pc.biloxi.com>;audio, <sip:bob@tv.biloxi.com>;video");
+        // The first contact does not declare video, so it is only discarded if explicit.
+        assert_some_eq!(preferences.score(&contacts[0]), 0.0);
+        assert_some_eq!(preferences.score(&contacts[1]), 1.0);
+
+        let preferences =
+            CallerPreferences::new(&request("Accept-Contact: *;video;require;explicit\r\n"));
+        assert_none!(preferences.score(&contacts[0]));
+        assert_some_eq!(preferences.score(&contacts[1]), 1.0);
+
+        let preferences = CallerPreferences::new(&request(
+            "Accept-Contact: *;+sip.bandwidth=\"#>=128\";require\r\n",
+        ));
+        let bandwidth_contacts = parse_contacts(
+            r##"<sip:bob@pc.biloxi.com>;+sip.bandwidth="#=64", <sip:bob@tv.biloxi.com>;+sip.bandwidth="#=256""##,
+        );
+        assert_none!(preferences.score(&bandwidth_contacts[0]));
+        assert_some_eq!(preferences.score(&bandwidth_contacts[1]), 1.0);
+    }
+
+    #[test]
+    fn test_rank_contacts() {
+        let preferences = CallerPreferences::new(&request(
+            "Accept-Contact: *;audio;video\r\nReject-Contact: *;automata\r\n",
+        ));
+        let contacts = parse_contacts(
+            "<sip:a@biloxi.com>;audio;q=0.5, \
+             <sip:b@biloxi.com>;audio, \
+             <sip:c@biloxi.com>;audio;video, \
+             <sip:d@biloxi.com>;automata;audio, \
+             <sip:e@biloxi.com>;audio;video;q=0.5",
+        );
+        let ranked = preferences.rank(&contacts);
+        assert_eq!(
+            ranked
+                .iter()
+                .map(|contact| contact.address().uri().to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "sip:c@biloxi.com",
+                "sip:b@biloxi.com",
+                "sip:e@biloxi.com",
+                "sip:a@biloxi.com"
+            ]
+        );
+    }
+}
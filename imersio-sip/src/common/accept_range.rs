@@ -47,6 +47,14 @@ impl AcceptRange {
     pub fn parameters(&self) -> &Vec<AcceptParameter> {
         &self.parameters
     }
+
+    /// Get the value of the `q` parameter for the range if it has one.
+    pub fn q(&self) -> Option<f32> {
+        self.parameters
+            .iter()
+            .find(|param| matches!(param, AcceptParameter::Q(_)))
+            .and_then(|param| param.q())
+    }
 }
 
 impl std::fmt::Display for AcceptRange {
@@ -10,6 +10,18 @@ pub enum Algorithm {
     Md5,
     /// MD5-sess algorithm.
     Md5Sess,
+    /// SHA-256 algorithm.
+    ///
+    /// [[RFC8760, Section 2.2](https://datatracker.ietf.org/doc/html/rfc8760#section-2.2)]
+    Sha256,
+    /// SHA-256-sess algorithm.
+    Sha256Sess,
+    /// SHA-512-256 algorithm.
+    ///
+    /// [[RFC8760, Section 2.2](https://datatracker.ietf.org/doc/html/rfc8760#section-2.2)]
+    Sha512_256,
+    /// SHA-512-256-sess algorithm.
+    Sha512_256Sess,
     /// Any other algorithm.
     Other(TokenString),
 }
@@ -19,6 +31,10 @@ impl Algorithm {
         match algo.to_ascii_lowercase().as_str() {
             "md5" => Self::Md5,
             "md5-sess" => Self::Md5Sess,
+            "sha-256" => Self::Sha256,
+            "sha-256-sess" => Self::Sha256Sess,
+            "sha-512-256" => Self::Sha512_256,
+            "sha-512-256-sess" => Self::Sha512_256Sess,
             _ => Self::Other(algo),
         }
     }
@@ -28,9 +44,22 @@ impl Algorithm {
         match self {
             Self::Md5 => "MD5",
             Self::Md5Sess => "MD5-Sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+            Self::Sha512_256 => "SHA-512-256",
+            Self::Sha512_256Sess => "SHA-512-256-sess",
             Self::Other(value) => value,
         }
     }
+
+    /// Tell whether the algorithm is a session variant, computing the A1 value from the nonce
+    /// and the cnonce.
+    pub fn is_session(&self) -> bool {
+        matches!(
+            self,
+            Self::Md5Sess | Self::Sha256Sess | Self::Sha512_256Sess
+        )
+    }
 }
 
 impl std::fmt::Display for Algorithm {
@@ -42,7 +71,12 @@ impl std::fmt::Display for Algorithm {
 impl PartialEq<Algorithm> for Algorithm {
     fn eq(&self, other: &Algorithm) -> bool {
         match (self, other) {
-            (Self::Md5, Self::Md5) | (Self::Md5Sess, Self::Md5Sess) => true,
+            (Self::Md5, Self::Md5)
+            | (Self::Md5Sess, Self::Md5Sess)
+            | (Self::Sha256, Self::Sha256)
+            | (Self::Sha256Sess, Self::Sha256Sess)
+            | (Self::Sha512_256, Self::Sha512_256)
+            | (Self::Sha512_256Sess, Self::Sha512_256Sess) => true,
             (Self::Other(a), Self::Other(b)) => a.eq_ignore_ascii_case(b),
             _ => false,
         }
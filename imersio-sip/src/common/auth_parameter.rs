@@ -269,7 +269,15 @@ pub(crate) mod parser {
         context(
             "request_digest",
             map(
-                delimited(ldquot, recognize(many_m_n(32, 32, lhex)), rdquot),
+                // 32 hex digits for MD5, 64 for SHA-256 and SHA-512-256.
+                delimited(
+                    ldquot,
+                    alt((
+                        recognize(many_m_n(64, 64, lhex)),
+                        recognize(many_m_n(32, 32, lhex)),
+                    )),
+                    rdquot,
+                ),
                 WrappedString::new_quoted,
             ),
         )
@@ -294,11 +302,8 @@ pub(crate) mod parser {
                 separated_pair(
                     tag_no_case("algorithm"),
                     equal,
-                    cut(alt((
-                        map(tag_no_case("MD5"), TokenString::new),
-                        map(tag_no_case("MD5-sess"), TokenString::new),
-                        token,
-                    ))),
+                    // The known algorithms, eg. `MD5`, `MD5-sess` or `SHA-256`, are tokens.
+                    cut(token),
                 ),
                 |(_, value)| AuthParameter::Algorithm(Algorithm::new(value)),
             ),
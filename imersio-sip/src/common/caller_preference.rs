@@ -0,0 +1,128 @@
+use itertools::join;
+
+use crate::{FeatureTag, GenericParameter, TokenString};
+
+/// Representation of a parameter of a caller preference.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::IsVariant)]
+pub enum CallerPreferenceParameter {
+    /// A feature parameter.
+    Feature(FeatureTag),
+    /// The `require` parameter, discarding the contacts not matching the preference.
+    Require,
+    /// The `explicit` parameter, only considering the features explicitly declared by the
+    /// contacts.
+    Explicit,
+    /// Any other parameter.
+    Other(GenericParameter<TokenString>),
+}
+
+impl std::fmt::Display for CallerPreferenceParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Feature(feature) => write!(f, "{feature}"),
+            Self::Require => write!(f, "require"),
+            Self::Explicit => write!(f, "explicit"),
+            Self::Other(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<GenericParameter<TokenString>> for CallerPreferenceParameter {
+    fn from(value: GenericParameter<TokenString>) -> Self {
+        match (value.key().to_ascii_lowercase().as_str(), value.value()) {
+            ("require", None) => Self::Require,
+            ("explicit", None) => Self::Explicit,
+            _ => match FeatureTag::from_parameter(&value) {
+                Some(feature) => Self::Feature(feature),
+                None => Self::Other(value),
+            },
+        }
+    }
+}
+
+/// Representation of a caller preference, as contained in an `Accept-Contact` or a
+/// `Reject-Contact` header.
+///
+/// [[RFC3841, Section 10](https://datatracker.ietf.org/doc/html/rfc3841#section-10)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CallerPreference {
+    parameters: Vec<CallerPreferenceParameter>,
+}
+
+impl CallerPreference {
+    pub(crate) fn new(parameters: Vec<CallerPreferenceParameter>) -> Self {
+        Self { parameters }
+    }
+
+    /// Get a reference to the parameters of the caller preference.
+    pub fn parameters(&self) -> &Vec<CallerPreferenceParameter> {
+        &self.parameters
+    }
+
+    /// Get the feature tags making the predicate of the caller preference.
+    pub fn feature_tags(&self) -> impl Iterator<Item = &FeatureTag> {
+        self.parameters.iter().filter_map(|param| match param {
+            CallerPreferenceParameter::Feature(feature) => Some(feature),
+            _ => None,
+        })
+    }
+
+    /// Tell whether the caller preference has the `require` parameter.
+    pub fn is_required(&self) -> bool {
+        self.parameters.iter().any(|param| param.is_require())
+    }
+
+    /// Tell whether the caller preference has the `explicit` parameter.
+    pub fn is_explicit(&self) -> bool {
+        self.parameters.iter().any(|param| param.is_explicit())
+    }
+}
+
+impl std::fmt::Display for CallerPreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "*{}{}",
+            if self.parameters.is_empty() { "" } else { ";" },
+            join(&self.parameters, ";")
+        )
+    }
+}
+
+impl From<Vec<FeatureTag>> for CallerPreference {
+    fn from(value: Vec<FeatureTag>) -> Self {
+        Self::new(
+            value
+                .into_iter()
+                .map(CallerPreferenceParameter::Feature)
+                .collect(),
+        )
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser, bytes::complete::tag, combinator::map, error::context, multi::many0,
+        sequence::preceded,
+    };
+
+    use crate::{
+        CallerPreference,
+        common::generic_parameter::parser::generic_param,
+        parser::{ParserResult, semi},
+    };
+
+    pub(crate) fn caller_preference(input: &str) -> ParserResult<&str, CallerPreference> {
+        context(
+            "caller_preference",
+            map(
+                preceded(
+                    tag("*"),
+                    many0(preceded(semi, map(generic_param, Into::into))),
+                ),
+                CallerPreference::new,
+            ),
+        )
+        .parse(input)
+    }
+}
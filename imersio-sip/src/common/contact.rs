@@ -3,6 +3,8 @@ use std::hash::Hash;
 use std::ops::Deref;
 
 use crate::ContactParameter;
+use crate::FeatureTag;
+use crate::FeatureValue;
 use crate::NameAddress;
 use crate::utils::compare_vectors;
 
@@ -101,6 +103,59 @@ impl Contact {
             .find(|param| matches!(param, ContactParameter::Expires(_)))
             .and_then(|param| param.expires())
     }
+
+    /// Get the feature tags of the contact, describing the capabilities of the user agent.
+    ///
+    /// [[RFC3840, Section 9](https://datatracker.ietf.org/doc/html/rfc3840#section-9)]
+    pub fn feature_tags(&self) -> Vec<FeatureTag> {
+        self.parameters
+            .iter()
+            .filter_map(ContactParameter::feature_tag)
+            .collect()
+    }
+
+    /// Get the instance identifier of the user agent, as given by the `+sip.instance` feature
+    /// tag of the contact.
+    ///
+    /// [[RFC5626, Section 4.1](https://datatracker.ietf.org/doc/html/rfc5626#section-4.1)]
+    pub fn instance_id(&self) -> Option<String> {
+        self.feature_tags()
+            .into_iter()
+            .find(|tag| tag.name() == "+sip.instance")
+            .and_then(|tag| match tag.values().first() {
+                Some(FeatureValue::String(value)) => Some(value.clone()),
+                _ => None,
+            })
+    }
+
+    /// Get the registration identifier of the contact, as given by its `reg-id` parameter,
+    /// distinguishing the flows of a same user agent instance.
+    ///
+    /// [[RFC5626, Section 4.2](https://datatracker.ietf.org/doc/html/rfc5626#section-4.2)]
+    pub fn reg_id(&self) -> Option<u32> {
+        self.parameters
+            .iter()
+            .find(|param| param.key().eq_ignore_ascii_case("reg-id"))
+            .and_then(|param| param.value())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Get a copy of the contact with its `expires` parameter set to the given number of seconds.
+    ///
+    /// This is how a registrar tells the remaining lifetime of each binding in the response to a
+    /// REGISTER request.
+    ///
+    /// [[RFC3261, Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3)]
+    pub fn with_expires(&self, expires: u32) -> Self {
+        let mut parameters: Vec<ContactParameter> = self
+            .parameters
+            .iter()
+            .filter(|param| !matches!(param, ContactParameter::Expires(_)))
+            .cloned()
+            .collect();
+        parameters.push(ContactParameter::Expires(expires.to_string()));
+        Self::new(self.address.clone(), parameters)
+    }
 }
 
 impl std::fmt::Display for Contact {
@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 
 use crate::common::generic_parameter::generic_parameter_display;
-use crate::{GenericParameter, TokenString};
+use crate::{FeatureTag, GenericParameter, TokenString};
 
 /// Representation of a contact parameter.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
@@ -41,6 +41,16 @@ impl ContactParameter {
         }
     }
 
+    /// Get the feature tag of the parameter if this is a feature parameter.
+    ///
+    /// [[RFC3840, Section 9](https://datatracker.ietf.org/doc/html/rfc3840#section-9)]
+    pub fn feature_tag(&self) -> Option<FeatureTag> {
+        match self {
+            Self::Other(value) => FeatureTag::from_parameter(value),
+            _ => None,
+        }
+    }
+
     /// Get the expires value of the parameter if this is an `expires`
     /// parameter.
     pub fn expires(&self) -> Option<u32> {
@@ -0,0 +1,95 @@
+/// Representation of a directive of a `Request-Disposition` header, telling proxies how to
+/// handle the request.
+///
+/// [[RFC3841, Section 10](https://datatracker.ietf.org/doc/html/rfc3841#section-10)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum Directive {
+    /// Proxy the request to the targets.
+    Proxy,
+    /// Redirect the caller to the targets.
+    Redirect,
+    /// Cancel the pending branches when a final response is received.
+    Cancel,
+    /// Leave the cancellation of the pending branches to the caller.
+    NoCancel,
+    /// Fork the request to all the targets.
+    Fork,
+    /// Only send the request to the best target.
+    NoFork,
+    /// Recurse on the 3xx responses.
+    Recurse,
+    /// Forward the 3xx responses upstream.
+    NoRecurse,
+    /// Try the targets in parallel.
+    Parallel,
+    /// Try the targets one after the other.
+    Sequential,
+    /// Wait for a busy callee to become available.
+    Queue,
+    /// Reject the request when the callee is busy.
+    NoQueue,
+}
+
+impl Directive {
+    /// Get the value of the `Directive`.
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Proxy => "proxy",
+            Self::Redirect => "redirect",
+            Self::Cancel => "cancel",
+            Self::NoCancel => "no-cancel",
+            Self::Fork => "fork",
+            Self::NoFork => "no-fork",
+            Self::Recurse => "recurse",
+            Self::NoRecurse => "no-recurse",
+            Self::Parallel => "parallel",
+            Self::Sequential => "sequential",
+            Self::Queue => "queue",
+            Self::NoQueue => "no-queue",
+        }
+    }
+}
+
+impl std::fmt::Display for Directive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        character::complete::satisfy,
+        combinator::{not, value},
+        error::context,
+        sequence::terminated,
+    };
+
+    use crate::{Directive, parser::ParserResult};
+
+    pub(crate) fn directive(input: &str) -> ParserResult<&str, Directive> {
+        context(
+            "directive",
+            terminated(
+                alt((
+                    value(Directive::Proxy, tag_no_case("proxy")),
+                    value(Directive::Redirect, tag_no_case("redirect")),
+                    value(Directive::Cancel, tag_no_case("cancel")),
+                    value(Directive::NoCancel, tag_no_case("no-cancel")),
+                    value(Directive::Fork, tag_no_case("fork")),
+                    value(Directive::NoFork, tag_no_case("no-fork")),
+                    value(Directive::Recurse, tag_no_case("recurse")),
+                    value(Directive::NoRecurse, tag_no_case("no-recurse")),
+                    value(Directive::Parallel, tag_no_case("parallel")),
+                    value(Directive::Sequential, tag_no_case("sequential")),
+                    value(Directive::Queue, tag_no_case("queue")),
+                    value(Directive::NoQueue, tag_no_case("no-queue")),
+                )),
+                not(satisfy(|c: char| c.is_ascii_alphanumeric() || c == '-')),
+            ),
+        )
+        .parse(input)
+    }
+}
@@ -0,0 +1,112 @@
+use itertools::join;
+use std::hash::Hash;
+
+use crate::common::value_collection::ValueCollection;
+use crate::utils::compare_vectors;
+use crate::{DiversionParameter, DiversionReason, NameAddress};
+
+/// Representation of the list of entries from a `DiversionHeader`.
+///
+/// This is usable as an iterator.
+pub type Diversions = ValueCollection<Diversion>;
+
+/// Representation of an entry contained in a `Diversion` header.
+#[derive(Clone, Debug, Eq)]
+pub struct Diversion {
+    name_addr: NameAddress,
+    parameters: Vec<DiversionParameter>,
+}
+
+impl Diversion {
+    /// Create a `Diversion` entry.
+    pub fn new(name_addr: NameAddress, parameters: Vec<DiversionParameter>) -> Self {
+        Diversion {
+            name_addr,
+            parameters,
+        }
+    }
+
+    /// Get a reference to the `NameAddress` of the diverting user.
+    pub fn name_address(&self) -> &NameAddress {
+        &self.name_addr
+    }
+
+    /// Get a reference to the parameters contained in the entry.
+    pub fn parameters(&self) -> &Vec<DiversionParameter> {
+        &self.parameters
+    }
+
+    /// Get the reason of the diversion.
+    pub fn reason(&self) -> Option<&DiversionReason> {
+        self.parameters.iter().find_map(|p| p.reason())
+    }
+
+    /// Get the number of diversions represented by the entry.
+    ///
+    /// When no `counter` parameter is present, the entry stands for a single diversion.
+    pub fn counter(&self) -> u8 {
+        self.parameters
+            .iter()
+            .find_map(|p| p.counter())
+            .unwrap_or(1)
+    }
+
+    /// Get the maximum number of diversions allowed by the diverting user.
+    pub fn limit(&self) -> Option<u8> {
+        self.parameters.iter().find_map(|p| p.limit())
+    }
+}
+
+impl std::fmt::Display for Diversion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.name_addr,
+            if self.parameters.is_empty() { "" } else { ";" },
+            join(&self.parameters, ";")
+        )
+    }
+}
+
+impl PartialEq for Diversion {
+    fn eq(&self, other: &Self) -> bool {
+        self.name_addr == other.name_addr && compare_vectors(self.parameters(), other.parameters())
+    }
+}
+
+impl Hash for Diversion {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name_addr.hash(state);
+        let mut sorted_params = self.parameters.clone();
+        sorted_params.sort();
+        sorted_params.hash(state);
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        combinator::map,
+        error::context,
+        multi::many0,
+        sequence::{pair, preceded},
+    };
+
+    use crate::{
+        Diversion,
+        common::{contact::parser::name_addr, diversion_parameter::parser::diversion_params},
+        parser::{ParserResult, semi},
+    };
+
+    pub(crate) fn diversion_entry(input: &str) -> ParserResult<&str, Diversion> {
+        context(
+            "diversion_entry",
+            map(
+                pair(name_addr, many0(preceded(semi, diversion_params))),
+                |(name_addr, params)| Diversion::new(name_addr, params),
+            ),
+        )
+        .parse(input)
+    }
+}
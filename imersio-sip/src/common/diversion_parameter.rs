@@ -0,0 +1,246 @@
+use std::cmp::Ordering;
+
+use crate::common::generic_parameter::generic_parameter_display;
+use crate::{DiversionReason, GenericParameter, TokenString};
+
+/// Representation of a parameter of an entry in a `Diversion` header.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum DiversionParameter {
+    /// A `reason` parameter.
+    Reason(DiversionReason),
+    /// A `counter` parameter.
+    Counter(String),
+    /// A `limit` parameter.
+    Limit(String),
+    /// A `privacy` parameter.
+    Privacy(String),
+    /// A `screen` parameter.
+    Screen(String),
+    /// Any other parameter.
+    Other(GenericParameter<TokenString>),
+}
+
+impl DiversionParameter {
+    /// Get the key of the parameter.
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Reason(_) => "reason",
+            Self::Counter(_) => "counter",
+            Self::Limit(_) => "limit",
+            Self::Privacy(_) => "privacy",
+            Self::Screen(_) => "screen",
+            Self::Other(value) => value.key(),
+        }
+    }
+
+    /// Get the value of the parameter.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Self::Reason(value) => Some(value.value()),
+            Self::Counter(value)
+            | Self::Limit(value)
+            | Self::Privacy(value)
+            | Self::Screen(value) => Some(value),
+            Self::Other(value) => value.value(),
+        }
+    }
+
+    /// Get the reason value of the parameter if this is a `reason` parameter.
+    pub fn reason(&self) -> Option<&DiversionReason> {
+        match self {
+            Self::Reason(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the counter value of the parameter if this is a `counter` parameter.
+    pub fn counter(&self) -> Option<u8> {
+        match self {
+            Self::Counter(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the limit value of the parameter if this is a `limit` parameter.
+    pub fn limit(&self) -> Option<u8> {
+        match self {
+            Self::Limit(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for DiversionParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        generic_parameter_display(self.key(), self.value(), f)
+    }
+}
+
+impl PartialOrd for DiversionParameter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DiversionParameter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.key().cmp(other.key()) {
+            Ordering::Equal => self.value().cmp(&other.value()),
+            ord => ord,
+        }
+    }
+}
+
+impl From<GenericParameter<TokenString>> for DiversionParameter {
+    fn from(value: GenericParameter<TokenString>) -> Self {
+        Self::Other(value)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        combinator::{map, not, recognize},
+        error::context,
+        multi::many_m_n,
+        sequence::{separated_pair, terminated},
+    };
+
+    use crate::{
+        DiversionParameter, DiversionReason, TokenString,
+        common::generic_parameter::parser::generic_param,
+        parser::{ParserResult, digit, equal, quoted_string, token},
+    };
+
+    fn token_or_quoted_string(input: &str) -> ParserResult<&str, String> {
+        alt((
+            map(token, |value| value.to_string()),
+            map(quoted_string, |value| value.to_string()),
+        ))
+        .parse(input)
+    }
+
+    fn one_or_two_digits(input: &str) -> ParserResult<&str, &str> {
+        terminated(recognize(many_m_n(1, 2, digit)), not(digit)).parse(input)
+    }
+
+    /// A quoted reason designates the same reason as the unquoted one, the quotes being kept
+    /// only for the reasons that are not known.
+    fn reason_value(input: &str) -> ParserResult<&str, DiversionReason> {
+        alt((
+            map(token, DiversionReason::new),
+            map(quoted_string, |value| {
+                match DiversionReason::new(TokenString::new(value.value())) {
+                    DiversionReason::Other(_) => {
+                        DiversionReason::Other(TokenString::new(value.to_string()))
+                    }
+                    reason => reason,
+                }
+            }),
+        ))
+        .parse(input)
+    }
+
+    fn diversion_reason(input: &str) -> ParserResult<&str, DiversionParameter> {
+        map(
+            separated_pair(tag_no_case("reason"), equal, reason_value),
+            |(_, reason)| DiversionParameter::Reason(reason),
+        )
+        .parse(input)
+    }
+
+    fn diversion_counter(input: &str) -> ParserResult<&str, DiversionParameter> {
+        map(
+            separated_pair(tag_no_case("counter"), equal, one_or_two_digits),
+            |(_, value)| DiversionParameter::Counter(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    fn diversion_limit(input: &str) -> ParserResult<&str, DiversionParameter> {
+        map(
+            separated_pair(tag_no_case("limit"), equal, one_or_two_digits),
+            |(_, value)| DiversionParameter::Limit(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    fn diversion_privacy(input: &str) -> ParserResult<&str, DiversionParameter> {
+        map(
+            separated_pair(tag_no_case("privacy"), equal, token_or_quoted_string),
+            |(_, value)| DiversionParameter::Privacy(value),
+        )
+        .parse(input)
+    }
+
+    fn diversion_screen(input: &str) -> ParserResult<&str, DiversionParameter> {
+        map(
+            separated_pair(tag_no_case("screen"), equal, token_or_quoted_string),
+            |(_, value)| DiversionParameter::Screen(value),
+        )
+        .parse(input)
+    }
+
+    pub(crate) fn diversion_params(input: &str) -> ParserResult<&str, DiversionParameter> {
+        context(
+            "diversion_params",
+            alt((
+                diversion_reason,
+                diversion_counter,
+                diversion_limit,
+                diversion_privacy,
+                diversion_screen,
+                map(generic_param, Into::into),
+            )),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diversion_reason_parameter() {
+        let (rest, parameter) = parser::diversion_params("reason=user-busy").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(parameter.key(), "reason");
+        assert_eq!(parameter.reason(), Some(&DiversionReason::UserBusy));
+        assert_eq!(parameter.to_string(), "reason=user-busy");
+
+        let (rest, parameter) = parser::diversion_params("reason=\"user-busy\"").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(parameter.reason(), Some(&DiversionReason::UserBusy));
+        assert_eq!(parameter.to_string(), "reason=user-busy");
+
+        let (_, parameter) = parser::diversion_params("reason=\"on holiday\"").unwrap();
+        assert_eq!(
+            parameter.reason(),
+            Some(&DiversionReason::Other(TokenString::new("\"on holiday\"")))
+        );
+        assert_eq!(parameter.to_string(), "reason=\"on holiday\"");
+    }
+
+    #[test]
+    fn test_diversion_counter_and_limit_parameters() {
+        let (_, parameter) = parser::diversion_params("counter=3").unwrap();
+        assert_eq!(parameter.counter(), Some(3));
+        let (_, parameter) = parser::diversion_params("limit=10").unwrap();
+        assert_eq!(parameter.limit(), Some(10));
+        let (_, parameter) = parser::diversion_params("counter=100").unwrap();
+        assert!(parameter.is_other());
+    }
+
+    #[test]
+    fn test_diversion_privacy_and_screen_parameters() {
+        let (_, parameter) = parser::diversion_params("privacy=full").unwrap();
+        assert!(parameter.is_privacy());
+        assert_eq!(parameter.value(), Some("full"));
+        let (_, parameter) = parser::diversion_params("screen=\"no\"").unwrap();
+        assert!(parameter.is_screen());
+        assert_eq!(parameter.value(), Some("\"no\""));
+    }
+}
@@ -0,0 +1,133 @@
+use std::cmp::Ordering;
+use std::hash::Hash;
+
+use crate::{SipError, TokenString};
+
+/// Representation of the `reason` parameter of an entry in a `Diversion` header.
+///
+/// [[RFC5806, Section 4](https://datatracker.ietf.org/doc/html/rfc5806#section-4)]
+#[derive(Clone, Debug, Eq, derive_more::IsVariant)]
+pub enum DiversionReason {
+    /// The reason of the diversion is unknown.
+    Unknown,
+    /// The diverting user was busy.
+    UserBusy,
+    /// The diverting user did not answer.
+    NoAnswer,
+    /// The diverting user was not reachable.
+    Unavailable,
+    /// The diverting user forwards all its calls.
+    Unconditional,
+    /// The call was diverted because of a time of day rule.
+    TimeOfDay,
+    /// The diverting user did not want to be disturbed.
+    DoNotDisturb,
+    /// The diverting user deflected the call.
+    Deflection,
+    /// The call was diverted by a follow me service.
+    FollowMe,
+    /// The diverting user was out of service.
+    OutOfService,
+    /// The diverting user was away.
+    Away,
+    /// Any extension value.
+    Other(TokenString),
+}
+
+impl DiversionReason {
+    pub(crate) fn new(reason: TokenString) -> Self {
+        match reason.to_ascii_lowercase().as_str() {
+            "unknown" => Self::Unknown,
+            "user-busy" => Self::UserBusy,
+            "no-answer" => Self::NoAnswer,
+            "unavailable" => Self::Unavailable,
+            "unconditional" => Self::Unconditional,
+            "time-of-day" => Self::TimeOfDay,
+            "do-not-disturb" => Self::DoNotDisturb,
+            "deflection" => Self::Deflection,
+            "follow-me" => Self::FollowMe,
+            "out-of-service" => Self::OutOfService,
+            "away" => Self::Away,
+            _ => Self::Other(reason),
+        }
+    }
+
+    /// Get the value of the diversion reason.
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::UserBusy => "user-busy",
+            Self::NoAnswer => "no-answer",
+            Self::Unavailable => "unavailable",
+            Self::Unconditional => "unconditional",
+            Self::TimeOfDay => "time-of-day",
+            Self::DoNotDisturb => "do-not-disturb",
+            Self::Deflection => "deflection",
+            Self::FollowMe => "follow-me",
+            Self::OutOfService => "out-of-service",
+            Self::Away => "away",
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for DiversionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl PartialEq for DiversionReason {
+    fn eq(&self, other: &DiversionReason) -> bool {
+        self.value().eq_ignore_ascii_case(other.value())
+    }
+}
+
+impl PartialOrd for DiversionReason {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DiversionReason {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value()
+            .to_ascii_lowercase()
+            .cmp(&other.value().to_ascii_lowercase())
+    }
+}
+
+impl Hash for DiversionReason {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value().to_ascii_lowercase().hash(state);
+    }
+}
+
+impl TryFrom<&str> for DiversionReason {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(DiversionReason::new(TokenString::try_from(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_diversion_reason() {
+        assert_eq!(
+            DiversionReason::try_from("user-busy").unwrap(),
+            DiversionReason::UserBusy
+        );
+        assert_eq!(
+            DiversionReason::try_from("No-Answer").unwrap(),
+            DiversionReason::NoAnswer
+        );
+        assert_eq!(DiversionReason::Unconditional.to_string(), "unconditional");
+        assert!(DiversionReason::try_from("vacation").unwrap().is_other());
+        assert_err!(DiversionReason::try_from("no answer"));
+    }
+}
@@ -0,0 +1,323 @@
+use itertools::join;
+
+use crate::{GenericParameter, TokenString};
+
+/// The feature tags of the SIP tree that are encoded without the leading `+`.
+///
+/// [[RFC3840, Section 9](https://datatracker.ietf.org/doc/html/rfc3840#section-9)]
+const BASE_TAGS: [&str; 20] = [
+    "audio",
+    "automata",
+    "class",
+    "duplex",
+    "data",
+    "control",
+    "mobility",
+    "description",
+    "events",
+    "priority",
+    "methods",
+    "schemes",
+    "application",
+    "video",
+    "language",
+    "type",
+    "isfocus",
+    "actor",
+    "text",
+    "extensions",
+];
+
+/// Representation of a value of a feature tag.
+///
+/// [[RFC3840, Section 9](https://datatracker.ietf.org/doc/html/rfc3840#section-9)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeatureValue {
+    /// A boolean value, `TRUE` being implied by a feature tag without value.
+    Boolean(bool),
+    /// A token value, possibly negated with a leading `!`.
+    Token {
+        /// The token.
+        value: String,
+        /// Whether the token is negated.
+        negated: bool,
+    },
+    /// A numeric value, expressed as an inclusive range of numbers, possibly negated with a
+    /// leading `!`.
+    Numeric {
+        /// The lower bound of the range.
+        min: f64,
+        /// The upper bound of the range.
+        max: f64,
+        /// Whether the range is negated.
+        negated: bool,
+    },
+    /// A string value, enclosed in angle brackets.
+    String(String),
+}
+
+// The numbers are parsed from the `number` rule that cannot produce NaN.
+impl Eq for FeatureValue {}
+
+impl FeatureValue {
+    fn parse(value: &str) -> Option<Self> {
+        let (negated, value) = match value.strip_prefix('!') {
+            Some(value) => (true, value),
+            None => (false, value),
+        };
+        if let Some(relation) = value.strip_prefix('#') {
+            let number = |value: &str| value.parse::<f64>().ok().filter(|v| v.is_finite());
+            let (min, max) = if let Some(value) = relation.strip_prefix(">=") {
+                (number(value)?, f64::INFINITY)
+            } else if let Some(value) = relation.strip_prefix("<=") {
+                (f64::NEG_INFINITY, number(value)?)
+            } else if let Some(value) = relation.strip_prefix('=') {
+                (number(value)?, number(value)?)
+            } else {
+                let (min, max) = relation.split_once(':')?;
+                (number(min)?, number(max)?)
+            };
+            return Some(Self::Numeric { min, max, negated });
+        }
+        if value.is_empty() || value.contains(['#', '!', '<', '>']) {
+            return None;
+        }
+        if !negated && value.eq_ignore_ascii_case("TRUE") {
+            Some(Self::Boolean(true))
+        } else if !negated && value.eq_ignore_ascii_case("FALSE") {
+            Some(Self::Boolean(false))
+        } else {
+            Some(Self::Token {
+                value: value.to_string(),
+                negated,
+            })
+        }
+    }
+
+    /// Tell whether this value, taken from a predicate, is satisfied by the given value of a
+    /// feature set.
+    fn is_satisfied_by(&self, other: &FeatureValue) -> bool {
+        match (self, other) {
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (
+                Self::Token { value, negated },
+                Self::Token {
+                    value: other_value,
+                    negated: false,
+                },
+            ) => value.eq_ignore_ascii_case(other_value) != *negated,
+            (
+                Self::Numeric { min, max, negated },
+                Self::Numeric {
+                    min: other_min,
+                    max: other_max,
+                    negated: false,
+                },
+            ) => (min <= other_max && other_min <= max) != *negated,
+            (Self::String(a), Self::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for FeatureValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Boolean(true) => write!(f, "TRUE"),
+            Self::Boolean(false) => write!(f, "FALSE"),
+            Self::Token { value, negated } => {
+                write!(f, "{}{value}", if *negated { "!" } else { "" })
+            }
+            Self::Numeric { min, max, negated } => {
+                write!(f, "{}#", if *negated { "!" } else { "" })?;
+                if min == max {
+                    write!(f, "={min}")
+                } else if max.is_infinite() {
+                    write!(f, ">={min}")
+                } else if min.is_infinite() {
+                    write!(f, "<={max}")
+                } else {
+                    write!(f, "{min}:{max}")
+                }
+            }
+            Self::String(value) => write!(f, "<{value}>"),
+        }
+    }
+}
+
+/// Representation of a feature tag of a contact or of a caller preference, with its values.
+///
+/// [[RFC3840, Section 9](https://datatracker.ietf.org/doc/html/rfc3840#section-9)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeatureTag {
+    name: String,
+    values: Vec<FeatureValue>,
+}
+
+impl FeatureTag {
+    pub(crate) fn new<S: Into<String>>(name: S, values: Vec<FeatureValue>) -> Self {
+        Self {
+            name: name.into(),
+            values,
+        }
+    }
+
+    /// Get the feature tag encoded in the given parameter, if it is a feature parameter.
+    pub(crate) fn from_parameter(value: &GenericParameter<TokenString>) -> Option<Self> {
+        let name = value.key().to_ascii_lowercase();
+        if !(BASE_TAGS.contains(&name.as_str()) || name.len() > 1 && name.starts_with('+')) {
+            return None;
+        }
+        let values = match value.value() {
+            None => vec![FeatureValue::Boolean(true)],
+            Some(value) => match value
+                .strip_prefix('<')
+                .and_then(|value| value.strip_suffix('>'))
+            {
+                Some(value) => vec![FeatureValue::String(value.to_string())],
+                None => value
+                    .split(',')
+                    .map(|value| FeatureValue::parse(value.trim()))
+                    .collect::<Option<Vec<_>>>()?,
+            },
+        };
+        Some(Self { name, values })
+    }
+
+    /// Get the name of the feature tag, as encoded in a parameter, eg. `audio` or
+    /// `+sip.instance`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the values of the feature tag.
+    pub fn values(&self) -> &Vec<FeatureValue> {
+        &self.values
+    }
+
+    /// Tell whether this feature tag, taken from a predicate, is satisfied by the given feature
+    /// tag of a feature set.
+    ///
+    /// The values of the predicate are alternatives, whereas the values of the feature set are
+    /// all supported together.
+    ///
+    /// [[RFC3841, Section 7.4](https://datatracker.ietf.org/doc/html/rfc3841#section-7.4)]
+    pub fn is_satisfied_by(&self, other: &FeatureTag) -> bool {
+        self.name == other.name
+            && self.values.iter().any(|value| {
+                other
+                    .values
+                    .iter()
+                    .any(|other_value| value.is_satisfied_by(other_value))
+            })
+    }
+}
+
+impl std::fmt::Display for FeatureTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.values.as_slice() {
+            [FeatureValue::Boolean(true)] => write!(f, "{}", self.name),
+            [FeatureValue::String(value)] => write!(f, "{}=\"<{value}>\"", self.name),
+            values => write!(f, "{}=\"{}\"", self.name, join(values, ",")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::wrapped_string::WrappedString;
+    use claims::{assert_none, assert_some};
+
+    fn feature_tag(key: &str, value: Option<&str>) -> Option<FeatureTag> {
+        FeatureTag::from_parameter(&GenericParameter::new(
+            TokenString::new(key),
+            value.map(WrappedString::new_quoted),
+        ))
+    }
+
+    #[test]
+    fn test_feature_tag_without_value() {
+        let tag = assert_some!(feature_tag("Audio", None));
+        assert_eq!(tag.name(), "audio");
+        assert_eq!(tag.values(), &vec![FeatureValue::Boolean(true)]);
+        assert_eq!(tag.to_string(), "audio");
+    }
+
+    #[test]
+    fn test_feature_tag_with_token_list() {
+        let tag = assert_some!(feature_tag("methods", Some("INVITE,BYE,!MESSAGE")));
+        assert_eq!(tag.values().len(), 3);
+        assert_eq!(
+            tag.values()[2],
+            FeatureValue::Token {
+                value: "MESSAGE".to_string(),
+                negated: true
+            }
+        );
+        assert_eq!(tag.to_string(), r#"methods="INVITE,BYE,!MESSAGE""#);
+    }
+
+    #[test]
+    fn test_feature_tag_with_numeric_values() {
+        let tag = assert_some!(feature_tag("+sip.bandwidth", Some("#>=64,#1:5,#=3")));
+        assert_eq!(tag.to_string(), r##"+sip.bandwidth="#>=64,#1:5,#=3""##);
+    }
+
+    #[test]
+    fn test_feature_tag_with_string_value() {
+        let tag = assert_some!(feature_tag(
+            "+sip.instance",
+            Some("<urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>")
+        ));
+        assert_eq!(
+            tag.values(),
+            &vec![FeatureValue::String(
+                "urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_not_a_feature_tag() {
+        assert_none!(feature_tag("transport", Some("udp")));
+        assert_none!(feature_tag("+", None));
+        assert_none!(feature_tag("methods", Some("#abc")));
+    }
+
+    #[test]
+    fn test_feature_tag_satisfaction() {
+        let supported = feature_tag("methods", Some("INVITE,BYE")).unwrap();
+        assert!(
+            feature_tag("methods", Some("invite"))
+                .unwrap()
+                .is_satisfied_by(&supported)
+        );
+        assert!(
+            !feature_tag("methods", Some("MESSAGE"))
+                .unwrap()
+                .is_satisfied_by(&supported)
+        );
+        assert!(
+            feature_tag("methods", Some("!MESSAGE"))
+                .unwrap()
+                .is_satisfied_by(&supported)
+        );
+        let bandwidth = feature_tag("+sip.bandwidth", Some("#=128")).unwrap();
+        assert!(
+            feature_tag("+sip.bandwidth", Some("#>=64"))
+                .unwrap()
+                .is_satisfied_by(&bandwidth)
+        );
+        assert!(
+            !feature_tag("+sip.bandwidth", Some("#<=64"))
+                .unwrap()
+                .is_satisfied_by(&bandwidth)
+        );
+        assert!(
+            !feature_tag("video", None)
+                .unwrap()
+                .is_satisfied_by(&feature_tag("audio", None).unwrap())
+        );
+    }
+}
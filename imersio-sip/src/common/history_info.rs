@@ -0,0 +1,189 @@
+use itertools::join;
+use std::hash::Hash;
+
+use crate::common::value_collection::ValueCollection;
+use crate::utils::compare_vectors;
+use crate::{HistoryInfoParameter, HistoryInfoTag, NameAddress, SipError, StatusCode, Uri};
+
+/// Representation of the list of entries from a `HistoryInfoHeader`.
+///
+/// This is usable as an iterator.
+pub type HistoryInfos = ValueCollection<HistoryInfo>;
+
+/// Representation of an entry contained in a `History-Info` header.
+#[derive(Clone, Debug, Eq)]
+pub struct HistoryInfo {
+    name_addr: NameAddress,
+    parameters: Vec<HistoryInfoParameter>,
+}
+
+impl HistoryInfo {
+    /// Create a `HistoryInfo` entry.
+    pub fn new(name_addr: NameAddress, parameters: Vec<HistoryInfoParameter>) -> Self {
+        HistoryInfo {
+            name_addr,
+            parameters,
+        }
+    }
+
+    /// Get a reference to the `NameAddress` of the target of the entry.
+    pub fn name_address(&self) -> &NameAddress {
+        &self.name_addr
+    }
+
+    /// Get a reference to the parameters contained in the entry.
+    pub fn parameters(&self) -> &Vec<HistoryInfoParameter> {
+        &self.parameters
+    }
+
+    /// Get the value of the `index` parameter of the entry.
+    pub fn index(&self) -> Option<&str> {
+        self.parameters.iter().find_map(|p| p.index())
+    }
+
+    /// Get the tag of the entry and the index of the entry it refers to, from its `rc`, `mp` or
+    /// `np` parameter.
+    pub fn tag(&self) -> Option<(HistoryInfoTag, &str)> {
+        self.parameters.iter().find_map(|p| p.tag())
+    }
+
+    /// Get the SIP cause for which the request sent to the target of the entry has been
+    /// retargeted.
+    ///
+    /// It is taken from the `Reason` header embedded in the URI of the entry, or from its `cause`
+    /// URI parameter.
+    ///
+    /// [[RFC7044, Section 4.3](https://datatracker.ietf.org/doc/html/rfc7044#section-4.3)]
+    /// [[RFC4458, Section 3.1](https://datatracker.ietf.org/doc/html/rfc4458#section-3.1)]
+    pub fn cause(&self) -> Option<u16> {
+        let uri = self.name_addr.uri();
+        uri.headers()
+            .iter()
+            .filter(|header| header.name().eq_ignore_ascii_case("Reason"))
+            .find_map(|header| sip_cause(header.value()))
+            .or_else(|| uri.parameter("cause").and_then(|cause| cause.parse().ok()))
+    }
+
+    pub(crate) fn uri(&self) -> &Uri {
+        self.name_addr.uri()
+    }
+
+    /// Embed a Reason header with the given SIP cause in the URI of the entry, if it is a SIP URI
+    /// that does not contain one yet.
+    pub(crate) fn set_cause(&mut self, cause: &StatusCode) -> Result<(), SipError> {
+        if let Uri::Sip(uri) = self.name_addr.uri() {
+            if !uri
+                .headers()
+                .iter()
+                .any(|header| header.name().eq_ignore_ascii_case("Reason"))
+            {
+                let mut builder = uri.clone().into_builder();
+                builder.try_header("Reason", format!("SIP;cause={}", cause.code()))?;
+                self.name_addr.set_uri(Uri::Sip(builder.build()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sip_cause(reason: &str) -> Option<u16> {
+    let mut parts = reason.split(';').map(str::trim);
+    if !parts.next()?.eq_ignore_ascii_case("SIP") {
+        return None;
+    }
+    parts.find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("cause") {
+            value.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+impl std::fmt::Display for HistoryInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.name_addr,
+            if self.parameters.is_empty() { "" } else { ";" },
+            join(&self.parameters, ";")
+        )
+    }
+}
+
+impl PartialEq for HistoryInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.name_addr == other.name_addr && compare_vectors(self.parameters(), other.parameters())
+    }
+}
+
+impl Hash for HistoryInfo {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name_addr.hash(state);
+        let mut sorted_params = self.parameters.clone();
+        sorted_params.sort();
+        sorted_params.hash(state);
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        combinator::map,
+        error::context,
+        multi::many0,
+        sequence::{pair, preceded},
+    };
+
+    use crate::{
+        HistoryInfo,
+        common::{contact::parser::name_addr, history_info_parameter::parser::hi_param},
+        parser::{ParserResult, semi},
+    };
+
+    pub(crate) fn hi_entry(input: &str) -> ParserResult<&str, HistoryInfo> {
+        context(
+            "hi_entry",
+            map(
+                pair(name_addr, many0(preceded(semi, hi_param))),
+                |(name_addr, params)| HistoryInfo::new(name_addr, params),
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_info_entry_cause_from_reason_uri_header() {
+        let (rest, entry) = parser::hi_entry(
+            "<sip:bob@example.com?Reason=SIP%3Bcause%3D302%3Btext%3D%22Moved%22>;index=1",
+        )
+        .unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(entry.index(), Some("1"));
+        assert_eq!(entry.cause(), Some(302));
+    }
+
+    #[test]
+    fn test_history_info_entry_cause_from_cause_uri_parameter() {
+        let (_, entry) = parser::hi_entry(
+            "<sip:voicemail@example.com;target=sip:bob%40example.com;cause=486>;index=1.1;mp=1",
+        )
+        .unwrap();
+        assert_eq!(entry.tag(), Some((HistoryInfoTag::Mp, "1")));
+        assert_eq!(entry.cause(), Some(486));
+    }
+
+    #[test]
+    fn test_history_info_entry_without_cause() {
+        let (_, entry) =
+            parser::hi_entry("<sip:bob@example.com?Reason=Q.850%3Bcause%3D17>;index=1").unwrap();
+        assert_eq!(entry.cause(), None);
+    }
+}
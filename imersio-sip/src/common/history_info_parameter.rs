@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+
+use crate::common::generic_parameter::generic_parameter_display;
+use crate::{GenericParameter, TokenString};
+
+/// Representation of a parameter of an entry in a `History-Info` header.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum HistoryInfoParameter {
+    /// An `index` parameter.
+    Index(String),
+    /// An `rc` parameter.
+    Rc(String),
+    /// An `mp` parameter.
+    Mp(String),
+    /// An `np` parameter.
+    Np(String),
+    /// Any other parameter.
+    Other(GenericParameter<TokenString>),
+}
+
+impl HistoryInfoParameter {
+    /// Get the key of the parameter.
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Index(_) => "index",
+            Self::Rc(_) => "rc",
+            Self::Mp(_) => "mp",
+            Self::Np(_) => "np",
+            Self::Other(value) => value.key(),
+        }
+    }
+
+    /// Get the value of the parameter.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Self::Index(value) | Self::Rc(value) | Self::Mp(value) | Self::Np(value) => Some(value),
+            Self::Other(value) => value.value(),
+        }
+    }
+
+    /// Get the index value of the parameter if this is an `index` parameter.
+    pub fn index(&self) -> Option<&str> {
+        match self {
+            Self::Index(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the tag of the parameter and the index it refers to, if this is an `rc`, `mp` or `np`
+    /// parameter.
+    pub fn tag(&self) -> Option<(HistoryInfoTag, &str)> {
+        match self {
+            Self::Rc(value) => Some((HistoryInfoTag::Rc, value)),
+            Self::Mp(value) => Some((HistoryInfoTag::Mp, value)),
+            Self::Np(value) => Some((HistoryInfoTag::Np, value)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for HistoryInfoParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        generic_parameter_display(self.key(), self.value(), f)
+    }
+}
+
+impl PartialOrd for HistoryInfoParameter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HistoryInfoParameter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.key().cmp(other.key()) {
+            Ordering::Equal => self.value().cmp(&other.value()),
+            ord => ord,
+        }
+    }
+}
+
+impl From<GenericParameter<TokenString>> for HistoryInfoParameter {
+    fn from(value: GenericParameter<TokenString>) -> Self {
+        Self::Other(value)
+    }
+}
+
+/// Representation of the tag of an entry in a `History-Info` header, telling how the target of
+/// the entry has been determined.
+///
+/// [[RFC7044, Section 4.2](https://datatracker.ietf.org/doc/html/rfc7044#section-4.2)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HistoryInfoTag {
+    /// The Request-URI has been changed while retaining the target user, eg. when using the
+    /// contact of a registered user (`rc` parameter).
+    Rc,
+    /// The target user has been changed, eg. when the call is forwarded (`mp` parameter).
+    Mp,
+    /// The Request-URI has not been changed (`np` parameter).
+    Np,
+}
+
+impl HistoryInfoTag {
+    /// Create the `HistoryInfoParameter` corresponding to this tag, referring to the given index.
+    pub fn to_parameter<S: Into<String>>(self, index: S) -> HistoryInfoParameter {
+        match self {
+            Self::Rc => HistoryInfoParameter::Rc(index.into()),
+            Self::Mp => HistoryInfoParameter::Mp(index.into()),
+            Self::Np => HistoryInfoParameter::Np(index.into()),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        character::complete::char,
+        combinator::{map, recognize},
+        error::context,
+        multi::{many0, many1},
+        sequence::{pair, preceded, separated_pair},
+    };
+
+    use crate::{
+        HistoryInfoParameter,
+        common::generic_parameter::parser::generic_param,
+        parser::{ParserResult, digit, equal},
+    };
+
+    fn hi_index_val(input: &str) -> ParserResult<&str, &str> {
+        recognize(pair(many1(digit), many0(preceded(char('.'), many1(digit))))).parse(input)
+    }
+
+    fn hi_index(input: &str) -> ParserResult<&str, HistoryInfoParameter> {
+        map(
+            separated_pair(tag_no_case("index"), equal, hi_index_val),
+            |(_, value)| HistoryInfoParameter::Index(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    fn rc_param(input: &str) -> ParserResult<&str, HistoryInfoParameter> {
+        map(
+            separated_pair(tag_no_case("rc"), equal, hi_index_val),
+            |(_, value)| HistoryInfoParameter::Rc(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    fn mp_param(input: &str) -> ParserResult<&str, HistoryInfoParameter> {
+        map(
+            separated_pair(tag_no_case("mp"), equal, hi_index_val),
+            |(_, value)| HistoryInfoParameter::Mp(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    fn np_param(input: &str) -> ParserResult<&str, HistoryInfoParameter> {
+        map(
+            separated_pair(tag_no_case("np"), equal, hi_index_val),
+            |(_, value)| HistoryInfoParameter::Np(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    pub(crate) fn hi_param(input: &str) -> ParserResult<&str, HistoryInfoParameter> {
+        context(
+            "hi_param",
+            alt((
+                hi_index,
+                rc_param,
+                mp_param,
+                np_param,
+                map(generic_param, Into::into),
+            )),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_info_index_parameter() {
+        let (rest, parameter) = parser::hi_param("index=1.1.2").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(parameter.key(), "index");
+        assert_eq!(parameter.value(), Some("1.1.2"));
+        assert_eq!(parameter.index(), Some("1.1.2"));
+        assert_eq!(parameter.tag(), None);
+    }
+
+    #[test]
+    fn test_history_info_tag_parameters() {
+        let (_, parameter) = parser::hi_param("rc=1.1").unwrap();
+        assert_eq!(parameter.tag(), Some((HistoryInfoTag::Rc, "1.1")));
+        let (_, parameter) = parser::hi_param("MP=1").unwrap();
+        assert_eq!(parameter.tag(), Some((HistoryInfoTag::Mp, "1")));
+        assert_eq!(parameter.to_string(), "mp=1");
+        let (_, parameter) = parser::hi_param("np=1.2").unwrap();
+        assert_eq!(parameter, HistoryInfoTag::Np.to_parameter("1.2"));
+    }
+
+    #[test]
+    fn test_history_info_other_parameter() {
+        let (rest, parameter) = parser::hi_param("foo=bar").unwrap();
+        assert_eq!(rest, "");
+        assert!(parameter.is_other());
+        assert_eq!(parameter.key(), "foo");
+        assert_eq!(parameter.value(), Some("bar"));
+    }
+
+    #[test]
+    fn test_history_info_invalid_index_parameter_is_generic() {
+        let (_, parameter) = parser::hi_param("index=a.1").unwrap();
+        assert!(parameter.is_other());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{cmp::Ordering, hash::Hash};
+
+use crate::{GenericParameter, TokenString};
+
+/// Representation of a parameter contained in an `Identity` header, following the `info`
+/// parameter.
+#[derive(Clone, Debug, Eq, derive_more::IsVariant)]
+pub enum IdentityParameter {
+    /// alg parameter
+    Alg(String),
+    /// ppt parameter
+    Ppt(String),
+    /// Any other parameter
+    Other(GenericParameter<TokenString>),
+}
+
+impl IdentityParameter {
+    /// Get the key of the parameter.
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Alg(_) => "alg",
+            Self::Ppt(_) => "ppt",
+            Self::Other(value) => value.key(),
+        }
+    }
+
+    /// Get the value of the parameter.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Self::Alg(value) | Self::Ppt(value) => Some(value),
+            Self::Other(value) => value.value(),
+        }
+    }
+}
+
+impl std::fmt::Display for IdentityParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Alg(value) => write!(f, "alg={value}"),
+            Self::Ppt(value) => write!(f, "ppt={value}"),
+            Self::Other(value) => write!(
+                f,
+                "{}{}{}",
+                value.key().to_ascii_lowercase(),
+                if value.value().is_some() { "=" } else { "" },
+                value.value().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl PartialEq for IdentityParameter {
+    fn eq(&self, other: &IdentityParameter) -> bool {
+        match (self, other) {
+            (Self::Alg(a), Self::Alg(b)) | (Self::Ppt(a), Self::Ppt(b)) => a == b,
+            (Self::Other(a), Self::Other(b)) => {
+                a.key().eq_ignore_ascii_case(b.key()) && a.value() == b.value()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for IdentityParameter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IdentityParameter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_string().cmp(&other.to_string())
+    }
+}
+
+impl Hash for IdentityParameter {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().to_ascii_lowercase().hash(state);
+        self.value().hash(state);
+    }
+}
+
+impl From<GenericParameter<TokenString>> for IdentityParameter {
+    fn from(value: GenericParameter<TokenString>) -> Self {
+        match (value.key().to_ascii_lowercase().as_str(), value.value()) {
+            ("alg", Some(alg)) => Self::Alg(alg.to_string()),
+            ("ppt", Some(ppt)) => Self::Ppt(ppt.to_string()),
+            _ => Self::Other(value),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{Parser, combinator::map, error::context};
+
+    use crate::{
+        common::{generic_parameter::parser::generic_param, identity_parameter::IdentityParameter},
+        parser::ParserResult,
+    };
+
+    pub(crate) fn ident_info_params(input: &str) -> ParserResult<&str, IdentityParameter> {
+        context("ident_info_params", map(generic_param, Into::into)).parse(input)
+    }
+}
@@ -0,0 +1,128 @@
+use itertools::join;
+use std::hash::Hash;
+
+use crate::common::value_collection::ValueCollection;
+use crate::utils::compare_vectors;
+use crate::{GenericParameter, SipError, TokenString};
+
+/// Representation of the list of info packages from a `RecvInfoHeader`.
+///
+/// This is usable as an iterator.
+pub type InfoPackages = ValueCollection<InfoPackage>;
+
+/// Representation of an info package contained in an `Info-Package` or `Recv-Info` header.
+///
+/// [[RFC6086, Section 7](https://datatracker.ietf.org/doc/html/rfc6086#section-7)]
+#[derive(Clone, Debug, Eq)]
+pub struct InfoPackage {
+    name: TokenString,
+    parameters: Vec<GenericParameter<TokenString>>,
+}
+
+impl InfoPackage {
+    /// Create an `InfoPackage`.
+    pub fn new(name: TokenString, parameters: Vec<GenericParameter<TokenString>>) -> Self {
+        Self { name, parameters }
+    }
+
+    /// Get the name of the info package.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Get a reference to the parameters of the info package.
+    pub fn parameters(&self) -> &Vec<GenericParameter<TokenString>> {
+        &self.parameters
+    }
+}
+
+impl std::fmt::Display for InfoPackage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.name.to_ascii_lowercase(),
+            if self.parameters.is_empty() { "" } else { ";" },
+            join(&self.parameters, ";")
+        )
+    }
+}
+
+impl PartialEq for InfoPackage {
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name)
+            && compare_vectors(self.parameters(), other.parameters())
+    }
+}
+
+impl PartialEq<str> for InfoPackage {
+    fn eq(&self, other: &str) -> bool {
+        self.name.eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<&str> for InfoPackage {
+    fn eq(&self, other: &&str) -> bool {
+        self.name.eq_ignore_ascii_case(other)
+    }
+}
+
+impl Hash for InfoPackage {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.to_ascii_lowercase().hash(state);
+        let mut sorted_params = self.parameters.clone();
+        sorted_params.sort();
+        sorted_params.hash(state);
+    }
+}
+
+impl TryFrom<&str> for InfoPackage {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(InfoPackage::new(TokenString::try_from(value)?, vec![]))
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        combinator::map,
+        error::context,
+        multi::many0,
+        sequence::{pair, preceded},
+    };
+
+    use crate::{
+        InfoPackage,
+        common::generic_parameter::parser::generic_param,
+        parser::{ParserResult, semi, token},
+    };
+
+    pub(crate) fn info_package_type(input: &str) -> ParserResult<&str, InfoPackage> {
+        context(
+            "info_package_type",
+            map(
+                pair(token, many0(preceded(semi, generic_param))),
+                |(name, params)| InfoPackage::new(name, params),
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info_package() {
+        let (rest, package) = parser::info_package_type("Foo;bar=baz").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(package.name(), "Foo");
+        assert_eq!(package, "foo");
+        assert_eq!(package.parameters().len(), 1);
+        assert_eq!(package.to_string(), "foo;bar=baz");
+        assert_eq!(InfoPackage::try_from("DTMF").unwrap(), "dtmf");
+    }
+}
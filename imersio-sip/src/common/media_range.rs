@@ -12,6 +12,16 @@ impl MediaRange {
     pub(crate) fn new(r#type: TokenString, subtype: TokenString) -> Self {
         MediaRange { r#type, subtype }
     }
+
+    /// Get the type of the media range.
+    pub fn r#type(&self) -> &str {
+        self.r#type.as_str()
+    }
+
+    /// Get the subtype of the media range.
+    pub fn subtype(&self) -> &str {
+        self.subtype.as_str()
+    }
 }
 
 pub(crate) mod parser {
@@ -1,9 +1,11 @@
 use itertools::join;
+use nom_language::error::convert_error;
 use std::hash::Hash;
 use std::ops::Deref;
 
 use crate::MediaParameter;
 use crate::MediaRange;
+use crate::SipError;
 use crate::utils::compare_vectors;
 
 /// Representation of a media type contained in a `ContentTypeHeader`.
@@ -63,6 +65,29 @@ impl Hash for MediaType {
     }
 }
 
+impl TryFrom<&str> for MediaType {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match parser::media_type(value) {
+            Ok((rest, media_type)) => {
+                if !rest.is_empty() {
+                    Err(SipError::RemainingUnparsedData(rest.to_string()))
+                } else {
+                    Ok(media_type)
+                }
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                Err(SipError::InvalidMediaType(convert_error(value, e)))
+            }
+            Err(nom::Err::Incomplete(_)) => Err(SipError::InvalidMediaType(format!(
+                "Incomplete media type `{}`",
+                value
+            ))),
+        }
+    }
+}
+
 pub(crate) mod parser {
     use nom::{
         Parser,
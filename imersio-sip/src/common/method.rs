@@ -47,13 +47,33 @@ pub enum Method {
     Bye,
     /// CANCEL method.
     Cancel,
+    /// INFO method.
+    ///
+    /// [[RFC6086, Section 4](https://datatracker.ietf.org/doc/html/rfc6086#section-4)]
+    Info,
     /// INVITE method.
     #[default]
     Invite,
+    /// MESSAGE method.
+    ///
+    /// [[RFC3428, Section 4](https://datatracker.ietf.org/doc/html/rfc3428#section-4)]
+    Message,
     /// OPTIONS method.
     Options,
+    /// PRACK method.
+    ///
+    /// [[RFC3262, Section 7.1](https://datatracker.ietf.org/doc/html/rfc3262#section-7.1)]
+    Prack,
+    /// PUBLISH method.
+    ///
+    /// [[RFC3903, Section 4](https://datatracker.ietf.org/doc/html/rfc3903#section-4)]
+    Publish,
     /// REGISTER method.
     Register,
+    /// UPDATE method.
+    ///
+    /// [[RFC3311, Section 5](https://datatracker.ietf.org/doc/html/rfc3311#section-5)]
+    Update,
     /// Any other method.
     Other(TokenString),
 }
@@ -65,9 +85,14 @@ impl Method {
             "ACK" => Self::Ack,
             "BYE" => Self::Bye,
             "CANCEL" => Self::Cancel,
+            "INFO" => Self::Info,
             "INVITE" => Self::Invite,
+            "MESSAGE" => Self::Message,
             "OPTIONS" => Self::Options,
+            "PRACK" => Self::Prack,
+            "PUBLISH" => Self::Publish,
             "REGISTER" => Self::Register,
+            "UPDATE" => Self::Update,
             _ => Self::Other(TokenString::new(method)),
         }
     }
@@ -78,9 +103,14 @@ impl Method {
             Self::Ack => "ACK",
             Self::Bye => "BYE",
             Self::Cancel => "CANCEL",
+            Self::Info => "INFO",
             Self::Invite => "INVITE",
+            Self::Message => "MESSAGE",
             Self::Options => "OPTIONS",
+            Self::Prack => "PRACK",
+            Self::Publish => "PUBLISH",
             Self::Register => "REGISTER",
+            Self::Update => "UPDATE",
             Self::Other(value) => value.as_str(),
         }
     }
@@ -168,21 +198,46 @@ pub(crate) mod parser {
         map(token, Method::Other).parse(input)
     }
 
+    #[inline]
+    fn info_method(input: &str) -> ParserResult<&str, Method> {
+        value(Method::Info, tag("INFO")).parse(input)
+    }
+
     #[inline]
     fn invite_method(input: &str) -> ParserResult<&str, Method> {
         value(Method::Invite, tag("INVITE")).parse(input)
     }
 
+    #[inline]
+    fn message_method(input: &str) -> ParserResult<&str, Method> {
+        value(Method::Message, tag("MESSAGE")).parse(input)
+    }
+
     #[inline]
     fn options_method(input: &str) -> ParserResult<&str, Method> {
         value(Method::Options, tag("OPTIONS")).parse(input)
     }
 
+    #[inline]
+    fn prack_method(input: &str) -> ParserResult<&str, Method> {
+        value(Method::Prack, tag("PRACK")).parse(input)
+    }
+
+    #[inline]
+    fn publish_method(input: &str) -> ParserResult<&str, Method> {
+        value(Method::Publish, tag("PUBLISH")).parse(input)
+    }
+
     #[inline]
     fn register_method(input: &str) -> ParserResult<&str, Method> {
         value(Method::Register, tag("REGISTER")).parse(input)
     }
 
+    #[inline]
+    fn update_method(input: &str) -> ParserResult<&str, Method> {
+        value(Method::Update, tag("UPDATE")).parse(input)
+    }
+
     pub(crate) fn method(input: &str) -> ParserResult<&str, Method> {
         context(
             "method",
@@ -193,6 +248,11 @@ pub(crate) mod parser {
                 bye_method,
                 cancel_method,
                 register_method,
+                publish_method,
+                message_method,
+                info_method,
+                update_method,
+                prack_method,
                 extension_method,
             )),
         )
@@ -215,6 +275,12 @@ mod test {
     fn test_valid_method() {
         assert!(Method::try_from("INVITE").is_ok_and(|method| method == Method::Invite));
         assert!(Method::try_from("CANCEL").is_ok_and(|method| method == Method::Cancel));
+        assert!(Method::try_from("PUBLISH").is_ok_and(|method| method == Method::Publish));
+        assert!(Method::try_from("MESSAGE").is_ok_and(|method| method == Method::Message));
+        assert!(Method::try_from("INFO").is_ok_and(|method| method == Method::Info));
+        assert!(Method::try_from("UPDATE").is_ok_and(|method| method == Method::Update));
+        assert!(Method::try_from("PRACK").is_ok_and(|method| method == Method::Prack));
+        assert_eq!(Method::Message.to_string(), "MESSAGE");
         assert_eq!(Method::Invite.as_str(), "INVITE");
     }
 
@@ -11,19 +11,29 @@ pub mod authentication_info;
 pub mod call_id;
 pub mod call_info;
 pub mod call_info_parameter;
+pub mod caller_preference;
 pub mod challenge;
 pub mod contact;
 pub mod contact_parameter;
 pub mod content_encoding;
 pub mod content_language;
 pub mod credentials;
+pub mod directive;
 pub mod disposition_parameter;
 pub mod disposition_type;
+pub mod diversion;
+pub mod diversion_parameter;
+pub mod diversion_reason;
 pub mod domain_uri;
 pub mod error_uri;
+pub mod feature_tag;
 pub mod from_parameter;
 pub mod generic_parameter;
 pub mod handling;
+pub mod history_info;
+pub mod history_info_parameter;
+pub mod identity_parameter;
+pub mod info_package;
 pub mod media_parameter;
 pub mod media_range;
 pub mod media_type;
@@ -34,7 +44,11 @@ pub mod option_tag;
 pub mod priority;
 pub mod product;
 pub mod protocol;
+pub mod q850;
 pub mod reason;
+pub mod reason_parameter;
+pub mod reason_protocol;
+pub mod reason_value;
 pub mod retry_parameter;
 pub mod route;
 pub mod server_value;
@@ -27,6 +27,10 @@ impl NameAddress {
     pub fn uri(&self) -> &Uri {
         &self.uri
     }
+
+    pub(crate) fn set_uri(&mut self, uri: Uri) {
+        self.uri = uri;
+    }
 }
 
 impl std::fmt::Display for NameAddress {
@@ -0,0 +1,84 @@
+//! Mapping between ITU-T Q.850 cause values and SIP status codes.
+
+use crate::StatusCode;
+
+/// Map a Q.850 cause value to the SIP status code of the response to send for a call released
+/// with this cause.
+///
+/// Cause values that are not listed in the mapping table, such as 16 (Normal call clearing), do
+/// not have a corresponding status code.
+///
+/// [[RFC3398, Section 8.2.6.1](https://datatracker.ietf.org/doc/html/rfc3398#section-8.2.6.1)]
+pub fn q850_cause_to_status_code(cause: u8) -> Option<StatusCode> {
+    let status_code = match cause {
+        1..=3 | 26 => StatusCode::NOT_FOUND,
+        17 => StatusCode::BUSY_HERE,
+        18 => StatusCode::REQUEST_TIMEOUT,
+        19 | 20 | 31 => StatusCode::TEMPORARILY_UNAVAILABLE,
+        21 | 55 | 57 | 87 => StatusCode::FORBIDDEN,
+        22 | 23 => StatusCode::GONE,
+        27 => StatusCode::BAD_GATEWAY,
+        28 => StatusCode::ADDRESS_INCOMPLETE,
+        29 | 79 => StatusCode::NOT_IMPLEMENTED,
+        34 | 38 | 41 | 42 | 47 | 58 | 88 => StatusCode::SERVICE_UNAVAILABLE,
+        65 | 70 => StatusCode::NOT_ACCEPTABLE_HERE,
+        102 => StatusCode::SERVER_TIMEOUT,
+        111 | 127 => StatusCode::SERVER_INTERNAL_ERROR,
+        _ => return None,
+    };
+    Some(status_code)
+}
+
+/// Map a SIP status code to the Q.850 cause value to use when releasing the call on the ISUP
+/// side.
+///
+/// Only failure status codes listed in the mapping table have a corresponding cause value.
+///
+/// [[RFC3398, Section 7.2.4.1](https://datatracker.ietf.org/doc/html/rfc3398#section-7.2.4.1)]
+pub fn status_code_to_q850_cause(status_code: &StatusCode) -> Option<u8> {
+    let cause = match status_code.code() {
+        400 | 481 | 500 | 503 => 41,
+        401 | 402 | 403 | 407 | 603 => 21,
+        404 | 485 | 604 => 1,
+        405 => 63,
+        406 | 415 | 501 => 79,
+        408 | 504 => 102,
+        410 => 22,
+        413 | 414 | 416 | 420 | 421 | 423 | 505 | 513 => 127,
+        480 => 18,
+        482 | 483 => 25,
+        484 => 28,
+        486 | 600 => 17,
+        502 => 38,
+        606 => 58,
+        _ => return None,
+    };
+    Some(cause)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_q850_cause_to_status_code() {
+        assert_eq!(q850_cause_to_status_code(17), Some(StatusCode::BUSY_HERE));
+        assert_eq!(q850_cause_to_status_code(1), Some(StatusCode::NOT_FOUND));
+        assert_eq!(
+            q850_cause_to_status_code(34),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(q850_cause_to_status_code(16), None);
+    }
+
+    #[test]
+    fn test_status_code_to_q850_cause() {
+        assert_eq!(status_code_to_q850_cause(&StatusCode::BUSY_HERE), Some(17));
+        assert_eq!(status_code_to_q850_cause(&StatusCode::DECLINE), Some(21));
+        assert_eq!(
+            status_code_to_q850_cause(&StatusCode::TEMPORARILY_UNAVAILABLE),
+            Some(18)
+        );
+        assert_eq!(status_code_to_q850_cause(&StatusCode::OK), None);
+    }
+}
@@ -299,6 +299,9 @@ reasons! {
     /// 410 Gone
     /// [[RFC3261, Section 21.4.10](https://datatracker.ietf.org/doc/html/rfc3261#section-21.4.10)]
     (410, GONE, "Gone"),
+    /// 412 Conditional Request Failed
+    /// [[RFC3903, Section 11.2.1](https://datatracker.ietf.org/doc/html/rfc3903#section-11.2.1)]
+    (412, CONDITIONAL_REQUEST_FAILED, "Conditional Request Failed"),
     /// 413 Request Entity Too Large
     /// [[RFC3261, Section 21.4.11](https://datatracker.ietf.org/doc/html/rfc3261#section-21.4.11)]
     (413, REQUEST_ENTITY_TOO_LARGE, "Request Entity Too Large"),
@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+
+use crate::common::generic_parameter::generic_parameter_display;
+use crate::{GenericParameter, TokenString};
+
+/// Representation of a parameter of a value of a `Reason` header.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum ReasonParameter {
+    /// A `cause` parameter.
+    Cause(String),
+    /// A `text` parameter.
+    Text(String),
+    /// Any other parameter.
+    Other(GenericParameter<TokenString>),
+}
+
+impl ReasonParameter {
+    /// Get the key of the parameter.
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Cause(_) => "cause",
+            Self::Text(_) => "text",
+            Self::Other(value) => value.key(),
+        }
+    }
+
+    /// Get the value of the parameter.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Self::Cause(value) | Self::Text(value) => Some(value),
+            Self::Other(value) => value.value(),
+        }
+    }
+
+    /// Get the cause value of the parameter if this is a `cause` parameter.
+    pub fn cause(&self) -> Option<u16> {
+        match self {
+            Self::Cause(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the text value of the parameter if this is a `text` parameter.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ReasonParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(value) => write!(f, r#"text="{}""#, value),
+            _ => generic_parameter_display(self.key(), self.value(), f),
+        }
+    }
+}
+
+impl PartialOrd for ReasonParameter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ReasonParameter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.key().cmp(other.key()) {
+            Ordering::Equal => self.value().cmp(&other.value()),
+            ord => ord,
+        }
+    }
+}
+
+impl From<GenericParameter<TokenString>> for ReasonParameter {
+    fn from(value: GenericParameter<TokenString>) -> Self {
+        Self::Other(value)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        combinator::{map, recognize},
+        error::context,
+        multi::many1,
+        sequence::separated_pair,
+    };
+
+    use crate::{
+        ReasonParameter,
+        common::generic_parameter::parser::generic_param,
+        parser::{ParserResult, digit, equal, quoted_string},
+    };
+
+    fn protocol_cause(input: &str) -> ParserResult<&str, ReasonParameter> {
+        map(
+            separated_pair(tag_no_case("cause"), equal, recognize(many1(digit))),
+            |(_, value)| ReasonParameter::Cause(value.to_string()),
+        )
+        .parse(input)
+    }
+
+    fn reason_text(input: &str) -> ParserResult<&str, ReasonParameter> {
+        map(
+            separated_pair(tag_no_case("text"), equal, quoted_string),
+            |(_, value)| ReasonParameter::Text(value.value()),
+        )
+        .parse(input)
+    }
+
+    pub(crate) fn reason_params(input: &str) -> ParserResult<&str, ReasonParameter> {
+        context(
+            "reason_params",
+            alt((protocol_cause, reason_text, map(generic_param, Into::into))),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_cause_parameter() {
+        let (rest, parameter) = parser::reason_params("cause=486").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(parameter.key(), "cause");
+        assert_eq!(parameter.cause(), Some(486));
+        assert_eq!(parameter.to_string(), "cause=486");
+    }
+
+    #[test]
+    fn test_reason_text_parameter() {
+        let (rest, parameter) = parser::reason_params(r#"TEXT="Busy Here""#).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(parameter.text(), Some("Busy Here"));
+        assert_eq!(parameter.to_string(), r#"text="Busy Here""#);
+    }
+
+    #[test]
+    fn test_reason_other_parameter() {
+        let (_, parameter) = parser::reason_params("location=LN").unwrap();
+        assert!(parameter.is_other());
+        let (_, parameter) = parser::reason_params("cause=abc").unwrap();
+        assert!(parameter.is_other());
+    }
+}
@@ -0,0 +1,101 @@
+use std::cmp::Ordering;
+use std::hash::Hash;
+
+use crate::{SipError, TokenString};
+
+/// Representation of the protocol of a value of a `Reason` header.
+///
+/// [[RFC3326, Section 2](https://datatracker.ietf.org/doc/html/rfc3326#section-2)]
+#[derive(Clone, Debug, Eq, derive_more::IsVariant)]
+pub enum ReasonProtocol {
+    /// The cause is a SIP status code.
+    Sip,
+    /// The cause is an ITU-T Q.850 cause value.
+    Q850,
+    /// Any extension value.
+    Other(TokenString),
+}
+
+impl ReasonProtocol {
+    pub(crate) fn new(protocol: TokenString) -> Self {
+        match protocol.to_ascii_uppercase().as_str() {
+            "SIP" => Self::Sip,
+            "Q.850" => Self::Q850,
+            _ => Self::Other(protocol),
+        }
+    }
+
+    /// Get the value of the reason protocol.
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Sip => "SIP",
+            Self::Q850 => "Q.850",
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for ReasonProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl PartialEq for ReasonProtocol {
+    fn eq(&self, other: &ReasonProtocol) -> bool {
+        self.value().eq_ignore_ascii_case(other.value())
+    }
+}
+
+impl PartialOrd for ReasonProtocol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ReasonProtocol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value()
+            .to_ascii_uppercase()
+            .cmp(&other.value().to_ascii_uppercase())
+    }
+}
+
+impl Hash for ReasonProtocol {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value().to_ascii_uppercase().hash(state);
+    }
+}
+
+impl TryFrom<&str> for ReasonProtocol {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(ReasonProtocol::new(TokenString::try_from(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_reason_protocol() {
+        assert_eq!(
+            ReasonProtocol::try_from("SIP").unwrap(),
+            ReasonProtocol::Sip
+        );
+        assert_eq!(
+            ReasonProtocol::try_from("sip").unwrap(),
+            ReasonProtocol::Sip
+        );
+        assert_eq!(
+            ReasonProtocol::try_from("q.850").unwrap(),
+            ReasonProtocol::Q850
+        );
+        assert_eq!(ReasonProtocol::Q850.to_string(), "Q.850");
+        assert!(ReasonProtocol::try_from("Preemption").unwrap().is_other());
+        assert_err!(ReasonProtocol::try_from("Q 850"));
+    }
+}
@@ -0,0 +1,209 @@
+use itertools::join;
+use std::hash::Hash;
+
+use crate::common::value_collection::ValueCollection;
+use crate::utils::compare_vectors;
+use crate::{
+    Reason, ReasonParameter, ReasonProtocol, StatusCode, q850_cause_to_status_code,
+    status_code_to_q850_cause,
+};
+
+/// Representation of the list of values from a `ReasonHeader`.
+///
+/// This is usable as an iterator.
+pub type ReasonValues = ValueCollection<ReasonValue>;
+
+/// Representation of a value contained in a `Reason` header.
+///
+/// [[RFC3326, Section 2](https://datatracker.ietf.org/doc/html/rfc3326#section-2)]
+#[derive(Clone, Debug, Eq)]
+pub struct ReasonValue {
+    protocol: ReasonProtocol,
+    parameters: Vec<ReasonParameter>,
+}
+
+impl ReasonValue {
+    /// Create a `ReasonValue`.
+    pub fn new(protocol: ReasonProtocol, parameters: Vec<ReasonParameter>) -> Self {
+        ReasonValue {
+            protocol,
+            parameters,
+        }
+    }
+
+    /// Create a `ReasonValue` with a SIP status code as cause.
+    pub fn from_status_code(status_code: &StatusCode) -> Self {
+        Self::new(
+            ReasonProtocol::Sip,
+            vec![ReasonParameter::Cause(status_code.code().to_string())],
+        )
+    }
+
+    /// Create a `ReasonValue` with the status code of a SIP response reason as cause, and its
+    /// phrase as text.
+    pub fn from_reason(reason: &Reason) -> Self {
+        Self::new(
+            ReasonProtocol::Sip,
+            vec![
+                ReasonParameter::Cause(reason.status().code().to_string()),
+                ReasonParameter::Text(reason.phrase().to_string()),
+            ],
+        )
+    }
+
+    /// Create a `ReasonValue` with a Q.850 cause value.
+    pub fn from_q850_cause(cause: u8) -> Self {
+        Self::new(
+            ReasonProtocol::Q850,
+            vec![ReasonParameter::Cause(cause.to_string())],
+        )
+    }
+
+    /// Get a reference to the protocol of the reason.
+    pub fn protocol(&self) -> &ReasonProtocol {
+        &self.protocol
+    }
+
+    /// Get a reference to the parameters of the reason.
+    pub fn parameters(&self) -> &Vec<ReasonParameter> {
+        &self.parameters
+    }
+
+    /// Get the value of the `cause` parameter of the reason.
+    pub fn cause(&self) -> Option<u16> {
+        self.parameters.iter().find_map(|p| p.cause())
+    }
+
+    /// Get the value of the `text` parameter of the reason.
+    pub fn text(&self) -> Option<&str> {
+        self.parameters.iter().find_map(|p| p.text())
+    }
+
+    /// Get the SIP status code of the reason, if its protocol is SIP.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self.protocol {
+            ReasonProtocol::Sip => self
+                .cause()
+                .and_then(|code| StatusCode::try_from(code).ok()),
+            _ => None,
+        }
+    }
+
+    /// Get the Q.850 cause value of the reason, if its protocol is Q.850.
+    pub fn q850_cause(&self) -> Option<u8> {
+        match self.protocol {
+            ReasonProtocol::Q850 => self.cause().and_then(|cause| u8::try_from(cause).ok()),
+            _ => None,
+        }
+    }
+
+    /// Get the SIP status code of the reason, mapping its Q.850 cause value if needed.
+    pub fn to_status_code(&self) -> Option<StatusCode> {
+        self.status_code()
+            .or_else(|| self.q850_cause().and_then(q850_cause_to_status_code))
+    }
+
+    /// Get the Q.850 cause value of the reason, mapping its SIP status code if needed.
+    pub fn to_q850_cause(&self) -> Option<u8> {
+        self.q850_cause().or_else(|| {
+            self.status_code()
+                .as_ref()
+                .and_then(status_code_to_q850_cause)
+        })
+    }
+}
+
+impl std::fmt::Display for ReasonValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.protocol,
+            if self.parameters.is_empty() { "" } else { ";" },
+            join(&self.parameters, ";")
+        )
+    }
+}
+
+impl PartialEq for ReasonValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.protocol == other.protocol && compare_vectors(self.parameters(), other.parameters())
+    }
+}
+
+impl Hash for ReasonValue {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.protocol.hash(state);
+        let mut sorted_params = self.parameters.clone();
+        sorted_params.sort();
+        sorted_params.hash(state);
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        combinator::map,
+        error::context,
+        multi::many0,
+        sequence::{pair, preceded},
+    };
+
+    use crate::{
+        ReasonProtocol, ReasonValue,
+        common::reason_parameter::parser::reason_params,
+        parser::{ParserResult, semi, token},
+    };
+
+    pub(crate) fn reason_value(input: &str) -> ParserResult<&str, ReasonValue> {
+        context(
+            "reason_value",
+            map(
+                pair(token, many0(preceded(semi, reason_params))),
+                |(protocol, params)| ReasonValue::new(ReasonProtocol::new(protocol), params),
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_value_sip() {
+        let (rest, value) = parser::reason_value(r#"SIP;cause=486;text="Busy Here""#).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(value.protocol(), &ReasonProtocol::Sip);
+        assert_eq!(value.status_code(), Some(StatusCode::BUSY_HERE));
+        assert_eq!(value.q850_cause(), None);
+        assert_eq!(value.to_q850_cause(), Some(17));
+        assert_eq!(value.text(), Some("Busy Here"));
+    }
+
+    #[test]
+    fn test_reason_value_q850() {
+        let (_, value) = parser::reason_value("Q.850;cause=17").unwrap();
+        assert_eq!(value.protocol(), &ReasonProtocol::Q850);
+        assert_eq!(value.q850_cause(), Some(17));
+        assert_eq!(value.status_code(), None);
+        assert_eq!(value.to_status_code(), Some(StatusCode::BUSY_HERE));
+    }
+
+    #[test]
+    fn test_reason_value_constructors() {
+        assert_eq!(
+            ReasonValue::from_status_code(&StatusCode::DECLINE).to_string(),
+            "SIP;cause=603"
+        );
+        assert_eq!(
+            ReasonValue::from_reason(&Reason::DECLINE).to_string(),
+            r#"SIP;cause=603;text="Decline""#
+        );
+        assert_eq!(
+            ReasonValue::from_q850_cause(16).to_string(),
+            "Q.850;cause=16"
+        );
+    }
+}
@@ -1,12 +1,13 @@
 use itertools::join;
 use std::hash::Hash;
 
-use crate::NameAddress;
 use crate::common::value_collection::ValueCollection;
 use crate::utils::compare_vectors;
 use crate::{GenericParameter, TokenString};
+use crate::{NameAddress, Uri};
 
-/// Representation of the list of routes from a `RecordRouteHeader`.
+/// Representation of the list of routes from a `RouteHeader`, a `RecordRouteHeader`, a `PathHeader`
+/// or a `ServiceRouteHeader`.
 ///
 /// This is usable as an iterator.
 pub type Routes = ValueCollection<Route>;
@@ -23,7 +24,8 @@ impl Routes {
     }
 }
 
-/// Representation of a route contained in a `Record-Route` header.
+/// Representation of a route contained in a `Route`, `Record-Route`, `Path` or `Service-Route`
+/// header.
 #[derive(Clone, Debug, Eq)]
 pub struct Route {
     name_addr: NameAddress,
@@ -64,6 +66,18 @@ impl std::fmt::Display for Route {
     }
 }
 
+impl From<NameAddress> for Route {
+    fn from(value: NameAddress) -> Self {
+        Self::new(value, vec![])
+    }
+}
+
+impl From<Uri> for Route {
+    fn from(value: Uri) -> Self {
+        Self::new(value.into(), vec![])
+    }
+}
+
 impl PartialEq for Route {
     fn eq(&self, other: &Self) -> bool {
         self.name_addr == other.name_addr && compare_vectors(self.parameters(), other.parameters())
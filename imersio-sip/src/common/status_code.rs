@@ -374,6 +374,7 @@ pub(crate) mod parser {
             value(StatusCode::PROXY_AUTHENTICATION_REQUIRED, tag("407")),
             value(StatusCode::REQUEST_TIMEOUT, tag("408")),
             value(StatusCode::GONE, tag("410")),
+            value(StatusCode::CONDITIONAL_REQUEST_FAILED, tag("412")),
             value(StatusCode::REQUEST_ENTITY_TOO_LARGE, tag("413")),
             value(StatusCode::REQUEST_URI_TOO_LONG, tag("414")),
             value(StatusCode::UNSUPPORTED_MEDIA_TYPE, tag("415")),
@@ -1,10 +1,10 @@
 use itertools::join;
 use std::hash::Hash;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use crate::common::value_collection::ValueCollection;
 use crate::utils::compare_vectors;
-use crate::{Host, Protocol, ViaParameter};
+use crate::{Host, Protocol, TokenString, Transport, ViaParameter};
 
 /// Representation of the list of vias from a `ViaHeader`.
 ///
@@ -35,6 +35,19 @@ impl Via {
         }
     }
 
+    /// Create the via of a SIP/2.0 element sending a request over the given transport from the
+    /// given sent-by, identifying the transaction with the given branch.
+    ///
+    /// [[RFC3261, Section 18.1.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.1.1)]
+    pub fn with_branch(transport: Transport, host: Host, port: Option<u16>, branch: &str) -> Self {
+        Self::new(
+            Protocol::new(TokenString::new("SIP"), TokenString::new("2.0"), transport),
+            host,
+            port,
+            vec![ViaParameter::Branch(branch.to_string())],
+        )
+    }
+
     /// Get a reference to the protocol contained in the via.
     pub fn protocol(&self) -> &Protocol {
         &self.protocol
@@ -104,6 +117,28 @@ impl Via {
     pub fn rport(&self) -> Option<u16> {
         self.parameters.iter().find_map(|p| p.rport())
     }
+
+    /// Record the source address a request has been received from in the via, as done by a
+    /// server transport on the top via of a received request.
+    ///
+    /// The `received` parameter is added when the host of the via is not the source IP address,
+    /// or when the via contains an `rport` parameter, that is then filled with the source port.
+    ///
+    /// [[RFC3261, Section 18.2.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.2.1)]
+    /// [[RFC3581, Section 4](https://datatracker.ietf.org/doc/html/rfc3581#section-4)]
+    pub fn set_received(&mut self, source: SocketAddr) {
+        let has_rport = self.has_rport();
+        self.parameters.retain(|p| !p.is_received());
+        for parameter in self.parameters.iter_mut() {
+            if parameter.is_r_port() {
+                *parameter = ViaParameter::RPort(Some(source.port().to_string()));
+            }
+        }
+        if has_rport || self.host.ip() != Some(&source.ip()) {
+            self.parameters
+                .push(ViaParameter::Received(source.ip().to_string()));
+        }
+    }
 }
 
 impl std::fmt::Display for Via {
@@ -0,0 +1,390 @@
+//! Content codings of message bodies.
+//!
+//! The bodies encoded with the `gzip` or `deflate` content codings are decoded, and the bodies
+//! can be compressed according to the Accept-Encoding header of the peer. The content codings
+//! other than `identity` are only supported when the `compression` feature is enabled.
+//!
+//! [[RFC3261, Section 20.12](https://datatracker.ietf.org/doc/html/rfc3261#section-20.12)]
+
+use std::borrow::Cow;
+
+use crate::{
+    AcceptEncoding, AcceptEncodingHeader, ContentEncoding, ContentEncodingHeader,
+    ContentLengthHeader, Header, SipError, TokenString,
+};
+
+/// Representation of a content coding that can be applied to a message body.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum ContentCoding {
+    /// The `gzip` content coding.
+    Gzip,
+    /// The `deflate` content coding, ie. the zlib format.
+    Deflate,
+}
+
+impl ContentCoding {
+    /// Default maximum size of a decoded body, protecting against the bodies that expand to huge
+    /// sizes once decoded.
+    pub const DEFAULT_DECODED_SIZE_LIMIT: usize = 1 << 20;
+
+    /// The content codings supported for encoding and decoding, by order of preference.
+    #[cfg(feature = "compression")]
+    const SUPPORTED: &'static [ContentCoding] = &[Self::Gzip, Self::Deflate];
+    #[cfg(not(feature = "compression"))]
+    const SUPPORTED: &'static [ContentCoding] = &[];
+
+    fn new(encoding: &str) -> Option<Self> {
+        match encoding.to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "deflate" => Some(Self::Deflate),
+            _ => None,
+        }
+    }
+
+    /// Get the value of the content coding.
+    pub fn value(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    /// Tell whether the content coding is supported, ie. the `compression` feature is enabled.
+    pub fn is_supported(&self) -> bool {
+        Self::SUPPORTED.contains(self)
+    }
+
+    /// Select the content coding to apply to a body sent to a peer, according to the q-values of
+    /// its Accept-Encoding header.
+    ///
+    /// `None` is returned if the `identity` coding is preferred, or if no supported content
+    /// coding is acceptable.
+    pub fn select(accept_encoding: &AcceptEncodingHeader) -> Option<Self> {
+        let encodings: Vec<&AcceptEncoding> = accept_encoding.encodings().iter().collect();
+        select(&encodings, Self::SUPPORTED.iter().map(Self::value)).and_then(Self::new)
+    }
+
+    #[cfg(feature = "compression")]
+    fn encode(&self, body: &[u8]) -> Result<Vec<u8>, SipError> {
+        use flate2::Compression;
+        use flate2::write::{GzEncoder, ZlibEncoder};
+        use std::io::Write;
+
+        let error = |e: std::io::Error| SipError::InvalidContentEncoding(e.to_string());
+        match self {
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], Compression::default());
+                encoder.write_all(body).map_err(error)?;
+                encoder.finish().map_err(error)
+            }
+            Self::Deflate => {
+                let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+                encoder.write_all(body).map_err(error)?;
+                encoder.finish().map_err(error)
+            }
+        }
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn encode(&self, _body: &[u8]) -> Result<Vec<u8>, SipError> {
+        Err(self.unsupported())
+    }
+
+    /// Decode a body, failing if it is larger than the given limit once decoded.
+    #[cfg(feature = "compression")]
+    fn decode(&self, body: &[u8], limit: usize) -> Result<Vec<u8>, SipError> {
+        use flate2::read::{GzDecoder, ZlibDecoder};
+        use std::io::Read;
+
+        // One more byte than the limit is read to detect the bodies exceeding it.
+        let max_read = u64::try_from(limit).unwrap_or(u64::MAX).saturating_add(1);
+        let mut decoded = vec![];
+        match self {
+            Self::Gzip => GzDecoder::new(body)
+                .take(max_read)
+                .read_to_end(&mut decoded),
+            Self::Deflate => ZlibDecoder::new(body)
+                .take(max_read)
+                .read_to_end(&mut decoded),
+        }
+        .map_err(|e| {
+            SipError::InvalidContentEncoding(format!("invalid {} body: {}", self.value(), e))
+        })?;
+        if decoded.len() > limit {
+            return Err(SipError::InvalidContentEncoding(format!(
+                "{} body larger than {} bytes once decoded",
+                self.value(),
+                limit
+            )));
+        }
+        Ok(decoded)
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn decode(&self, _body: &[u8], _limit: usize) -> Result<Vec<u8>, SipError> {
+        Err(self.unsupported())
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn unsupported(&self) -> SipError {
+        SipError::InvalidContentEncoding(format!(
+            "unsupported content coding {} without the compression feature",
+            self.value()
+        ))
+    }
+}
+
+impl std::fmt::Display for ContentCoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl From<ContentCoding> for ContentEncoding {
+    fn from(value: ContentCoding) -> Self {
+        ContentEncoding::new(TokenString::new(value.value()))
+    }
+}
+
+/// Get the q-value given to a content coding by the values of Accept-Encoding headers.
+fn q(encodings: &[&AcceptEncoding], name: &str) -> Option<f32> {
+    encodings
+        .iter()
+        .find(|encoding| encoding.encoding().eq_ignore_ascii_case(name))
+        .or_else(|| encodings.iter().find(|encoding| encoding.encoding() == "*"))
+        .map(|encoding| encoding.q().unwrap_or(1.0))
+}
+
+/// Tell whether the `identity` content coding is acceptable according to the values of
+/// Accept-Encoding headers, ie. unless it is explicitly refused.
+pub(crate) fn is_identity_acceptable(encodings: &[&AcceptEncoding]) -> bool {
+    q(encodings, "identity").unwrap_or(0.001) > 0.0
+}
+
+/// Select, among the given content codings ordered by preference, the one with the highest
+/// q-value in the values of Accept-Encoding headers.
+///
+/// A content coding is preferred to the `identity` one having the same q-value. `None` is
+/// returned if the `identity` coding is preferred, or if none of the content codings is
+/// acceptable.
+pub(crate) fn select<'a>(
+    encodings: &[&AcceptEncoding],
+    codings: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let identity_q = q(encodings, "identity").unwrap_or(0.001);
+    codings
+        .into_iter()
+        .filter_map(|coding| q(encodings, coding).map(|q| (coding, q)))
+        .filter(|(_, q)| *q > 0.0 && *q >= identity_q)
+        .fold(None, |best: Option<(&str, f32)>, (coding, q)| match best {
+            Some((_, best_q)) if best_q >= q => best,
+            _ => Some((coding, q)),
+        })
+        .map(|(coding, _)| coding)
+}
+
+/// Decode the body of a SIP message according to its Content-Encoding header, failing if it is
+/// larger than the given limit once decoded.
+///
+/// The content codings are removed in the reverse order of the one in which they have been
+/// applied.
+pub(crate) fn decode<'a>(
+    headers: &[Header],
+    body: &'a [u8],
+    limit: usize,
+) -> Result<Cow<'a, [u8]>, SipError> {
+    let encodings: Vec<&ContentEncoding> = headers
+        .iter()
+        .filter_map(|header| match header {
+            Header::ContentEncoding(header) => Some(header.encodings().iter()),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let mut decoded = Cow::Borrowed(body);
+    for encoding in encodings.into_iter().rev() {
+        let name = encoding.to_string();
+        if name == "identity" {
+            continue;
+        }
+        let coding = ContentCoding::new(&name).ok_or_else(|| {
+            SipError::InvalidContentEncoding(format!("unsupported content coding {name}"))
+        })?;
+        decoded = Cow::Owned(coding.decode(&decoded, limit)?);
+    }
+    Ok(decoded)
+}
+
+/// Compress the body of a SIP message with the content coding preferred by the peer, and update
+/// its Content-Encoding and Content-Length headers.
+///
+/// Empty bodies and bodies that are already encoded are left untouched.
+pub(crate) fn encode(
+    headers: &mut Vec<Header>,
+    body: &mut Vec<u8>,
+    accept_encoding: &AcceptEncodingHeader,
+) -> Result<Option<ContentCoding>, SipError> {
+    if body.is_empty()
+        || headers
+            .iter()
+            .any(|header| matches!(header, Header::ContentEncoding(_)))
+    {
+        return Ok(None);
+    }
+    let Some(coding) = ContentCoding::select(accept_encoding) else {
+        return Ok(None);
+    };
+    *body = coding.encode(body)?;
+    crate::messages::replace_header(
+        headers,
+        Header::ContentEncoding(ContentEncodingHeader::from(ContentEncoding::from(coding))),
+    );
+    crate::messages::replace_header(
+        headers,
+        Header::ContentLength(ContentLengthHeader::from(body.len() as u32)),
+    );
+    Ok(Some(coding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Request;
+    use claims::{assert_err, assert_none};
+
+    fn accept_encoding(value: &str) -> AcceptEncodingHeader {
+        match Header::try_from(format!("Accept-Encoding: {value}").as_str()).unwrap() {
+            Header::AcceptEncoding(header) => header,
+            _ => unreachable!(),
+        }
+    }
+
+    fn notify(headers: &str, body: &[u8]) -> Request {
+        let mut request = Request::try_from(
+            format!("NOTIFY sip:alice@example.com SIP/2.0\r\n{headers}\r\n").as_str(),
+        )
+        .unwrap();
+        request.set_body(body);
+        request
+    }
+
+    #[test]
+    fn test_identity_bodies() {
+        let request = notify("Content-Encoding: identity\r\n", b"text");
+        assert_eq!(request.decoded_body().unwrap().as_ref(), b"text");
+        let request = notify("", b"text");
+        assert_eq!(request.decoded_body().unwrap().as_ref(), b"text");
+        let request = notify("Content-Encoding: br\r\n", b"text");
+        assert_err!(request.decoded_body());
+    }
+
+    #[test]
+    fn test_select_without_acceptable_coding() {
+        assert_none!(ContentCoding::select(&accept_encoding("")));
+        assert_none!(ContentCoding::select(&accept_encoding("identity")));
+        assert_none!(ContentCoding::select(&accept_encoding("br")));
+        assert_none!(ContentCoding::select(&accept_encoding(
+            "gzip;q=0, deflate;q=0"
+        )));
+        assert_none!(ContentCoding::select(&accept_encoding(
+            "gzip;q=0.5, identity"
+        )));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_select() {
+        assert_eq!(
+            ContentCoding::select(&accept_encoding("gzip")),
+            Some(ContentCoding::Gzip)
+        );
+        assert_eq!(
+            ContentCoding::select(&accept_encoding("gzip;q=0.5, deflate;q=0.8")),
+            Some(ContentCoding::Deflate)
+        );
+        assert_eq!(
+            ContentCoding::select(&accept_encoding("*;q=0.3, identity;q=0.2")),
+            Some(ContentCoding::Gzip)
+        );
+        assert_eq!(
+            ContentCoding::select(&accept_encoding("*, gzip;q=0")),
+            Some(ContentCoding::Deflate)
+        );
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn test_no_compression_without_feature() {
+        assert_none!(ContentCoding::select(&accept_encoding("gzip")));
+        let mut request = notify("", b"text");
+        assert_none!(request.encode_body(&accept_encoding("gzip")).unwrap());
+        assert_eq!(request.body(), b"text");
+        let request = notify("Content-Encoding: gzip\r\n", b"text");
+        assert_err!(request.decoded_body());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_encode_and_decode_body() {
+        let body = "<presence entity=\"sip:alice@example.com\"/>".repeat(50);
+        for (accept, coding) in [
+            ("gzip", ContentCoding::Gzip),
+            ("deflate", ContentCoding::Deflate),
+        ] {
+            let mut request = notify("Content-Type: application/pidf+xml\r\n", body.as_bytes());
+            assert_eq!(
+                request.encode_body(&accept_encoding(accept)).unwrap(),
+                Some(coding)
+            );
+            assert!(request.body().len() < body.len());
+            assert_eq!(
+                request.headers()[1].to_string(),
+                format!("Content-Encoding: {accept}")
+            );
+            assert_eq!(
+                request.headers()[2].to_string(),
+                format!("Content-Length: {}", request.body().len())
+            );
+            assert_eq!(request.decoded_body().unwrap().as_ref(), body.as_bytes());
+            assert_none!(request.encode_body(&accept_encoding(accept)).unwrap());
+        }
+
+        let request = notify("Content-Encoding: gzip\r\n", b"not gzip");
+        assert_err!(request.decoded_body());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decoded_size_limit() {
+        let body = vec![0u8; ContentCoding::DEFAULT_DECODED_SIZE_LIMIT + 1];
+        for coding in [ContentCoding::Gzip, ContentCoding::Deflate] {
+            let encoded = coding.encode(&body).unwrap();
+            assert!(encoded.len() < 10_000);
+            let request = notify(&format!("Content-Encoding: {coding}\r\n"), &encoded);
+            assert!(matches!(
+                request.decoded_body(),
+                Err(SipError::InvalidContentEncoding(_))
+            ));
+            assert_eq!(
+                request
+                    .decoded_body_with_limit(body.len())
+                    .unwrap()
+                    .as_ref(),
+                body.as_slice()
+            );
+            assert_err!(request.decoded_body_with_limit(body.len() - 1));
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decode_sdp_body() {
+        let sdp = "v=0\r\no=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\nt=0 0\r\n";
+        let gzipped = ContentCoding::Gzip.encode(sdp.as_bytes()).unwrap();
+        let request = notify(
+            "Content-Type: application/sdp\r\nContent-Encoding: gzip\r\n",
+            &gzipped,
+        );
+        assert_eq!(request.sdp().unwrap().unwrap().to_string(), sdp);
+    }
+}
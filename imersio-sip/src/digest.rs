@@ -0,0 +1,568 @@
+//! Digest authentication.
+//!
+//! The `DigestClient` answers the Digest challenges of the WWW-Authenticate and
+//! Proxy-Authenticate headers with the credentials of the Authorization and Proxy-Authorization
+//! headers, and the `DigestSecret` verifies these credentials and generates the
+//! Authentication-Info header of the response.
+//!
+//! [[RFC3261, Section 22.4](https://datatracker.ietf.org/doc/html/rfc3261#section-22.4)]
+//! [[RFC2617, Section 3.2](https://datatracker.ietf.org/doc/html/rfc2617#section-3.2)]
+//! [[RFC8760](https://datatracker.ietf.org/doc/html/rfc8760)]
+
+use md5::Md5;
+use sha2::{Digest, Sha256, Sha512_256};
+use std::collections::HashMap;
+
+use crate::common::wrapped_string::WrappedString;
+use crate::utils::constant_time_eq;
+use crate::{
+    Algorithm, AuthParameter, AuthenticationInfo, AuthenticationInfoHeader, AuthorizationHeader,
+    Challenge, Credentials, MessageQop, ProxyAuthenticateHeader, ProxyAuthorizationHeader, Request,
+    SipError, TokenString, WWWAuthenticateHeader,
+};
+
+/// Representation of a client answering Digest challenges on behalf of a user.
+///
+/// The nonce count of each nonce is kept so that the credentials computed for successive
+/// requests using the same nonce are not replayed.
+#[derive(Clone, Debug)]
+pub struct DigestClient {
+    username: String,
+    password: String,
+    nonce_counts: HashMap<String, u32>,
+}
+
+impl DigestClient {
+    /// Create a `DigestClient` for the given username and password.
+    pub fn new<S: Into<String>>(username: S, password: S) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            nonce_counts: HashMap::new(),
+        }
+    }
+
+    /// Get the username of the client.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Compute the Authorization header answering the challenge of a WWW-Authenticate header
+    /// received in a 401 (Unauthorized) response to the given request.
+    pub fn authorization_header(
+        &mut self,
+        header: &WWWAuthenticateHeader,
+        request: &Request,
+    ) -> Result<AuthorizationHeader, SipError> {
+        Ok(AuthorizationHeader::from(
+            self.credentials(header.challenge(), request)?,
+        ))
+    }
+
+    /// Compute the Proxy-Authorization header answering the challenge of a Proxy-Authenticate
+    /// header received in a 407 (Proxy Authentication Required) response to the given request.
+    pub fn proxy_authorization_header(
+        &mut self,
+        header: &ProxyAuthenticateHeader,
+        request: &Request,
+    ) -> Result<ProxyAuthorizationHeader, SipError> {
+        Ok(ProxyAuthorizationHeader::from(
+            self.credentials(header.challenge(), request)?,
+        ))
+    }
+
+    /// Compute the credentials answering a Digest challenge for the given request.
+    ///
+    /// The `auth` quality of protection is preferred to `auth-int` when both are offered.
+    pub fn credentials(
+        &mut self,
+        challenge: &Challenge,
+        request: &Request,
+    ) -> Result<Credentials, SipError> {
+        self.credentials_with_cnonce(challenge, request, &cnonce()?)
+    }
+
+    fn credentials_with_cnonce(
+        &mut self,
+        challenge: &Challenge,
+        request: &Request,
+        cnonce: &str,
+    ) -> Result<Credentials, SipError> {
+        if !challenge.is_digest() {
+            return Err(SipError::InvalidDigest(format!(
+                "unsupported authentication scheme {}",
+                challenge.scheme()
+            )));
+        }
+        let realm = challenge
+            .realm()
+            .ok_or_else(|| SipError::InvalidDigest("missing realm in challenge".into()))?;
+        let nonce = challenge
+            .nonce()
+            .ok_or_else(|| SipError::InvalidDigest("missing nonce in challenge".into()))?;
+        let algorithm = challenge.algorithm().cloned().unwrap_or(Algorithm::Md5);
+        let qop = match challenge.qop() {
+            None => None,
+            Some(qops) => Some(
+                qops.iter()
+                    .find(|qop| qop.is_auth())
+                    .or_else(|| qops.iter().find(|qop| qop.is_auth_int()))
+                    .cloned()
+                    .ok_or_else(|| {
+                        SipError::InvalidDigest(format!("unsupported qop options {qops}"))
+                    })?,
+            ),
+        };
+        let nonce_count = qop.as_ref().map(|_| {
+            let count = self.nonce_counts.entry(nonce.to_string()).or_insert(0);
+            *count += 1;
+            format!("{:08x}", count)
+        });
+        let cnonce = qop.as_ref().map(|_| cnonce);
+        let computation = DigestComputation {
+            algorithm: &algorithm,
+            username: &self.username,
+            realm,
+            nonce,
+            cnonce,
+            nonce_count: nonce_count.as_deref(),
+            qop: qop.as_ref(),
+            uri: request.uri().to_string(),
+        };
+        let ha1 = computation.ha1(&computation.base_ha1(&self.password)?)?;
+        let response = computation.response(&ha1, request.method().as_ref(), request.body())?;
+
+        let mut parameters = vec![
+            AuthParameter::Username(WrappedString::new_quoted(self.username.as_str())),
+            AuthParameter::Realm(WrappedString::new_quoted(realm)),
+            AuthParameter::Nonce(WrappedString::new_quoted(nonce)),
+            AuthParameter::DigestUri(request.uri().clone()),
+            AuthParameter::DResponse(WrappedString::new_quoted(response)),
+            AuthParameter::Algorithm(algorithm.clone()),
+        ];
+        if let Some(opaque) = challenge.opaque() {
+            parameters.push(AuthParameter::Opaque(WrappedString::new_quoted(opaque)));
+        }
+        if let (Some(qop), Some(nonce_count), Some(cnonce)) = (qop, nonce_count, cnonce) {
+            parameters.push(AuthParameter::Qop(qop));
+            parameters.push(AuthParameter::CNonce(WrappedString::new_quoted(cnonce)));
+            parameters.push(AuthParameter::NonceCount(WrappedString::new_not_wrapped(
+                TokenString::new(nonce_count),
+            )));
+        }
+        Ok(Credentials::Digest(parameters.into()))
+    }
+
+    /// Verify the `rspauth` value of an Authentication-Info header received in the response
+    /// to a request authenticated with the given credentials, providing mutual authentication.
+    pub fn verify_authentication_info(
+        &self,
+        header: &AuthenticationInfoHeader,
+        credentials: &Credentials,
+        response_body: &[u8],
+    ) -> Result<bool, SipError> {
+        let Some(rspauth) = header.response_auth() else {
+            return Ok(false);
+        };
+        let computation = DigestComputation::from_credentials(credentials)?;
+        let ha1 = computation.ha1(&computation.base_ha1(&self.password)?)?;
+        let expected = computation.response(&ha1, "", response_body)?;
+        Ok(digests_eq(&expected, rspauth))
+    }
+}
+
+/// Representation of the secret of a user, used to verify Digest credentials.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::IsVariant)]
+pub enum DigestSecret {
+    /// The password of the user.
+    Password(String),
+    /// The hash of the username, the realm and the password of the user, ie. the A1 value
+    /// without the session parameters, as it is usually stored by a server.
+    Ha1(String),
+}
+
+impl DigestSecret {
+    /// Compute the HA1 secret of a user for the given realm and algorithm.
+    pub fn ha1(
+        username: &str,
+        realm: &str,
+        password: &str,
+        algorithm: &Algorithm,
+    ) -> Result<Self, SipError> {
+        Ok(Self::Ha1(hash(
+            algorithm,
+            format!("{username}:{realm}:{password}").as_bytes(),
+        )?))
+    }
+
+    /// Verify the Digest credentials received in the given request.
+    ///
+    /// Only the `response` value is checked, the validity of the realm, the nonce, the nonce
+    /// count and the `uri` value is to be checked by the caller.
+    pub fn verify(&self, credentials: &Credentials, request: &Request) -> Result<bool, SipError> {
+        let computation = DigestComputation::from_credentials(credentials)?;
+        let dresponse = credentials
+            .dresponse()
+            .ok_or_else(|| SipError::InvalidDigest("missing response in credentials".into()))?;
+        let ha1 = computation.ha1(&self.base_ha1(&computation)?)?;
+        let expected = computation.response(&ha1, request.method().as_ref(), request.body())?;
+        Ok(digests_eq(&expected, dresponse))
+    }
+
+    /// Generate the Authentication-Info header to be added to the response to a request
+    /// successfully authenticated with the given credentials.
+    ///
+    /// The `rspauth` value is only present if the credentials have a qop value.
+    pub fn authentication_info_header(
+        &self,
+        credentials: &Credentials,
+        response_body: &[u8],
+        next_nonce: Option<&str>,
+    ) -> Result<AuthenticationInfoHeader, SipError> {
+        let computation = DigestComputation::from_credentials(credentials)?;
+        let mut infos = vec![];
+        if let Some(next_nonce) = next_nonce {
+            infos.push(AuthenticationInfo::NextNonce(WrappedString::new_quoted(
+                next_nonce,
+            )));
+        }
+        if let (Some(qop), Some(cnonce), Some(nonce_count)) =
+            (computation.qop, computation.cnonce, computation.nonce_count)
+        {
+            let ha1 = computation.ha1(&self.base_ha1(&computation)?)?;
+            let rspauth = computation.response(&ha1, "", response_body)?;
+            infos.push(AuthenticationInfo::Qop(qop.clone()));
+            infos.push(AuthenticationInfo::ResponseAuth(WrappedString::new_quoted(
+                rspauth,
+            )));
+            infos.push(AuthenticationInfo::CNonce(WrappedString::new_quoted(
+                cnonce,
+            )));
+            infos.push(AuthenticationInfo::NonceCount(
+                WrappedString::new_not_wrapped(TokenString::new(nonce_count)),
+            ));
+        }
+        Ok(AuthenticationInfoHeader::from(infos))
+    }
+
+    fn base_ha1(&self, computation: &DigestComputation) -> Result<String, SipError> {
+        match self {
+            Self::Password(password) => computation.base_ha1(password),
+            Self::Ha1(ha1) => Ok(ha1.to_ascii_lowercase()),
+        }
+    }
+}
+
+/// The values entering the computation of a Digest response.
+struct DigestComputation<'a> {
+    algorithm: &'a Algorithm,
+    username: &'a str,
+    realm: &'a str,
+    nonce: &'a str,
+    cnonce: Option<&'a str>,
+    nonce_count: Option<&'a str>,
+    qop: Option<&'a MessageQop>,
+    uri: String,
+}
+
+impl<'a> DigestComputation<'a> {
+    fn from_credentials(credentials: &'a Credentials) -> Result<Self, SipError> {
+        if !credentials.is_digest() {
+            return Err(SipError::InvalidDigest(format!(
+                "unsupported authentication scheme {}",
+                credentials.scheme()
+            )));
+        }
+        let missing =
+            |name: &str| SipError::InvalidDigest(format!("missing {name} in credentials"));
+        let uri = credentials
+            .digest_uri()
+            .ok_or_else(|| missing("uri"))?
+            .to_string();
+        let computation = Self {
+            algorithm: credentials.algorithm().unwrap_or(&Algorithm::Md5),
+            username: credentials.username().ok_or_else(|| missing("username"))?,
+            realm: credentials.realm().ok_or_else(|| missing("realm"))?,
+            nonce: credentials.nonce().ok_or_else(|| missing("nonce"))?,
+            cnonce: credentials.cnonce(),
+            nonce_count: credentials.nonce_count(),
+            qop: credentials.qop(),
+            uri,
+        };
+        if computation.qop.is_some()
+            && (computation.cnonce.is_none() || computation.nonce_count.is_none())
+        {
+            return Err(missing("cnonce or nc"));
+        }
+        if computation.algorithm.is_session() && computation.cnonce.is_none() {
+            return Err(missing("cnonce"));
+        }
+        Ok(computation)
+    }
+
+    /// Compute H(A1) without the session parameters.
+    fn base_ha1(&self, password: &str) -> Result<String, SipError> {
+        hash(
+            self.algorithm,
+            format!("{}:{}:{}", self.username, self.realm, password).as_bytes(),
+        )
+    }
+
+    /// Compute H(A1), applying the session parameters to the base H(A1) if needed.
+    fn ha1(&self, base_ha1: &str) -> Result<String, SipError> {
+        if self.algorithm.is_session() {
+            hash(
+                self.algorithm,
+                format!(
+                    "{}:{}:{}",
+                    base_ha1,
+                    self.nonce,
+                    self.cnonce.unwrap_or_default()
+                )
+                .as_bytes(),
+            )
+        } else {
+            Ok(base_ha1.to_string())
+        }
+    }
+
+    /// Compute the request digest, or the `rspauth` value when the method is empty.
+    fn response(&self, ha1: &str, method: &str, body: &[u8]) -> Result<String, SipError> {
+        let a2 = match self.qop {
+            Some(MessageQop::AuthInt) => {
+                format!("{}:{}:{}", method, self.uri, hash(self.algorithm, body)?)
+            }
+            _ => format!("{}:{}", method, self.uri),
+        };
+        let ha2 = hash(self.algorithm, a2.as_bytes())?;
+        let data = match (self.qop, self.nonce_count, self.cnonce) {
+            (Some(qop), Some(nonce_count), Some(cnonce)) => format!(
+                "{}:{}:{}:{}:{}:{}",
+                ha1, self.nonce, nonce_count, cnonce, qop, ha2
+            ),
+            _ => format!("{}:{}:{}", ha1, self.nonce, ha2),
+        };
+        hash(self.algorithm, data.as_bytes())
+    }
+}
+
+/// Compute the hexadecimal hash of some data with the given algorithm.
+fn hash(algorithm: &Algorithm, data: &[u8]) -> Result<String, SipError> {
+    let bytes = match algorithm {
+        Algorithm::Md5 | Algorithm::Md5Sess => Md5::digest(data).to_vec(),
+        Algorithm::Sha256 | Algorithm::Sha256Sess => Sha256::digest(data).to_vec(),
+        Algorithm::Sha512_256 | Algorithm::Sha512_256Sess => Sha512_256::digest(data).to_vec(),
+        Algorithm::Other(value) => {
+            return Err(SipError::InvalidDigest(format!(
+                "unsupported algorithm {value}"
+            )));
+        }
+    };
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Compare a computed Digest response with a received one, ignoring the case of the hexadecimal
+/// digits, in constant time.
+fn digests_eq(expected: &str, received: &str) -> bool {
+    constant_time_eq(
+        expected.to_ascii_lowercase().as_bytes(),
+        received.to_ascii_lowercase().as_bytes(),
+    )
+}
+
+/// Generate a random cnonce value.
+fn cnonce() -> Result<String, SipError> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| SipError::InvalidDigest(e.to_string()))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Header;
+    use claims::{assert_err, assert_ok};
+
+    fn www_authenticate(value: &str) -> WWWAuthenticateHeader {
+        match Header::try_from(format!("WWW-Authenticate: {value}").as_str()).unwrap() {
+            Header::WWWAuthenticate(header) => header,
+            _ => unreachable!(),
+        }
+    }
+
+    fn register() -> Request {
+        let mut request = Request::try_from(
+            "REGISTER sip:biloxi.com SIP/2.0\r\n\
+            To: Bob <sip:bob@biloxi.com>\r\n\
+            Call-ID: a84b4c76e66710\r\n\
+            CSeq: 2 REGISTER\r\n\
+            \r\n",
+        )
+        .unwrap();
+        request.set_body(b"body");
+        request
+    }
+
+    fn computation<'a>(
+        algorithm: &'a Algorithm,
+        realm: &'a str,
+        nonce: &'a str,
+        cnonce: &'a str,
+        qop: &'a MessageQop,
+    ) -> DigestComputation<'a> {
+        DigestComputation {
+            algorithm,
+            username: "Mufasa",
+            realm,
+            nonce,
+            cnonce: Some(cnonce),
+            nonce_count: Some("00000001"),
+            qop: Some(qop),
+            uri: "/dir/index.html".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rfc2617_md5() {
+        let qop = MessageQop::Auth;
+        let computation = computation(
+            &Algorithm::Md5,
+            "testrealm@host.com",
+            "dcd98b7102dd2f0e8b11d0f600bfb0c093",
+            "0a4f113b",
+            &qop,
+        );
+        let ha1 = computation.base_ha1("Circle Of Life").unwrap();
+        assert_eq!(
+            computation.response(&ha1, "GET", b"").unwrap(),
+            "6629fae49393a05397450978507c4ef1"
+        );
+    }
+
+    #[test]
+    fn test_rfc7616_sha256() {
+        let qop = MessageQop::Auth;
+        let nonce = "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v";
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        let computation = computation(
+            &Algorithm::Sha256,
+            "http-auth@example.org",
+            nonce,
+            cnonce,
+            &qop,
+        );
+        let ha1 = computation.base_ha1("Circle of Life").unwrap();
+        assert_eq!(
+            computation.response(&ha1, "GET", b"").unwrap(),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+    }
+
+    #[test]
+    fn test_authenticate_and_verify() {
+        for challenge in [
+            r#"Digest realm="biloxi.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093""#,
+            r#"Digest realm="biloxi.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+            r#"Digest realm="biloxi.com", qop="auth-int", nonce="abc", algorithm=MD5-sess"#,
+            r#"Digest realm="biloxi.com", qop="auth", nonce="abc", algorithm=SHA-256"#,
+            r#"Digest realm="biloxi.com", qop="auth-int", nonce="abc", algorithm=SHA-512-256-sess"#,
+        ] {
+            let header = www_authenticate(challenge);
+            let algorithm = header
+                .challenge()
+                .algorithm()
+                .cloned()
+                .unwrap_or(Algorithm::Md5);
+            let mut client = DigestClient::new("bob", "zanzibar");
+            let request = register();
+            let authorization = client.authorization_header(&header, &request).unwrap();
+            let credentials = authorization.credentials();
+            assert_eq!(credentials.username(), Some("bob"));
+            assert_eq!(credentials.algorithm(), Some(&algorithm));
+
+            let password = DigestSecret::Password("zanzibar".to_string());
+            let ha1 = DigestSecret::ha1("bob", "biloxi.com", "zanzibar", &algorithm).unwrap();
+            assert!(password.verify(credentials, &request).unwrap());
+            assert!(ha1.verify(credentials, &request).unwrap());
+            assert!(
+                !DigestSecret::Password("wrong".to_string())
+                    .verify(credentials, &request)
+                    .unwrap()
+            );
+            if credentials.qop().is_some_and(MessageQop::is_auth_int) {
+                let mut tampered = request.clone();
+                tampered.set_body(b"tampered");
+                assert!(!password.verify(credentials, &tampered).unwrap());
+            }
+
+            // The parsed credentials are verified the same way.
+            let parsed = match Header::try_from(authorization.to_string().as_str()).unwrap() {
+                Header::Authorization(header) => header,
+                _ => unreachable!(),
+            };
+            assert!(ha1.verify(parsed.credentials(), &request).unwrap());
+
+            let info = password
+                .authentication_info_header(credentials, b"", Some("nextone"))
+                .unwrap();
+            assert_eq!(info.next_nonce(), Some("nextone"));
+            assert_eq!(
+                client
+                    .verify_authentication_info(&info, credentials, b"")
+                    .unwrap(),
+                credentials.has_qop()
+            );
+        }
+    }
+
+    #[test]
+    fn test_digests_eq() {
+        assert!(digests_eq(
+            "6629fae49393a05397450978507c4ef1",
+            "6629FAE49393A05397450978507C4EF1"
+        ));
+        assert!(!digests_eq(
+            "6629fae49393a05397450978507c4ef1",
+            "6629fae49393a05397450978507c4ef2"
+        ));
+        assert!(!digests_eq("6629fae49393a05397450978507c4ef1", "6629fae4"));
+        assert!(!digests_eq("6629fae49393a05397450978507c4ef1", ""));
+    }
+
+    #[test]
+    fn test_nonce_count() {
+        let header = www_authenticate(r#"Digest realm="biloxi.com", qop="auth", nonce="abc""#);
+        let mut client = DigestClient::new("bob", "zanzibar");
+        let request = register();
+        let first = client
+            .credentials_with_cnonce(header.challenge(), &request, "0a4f113b")
+            .unwrap();
+        let second = client
+            .credentials_with_cnonce(header.challenge(), &request, "0a4f113b")
+            .unwrap();
+        assert_eq!(first.nonce_count(), Some("00000001"));
+        assert_eq!(second.nonce_count(), Some("00000002"));
+        assert_ne!(first.dresponse(), second.dresponse());
+    }
+
+    #[test]
+    fn test_invalid_challenges() {
+        let mut client = DigestClient::new("bob", "zanzibar");
+        let request = register();
+        assert_err!(client.authorization_header(
+            &www_authenticate(r#"Digest realm="biloxi.com", nonce="abc", algorithm=AKAv1-MD5"#),
+            &request
+        ));
+        assert_err!(client.authorization_header(
+            &www_authenticate(r#"Digest realm="biloxi.com", nonce="abc", qop="other""#),
+            &request
+        ));
+        assert_err!(
+            client.authorization_header(&www_authenticate(r#"Digest nonce="abc""#), &request)
+        );
+        assert_ok!(client.authorization_header(
+            &www_authenticate(r#"Digest realm="biloxi.com", nonce="abc""#),
+            &request
+        ));
+    }
+}
@@ -18,12 +18,21 @@ pub enum SipError {
     /// Invalid content language.
     #[display("Invalid content language: `{_0}`")]
     InvalidContentLanguage(String),
+    /// Invalid digest authentication.
+    #[display("Invalid digest: `{_0}`")]
+    InvalidDigest(String),
     /// Invalid host.
     #[display("Invalid host: `{_0}`")]
     InvalidHost(String),
     /// Invalid hostname.
     #[display("Invalid hostname: `{_0}`")]
     InvalidHostname(String),
+    /// Invalid STIR/SHAKEN identity.
+    #[display("Invalid identity: `{_0}`")]
+    InvalidIdentity(String),
+    /// Invalid media type.
+    #[display("Invalid media type: `{_0}`")]
+    InvalidMediaType(String),
     /// Invalid message.
     #[display("Invalid message:\n{_0}")]
     InvalidMessage(String),
@@ -33,6 +42,12 @@ pub enum SipError {
     /// Invalid method.
     #[display("Invalid method: `{_0}`")]
     InvalidMethod(String),
+    /// Invalid multipart body.
+    #[display("Invalid multipart body: `{_0}`")]
+    InvalidMultipartBody(String),
+    /// Invalid SDP offer/answer exchange.
+    #[display("Invalid offer/answer exchange: `{_0}`")]
+    InvalidOfferAnswer(String),
     /// Invalid option tag.
     #[display("Invalid option tag: `{_0}`")]
     InvalidOptionTag(String),
@@ -45,12 +60,21 @@ pub enum SipError {
     /// Invalid response.
     #[display("Invalid response:\n{_0}")]
     InvalidResponse(String),
+    /// Invalid SDP session description.
+    #[display("Invalid sdp: `{_0}`")]
+    InvalidSdp(String),
+    /// Invalid SIP message fragment.
+    #[display("Invalid sipfrag: `{_0}`")]
+    InvalidSipFrag(String),
     /// Invalid response status code.
     #[display("Invalid status code: `{_0}`")]
     InvalidStatusCode(String),
     /// Invalid Token String.
     #[display("Invalid token string: `{_0}`")]
     InvalidTokenString(String),
+    /// Invalid transaction.
+    #[display("Invalid transaction: `{_0}`")]
+    InvalidTransaction(String),
     /// Invalid URI.
     #[display("Invalid uri: `{_0}`")]
     InvalidUri(String),
@@ -0,0 +1,194 @@
+//! SIP Accept-Contact header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{CallerPreference, TokenString};
+
+/// Representation of an Accept-Contact header.
+///
+/// The Accept-Contact header field lists the feature sets of the contacts the caller would
+/// like the request to be routed to.
+///
+/// [[RFC3841, Section 10](https://datatracker.ietf.org/doc/html/rfc3841#section-10)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct AcceptContactHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    preferences: Vec<CallerPreference>,
+}
+
+impl AcceptContactHeader {
+    pub(crate) fn new(header: GenericHeader, preferences: Vec<CallerPreference>) -> Self {
+        Self {
+            header,
+            preferences,
+        }
+    }
+
+    /// Get a reference to the caller preferences from the Accept-Contact header.
+    pub fn preferences(&self) -> &Vec<CallerPreference> {
+        &self.preferences
+    }
+}
+
+impl HeaderAccessor for AcceptContactHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        Some("a")
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Accept-Contact")
+    }
+    fn normalized_value(&self) -> String {
+        join(&self.preferences, ", ")
+    }
+}
+
+impl From<Vec<CallerPreference>> for AcceptContactHeader {
+    fn from(value: Vec<CallerPreference>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Accept-Contact"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        AcceptContactHeader, Header, TokenString,
+        common::caller_preference::parser::caller_preference,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn accept_contact(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Accept-Contact header",
+            map(
+                (
+                    map(
+                        alt((tag_no_case("Accept-Contact"), tag_no_case("a"))),
+                        TokenString::new,
+                    ),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, caller_preference))),
+                ),
+                |(name, separator, (value, preferences))| {
+                    Header::AcceptContact(AcceptContactHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        preferences,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        AcceptContactHeader, CallerPreferenceParameter, Header,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(AcceptContact, AcceptContactHeader, "Accept-Contact");
+    header_equality!(AcceptContact, "Accept-Contact");
+    header_inequality!(AcceptContact, "Accept-Contact");
+
+    #[test]
+    fn test_valid_accept_contact_header() {
+        valid_header(
+            r#"Accept-Contact: *;audio;video;require, *;methods="BYE";class="business";q=1.0"#,
+            |header| {
+                assert_eq!(header.preferences().len(), 2);
+                let first = &header.preferences()[0];
+                assert!(first.is_required());
+                assert!(!first.is_explicit());
+                assert_eq!(
+                    first
+                        .feature_tags()
+                        .map(|tag| tag.name())
+                        .collect::<Vec<_>>(),
+                    vec!["audio", "video"]
+                );
+                let second = &header.preferences()[1];
+                assert_eq!(second.feature_tags().count(), 2);
+                assert!(matches!(
+                    second.parameters().last().unwrap(),
+                    CallerPreferenceParameter::Other(_)
+                ));
+            },
+        );
+    }
+
+    #[test]
+    fn test_valid_accept_contact_header_in_compact_form() {
+        valid_header("a: *;+sip.message;explicit", |header| {
+            assert_eq!(header.preferences().len(), 1);
+            assert!(header.preferences()[0].is_explicit());
+        });
+    }
+
+    #[test]
+    fn test_invalid_accept_contact_header_empty() {
+        invalid_header("Accept-Contact:");
+    }
+
+    #[test]
+    fn test_invalid_accept_contact_header_without_star() {
+        invalid_header("Accept-Contact: audio");
+    }
+
+    #[test]
+    fn test_accept_contact_header_equality_with_space_characters_differences() {
+        header_equality("Accept-Contact: *;audio", "Accept-Contact:   *;audio");
+    }
+
+    #[test]
+    fn test_accept_contact_header_inequality_different_features() {
+        header_inequality("Accept-Contact: *;audio", "Accept-Contact: *;video");
+    }
+
+    #[test]
+    fn test_accept_contact_header_to_string() {
+        let header = Header::try_from(r#"a :  *;Audio;methods="INVITE,BYE";require"#);
+        if let Header::AcceptContact(header) = header.unwrap() {
+            assert_eq!(
+                header.to_string(),
+                r#"a :  *;Audio;methods="INVITE,BYE";require"#
+            );
+            assert_eq!(
+                header.to_normalized_string(),
+                r#"Accept-Contact: *;audio;methods="INVITE,BYE";require"#
+            );
+            assert_eq!(
+                header.to_compact_string(),
+                r#"a: *;audio;methods="INVITE,BYE";require"#
+            );
+        } else {
+            panic!("Not an Accept-Contact header");
+        }
+    }
+}
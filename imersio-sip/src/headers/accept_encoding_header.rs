@@ -1,9 +1,10 @@
 //! SIP Accept-Encoding header parsing and generation.
 
 use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{AcceptEncoding, AcceptEncodings};
+use crate::{AcceptEncoding, AcceptEncodings, TokenString};
 
 /// Representation of an Accept-Encoding header.
 ///
@@ -33,6 +34,19 @@ impl AcceptEncodingHeader {
     }
 }
 
+impl From<Vec<AcceptEncoding>> for AcceptEncodingHeader {
+    fn from(value: Vec<AcceptEncoding>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Accept-Encoding"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for AcceptEncodingHeader {
     crate::headers::generic_header_accessors!(header);
 
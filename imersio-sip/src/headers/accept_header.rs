@@ -1,9 +1,10 @@
 //! SIP Accept header parsing and generation.
 
 use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{AcceptRange, AcceptRanges};
+use crate::{AcceptRange, AcceptRanges, TokenString};
 
 /// Representation of an Accept header.
 ///
@@ -34,6 +35,19 @@ impl AcceptHeader {
     }
 }
 
+impl From<Vec<AcceptRange>> for AcceptHeader {
+    fn from(value: Vec<AcceptRange>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Accept"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for AcceptHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -1,9 +1,10 @@
 //! SIP Accept-Language header parsing and generation.
 
 use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{AcceptLanguage, AcceptLanguages};
+use crate::{AcceptLanguage, AcceptLanguages, TokenString};
 
 /// Representation of an Accept-Language header.
 ///
@@ -36,6 +37,19 @@ impl AcceptLanguageHeader {
     }
 }
 
+impl From<Vec<AcceptLanguage>> for AcceptLanguageHeader {
+    fn from(value: Vec<AcceptLanguage>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Accept-Language"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for AcceptLanguageHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -1,9 +1,10 @@
 //! SIP Authentication-Info header parsing and generation.
 
 use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{AuthenticationInfo, AuthenticationInfos, MessageQop};
+use crate::{AuthenticationInfo, AuthenticationInfos, MessageQop, TokenString};
 
 /// Representation of an Authentication-Info header.
 ///
@@ -54,6 +55,19 @@ impl AuthenticationInfoHeader {
     }
 }
 
+impl From<Vec<AuthenticationInfo>> for AuthenticationInfoHeader {
+    fn from(value: Vec<AuthenticationInfo>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Authentication-Info"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for AuthenticationInfoHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::Credentials;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Credentials, TokenString};
 
 /// Representation of an Authorization header.
 ///
@@ -32,6 +32,19 @@ impl AuthorizationHeader {
     }
 }
 
+impl From<Credentials> for AuthorizationHeader {
+    fn from(value: Credentials) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Authorization"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for AuthorizationHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::Contacts;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Contacts, TokenString};
 
 /// Representation of a Contact header.
 ///
@@ -32,6 +32,19 @@ impl ContactHeader {
     }
 }
 
+impl From<Contacts> for ContactHeader {
+    fn from(value: Contacts) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Contact"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for ContactHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -246,4 +259,39 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_contact_header_from_contacts() {
+        let header = Header::try_from("Contact: <sip:bob@192.0.2.4>;q=0.5;expires=60").unwrap();
+        let Header::Contact(header) = header else {
+            panic!("Not a Contact header");
+        };
+        let contact = header.contacts().first().unwrap().with_expires(3600);
+        assert_eq!(contact.expires(), Some(3600));
+        let header = ContactHeader::from(Contacts::from(vec![contact]));
+        assert_eq!(
+            header.to_string(),
+            "Contact: <sip:bob@192.0.2.4>;q=0.5;expires=3600"
+        );
+        assert_eq!(ContactHeader::from(Contacts::Any).to_string(), "Contact: *");
+    }
+
+    #[test]
+    fn test_contact_instance_id() {
+        valid_header(
+            r#"Contact: <sip:bob@192.0.2.4>;+sip.instance="<urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>";reg-id=1;expires=60, <sip:bob@192.0.2.5>"#,
+            |header| {
+                let mut contacts = header.contacts().iter();
+                let contact = contacts.next().unwrap();
+                assert_eq!(
+                    contact.instance_id().as_deref(),
+                    Some("urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6")
+                );
+                assert_eq!(contact.reg_id(), Some(1));
+                let contact = contacts.next().unwrap();
+                assert_eq!(contact.instance_id(), None);
+                assert_eq!(contact.reg_id(), None);
+            },
+        );
+    }
 }
@@ -3,7 +3,7 @@
 use derive_partial_eq_extras::PartialEqExtras;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{ContentEncoding, ContentEncodings};
+use crate::{ContentEncoding, ContentEncodings, TokenString};
 
 /// Representation of a Content-Encoding header.
 ///
@@ -41,6 +41,19 @@ impl ContentEncodingHeader {
     }
 }
 
+impl From<ContentEncoding> for ContentEncodingHeader {
+    fn from(value: ContentEncoding) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Content-Encoding"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            vec![value],
+        )
+    }
+}
+
 impl HeaderAccessor for ContentEncodingHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -2,6 +2,7 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
+use crate::TokenString;
 use crate::headers::{GenericHeader, HeaderAccessor};
 
 /// Representation of a Content-Length header.
@@ -36,6 +37,19 @@ impl ContentLengthHeader {
     }
 }
 
+impl From<u32> for ContentLengthHeader {
+    fn from(value: u32) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Content-Length"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for ContentLengthHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::MediaType;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{MediaType, TokenString};
 
 /// Representation of a Content-Type header.
 ///
@@ -33,6 +33,19 @@ impl ContentTypeHeader {
     }
 }
 
+impl From<MediaType> for ContentTypeHeader {
+    fn from(value: MediaType) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Content-Type"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for ContentTypeHeader {
     crate::headers::generic_header_accessors!(header);
 
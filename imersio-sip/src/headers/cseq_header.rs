@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::Method;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Method, TokenString};
 
 /// Representation of a CSeq header.
 ///
@@ -44,6 +44,20 @@ impl CSeqHeader {
     }
 }
 
+impl From<(u32, Method)> for CSeqHeader {
+    fn from((cseq, method): (u32, Method)) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("CSeq"),
+                ": ".to_string(),
+                format!("{} {}", cseq, method),
+            ),
+            cseq,
+            method,
+        )
+    }
+}
+
 impl HeaderAccessor for CSeqHeader {
     crate::headers::generic_header_accessors!(header);
 
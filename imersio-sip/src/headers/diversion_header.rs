@@ -0,0 +1,233 @@
+//! SIP Diversion header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Diversion, Diversions, TokenString};
+
+/// Representation of a Diversion header.
+///
+/// The Diversion header field tells the users from which a call has been diverted, the most recent
+/// diversion being listed first, and why.
+///
+/// [[RFC5806, Section 4](https://datatracker.ietf.org/doc/html/rfc5806#section-4)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct DiversionHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    entries: Diversions,
+}
+
+impl DiversionHeader {
+    pub(crate) fn new(header: GenericHeader, entries: Vec<Diversion>) -> Self {
+        Self {
+            header,
+            entries: entries.into(),
+        }
+    }
+
+    /// Get a reference to the entries from the Diversion header.
+    pub fn entries(&self) -> &Diversions {
+        &self.entries
+    }
+
+    /// Get a `DiversionHeader` builder.
+    pub fn builder() -> DiversionHeaderBuilder {
+        DiversionHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for DiversionHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Diversion")
+    }
+    fn normalized_value(&self) -> String {
+        self.entries.to_string()
+    }
+}
+
+/// Representation of a builder of `Diversion` header.
+#[derive(Clone, Debug, Default)]
+pub struct DiversionHeaderBuilder {
+    entries: Diversions,
+}
+
+impl DiversionHeaderBuilder {
+    /// Add an entry.
+    pub fn entry(&mut self, entry: Diversion) -> &mut Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add several entries.
+    pub fn entries<I: IntoIterator<Item = Diversion>>(&mut self, entries: I) -> &mut Self {
+        self.entries.extend(entries);
+        self
+    }
+
+    /// Clear the list of already added entries.
+    pub fn clear_entries(&mut self) -> &mut Self {
+        self.entries.clear();
+        self
+    }
+
+    /// Build the `DiversionHeader`.
+    pub fn build(&self) -> DiversionHeader {
+        DiversionHeader {
+            header: GenericHeader::new(
+                TokenString::new("Diversion"),
+                ": ".to_string(),
+                self.entries.to_string(),
+            ),
+            entries: Clone::clone(&self.entries),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        DiversionHeader, Header, TokenString,
+        common::diversion::parser::diversion_entry,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn diversion(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Diversion header",
+            map(
+                (
+                    map(tag_no_case("Diversion"), TokenString::new),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, diversion_entry))),
+                ),
+                |(name, separator, (value, entries))| {
+                    Header::Diversion(DiversionHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        entries,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Diversion, DiversionHeader, DiversionParameter, DiversionReason, Header, NameAddress, Uri,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(Diversion, DiversionHeader, "Diversion");
+    header_equality!(Diversion, "Diversion");
+    header_inequality!(Diversion, "Diversion");
+
+    #[test]
+    fn test_valid_diversion_header() {
+        valid_header(
+            "Diversion: <sip:bob@example.com>;reason=user-busy;counter=1;privacy=full, \
+            \"Alice\" <sip:alice@example.com>;reason=unconditional;counter=2;limit=5",
+            |header| {
+                assert_eq!(header.entries().len(), 2);
+                let mut entries = header.entries().iter();
+                let first_entry = entries.next().unwrap();
+                assert_eq!(
+                    first_entry.name_address().uri(),
+                    Uri::try_from("sip:bob@example.com").unwrap()
+                );
+                assert_eq!(first_entry.reason(), Some(&DiversionReason::UserBusy));
+                assert_eq!(first_entry.counter(), 1);
+                assert_eq!(first_entry.limit(), None);
+                let second_entry = entries.next().unwrap();
+                assert_eq!(second_entry.name_address().display_name(), Some("Alice"));
+                assert_eq!(second_entry.reason(), Some(&DiversionReason::Unconditional));
+                assert_eq!(second_entry.counter(), 2);
+                assert_eq!(second_entry.limit(), Some(5));
+            },
+        );
+    }
+
+    #[test]
+    fn test_valid_diversion_header_without_counter() {
+        valid_header("Diversion: <tel:+15551234567>;reason=no-answer", |header| {
+            let entry = header.entries().first().unwrap();
+            assert_eq!(entry.reason(), Some(&DiversionReason::NoAnswer));
+            assert_eq!(entry.counter(), 1);
+        });
+    }
+
+    #[test]
+    fn test_invalid_diversion_header_empty() {
+        invalid_header("Diversion:");
+    }
+
+    #[test]
+    fn test_diversion_header_equality_with_different_cases() {
+        header_equality(
+            "Diversion: <sip:bob@example.com>;reason=user-busy",
+            "Diversion: <sip:bob@example.com>;REASON=User-Busy",
+        );
+    }
+
+    #[test]
+    fn test_diversion_header_inequality_different_reasons() {
+        header_inequality(
+            "Diversion: <sip:bob@example.com>;reason=user-busy",
+            "Diversion: <sip:bob@example.com>;reason=no-answer",
+        );
+    }
+
+    #[test]
+    fn test_diversion_header_to_string() {
+        let header = Header::try_from("diversion:  <sip:bob@example.com>;Reason=User-Busy");
+        if let Header::Diversion(header) = header.unwrap() {
+            assert_eq!(
+                header.to_string(),
+                "diversion:  <sip:bob@example.com>;Reason=User-Busy"
+            );
+            assert_eq!(
+                header.to_normalized_string(),
+                "Diversion: <sip:bob@example.com>;reason=user-busy"
+            );
+        } else {
+            panic!("Not a Diversion header");
+        }
+    }
+
+    #[test]
+    fn test_valid_diversion_header_builder() {
+        let header = DiversionHeader::builder()
+            .entry(Diversion::new(
+                NameAddress::from(Uri::try_from("sip:bob@example.com").unwrap()),
+                vec![
+                    DiversionParameter::Reason(DiversionReason::NoAnswer),
+                    DiversionParameter::Counter("1".to_string()),
+                ],
+            ))
+            .build();
+        assert_eq!(
+            header.to_string(),
+            "Diversion: <sip:bob@example.com>;reason=no-answer;counter=1"
+        );
+    }
+}
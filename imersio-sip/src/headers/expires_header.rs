@@ -3,6 +3,7 @@
 use chrono::TimeDelta;
 use derive_partial_eq_extras::PartialEqExtras;
 
+use crate::TokenString;
 use crate::headers::{GenericHeader, HeaderAccessor};
 
 /// Representation of an Expires header.
@@ -33,6 +34,19 @@ impl ExpiresHeader {
     }
 }
 
+impl From<TimeDelta> for ExpiresHeader {
+    fn from(value: TimeDelta) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Expires"),
+                ": ".to_string(),
+                value.num_seconds().to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for ExpiresHeader {
     crate::headers::generic_header_accessors!(header);
 
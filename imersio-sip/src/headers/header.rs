@@ -5,15 +5,18 @@ use std::convert::TryFrom;
 
 use crate::headers::generic_header::GenericHeader;
 use crate::{
-    AcceptEncodingHeader, AcceptHeader, AcceptLanguageHeader, AlertInfoHeader, AllowHeader,
-    AuthenticationInfoHeader, AuthorizationHeader, CSeqHeader, CallIdHeader, CallInfoHeader,
-    ContactHeader, ContentDispositionHeader, ContentEncodingHeader, ContentLanguageHeader,
-    ContentLengthHeader, ContentTypeHeader, DateHeader, ErrorInfoHeader, ExpiresHeader, FromHeader,
-    InReplyToHeader, MaxForwardsHeader, MimeVersionHeader, MinExpiresHeader, OrganizationHeader,
-    PriorityHeader, ProxyAuthenticateHeader, ProxyAuthorizationHeader, ProxyRequireHeader,
-    RecordRouteHeader, ReplyToHeader, RequireHeader, RetryAfterHeader, RouteHeader, ServerHeader,
-    SipError, SubjectHeader, SupportedHeader, TimestampHeader, ToHeader, UnsupportedHeader,
-    UserAgentHeader, ViaHeader, WWWAuthenticateHeader, WarningHeader,
+    AcceptContactHeader, AcceptEncodingHeader, AcceptHeader, AcceptLanguageHeader, AlertInfoHeader,
+    AllowHeader, AuthenticationInfoHeader, AuthorizationHeader, CSeqHeader, CallIdHeader,
+    CallInfoHeader, ContactHeader, ContentDispositionHeader, ContentEncodingHeader,
+    ContentLanguageHeader, ContentLengthHeader, ContentTypeHeader, DateHeader, DiversionHeader,
+    ErrorInfoHeader, ExpiresHeader, FromHeader, HistoryInfoHeader, IdentityHeader, InReplyToHeader,
+    InfoPackageHeader, MaxForwardsHeader, MimeVersionHeader, MinExpiresHeader, OrganizationHeader,
+    PAssertedIdentityHeader, PathHeader, PriorityHeader, ProxyAuthenticateHeader,
+    ProxyAuthorizationHeader, ProxyRequireHeader, ReasonHeader, RecordRouteHeader, RecvInfoHeader,
+    RejectContactHeader, ReplyToHeader, RequestDispositionHeader, RequireHeader, RetryAfterHeader,
+    RouteHeader, ServerHeader, ServiceRouteHeader, SipETagHeader, SipError, SipIfMatchHeader,
+    SubjectHeader, SupportedHeader, TimestampHeader, ToHeader, UnsupportedHeader, UserAgentHeader,
+    ViaHeader, WWWAuthenticateHeader, WarningHeader,
 };
 
 macro_rules! headers {
@@ -51,6 +54,8 @@ macro_rules! headers {
 headers! {
     /// An Accept message header.
     (Accept, AcceptHeader),
+    /// An Accept-Contact message header.
+    (AcceptContact, AcceptContactHeader),
     /// An Accept-Encoding message header.
     (AcceptEncoding, AcceptEncodingHeader),
     /// An Accept-Language message header.
@@ -83,14 +88,22 @@ headers! {
     (CSeq, CSeqHeader),
     /// A Date header.
     (Date, DateHeader),
+    /// A Diversion header.
+    (Diversion, DiversionHeader),
     /// An Error-Info header.
     (ErrorInfo, ErrorInfoHeader),
     /// An Expires header.
     (Expires, ExpiresHeader),
     /// A From header.
     (From, FromHeader),
+    /// A History-Info header.
+    (HistoryInfo, HistoryInfoHeader),
+    /// An Identity header.
+    (Identity, IdentityHeader),
     /// An In-Reply-To header.
     (InReplyTo, InReplyToHeader),
+    /// An Info-Package header.
+    (InfoPackage, InfoPackageHeader),
     /// A Max-Forwards header.
     (MaxForwards, MaxForwardsHeader),
     /// A MIME-Version header.
@@ -99,6 +112,10 @@ headers! {
     (MinExpires, MinExpiresHeader),
     /// An Organization header.
     (Organization, OrganizationHeader),
+    /// A P-Asserted-Identity header.
+    (PAssertedIdentity, PAssertedIdentityHeader),
+    /// A Path header.
+    (Path, PathHeader),
     /// A Priority header.
     (Priority, PriorityHeader),
     /// A Proxy-Authenticate header.
@@ -107,10 +124,18 @@ headers! {
     (ProxyAuthorization, ProxyAuthorizationHeader),
     /// A Proxy-Require header.
     (ProxyRequire, ProxyRequireHeader),
+    /// A Reason header.
+    (Reason, ReasonHeader),
     /// A Record-Route header.
     (RecordRoute, RecordRouteHeader),
+    /// A Recv-Info header.
+    (RecvInfo, RecvInfoHeader),
+    /// A Reject-Contact header.
+    (RejectContact, RejectContactHeader),
     /// A Reply-To header.
     (ReplyTo, ReplyToHeader),
+    /// A Request-Disposition header.
+    (RequestDisposition, RequestDispositionHeader),
     /// A Require header.
     (Require, RequireHeader),
     /// A Retry-After header.
@@ -119,6 +144,12 @@ headers! {
     (Route, RouteHeader),
     /// A Server header.
     (Server, ServerHeader),
+    /// A Service-Route header.
+    (ServiceRoute, ServiceRouteHeader),
+    /// A SIP-ETag header.
+    (SipETag, SipETagHeader),
+    /// A SIP-If-Match header.
+    (SipIfMatch, SipIfMatchHeader),
     /// A Subject header.
     (Subject, SubjectHeader),
     /// A Supported header.
@@ -170,6 +201,7 @@ pub(crate) mod parser {
     use crate::{
         Header,
         headers::{
+            accept_contact_header::parser::accept_contact,
             accept_encoding_header::parser::accept_encoding, accept_header::parser::accept,
             accept_language_header::parser::accept_language, alert_info_header::parser::alert_info,
             allow_header::parser::allow, authentication_info_header::parser::authentication_info,
@@ -180,18 +212,25 @@ pub(crate) mod parser {
             content_language_header::parser::content_language,
             content_length_header::parser::content_length,
             content_type_header::parser::content_type, cseq_header::parser::cseq,
-            date_header::parser::date, error_info_header::parser::error_info,
-            expires_header::parser::expires, from_header::parser::from,
-            generic_header::parser::extension_header, in_reply_to_header::parser::in_reply_to,
+            date_header::parser::date, diversion_header::parser::diversion,
+            error_info_header::parser::error_info, expires_header::parser::expires,
+            from_header::parser::from, generic_header::parser::extension_header,
+            history_info_header::parser::history_info, identity_header::parser::identity,
+            in_reply_to_header::parser::in_reply_to, info_package_header::parser::info_package,
             max_forwards_header::parser::max_forwards, mime_version_header::parser::mime_version,
             min_expires_header::parser::min_expires, organization_header::parser::organization,
+            p_asserted_identity_header::parser::p_asserted_identity, path_header::parser::path,
             priority_header::parser::priority,
             proxy_authenticate_header::parser::proxy_authenticate,
             proxy_authorization_header::parser::proxy_authorization,
-            proxy_require_header::parser::proxy_require, record_route_header::parser::record_route,
-            reply_to_header::parser::reply_to, require_header::parser::require,
-            retry_after_header::parser::retry_after, route_header::parser::route,
-            server_header::parser::server, subject_header::parser::subject,
+            proxy_require_header::parser::proxy_require, reason_header::parser::reason,
+            record_route_header::parser::record_route, recv_info_header::parser::recv_info,
+            reject_contact_header::parser::reject_contact, reply_to_header::parser::reply_to,
+            request_disposition_header::parser::request_disposition,
+            require_header::parser::require, retry_after_header::parser::retry_after,
+            route_header::parser::route, server_header::parser::server,
+            service_route_header::parser::service_route, sip_etag_header::parser::sip_etag,
+            sip_if_match_header::parser::sip_if_match, subject_header::parser::subject,
             supported_header::parser::supported, timestamp_header::parser::timestamp,
             to_header::parser::to, unsupported_header::parser::unsupported,
             user_agent_header::parser::user_agent, via_header::parser::via,
@@ -250,7 +289,24 @@ pub(crate) mod parser {
                     user_agent,
                     via,
                 )),
-                alt((warning, www_authenticate)),
+                alt((
+                    warning,
+                    www_authenticate,
+                    path,
+                    service_route,
+                    diversion,
+                    history_info,
+                    reason,
+                    sip_etag,
+                    sip_if_match,
+                    info_package,
+                    recv_info,
+                    identity,
+                    p_asserted_identity,
+                    accept_contact,
+                    reject_contact,
+                    request_disposition,
+                )),
                 extension_header,
             )),
         )
@@ -0,0 +1,248 @@
+//! SIP History-Info header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{HistoryInfo, HistoryInfos, TokenString};
+
+/// Representation of a History-Info header.
+///
+/// The History-Info header field captures the history of the targets to which a request has been
+/// sent, and the reasons for which it has been retargeted.
+///
+/// [[RFC7044, Section 4](https://datatracker.ietf.org/doc/html/rfc7044#section-4)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct HistoryInfoHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    entries: HistoryInfos,
+}
+
+impl HistoryInfoHeader {
+    pub(crate) fn new(header: GenericHeader, entries: Vec<HistoryInfo>) -> Self {
+        Self {
+            header,
+            entries: entries.into(),
+        }
+    }
+
+    /// Get a reference to the entries from the History-Info header.
+    pub fn entries(&self) -> &HistoryInfos {
+        &self.entries
+    }
+
+    /// Get a `HistoryInfoHeader` builder.
+    pub fn builder() -> HistoryInfoHeaderBuilder {
+        HistoryInfoHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for HistoryInfoHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("History-Info")
+    }
+    fn normalized_value(&self) -> String {
+        self.entries.to_string()
+    }
+}
+
+/// Representation of a builder of `History-Info` header.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryInfoHeaderBuilder {
+    entries: HistoryInfos,
+}
+
+impl HistoryInfoHeaderBuilder {
+    /// Add an entry.
+    pub fn entry(&mut self, entry: HistoryInfo) -> &mut Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add several entries.
+    pub fn entries<I: IntoIterator<Item = HistoryInfo>>(&mut self, entries: I) -> &mut Self {
+        self.entries.extend(entries);
+        self
+    }
+
+    /// Clear the list of already added entries.
+    pub fn clear_entries(&mut self) -> &mut Self {
+        self.entries.clear();
+        self
+    }
+
+    /// Build the `HistoryInfoHeader`.
+    pub fn build(&self) -> HistoryInfoHeader {
+        HistoryInfoHeader {
+            header: GenericHeader::new(
+                TokenString::new("History-Info"),
+                ": ".to_string(),
+                self.entries.to_string(),
+            ),
+            entries: Clone::clone(&self.entries),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, HistoryInfoHeader, TokenString,
+        common::history_info::parser::hi_entry,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn history_info(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "History-Info header",
+            map(
+                (
+                    map(tag_no_case("History-Info"), TokenString::new),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, hi_entry))),
+                ),
+                |(name, separator, (value, entries))| {
+                    Header::HistoryInfo(HistoryInfoHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        entries,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, HistoryInfo, HistoryInfoHeader, HistoryInfoTag, NameAddress, Uri,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(HistoryInfo, HistoryInfoHeader, "History-Info");
+    header_equality!(HistoryInfo, "History-Info");
+    header_inequality!(HistoryInfo, "History-Info");
+
+    #[test]
+    fn test_valid_history_info_header() {
+        valid_header(
+            "History-Info: <sip:bob@example.com?Reason=SIP%3Bcause%3D302>;index=1, \
+            <sip:office@example.com;cause=302>;index=1.1;mp=1",
+            |header| {
+                assert_eq!(header.entries().len(), 2);
+                let mut entries = header.entries().iter();
+                let first_entry = entries.next().unwrap();
+                assert_eq!(first_entry.index(), Some("1"));
+                assert_eq!(first_entry.tag(), None);
+                assert_eq!(first_entry.cause(), Some(302));
+                let second_entry = entries.next().unwrap();
+                assert_eq!(
+                    second_entry.name_address().uri(),
+                    Uri::try_from("sip:office@example.com;cause=302").unwrap()
+                );
+                assert_eq!(second_entry.index(), Some("1.1"));
+                assert_eq!(second_entry.tag(), Some((HistoryInfoTag::Mp, "1")));
+            },
+        );
+    }
+
+    #[test]
+    fn test_valid_history_info_header_with_extension_parameter() {
+        valid_header(
+            "History-Info: <sip:bob@example.com>;index=1;foo=bar",
+            |header| {
+                let entry = header.entries().first().unwrap();
+                assert_eq!(entry.parameters().len(), 2);
+                assert_eq!(entry.index(), Some("1"));
+            },
+        );
+    }
+
+    #[test]
+    fn test_invalid_history_info_header_empty() {
+        invalid_header("History-Info:");
+    }
+
+    #[test]
+    fn test_invalid_history_info_header_with_addr_spec() {
+        invalid_header("History-Info: sip:bob@example.com;index=1");
+    }
+
+    #[test]
+    fn test_history_info_header_equality_with_different_parameters_order() {
+        header_equality(
+            "History-Info: <sip:bob@example.com>;index=1.1;rc=1",
+            "History-Info: <sip:bob@example.com>;rc=1;index=1.1",
+        );
+    }
+
+    #[test]
+    fn test_history_info_header_inequality_different_indexes() {
+        header_inequality(
+            "History-Info: <sip:bob@example.com>;index=1.1",
+            "History-Info: <sip:bob@example.com>;index=1.2",
+        );
+    }
+
+    #[test]
+    fn test_history_info_header_to_string() {
+        let header = Header::try_from("history-info :   <sip:bob@example.com>;INDEX=1");
+        if let Header::HistoryInfo(header) = header.unwrap() {
+            assert_eq!(
+                header.to_string(),
+                "history-info :   <sip:bob@example.com>;INDEX=1"
+            );
+            assert_eq!(
+                header.to_normalized_string(),
+                "History-Info: <sip:bob@example.com>;index=1"
+            );
+            assert_eq!(
+                header.to_compact_string(),
+                "History-Info: <sip:bob@example.com>;index=1"
+            );
+        } else {
+            panic!("Not a History-Info header");
+        }
+    }
+
+    #[test]
+    fn test_valid_history_info_header_builder() {
+        let header = HistoryInfoHeader::builder()
+            .entry(HistoryInfo::new(
+                NameAddress::from(Uri::try_from("sip:bob@example.com").unwrap()),
+                vec![crate::HistoryInfoParameter::Index("1".to_string())],
+            ))
+            .entry(HistoryInfo::new(
+                NameAddress::from(Uri::try_from("sip:bob@192.0.2.4").unwrap()),
+                vec![
+                    crate::HistoryInfoParameter::Index("1.1".to_string()),
+                    HistoryInfoTag::Rc.to_parameter("1"),
+                ],
+            ))
+            .build();
+        assert_eq!(header.entries().len(), 2);
+        assert_eq!(
+            header.to_string(),
+            "History-Info: <sip:bob@example.com>;index=1, <sip:bob@192.0.2.4>;index=1.1;rc=1"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! SIP Info-Package header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{InfoPackage, TokenString};
+
+/// Representation of an Info-Package header.
+///
+/// The Info-Package header field is used in an INFO request to indicate the info package
+/// associated with the request.
+///
+/// [[RFC6086, Section 7.2](https://datatracker.ietf.org/doc/html/rfc6086#section-7.2)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct InfoPackageHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    info_package: InfoPackage,
+}
+
+impl InfoPackageHeader {
+    pub(crate) fn new(header: GenericHeader, info_package: InfoPackage) -> Self {
+        Self {
+            header,
+            info_package,
+        }
+    }
+
+    /// Get a reference to the info package from the Info-Package header.
+    pub fn info_package(&self) -> &InfoPackage {
+        &self.info_package
+    }
+}
+
+impl From<InfoPackage> for InfoPackageHeader {
+    fn from(value: InfoPackage) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Info-Package"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
+impl HeaderAccessor for InfoPackageHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Info-Package")
+    }
+    fn normalized_value(&self) -> String {
+        self.info_package.to_string()
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+    };
+
+    use crate::{
+        Header, InfoPackageHeader, TokenString,
+        common::info_package::parser::info_package_type,
+        headers::GenericHeader,
+        parser::{ParserResult, hcolon},
+    };
+
+    pub(crate) fn info_package(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Info-Package header",
+            map(
+                (
+                    map(tag_no_case("Info-Package"), TokenString::new),
+                    hcolon,
+                    cut(consumed(info_package_type)),
+                ),
+                |(name, separator, (value, info_package))| {
+                    Header::InfoPackage(InfoPackageHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        info_package,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, InfoPackage, InfoPackageHeader,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(InfoPackage, InfoPackageHeader, "Info-Package");
+    header_equality!(InfoPackage, "Info-Package");
+    header_inequality!(InfoPackage, "Info-Package");
+
+    #[test]
+    fn test_valid_info_package_header() {
+        valid_header("Info-Package: foo", |header| {
+            assert_eq!(header.info_package().name(), "foo");
+            assert!(header.info_package().parameters().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_valid_info_package_header_with_parameter() {
+        valid_header("Info-Package: foo;bar=baz", |header| {
+            assert_eq!(header.info_package(), "foo");
+            assert_eq!(header.info_package().parameters().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_invalid_info_package_header_empty() {
+        invalid_header("Info-Package:");
+    }
+
+    #[test]
+    fn test_invalid_info_package_header_with_several_packages() {
+        invalid_header("Info-Package: foo, bar");
+    }
+
+    #[test]
+    fn test_info_package_header_equality_with_different_cases() {
+        header_equality("Info-Package: foo", "Info-Package: FOO");
+    }
+
+    #[test]
+    fn test_info_package_header_inequality_different_packages() {
+        header_inequality("Info-Package: foo", "Info-Package: bar");
+    }
+
+    #[test]
+    fn test_info_package_header_to_string() {
+        let header = Header::try_from("info-package :   DTMF");
+        if let Header::InfoPackage(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "info-package :   DTMF");
+            assert_eq!(header.to_normalized_string(), "Info-Package: dtmf");
+            assert_eq!(header.to_compact_string(), "Info-Package: dtmf");
+        } else {
+            panic!("Not an Info-Package header");
+        }
+    }
+
+    #[test]
+    fn test_info_package_header_from_info_package() {
+        let header = InfoPackageHeader::from(InfoPackage::try_from("dtmf").unwrap());
+        assert_eq!(header.to_string(), "Info-Package: dtmf");
+    }
+}
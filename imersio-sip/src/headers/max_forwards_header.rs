@@ -2,6 +2,7 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
+use crate::TokenString;
 use crate::headers::{GenericHeader, HeaderAccessor};
 
 /// Representation of a Max-Forwards header.
@@ -34,6 +35,19 @@ impl MaxForwardsHeader {
     }
 }
 
+impl From<u8> for MaxForwardsHeader {
+    fn from(max_forwards: u8) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Max-Forwards"),
+                ": ".to_string(),
+                max_forwards.to_string(),
+            ),
+            max_forwards,
+        )
+    }
+}
+
 impl HeaderAccessor for MaxForwardsHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -3,6 +3,7 @@
 use chrono::TimeDelta;
 use derive_partial_eq_extras::PartialEqExtras;
 
+use crate::TokenString;
 use crate::headers::{GenericHeader, HeaderAccessor};
 
 /// Representation of a Min-Expires header.
@@ -34,6 +35,19 @@ impl MinExpiresHeader {
     }
 }
 
+impl From<TimeDelta> for MinExpiresHeader {
+    fn from(value: TimeDelta) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Min-Expires"),
+                ": ".to_string(),
+                value.num_seconds().to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for MinExpiresHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -1,5 +1,6 @@
 //! TODO
 
+pub mod accept_contact_header;
 pub mod accept_encoding_header;
 pub mod accept_header;
 pub mod accept_language_header;
@@ -17,27 +18,40 @@ pub mod content_length_header;
 pub mod content_type_header;
 pub mod cseq_header;
 pub mod date_header;
+pub mod diversion_header;
 pub mod error_info_header;
 pub mod expires_header;
 pub mod from_header;
 mod generic_header;
 pub mod header;
 mod header_accessor;
+pub mod history_info_header;
+pub mod identity_header;
 pub mod in_reply_to_header;
+pub mod info_package_header;
 pub mod max_forwards_header;
 pub mod mime_version_header;
 pub mod min_expires_header;
 pub mod organization_header;
+pub mod p_asserted_identity_header;
+pub mod path_header;
 pub mod priority_header;
 pub mod proxy_authenticate_header;
 pub mod proxy_authorization_header;
 pub mod proxy_require_header;
+pub mod reason_header;
 pub mod record_route_header;
+pub mod recv_info_header;
+pub mod reject_contact_header;
 pub mod reply_to_header;
+pub mod request_disposition_header;
 pub mod require_header;
 pub mod retry_after_header;
 pub mod route_header;
 pub mod server_header;
+pub mod service_route_header;
+pub mod sip_etag_header;
+pub mod sip_if_match_header;
 pub mod subject_header;
 pub mod supported_header;
 pub mod timestamp_header;
@@ -0,0 +1,237 @@
+//! SIP P-Asserted-Identity header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{NameAddress, TokenString};
+
+/// Representation of a P-Asserted-Identity header.
+///
+/// The P-Asserted-Identity header field is used among trusted SIP entities to carry the identity
+/// of the user sending a SIP message as it was verified by authentication.
+///
+/// [[RFC3325, Section 9.1](https://datatracker.ietf.org/doc/html/rfc3325#section-9.1)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct PAssertedIdentityHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    identities: Vec<NameAddress>,
+}
+
+impl PAssertedIdentityHeader {
+    pub(crate) fn new(header: GenericHeader, identities: Vec<NameAddress>) -> Self {
+        Self { header, identities }
+    }
+
+    /// Get a reference to the asserted identities from the P-Asserted-Identity header.
+    pub fn identities(&self) -> &Vec<NameAddress> {
+        &self.identities
+    }
+
+    /// Get a `PAssertedIdentityHeader` builder.
+    pub fn builder() -> PAssertedIdentityHeaderBuilder {
+        PAssertedIdentityHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for PAssertedIdentityHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("P-Asserted-Identity")
+    }
+    fn normalized_value(&self) -> String {
+        join(&self.identities, ", ")
+    }
+}
+
+/// Representation of a builder of `P-Asserted-Identity` header.
+#[derive(Clone, Debug, Default)]
+pub struct PAssertedIdentityHeaderBuilder {
+    identities: Vec<NameAddress>,
+}
+
+impl PAssertedIdentityHeaderBuilder {
+    /// Add an asserted identity.
+    pub fn identity<N: Into<NameAddress>>(&mut self, identity: N) -> &mut Self {
+        self.identities.push(identity.into());
+        self
+    }
+
+    /// Clear the list of already added identities.
+    pub fn clear_identities(&mut self) -> &mut Self {
+        self.identities.clear();
+        self
+    }
+
+    /// Build the `PAssertedIdentityHeader`.
+    pub fn build(&self) -> PAssertedIdentityHeader {
+        PAssertedIdentityHeader {
+            header: GenericHeader::new(
+                TokenString::new("P-Asserted-Identity"),
+                ": ".to_string(),
+                join(&self.identities, ", "),
+            ),
+            identities: self.identities.clone(),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, NameAddress, PAssertedIdentityHeader, TokenString,
+        common::contact::parser::{addr_spec, name_addr},
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    fn p_asserted_id_value(input: &str) -> ParserResult<&str, NameAddress> {
+        context(
+            "PAssertedID-value",
+            alt((name_addr, map(addr_spec, NameAddress::from))),
+        )
+        .parse(input)
+    }
+
+    pub(crate) fn p_asserted_identity(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "P-Asserted-Identity header",
+            map(
+                (
+                    map(tag_no_case("P-Asserted-Identity"), TokenString::new),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, p_asserted_id_value))),
+                ),
+                |(name, separator, (value, identities))| {
+                    Header::PAssertedIdentity(PAssertedIdentityHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        identities,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, PAssertedIdentityHeader, Uri,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(
+        PAssertedIdentity,
+        PAssertedIdentityHeader,
+        "P-Asserted-Identity"
+    );
+    header_equality!(PAssertedIdentity, "P-Asserted-Identity");
+    header_inequality!(PAssertedIdentity, "P-Asserted-Identity");
+
+    #[test]
+    fn test_valid_p_asserted_identity_header() {
+        valid_header(
+            r#"P-Asserted-Identity: "Cullen Jennings" <sip:fluffy@cisco.com>, <tel:+14085264000>"#,
+            |header| {
+                assert_eq!(header.identities().len(), 2);
+                assert_eq!(
+                    header.identities()[0].display_name(),
+                    Some("Cullen Jennings")
+                );
+                assert_eq!(
+                    header.identities()[0].uri(),
+                    Uri::try_from("sip:fluffy@cisco.com").unwrap()
+                );
+                assert_eq!(header.identities()[1].display_name(), None);
+                assert_eq!(header.identities()[1].uri().to_string(), "tel:+14085264000");
+            },
+        );
+    }
+
+    #[test]
+    fn test_valid_p_asserted_identity_header_with_addr_spec() {
+        valid_header("P-Asserted-Identity: tel:+14085264000", |header| {
+            assert_eq!(header.identities().len(), 1);
+            assert_eq!(header.identities()[0].uri().to_string(), "tel:+14085264000");
+        });
+    }
+
+    #[test]
+    fn test_invalid_p_asserted_identity_header_empty() {
+        invalid_header("P-Asserted-Identity:");
+    }
+
+    #[test]
+    fn test_invalid_p_asserted_identity_header_with_invalid_character() {
+        invalid_header("P-Asserted-Identity: 😁");
+    }
+
+    #[test]
+    fn test_p_asserted_identity_header_equality_with_space_characters_differences() {
+        header_equality(
+            "P-Asserted-Identity: <sip:alice@atlanta.com>",
+            "P-Asserted-Identity:   <sip:alice@atlanta.com>",
+        );
+    }
+
+    #[test]
+    fn test_p_asserted_identity_header_inequality_different_identities() {
+        header_inequality(
+            "P-Asserted-Identity: <sip:alice@atlanta.com>",
+            "P-Asserted-Identity: <sip:bob@biloxi.com>",
+        );
+    }
+
+    #[test]
+    fn test_p_asserted_identity_header_to_string() {
+        let header = Header::try_from(
+            r#"p-asserted-identity :  "Alice"  <sip:alice@atlanta.com>,<tel:+15551234>"#,
+        );
+        if let Header::PAssertedIdentity(header) = header.unwrap() {
+            assert_eq!(
+                header.to_string(),
+                r#"p-asserted-identity :  "Alice"  <sip:alice@atlanta.com>,<tel:+15551234>"#
+            );
+            assert_eq!(
+                header.to_normalized_string(),
+                r#"P-Asserted-Identity: "Alice" <sip:alice@atlanta.com>, <tel:+15551234>"#
+            );
+            assert_eq!(
+                header.to_compact_string(),
+                r#"P-Asserted-Identity: "Alice" <sip:alice@atlanta.com>, <tel:+15551234>"#
+            );
+        } else {
+            panic!("Not a P-Asserted-Identity header");
+        }
+    }
+
+    #[test]
+    fn test_p_asserted_identity_header_builder() {
+        let header = PAssertedIdentityHeader::builder()
+            .identity(Uri::try_from("sip:alice@atlanta.com").unwrap())
+            .build();
+        assert_eq!(
+            header.to_string(),
+            "P-Asserted-Identity: <sip:alice@atlanta.com>"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! SIP Path header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Route, RouteHeader, Routes, TokenString};
+
+/// Representation of a Path header.
+///
+/// The Path header field is inserted in a REGISTER request by the proxies between the user agent
+/// and the registrar, so that requests for the registered contact can later be routed back through
+/// them.
+///
+/// [[RFC3327, Section 4](https://datatracker.ietf.org/doc/html/rfc3327#section-4)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct PathHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    routes: Routes,
+}
+
+impl PathHeader {
+    pub(crate) fn new(header: GenericHeader, routes: Vec<Route>) -> Self {
+        Self {
+            header,
+            routes: routes.into(),
+        }
+    }
+
+    /// Get a reference to the routes from the Path header.
+    pub fn routes(&self) -> &Routes {
+        &self.routes
+    }
+
+    /// Get the Route header to preload in requests sent to the registered contact.
+    ///
+    /// The path vector stored by the registrar is used as is, in the same order.
+    ///
+    /// [[RFC3327, Section 5.3](https://datatracker.ietf.org/doc/html/rfc3327#section-5.3)]
+    pub fn to_route_header(&self) -> RouteHeader {
+        RouteHeader::builder()
+            .routes(self.routes.iter().cloned())
+            .build()
+    }
+
+    /// Get a `PathHeader` builder.
+    pub fn builder() -> PathHeaderBuilder {
+        PathHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for PathHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Path")
+    }
+    fn normalized_value(&self) -> String {
+        self.routes.to_string()
+    }
+}
+
+/// Representation of a builder of `Path` header.
+#[derive(Clone, Debug, Default)]
+pub struct PathHeaderBuilder {
+    routes: Routes,
+}
+
+impl PathHeaderBuilder {
+    /// Add a route.
+    pub fn route<R: Into<Route>>(&mut self, route: R) -> &mut Self {
+        self.routes.push(route.into());
+        self
+    }
+
+    /// Add several routes.
+    pub fn routes<I: IntoIterator<Item = Route>>(&mut self, routes: I) -> &mut Self {
+        self.routes.extend(routes);
+        self
+    }
+
+    /// Clear the list of already added routes.
+    pub fn clear_routes(&mut self) -> &mut Self {
+        self.routes.clear();
+        self
+    }
+
+    /// Build the `PathHeader`.
+    pub fn build(&self) -> PathHeader {
+        PathHeader {
+            header: GenericHeader::new(
+                TokenString::new("Path"),
+                ": ".to_string(),
+                self.routes.to_string(),
+            ),
+            routes: Clone::clone(&self.routes),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, PathHeader, TokenString,
+        common::route::parser::route_spec,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn path(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Path header",
+            map(
+                (
+                    map(tag_no_case("Path"), TokenString::new),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, route_spec))),
+                ),
+                |(name, separator, (value, routes))| {
+                    Header::Path(PathHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        routes,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, PathHeader, Route, Uri,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(Path, PathHeader, "Path");
+    header_equality!(Path, "Path");
+    header_inequality!(Path, "Path");
+
+    #[test]
+    fn test_valid_path_header() {
+        valid_header(
+            r#"Path: <sip:P3.EXAMPLEHOME.COM;lr>,<sip:P1.EXAMPLEVISITED.COM;lr>"#,
+            |header| {
+                assert_eq!(header.routes().len(), 2);
+                let mut routes = header.routes().iter();
+                let first_route = routes.next().unwrap();
+                assert_eq!(first_route.name_address().display_name(), None);
+                assert_eq!(
+                    first_route.name_address().uri(),
+                    Uri::try_from("sip:P3.EXAMPLEHOME.COM;lr").unwrap()
+                );
+                let second_route = routes.next().unwrap();
+                assert_eq!(second_route.name_address().display_name(), None);
+                assert_eq!(
+                    second_route.name_address().uri(),
+                    Uri::try_from("sip:P1.EXAMPLEVISITED.COM;lr").unwrap()
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_invalid_path_header_empty() {
+        invalid_header("Path:");
+    }
+
+    #[test]
+    fn test_invalid_path_header_empty_with_space_characters() {
+        invalid_header("Path:    ");
+    }
+
+    #[test]
+    fn test_invalid_path_header_with_invalid_character() {
+        invalid_header("Path: 😁");
+    }
+
+    #[test]
+    fn test_path_header_equality_same_header_with_space_characters_differences() {
+        header_equality(
+            r#"Path: <sip:P1.EXAMPLEVISITED.COM;lr>"#,
+            r#"Path:    <sip:P1.EXAMPLEVISITED.COM;lr>"#,
+        );
+    }
+
+    #[test]
+    fn test_path_header_equality_same_header_with_different_cases() {
+        header_equality(
+            r#"Path: <sip:p1.examplevisited.com;lr>"#,
+            r#"Path: <SIP:P1.EXAMPLEVISITED.COM;LR>"#,
+        );
+    }
+
+    #[test]
+    fn test_path_header_inequality_different_uris() {
+        header_inequality(
+            r#"Path: <sip:P1.EXAMPLEVISITED.COM;lr>"#,
+            r#"Path: <sip:P3.EXAMPLEHOME.COM;lr>"#,
+        );
+    }
+
+    #[test]
+    fn test_path_header_to_string() {
+        let header = Header::try_from(r#"path :    <Sip:P1.EXAMPLEVISITED.COM;LR>"#);
+        if let Header::Path(header) = header.unwrap() {
+            assert_eq!(
+                header.to_string(),
+                r#"path :    <Sip:P1.EXAMPLEVISITED.COM;LR>"#
+            );
+            assert_eq!(
+                header.to_normalized_string(),
+                r#"Path: <sip:P1.EXAMPLEVISITED.COM;LR>"#
+            );
+            assert_eq!(
+                header.to_compact_string(),
+                r#"Path: <sip:P1.EXAMPLEVISITED.COM;LR>"#
+            );
+        }
+    }
+
+    #[test]
+    fn test_valid_path_header_builder() {
+        let header = PathHeader::builder()
+            .route(Uri::try_from("sip:edge.example.com;lr").unwrap())
+            .build();
+        assert_eq!(header.routes().len(), 1);
+        assert_eq!(
+            header.routes().first().unwrap(),
+            &Route::from(Uri::try_from("sip:edge.example.com;lr").unwrap())
+        );
+        assert_eq!(header.to_string(), "Path: <sip:edge.example.com;lr>");
+    }
+
+    #[test]
+    fn test_path_header_to_route_header() {
+        let header =
+            Header::try_from("Path: <sip:P3.EXAMPLEHOME.COM;lr>, <sip:P1.EXAMPLEVISITED.COM;lr>");
+        if let Header::Path(header) = header.unwrap() {
+            let route_header = header.to_route_header();
+            assert_eq!(route_header.routes(), header.routes());
+            assert_eq!(
+                route_header.to_string(),
+                "Route: <sip:P3.EXAMPLEHOME.COM;lr>, <sip:P1.EXAMPLEVISITED.COM;lr>"
+            );
+        } else {
+            panic!("Not a Path header");
+        }
+    }
+}
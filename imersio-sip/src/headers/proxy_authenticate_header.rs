@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::Challenge;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Challenge, TokenString};
 
 /// Representation of a Proxy-Authenticate header.
 ///
@@ -29,6 +29,19 @@ impl ProxyAuthenticateHeader {
     }
 }
 
+impl From<Challenge> for ProxyAuthenticateHeader {
+    fn from(value: Challenge) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Proxy-Authenticate"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for ProxyAuthenticateHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::Credentials;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Credentials, TokenString};
 
 /// Representation of a Proxy-Authorization header.
 ///
@@ -35,6 +35,19 @@ impl ProxyAuthorizationHeader {
     }
 }
 
+impl From<Credentials> for ProxyAuthorizationHeader {
+    fn from(value: Credentials) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Proxy-Authorization"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for ProxyAuthorizationHeader {
     crate::headers::generic_header_accessors!(header);
 
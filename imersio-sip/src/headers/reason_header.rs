@@ -0,0 +1,287 @@
+//! SIP Reason header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{ReasonProtocol, ReasonValue, ReasonValues, StatusCode, TokenString};
+
+/// Representation of a Reason header.
+///
+/// The Reason header field indicates why a SIP request was issued, eg. why a call was released
+/// with a BYE or a CANCEL, or why a response was sent when carried in it. The cause is expressed
+/// either as a SIP status code or as an ITU-T Q.850 cause value, so that the reason of a release
+/// can be conveyed across SIP and ISUP legs.
+///
+/// [[RFC3326, Section 2](https://datatracker.ietf.org/doc/html/rfc3326#section-2)]
+/// [[RFC6432, Section 2](https://datatracker.ietf.org/doc/html/rfc6432#section-2)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct ReasonHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    reasons: ReasonValues,
+}
+
+impl ReasonHeader {
+    pub(crate) fn new(header: GenericHeader, reasons: Vec<ReasonValue>) -> Self {
+        Self {
+            header,
+            reasons: reasons.into(),
+        }
+    }
+
+    /// Get a reference to the reasons from the Reason header.
+    pub fn reasons(&self) -> &ReasonValues {
+        &self.reasons
+    }
+
+    /// Get the SIP status code of the release, taken from the SIP reason or mapped from the Q.850
+    /// reason if there is no SIP reason.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        self.reason_with_protocol(&ReasonProtocol::Sip)
+            .and_then(ReasonValue::status_code)
+            .or_else(|| {
+                self.reason_with_protocol(&ReasonProtocol::Q850)
+                    .and_then(ReasonValue::to_status_code)
+            })
+    }
+
+    /// Get the Q.850 cause value of the release, taken from the Q.850 reason or mapped from the SIP
+    /// reason if there is no Q.850 reason.
+    pub fn q850_cause(&self) -> Option<u8> {
+        self.reason_with_protocol(&ReasonProtocol::Q850)
+            .and_then(ReasonValue::q850_cause)
+            .or_else(|| {
+                self.reason_with_protocol(&ReasonProtocol::Sip)
+                    .and_then(ReasonValue::to_q850_cause)
+            })
+    }
+
+    fn reason_with_protocol(&self, protocol: &ReasonProtocol) -> Option<&ReasonValue> {
+        self.reasons
+            .iter()
+            .find(|reason| reason.protocol() == protocol)
+    }
+
+    /// Get a `ReasonHeader` builder.
+    pub fn builder() -> ReasonHeaderBuilder {
+        ReasonHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for ReasonHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Reason")
+    }
+    fn normalized_value(&self) -> String {
+        self.reasons.to_string()
+    }
+}
+
+/// Representation of a builder of `Reason` header.
+#[derive(Clone, Debug, Default)]
+pub struct ReasonHeaderBuilder {
+    reasons: ReasonValues,
+}
+
+impl ReasonHeaderBuilder {
+    /// Add a reason.
+    pub fn reason(&mut self, reason: ReasonValue) -> &mut Self {
+        self.reasons.push(reason);
+        self
+    }
+
+    /// Add several reasons.
+    pub fn reasons<I: IntoIterator<Item = ReasonValue>>(&mut self, reasons: I) -> &mut Self {
+        self.reasons.extend(reasons);
+        self
+    }
+
+    /// Clear the list of already added reasons.
+    pub fn clear_reasons(&mut self) -> &mut Self {
+        self.reasons.clear();
+        self
+    }
+
+    /// Build the `ReasonHeader`.
+    pub fn build(&self) -> ReasonHeader {
+        ReasonHeader {
+            header: GenericHeader::new(
+                TokenString::new("Reason"),
+                ": ".to_string(),
+                self.reasons.to_string(),
+            ),
+            reasons: Clone::clone(&self.reasons),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, ReasonHeader, TokenString,
+        common::reason_value::parser::reason_value,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn reason(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Reason header",
+            map(
+                (
+                    map(tag_no_case("Reason"), TokenString::new),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, reason_value))),
+                ),
+                |(name, separator, (value, reasons))| {
+                    Header::Reason(ReasonHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        reasons,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, Reason, ReasonHeader, ReasonProtocol, ReasonValue, StatusCode,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(Reason, ReasonHeader, "Reason");
+    header_equality!(Reason, "Reason");
+    header_inequality!(Reason, "Reason");
+
+    #[test]
+    fn test_valid_reason_header_sip() {
+        valid_header(
+            r#"Reason: SIP ;cause=200 ;text="Call completed elsewhere""#,
+            |header| {
+                assert_eq!(header.reasons().len(), 1);
+                let reason = header.reasons().first().unwrap();
+                assert_eq!(reason.protocol(), &ReasonProtocol::Sip);
+                assert_eq!(reason.cause(), Some(200));
+                assert_eq!(reason.text(), Some("Call completed elsewhere"));
+                assert_eq!(header.status_code(), Some(StatusCode::OK));
+                assert_eq!(header.q850_cause(), None);
+            },
+        );
+    }
+
+    #[test]
+    fn test_valid_reason_header_q850() {
+        valid_header(r#"Reason: Q.850 ;cause=16 ;text="Terminated""#, |header| {
+            let reason = header.reasons().first().unwrap();
+            assert_eq!(reason.protocol(), &ReasonProtocol::Q850);
+            assert_eq!(header.q850_cause(), Some(16));
+            assert_eq!(header.status_code(), None);
+        });
+    }
+
+    #[test]
+    fn test_valid_reason_header_with_several_protocols() {
+        valid_header("Reason: SIP;cause=480, Q.850;cause=19", |header| {
+            assert_eq!(header.reasons().len(), 2);
+            assert_eq!(
+                header.status_code(),
+                Some(StatusCode::TEMPORARILY_UNAVAILABLE)
+            );
+            assert_eq!(header.q850_cause(), Some(19));
+        });
+    }
+
+    #[test]
+    fn test_valid_reason_header_with_mapped_causes() {
+        valid_header("Reason: Q.850;cause=17", |header| {
+            assert_eq!(header.status_code(), Some(StatusCode::BUSY_HERE));
+        });
+        valid_header("Reason: SIP;cause=486", |header| {
+            assert_eq!(header.q850_cause(), Some(17));
+        });
+    }
+
+    #[test]
+    fn test_valid_reason_header_with_extension_protocol() {
+        valid_header("Reason: preemption;cause=1;foo=bar", |header| {
+            let reason = header.reasons().first().unwrap();
+            assert!(reason.protocol().is_other());
+            assert_eq!(reason.parameters().len(), 2);
+            assert_eq!(header.status_code(), None);
+            assert_eq!(header.q850_cause(), None);
+        });
+    }
+
+    #[test]
+    fn test_invalid_reason_header_empty() {
+        invalid_header("Reason:");
+    }
+
+    #[test]
+    fn test_invalid_reason_header_with_invalid_text() {
+        invalid_header("Reason: SIP;text=\"unterminated");
+    }
+
+    #[test]
+    fn test_reason_header_equality_with_different_cases() {
+        header_equality("Reason: SIP;cause=486", "Reason: sip;CAUSE=486");
+    }
+
+    #[test]
+    fn test_reason_header_equality_with_different_parameters_order() {
+        header_equality(
+            r#"Reason: SIP;cause=486;text="Busy Here""#,
+            r#"Reason: SIP;text="Busy Here";cause=486"#,
+        );
+    }
+
+    #[test]
+    fn test_reason_header_inequality_different_causes() {
+        header_inequality("Reason: SIP;cause=486", "Reason: SIP;cause=600");
+    }
+
+    #[test]
+    fn test_reason_header_to_string() {
+        let header = Header::try_from(r#"reason :   q.850 ;  CAUSE=16"#);
+        if let Header::Reason(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "reason :   q.850 ;  CAUSE=16");
+            assert_eq!(header.to_normalized_string(), "Reason: Q.850;cause=16");
+            assert_eq!(header.to_compact_string(), "Reason: Q.850;cause=16");
+        } else {
+            panic!("Not a Reason header");
+        }
+    }
+
+    #[test]
+    fn test_valid_reason_header_builder() {
+        let header = ReasonHeader::builder()
+            .reason(ReasonValue::from_reason(&Reason::BUSY_HERE))
+            .reason(ReasonValue::from_q850_cause(17))
+            .build();
+        assert_eq!(header.reasons().len(), 2);
+        assert_eq!(
+            header.to_string(),
+            r#"Reason: SIP;cause=486;text="Busy Here", Q.850;cause=17"#
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! SIP Recv-Info header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{InfoPackage, InfoPackages, TokenString};
+
+/// Representation of a Recv-Info header.
+///
+/// The Recv-Info header field is used to indicate the info packages the sender is willing to
+/// receive INFO requests for, in the dialog being established or already established. An empty
+/// list indicates that the sender is not willing to receive any INFO request.
+///
+/// [[RFC6086, Section 7.3](https://datatracker.ietf.org/doc/html/rfc6086#section-7.3)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct RecvInfoHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    info_packages: InfoPackages,
+}
+
+impl RecvInfoHeader {
+    pub(crate) fn new(header: GenericHeader, info_packages: Vec<InfoPackage>) -> Self {
+        Self {
+            header,
+            info_packages: info_packages.into(),
+        }
+    }
+
+    /// Get a reference to the info packages from the Recv-Info header.
+    pub fn info_packages(&self) -> &InfoPackages {
+        &self.info_packages
+    }
+
+    /// Tell whether the Recv-Info header contains the given info package.
+    pub fn contains(&self, info_package: &str) -> bool {
+        self.info_packages
+            .iter()
+            .any(|package| package == info_package)
+    }
+
+    /// Get a `RecvInfoHeader` builder.
+    pub fn builder() -> RecvInfoHeaderBuilder {
+        RecvInfoHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for RecvInfoHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Recv-Info")
+    }
+    fn normalized_value(&self) -> String {
+        self.info_packages.to_string()
+    }
+}
+
+/// Representation of a builder of `Recv-Info` header.
+#[derive(Clone, Debug, Default)]
+pub struct RecvInfoHeaderBuilder {
+    info_packages: InfoPackages,
+}
+
+impl RecvInfoHeaderBuilder {
+    /// Add an info package.
+    pub fn info_package(&mut self, info_package: InfoPackage) -> &mut Self {
+        self.info_packages.push(info_package);
+        self
+    }
+
+    /// Add several info packages.
+    pub fn info_packages<I: IntoIterator<Item = InfoPackage>>(
+        &mut self,
+        info_packages: I,
+    ) -> &mut Self {
+        self.info_packages.extend(info_packages);
+        self
+    }
+
+    /// Clear the list of already added info packages.
+    pub fn clear_info_packages(&mut self) -> &mut Self {
+        self.info_packages.clear();
+        self
+    }
+
+    /// Build the `RecvInfoHeader`.
+    pub fn build(&self) -> RecvInfoHeader {
+        RecvInfoHeader {
+            header: GenericHeader::new(
+                TokenString::new("Recv-Info"),
+                ": ".to_string(),
+                self.info_packages.to_string(),
+            ),
+            info_packages: Clone::clone(&self.info_packages),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map, opt},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, RecvInfoHeader, TokenString,
+        common::info_package::parser::info_package_type,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn recv_info(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Recv-Info header",
+            map(
+                (
+                    map(tag_no_case("Recv-Info"), TokenString::new),
+                    hcolon,
+                    cut(consumed(opt(separated_list1(comma, info_package_type)))),
+                ),
+                |(name, separator, (value, info_packages))| {
+                    Header::RecvInfo(RecvInfoHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        info_packages.unwrap_or_default(),
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, InfoPackage, RecvInfoHeader,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(RecvInfo, RecvInfoHeader, "Recv-Info");
+    header_equality!(RecvInfo, "Recv-Info");
+    header_inequality!(RecvInfo, "Recv-Info");
+
+    #[test]
+    fn test_valid_recv_info_header() {
+        valid_header("Recv-Info: foo, bar;baz", |header| {
+            assert_eq!(header.info_packages().len(), 2);
+            assert!(header.contains("foo"));
+            assert!(header.contains("BAR"));
+            assert!(!header.contains("dtmf"));
+        });
+    }
+
+    #[test]
+    fn test_valid_recv_info_header_empty() {
+        valid_header("Recv-Info:", |header| {
+            assert!(header.info_packages().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_invalid_recv_info_header_with_invalid_character() {
+        invalid_header("Recv-Info: foo, b@r");
+    }
+
+    #[test]
+    fn test_recv_info_header_equality_with_different_order() {
+        header_equality("Recv-Info: foo, bar", "Recv-Info: bar, foo");
+    }
+
+    #[test]
+    fn test_recv_info_header_inequality_different_packages() {
+        header_inequality("Recv-Info: foo", "Recv-Info: bar");
+    }
+
+    #[test]
+    fn test_recv_info_header_to_string() {
+        let header = Header::try_from("recv-info :   Foo ,  bar");
+        if let Header::RecvInfo(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "recv-info :   Foo ,  bar");
+            assert_eq!(header.to_normalized_string(), "Recv-Info: foo, bar");
+            assert_eq!(header.to_compact_string(), "Recv-Info: foo, bar");
+        } else {
+            panic!("Not a Recv-Info header");
+        }
+    }
+
+    #[test]
+    fn test_valid_recv_info_header_builder() {
+        let header = RecvInfoHeader::builder()
+            .info_package(InfoPackage::try_from("dtmf").unwrap())
+            .info_package(InfoPackage::try_from("foo").unwrap())
+            .build();
+        assert_eq!(header.to_string(), "Recv-Info: dtmf, foo");
+    }
+}
@@ -0,0 +1,161 @@
+//! SIP Reject-Contact header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{CallerPreference, TokenString};
+
+/// Representation of a Reject-Contact header.
+///
+/// The Reject-Contact header field lists the feature sets of the contacts the caller does not
+/// want the request to be routed to.
+///
+/// [[RFC3841, Section 10](https://datatracker.ietf.org/doc/html/rfc3841#section-10)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct RejectContactHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    preferences: Vec<CallerPreference>,
+}
+
+impl RejectContactHeader {
+    pub(crate) fn new(header: GenericHeader, preferences: Vec<CallerPreference>) -> Self {
+        Self {
+            header,
+            preferences,
+        }
+    }
+
+    /// Get a reference to the caller preferences from the Reject-Contact header.
+    pub fn preferences(&self) -> &Vec<CallerPreference> {
+        &self.preferences
+    }
+}
+
+impl HeaderAccessor for RejectContactHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        Some("j")
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Reject-Contact")
+    }
+    fn normalized_value(&self) -> String {
+        join(&self.preferences, ", ")
+    }
+}
+
+impl From<Vec<CallerPreference>> for RejectContactHeader {
+    fn from(value: Vec<CallerPreference>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Reject-Contact"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, RejectContactHeader, TokenString,
+        common::caller_preference::parser::caller_preference,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn reject_contact(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Reject-Contact header",
+            map(
+                (
+                    map(
+                        alt((tag_no_case("Reject-Contact"), tag_no_case("j"))),
+                        TokenString::new,
+                    ),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, caller_preference))),
+                ),
+                |(name, separator, (value, preferences))| {
+                    Header::RejectContact(RejectContactHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        preferences,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, RejectContactHeader,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(RejectContact, RejectContactHeader, "Reject-Contact");
+    header_equality!(RejectContact, "Reject-Contact");
+    header_inequality!(RejectContact, "Reject-Contact");
+
+    #[test]
+    fn test_valid_reject_contact_header() {
+        valid_header(r#"Reject-Contact: *;actor="msg-taker";video"#, |header| {
+            assert_eq!(header.preferences().len(), 1);
+            assert_eq!(header.preferences()[0].feature_tags().count(), 2);
+        });
+    }
+
+    #[test]
+    fn test_valid_reject_contact_header_in_compact_form() {
+        valid_header("j: *;automata", |header| {
+            assert_eq!(header.preferences().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_invalid_reject_contact_header_empty() {
+        invalid_header("Reject-Contact:");
+    }
+
+    #[test]
+    fn test_reject_contact_header_equality_with_space_characters_differences() {
+        header_equality("Reject-Contact: *;video", "Reject-Contact:    *;video");
+    }
+
+    #[test]
+    fn test_reject_contact_header_inequality_different_features() {
+        header_inequality("Reject-Contact: *;video", "Reject-Contact: *;audio");
+    }
+
+    #[test]
+    fn test_reject_contact_header_to_string() {
+        let header = Header::try_from("reject-contact :  *;Video");
+        if let Header::RejectContact(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "reject-contact :  *;Video");
+            assert_eq!(header.to_normalized_string(), "Reject-Contact: *;video");
+            assert_eq!(header.to_compact_string(), "j: *;video");
+        } else {
+            panic!("Not a Reject-Contact header");
+        }
+    }
+}
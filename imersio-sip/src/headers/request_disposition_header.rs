@@ -0,0 +1,185 @@
+//! SIP Request-Disposition header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Directive, TokenString};
+
+/// Representation of a Request-Disposition header.
+///
+/// The Request-Disposition header field specifies caller preferences for how a proxy handles
+/// the request, eg. whether it forks it, or whether it proxies or redirects it.
+///
+/// [[RFC3841, Section 10](https://datatracker.ietf.org/doc/html/rfc3841#section-10)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct RequestDispositionHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    directives: Vec<Directive>,
+}
+
+impl RequestDispositionHeader {
+    pub(crate) fn new(header: GenericHeader, directives: Vec<Directive>) -> Self {
+        Self { header, directives }
+    }
+
+    /// Get a reference to the directives from the Request-Disposition header.
+    pub fn directives(&self) -> &Vec<Directive> {
+        &self.directives
+    }
+
+    /// Tell whether the Request-Disposition header contains the given directive.
+    pub fn contains(&self, directive: Directive) -> bool {
+        self.directives.contains(&directive)
+    }
+}
+
+impl HeaderAccessor for RequestDispositionHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        Some("d")
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Request-Disposition")
+    }
+    fn normalized_value(&self) -> String {
+        join(&self.directives, ", ")
+    }
+}
+
+impl From<Vec<Directive>> for RequestDispositionHeader {
+    fn from(value: Vec<Directive>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Request-Disposition"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, RequestDispositionHeader, TokenString,
+        common::directive::parser::directive,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn request_disposition(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Request-Disposition header",
+            map(
+                (
+                    map(
+                        alt((tag_no_case("Request-Disposition"), tag_no_case("d"))),
+                        TokenString::new,
+                    ),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, directive))),
+                ),
+                |(name, separator, (value, directives))| {
+                    Header::RequestDisposition(RequestDispositionHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        directives,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Directive, Header, RequestDispositionHeader,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(
+        RequestDisposition,
+        RequestDispositionHeader,
+        "Request-Disposition"
+    );
+    header_equality!(RequestDisposition, "Request-Disposition");
+    header_inequality!(RequestDisposition, "Request-Disposition");
+
+    #[test]
+    fn test_valid_request_disposition_header() {
+        valid_header("Request-Disposition: proxy, recurse, parallel", |header| {
+            assert_eq!(
+                header.directives(),
+                &vec![Directive::Proxy, Directive::Recurse, Directive::Parallel]
+            );
+            assert!(header.contains(Directive::Parallel));
+            assert!(!header.contains(Directive::NoFork));
+        });
+    }
+
+    #[test]
+    fn test_valid_request_disposition_header_in_compact_form() {
+        valid_header("d: No-Fork", |header| {
+            assert_eq!(header.directives(), &vec![Directive::NoFork]);
+        });
+    }
+
+    #[test]
+    fn test_invalid_request_disposition_header_empty() {
+        invalid_header("Request-Disposition:");
+    }
+
+    #[test]
+    fn test_invalid_request_disposition_header_unknown_directive() {
+        invalid_header("Request-Disposition: proxyish");
+    }
+
+    #[test]
+    fn test_request_disposition_header_equality_with_space_characters_differences() {
+        header_equality("Request-Disposition: fork", "Request-Disposition:   fork");
+    }
+
+    #[test]
+    fn test_request_disposition_header_inequality_different_directives() {
+        header_inequality("Request-Disposition: fork", "Request-Disposition: no-fork");
+    }
+
+    #[test]
+    fn test_request_disposition_header_to_string() {
+        let header = Header::try_from("d :  Sequential,NO-cancel");
+        if let Header::RequestDisposition(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "d :  Sequential,NO-cancel");
+            assert_eq!(
+                header.to_normalized_string(),
+                "Request-Disposition: sequential, no-cancel"
+            );
+            assert_eq!(header.to_compact_string(), "d: sequential, no-cancel");
+        } else {
+            panic!("Not a Request-Disposition header");
+        }
+    }
+
+    #[test]
+    fn test_request_disposition_header_from_directives() {
+        let header = RequestDispositionHeader::from(vec![Directive::Redirect]);
+        assert_eq!(header.to_string(), "Request-Disposition: redirect");
+    }
+}
@@ -3,7 +3,7 @@
 use derive_partial_eq_extras::PartialEqExtras;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{Route, Routes};
+use crate::{Route, Routes, TokenString};
 
 /// Representation of a Route header.
 ///
@@ -30,6 +30,11 @@ impl RouteHeader {
     pub fn routes(&self) -> &Routes {
         &self.routes
     }
+
+    /// Get a `RouteHeader` builder.
+    pub fn builder() -> RouteHeaderBuilder {
+        RouteHeaderBuilder::default()
+    }
 }
 
 impl HeaderAccessor for RouteHeader {
@@ -46,6 +51,44 @@ impl HeaderAccessor for RouteHeader {
     }
 }
 
+/// Representation of a builder of `Route` header.
+#[derive(Clone, Debug, Default)]
+pub struct RouteHeaderBuilder {
+    routes: Routes,
+}
+
+impl RouteHeaderBuilder {
+    /// Add a route.
+    pub fn route<R: Into<Route>>(&mut self, route: R) -> &mut Self {
+        self.routes.push(route.into());
+        self
+    }
+
+    /// Add several routes.
+    pub fn routes<I: IntoIterator<Item = Route>>(&mut self, routes: I) -> &mut Self {
+        self.routes.extend(routes);
+        self
+    }
+
+    /// Clear the list of already added routes.
+    pub fn clear_routes(&mut self) -> &mut Self {
+        self.routes.clear();
+        self
+    }
+
+    /// Build the `RouteHeader`.
+    pub fn build(&self) -> RouteHeader {
+        RouteHeader {
+            header: GenericHeader::new(
+                TokenString::new("Route"),
+                ": ".to_string(),
+                self.routes.to_string(),
+            ),
+            routes: Clone::clone(&self.routes),
+        }
+    }
+}
+
 pub(crate) mod parser {
     use nom::{
         Parser,
@@ -194,4 +237,21 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_valid_route_header_builder() {
+        let header = RouteHeader::builder()
+            .route(Uri::try_from("sip:p1.example.com;lr").unwrap())
+            .route(Uri::try_from("sip:p2.example.com;lr").unwrap())
+            .build();
+        assert_eq!(header.routes().len(), 2);
+        assert_eq!(
+            header.to_string(),
+            "Route: <sip:p1.example.com;lr>, <sip:p2.example.com;lr>"
+        );
+        assert_eq!(
+            header.to_normalized_string(),
+            "Route: <sip:p1.example.com;lr>, <sip:p2.example.com;lr>"
+        );
+    }
 }
@@ -0,0 +1,269 @@
+//! SIP Service-Route header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Route, RouteHeader, Routes, TokenString};
+
+/// Representation of a Service-Route header.
+///
+/// The Service-Route header field is returned by a registrar in the 200 response to a REGISTER
+/// request, to tell the user agent the route to use for the requests it sends afterwards.
+///
+/// [[RFC3608, Section 5](https://datatracker.ietf.org/doc/html/rfc3608#section-5)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct ServiceRouteHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    routes: Routes,
+}
+
+impl ServiceRouteHeader {
+    pub(crate) fn new(header: GenericHeader, routes: Vec<Route>) -> Self {
+        Self {
+            header,
+            routes: routes.into(),
+        }
+    }
+
+    /// Get a reference to the routes from the Service-Route header.
+    pub fn routes(&self) -> &Routes {
+        &self.routes
+    }
+
+    /// Get the Route header to preload in the requests sent by the user agent.
+    ///
+    /// The service route is used as is, in the same order.
+    ///
+    /// [[RFC3608, Section 6.1](https://datatracker.ietf.org/doc/html/rfc3608#section-6.1)]
+    pub fn to_route_header(&self) -> RouteHeader {
+        RouteHeader::builder()
+            .routes(self.routes.iter().cloned())
+            .build()
+    }
+
+    /// Get a `ServiceRouteHeader` builder.
+    pub fn builder() -> ServiceRouteHeaderBuilder {
+        ServiceRouteHeaderBuilder::default()
+    }
+}
+
+impl HeaderAccessor for ServiceRouteHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("Service-Route")
+    }
+    fn normalized_value(&self) -> String {
+        self.routes.to_string()
+    }
+}
+
+/// Representation of a builder of `Service-Route` header.
+#[derive(Clone, Debug, Default)]
+pub struct ServiceRouteHeaderBuilder {
+    routes: Routes,
+}
+
+impl ServiceRouteHeaderBuilder {
+    /// Add a route.
+    pub fn route<R: Into<Route>>(&mut self, route: R) -> &mut Self {
+        self.routes.push(route.into());
+        self
+    }
+
+    /// Add several routes.
+    pub fn routes<I: IntoIterator<Item = Route>>(&mut self, routes: I) -> &mut Self {
+        self.routes.extend(routes);
+        self
+    }
+
+    /// Clear the list of already added routes.
+    pub fn clear_routes(&mut self) -> &mut Self {
+        self.routes.clear();
+        self
+    }
+
+    /// Build the `ServiceRouteHeader`.
+    pub fn build(&self) -> ServiceRouteHeader {
+        ServiceRouteHeader {
+            header: GenericHeader::new(
+                TokenString::new("Service-Route"),
+                ": ".to_string(),
+                self.routes.to_string(),
+            ),
+            routes: Clone::clone(&self.routes),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+        multi::separated_list1,
+    };
+
+    use crate::{
+        Header, ServiceRouteHeader, TokenString,
+        common::route::parser::route_spec,
+        headers::GenericHeader,
+        parser::{ParserResult, comma, hcolon},
+    };
+
+    pub(crate) fn service_route(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "Service-Route header",
+            map(
+                (
+                    map(tag_no_case("Service-Route"), TokenString::new),
+                    hcolon,
+                    cut(consumed(separated_list1(comma, route_spec))),
+                ),
+                |(name, separator, (value, routes))| {
+                    Header::ServiceRoute(ServiceRouteHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        routes,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, Route, ServiceRouteHeader, Uri,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(ServiceRoute, ServiceRouteHeader, "Service-Route");
+    header_equality!(ServiceRoute, "Service-Route");
+    header_inequality!(ServiceRoute, "Service-Route");
+
+    #[test]
+    fn test_valid_service_route_header() {
+        valid_header(
+            r#"Service-Route: <sip:P2.HOME.EXAMPLE.COM;lr>,<sip:HSP.HOME.EXAMPLE.COM;lr>"#,
+            |header| {
+                assert_eq!(header.routes().len(), 2);
+                let mut routes = header.routes().iter();
+                let first_route = routes.next().unwrap();
+                assert_eq!(first_route.name_address().display_name(), None);
+                assert_eq!(
+                    first_route.name_address().uri(),
+                    Uri::try_from("sip:P2.HOME.EXAMPLE.COM;lr").unwrap()
+                );
+                let second_route = routes.next().unwrap();
+                assert_eq!(second_route.name_address().display_name(), None);
+                assert_eq!(
+                    second_route.name_address().uri(),
+                    Uri::try_from("sip:HSP.HOME.EXAMPLE.COM;lr").unwrap()
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_invalid_service_route_header_empty() {
+        invalid_header("Service-Route:");
+    }
+
+    #[test]
+    fn test_invalid_service_route_header_empty_with_space_characters() {
+        invalid_header("Service-Route:    ");
+    }
+
+    #[test]
+    fn test_invalid_service_route_header_with_invalid_character() {
+        invalid_header("Service-Route: 😁");
+    }
+
+    #[test]
+    fn test_service_route_header_equality_same_header_with_space_characters_differences() {
+        header_equality(
+            r#"Service-Route: <sip:P2.HOME.EXAMPLE.COM;lr>"#,
+            r#"Service-Route:    <sip:P2.HOME.EXAMPLE.COM;lr>"#,
+        );
+    }
+
+    #[test]
+    fn test_service_route_header_equality_same_header_with_different_cases() {
+        header_equality(
+            r#"Service-Route: <sip:p2.home.example.com;lr>"#,
+            r#"Service-Route: <SIP:P2.HOME.EXAMPLE.COM;LR>"#,
+        );
+    }
+
+    #[test]
+    fn test_service_route_header_inequality_different_uris() {
+        header_inequality(
+            r#"Service-Route: <sip:P2.HOME.EXAMPLE.COM;lr>"#,
+            r#"Service-Route: <sip:HSP.HOME.EXAMPLE.COM;lr>"#,
+        );
+    }
+
+    #[test]
+    fn test_service_route_header_to_string() {
+        let header = Header::try_from(r#"service-route :    <Sip:P2.HOME.EXAMPLE.COM;LR>"#);
+        if let Header::ServiceRoute(header) = header.unwrap() {
+            assert_eq!(
+                header.to_string(),
+                r#"service-route :    <Sip:P2.HOME.EXAMPLE.COM;LR>"#
+            );
+            assert_eq!(
+                header.to_normalized_string(),
+                r#"Service-Route: <sip:P2.HOME.EXAMPLE.COM;LR>"#
+            );
+            assert_eq!(
+                header.to_compact_string(),
+                r#"Service-Route: <sip:P2.HOME.EXAMPLE.COM;LR>"#
+            );
+        }
+    }
+
+    #[test]
+    fn test_valid_service_route_header_builder() {
+        let header = ServiceRouteHeader::builder()
+            .route(Uri::try_from("sip:orig@scscf.example.com;lr").unwrap())
+            .build();
+        assert_eq!(header.routes().len(), 1);
+        assert_eq!(
+            header.routes().first().unwrap(),
+            &Route::from(Uri::try_from("sip:orig@scscf.example.com;lr").unwrap())
+        );
+        assert_eq!(
+            header.to_string(),
+            "Service-Route: <sip:orig@scscf.example.com;lr>"
+        );
+    }
+
+    #[test]
+    fn test_service_route_header_to_route_header() {
+        let header = Header::try_from(
+            "Service-Route: <sip:P2.HOME.EXAMPLE.COM;lr>, <sip:HSP.HOME.EXAMPLE.COM;lr>",
+        );
+        if let Header::ServiceRoute(header) = header.unwrap() {
+            let route_header = header.to_route_header();
+            assert_eq!(route_header.routes(), header.routes());
+            assert_eq!(
+                route_header.to_string(),
+                "Route: <sip:P2.HOME.EXAMPLE.COM;lr>, <sip:HSP.HOME.EXAMPLE.COM;lr>"
+            );
+        } else {
+            panic!("Not a Service-Route header");
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! SIP-ETag header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::TokenString;
+use crate::headers::{GenericHeader, HeaderAccessor};
+
+/// Representation of a SIP-ETag header.
+///
+/// The SIP-ETag header field is sent by the event state compositor in a 2xx response to a PUBLISH
+/// request, to give the entity-tag that identifies the published event state.
+///
+/// [[RFC3903, Section 11.3.1](https://datatracker.ietf.org/doc/html/rfc3903#section-11.3.1)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct SipETagHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    entity_tag: TokenString,
+}
+
+impl SipETagHeader {
+    pub(crate) fn new(header: GenericHeader, entity_tag: TokenString) -> Self {
+        Self { header, entity_tag }
+    }
+
+    /// Get the entity-tag from the SIP-ETag header.
+    pub fn entity_tag(&self) -> &str {
+        self.entity_tag.as_str()
+    }
+}
+
+impl From<TokenString> for SipETagHeader {
+    fn from(value: TokenString) -> Self {
+        Self::new(
+            GenericHeader::new(TokenString::new("SIP-ETag"), ": ", value.as_str()),
+            value,
+        )
+    }
+}
+
+impl HeaderAccessor for SipETagHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("SIP-ETag")
+    }
+    fn normalized_value(&self) -> String {
+        self.entity_tag.to_string()
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+    };
+
+    use crate::{
+        Header, SipETagHeader, TokenString,
+        headers::GenericHeader,
+        parser::{ParserResult, hcolon, token},
+    };
+
+    pub(crate) fn sip_etag(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "SIP-ETag header",
+            map(
+                (
+                    map(tag_no_case("SIP-ETag"), TokenString::new),
+                    hcolon,
+                    cut(consumed(token)),
+                ),
+                |(name, separator, (value, entity_tag))| {
+                    Header::SipETag(SipETagHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        entity_tag,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, SipETagHeader, TokenString,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(SipETag, SipETagHeader, "SIP-ETag");
+    header_equality!(SipETag, "SIP-ETag");
+    header_inequality!(SipETag, "SIP-ETag");
+
+    #[test]
+    fn test_valid_sip_etag_header() {
+        valid_header("SIP-ETag: dx200xyz", |header| {
+            assert_eq!(header.entity_tag(), "dx200xyz");
+        });
+    }
+
+    #[test]
+    fn test_invalid_sip_etag_header_empty() {
+        invalid_header("SIP-ETag:");
+    }
+
+    #[test]
+    fn test_invalid_sip_etag_header_with_invalid_character() {
+        invalid_header("SIP-ETag: dx200\"xyz\"");
+    }
+
+    #[test]
+    fn test_sip_etag_header_equality_with_space_characters_differences() {
+        header_equality("SIP-ETag: dx200xyz", "SIP-ETag:   dx200xyz");
+    }
+
+    #[test]
+    fn test_sip_etag_header_inequality_different_entity_tags() {
+        header_inequality("SIP-ETag: dx200xyz", "SIP-ETag: kwj449x");
+    }
+
+    #[test]
+    fn test_sip_etag_header_to_string() {
+        let header = Header::try_from("sip-etag :   dx200xyz");
+        if let Header::SipETag(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "sip-etag :   dx200xyz");
+            assert_eq!(header.to_normalized_string(), "SIP-ETag: dx200xyz");
+            assert_eq!(header.to_compact_string(), "SIP-ETag: dx200xyz");
+        } else {
+            panic!("Not a SIP-ETag header");
+        }
+    }
+
+    #[test]
+    fn test_sip_etag_header_from_entity_tag() {
+        let header = SipETagHeader::from(TokenString::try_from("kwj449x").unwrap());
+        assert_eq!(header.entity_tag(), "kwj449x");
+        assert_eq!(header.to_string(), "SIP-ETag: kwj449x");
+    }
+}
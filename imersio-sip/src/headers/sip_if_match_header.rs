@@ -0,0 +1,151 @@
+//! SIP-If-Match header parsing and generation.
+
+use derive_partial_eq_extras::PartialEqExtras;
+
+use crate::TokenString;
+use crate::headers::{GenericHeader, HeaderAccessor};
+
+/// Representation of a SIP-If-Match header.
+///
+/// The SIP-If-Match header field is sent by the event publication agent in a PUBLISH request to
+/// refresh, modify or remove the event state identified by the entity-tag.
+///
+/// [[RFC3903, Section 11.3.2](https://datatracker.ietf.org/doc/html/rfc3903#section-11.3.2)]
+#[derive(Clone, Debug, Eq, derive_more::Display, PartialEqExtras)]
+#[display("{}", header)]
+pub struct SipIfMatchHeader {
+    #[partial_eq_ignore]
+    header: GenericHeader,
+    entity_tag: TokenString,
+}
+
+impl SipIfMatchHeader {
+    pub(crate) fn new(header: GenericHeader, entity_tag: TokenString) -> Self {
+        Self { header, entity_tag }
+    }
+
+    /// Get the entity-tag from the SIP-If-Match header.
+    pub fn entity_tag(&self) -> &str {
+        self.entity_tag.as_str()
+    }
+}
+
+impl From<TokenString> for SipIfMatchHeader {
+    fn from(value: TokenString) -> Self {
+        Self::new(
+            GenericHeader::new(TokenString::new("SIP-If-Match"), ": ", value.as_str()),
+            value,
+        )
+    }
+}
+
+impl HeaderAccessor for SipIfMatchHeader {
+    crate::headers::generic_header_accessors!(header);
+
+    fn compact_name(&self) -> Option<&str> {
+        None
+    }
+    fn normalized_name(&self) -> Option<&str> {
+        Some("SIP-If-Match")
+    }
+    fn normalized_value(&self) -> String {
+        self.entity_tag.to_string()
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::tag_no_case,
+        combinator::{consumed, cut, map},
+        error::context,
+    };
+
+    use crate::{
+        Header, SipIfMatchHeader, TokenString,
+        headers::GenericHeader,
+        parser::{ParserResult, hcolon, token},
+    };
+
+    pub(crate) fn sip_if_match(input: &str) -> ParserResult<&str, Header> {
+        context(
+            "SIP-If-Match header",
+            map(
+                (
+                    map(tag_no_case("SIP-If-Match"), TokenString::new),
+                    hcolon,
+                    cut(consumed(token)),
+                ),
+                |(name, separator, (value, entity_tag))| {
+                    Header::SipIfMatch(SipIfMatchHeader::new(
+                        GenericHeader::new(name, separator, value),
+                        entity_tag,
+                    ))
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Header, SipIfMatchHeader, TokenString,
+        headers::{
+            HeaderAccessor,
+            tests::{header_equality, header_inequality, invalid_header, valid_header},
+        },
+    };
+    use claims::assert_ok;
+
+    valid_header!(SipIfMatch, SipIfMatchHeader, "SIP-If-Match");
+    header_equality!(SipIfMatch, "SIP-If-Match");
+    header_inequality!(SipIfMatch, "SIP-If-Match");
+
+    #[test]
+    fn test_valid_sip_if_match_header() {
+        valid_header("SIP-If-Match: dx200xyz", |header| {
+            assert_eq!(header.entity_tag(), "dx200xyz");
+        });
+    }
+
+    #[test]
+    fn test_invalid_sip_if_match_header_empty() {
+        invalid_header("SIP-If-Match:");
+    }
+
+    #[test]
+    fn test_invalid_sip_if_match_header_with_invalid_character() {
+        invalid_header("SIP-If-Match: dx200\"xyz\"");
+    }
+
+    #[test]
+    fn test_sip_if_match_header_equality_with_space_characters_differences() {
+        header_equality("SIP-If-Match: dx200xyz", "SIP-If-Match:   dx200xyz");
+    }
+
+    #[test]
+    fn test_sip_if_match_header_inequality_different_entity_tags() {
+        header_inequality("SIP-If-Match: dx200xyz", "SIP-If-Match: kwj449x");
+    }
+
+    #[test]
+    fn test_sip_if_match_header_to_string() {
+        let header = Header::try_from("sip-if-match :   dx200xyz");
+        if let Header::SipIfMatch(header) = header.unwrap() {
+            assert_eq!(header.to_string(), "sip-if-match :   dx200xyz");
+            assert_eq!(header.to_normalized_string(), "SIP-If-Match: dx200xyz");
+            assert_eq!(header.to_compact_string(), "SIP-If-Match: dx200xyz");
+        } else {
+            panic!("Not a SIP-If-Match header");
+        }
+    }
+
+    #[test]
+    fn test_sip_if_match_header_from_entity_tag() {
+        let header = SipIfMatchHeader::from(TokenString::try_from("kwj449x").unwrap());
+        assert_eq!(header.entity_tag(), "kwj449x");
+        assert_eq!(header.to_string(), "SIP-If-Match: kwj449x");
+    }
+}
@@ -3,7 +3,7 @@
 use derive_partial_eq_extras::PartialEqExtras;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{NameAddress, ToParameter, ToParameters};
+use crate::{NameAddress, ToParameter, ToParameters, TokenString};
 
 /// Representation of a To header.
 ///
@@ -52,6 +52,27 @@ impl ToHeader {
             .find(|param| matches!(param, ToParameter::Tag(_)))
             .and_then(|param| param.tag())
     }
+
+    /// Get a copy of the To header with the given tag, or the To header itself if it already
+    /// has a tag.
+    ///
+    /// [[RFC3261, Section 8.2.6.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.2.6.2)]
+    pub fn with_tag(&self, tag: &str) -> Self {
+        if self.tag().is_some() {
+            return self.clone();
+        }
+        let mut parameters = self.parameters.to_vec();
+        parameters.push(ToParameter::Tag(tag.to_string()));
+        Self::new(
+            GenericHeader::new(
+                TokenString::new(self.name()),
+                self.separator().to_string(),
+                format!("{};tag={}", self.value(), tag),
+            ),
+            self.address.clone(),
+            parameters,
+        )
+    }
 }
 
 impl HeaderAccessor for ToHeader {
@@ -283,4 +304,20 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_to_header_with_tag() {
+        let header = Header::try_from("t: sip:+12125551212@server.phone2net.com");
+        if let Header::To(header) = header.unwrap() {
+            let header = header.with_tag("a6c85cf");
+            assert_eq!(header.tag(), Some("a6c85cf"));
+            assert_eq!(
+                header.to_string(),
+                "t: sip:+12125551212@server.phone2net.com;tag=a6c85cf"
+            );
+            assert_eq!(header.with_tag("hyh8").tag(), Some("a6c85cf"));
+        } else {
+            panic!("Not a To header");
+        }
+    }
 }
@@ -1,9 +1,10 @@
 //! SIP Via header parsing and generation.
 
 use derive_partial_eq_extras::PartialEqExtras;
+use itertools::join;
 
 use crate::headers::{GenericHeader, HeaderAccessor};
-use crate::{Via, Vias};
+use crate::{TokenString, Via, Vias};
 
 /// Representation of a Via header.
 ///
@@ -53,6 +54,19 @@ impl HeaderAccessor for ViaHeader {
     }
 }
 
+impl From<Vec<Via>> for ViaHeader {
+    fn from(value: Vec<Via>) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("Via"),
+                ": ".to_string(),
+                join(&value, ", "),
+            ),
+            value,
+        )
+    }
+}
+
 pub(crate) mod parser {
     use nom::{
         Parser,
@@ -2,8 +2,8 @@
 
 use derive_partial_eq_extras::PartialEqExtras;
 
-use crate::Challenge;
 use crate::headers::{GenericHeader, HeaderAccessor};
+use crate::{Challenge, TokenString};
 
 /// Representation of a WWW-Authenticate header.
 ///
@@ -29,6 +29,19 @@ impl WWWAuthenticateHeader {
     }
 }
 
+impl From<Challenge> for WWWAuthenticateHeader {
+    fn from(value: Challenge) -> Self {
+        Self::new(
+            GenericHeader::new(
+                TokenString::new("WWW-Authenticate"),
+                ": ".to_string(),
+                value.to_string(),
+            ),
+            value,
+        )
+    }
+}
+
 impl HeaderAccessor for WWWAuthenticateHeader {
     crate::headers::generic_header_accessors!(header);
 
@@ -5,17 +5,29 @@
 #![deny(warnings, missing_docs, missing_debug_implementations)]
 
 mod builder_helper;
+mod caller_preferences;
 mod common;
+mod compression;
+mod digest;
 mod error;
 pub mod headers;
 mod messages;
+mod multipart;
+mod negotiation;
+mod nonce;
 mod parser;
+mod publication;
+pub mod sdp;
+mod sipfrag;
+mod stir;
+pub mod transaction;
 mod uris;
 mod utils;
 
 pub use crate::builder_helper::{
     IntoHost, IntoMethod, IntoPort, IntoSpecificString, IntoUriScheme,
 };
+pub use crate::caller_preferences::CallerPreferences;
 pub use crate::common::{
     accept_encoding::{AcceptEncoding, AcceptEncodings},
     accept_language::{AcceptLanguage, AcceptLanguages},
@@ -28,19 +40,29 @@ pub use crate::common::{
     call_id::{CallId, CallIds},
     call_info::{CallInfo, CallInfos},
     call_info_parameter::CallInfoParameter,
+    caller_preference::{CallerPreference, CallerPreferenceParameter},
     challenge::Challenge,
     contact::{Contact, Contacts},
     contact_parameter::ContactParameter,
     content_encoding::{ContentEncoding, ContentEncodings},
     content_language::{ContentLanguage, ContentLanguages},
     credentials::Credentials,
+    directive::Directive,
     disposition_parameter::DispositionParameter,
     disposition_type::DispositionType,
+    diversion::{Diversion, Diversions},
+    diversion_parameter::DiversionParameter,
+    diversion_reason::DiversionReason,
     domain_uri::{DomainUri, DomainUris},
     error_uri::{ErrorUri, ErrorUris},
+    feature_tag::{FeatureTag, FeatureValue},
     from_parameter::{FromParameter, FromParameters},
     generic_parameter::{GenericParameter, GenericParameters},
     handling::Handling,
+    history_info::{HistoryInfo, HistoryInfos},
+    history_info_parameter::{HistoryInfoParameter, HistoryInfoTag},
+    identity_parameter::IdentityParameter,
+    info_package::{InfoPackage, InfoPackages},
     media_parameter::MediaParameter,
     media_range::MediaRange,
     media_type::MediaType,
@@ -51,7 +73,11 @@ pub use crate::common::{
     priority::Priority,
     product::Product,
     protocol::Protocol,
+    q850::{q850_cause_to_status_code, status_code_to_q850_cause},
     reason::Reason,
+    reason_parameter::ReasonParameter,
+    reason_protocol::ReasonProtocol,
+    reason_value::{ReasonValue, ReasonValues},
     retry_parameter::RetryParameter,
     route::{Route, Routes},
     server_value::{ServerValue, ServerValues},
@@ -67,9 +93,12 @@ pub use crate::common::{
     warn_agent::WarnAgent,
     warning_value::{WarningValue, WarningValues},
 };
+pub use crate::compression::ContentCoding;
+pub use crate::digest::{DigestClient, DigestSecret};
 pub use crate::error::SipError;
 pub use crate::headers::{
-    Header, accept_encoding_header::AcceptEncodingHeader, accept_header::AcceptHeader,
+    Header, accept_contact_header::AcceptContactHeader,
+    accept_encoding_header::AcceptEncodingHeader, accept_header::AcceptHeader,
     accept_language_header::AcceptLanguageHeader, alert_info_header::AlertInfoHeader,
     allow_header::AllowHeader, authentication_info_header::AuthenticationInfoHeader,
     authorization_header::AuthorizationHeader, call_id_header::CallIdHeader,
@@ -77,20 +106,37 @@ pub use crate::headers::{
     content_disposition_header::ContentDispositionHeader,
     content_encoding_header::ContentEncodingHeader, content_language_header::ContentLanguageHeader,
     content_length_header::ContentLengthHeader, content_type_header::ContentTypeHeader,
-    cseq_header::CSeqHeader, date_header::DateHeader, error_info_header::ErrorInfoHeader,
-    expires_header::ExpiresHeader, from_header::FromHeader, in_reply_to_header::InReplyToHeader,
+    cseq_header::CSeqHeader, date_header::DateHeader, diversion_header::DiversionHeader,
+    error_info_header::ErrorInfoHeader, expires_header::ExpiresHeader, from_header::FromHeader,
+    history_info_header::HistoryInfoHeader, identity_header::IdentityHeader,
+    in_reply_to_header::InReplyToHeader, info_package_header::InfoPackageHeader,
     max_forwards_header::MaxForwardsHeader, mime_version_header::MimeVersionHeader,
     min_expires_header::MinExpiresHeader, organization_header::OrganizationHeader,
+    p_asserted_identity_header::PAssertedIdentityHeader, path_header::PathHeader,
     priority_header::PriorityHeader, proxy_authenticate_header::ProxyAuthenticateHeader,
     proxy_authorization_header::ProxyAuthorizationHeader, proxy_require_header::ProxyRequireHeader,
-    record_route_header::RecordRouteHeader, reply_to_header::ReplyToHeader,
+    reason_header::ReasonHeader, record_route_header::RecordRouteHeader,
+    recv_info_header::RecvInfoHeader, reject_contact_header::RejectContactHeader,
+    reply_to_header::ReplyToHeader, request_disposition_header::RequestDispositionHeader,
     require_header::RequireHeader, retry_after_header::RetryAfterHeader, route_header::RouteHeader,
-    server_header::ServerHeader, subject_header::SubjectHeader, supported_header::SupportedHeader,
+    server_header::ServerHeader, service_route_header::ServiceRouteHeader,
+    sip_etag_header::SipETagHeader, sip_if_match_header::SipIfMatchHeader,
+    subject_header::SubjectHeader, supported_header::SupportedHeader,
     timestamp_header::TimestampHeader, to_header::ToHeader, unsupported_header::UnsupportedHeader,
     user_agent_header::UserAgentHeader, via_header::ViaHeader, warning_header::WarningHeader,
     www_authenticate_header::WWWAuthenticateHeader,
 };
-pub use crate::messages::{message::Message, request::Request, response::Response};
+pub use crate::messages::{
+    message::Message,
+    request::{Request, RequestIssue},
+    response::Response,
+};
+pub use crate::multipart::{BodyPart, MultipartBody, MultipartType};
+pub use crate::negotiation::ContentNegotiator;
+pub use crate::nonce::{AuthenticationOutcome, NonceService};
+pub use crate::publication::{Publication, PublicationOutcome, PublicationStore};
+pub use crate::sipfrag::{SipFrag, SipFragBuilder, StartLine};
+pub use crate::stir::{Attestation, CertificateStore, Passport, PassportSigner};
 pub use crate::uris::{
     absolute_uri::{AbsoluteUri, OpaquePartString},
     host::{Host, HostnameString},
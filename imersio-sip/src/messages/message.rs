@@ -1,4 +1,4 @@
-use crate::{Request, Response, SipError};
+use crate::{Header, Request, Response, SipError};
 use nom_language::error::convert_error;
 use std::str::from_utf8;
 
@@ -12,6 +12,38 @@ pub enum Message {
 }
 
 impl Message {
+    /// Get a reference to the headers of the message.
+    pub fn headers(&self) -> &Vec<Header> {
+        match self {
+            Self::Request(request) => request.headers(),
+            Self::Response(response) => response.headers(),
+        }
+    }
+
+    /// Get a reference to the body of the message.
+    pub fn body(&self) -> &[u8] {
+        match self {
+            Self::Request(request) => request.body(),
+            Self::Response(response) => response.body(),
+        }
+    }
+
+    /// Get the value of the Content-Length header of the message, if it has one.
+    pub fn content_length(&self) -> Option<usize> {
+        self.headers().iter().find_map(|header| match header {
+            Header::ContentLength(header) => Some(header.content_length() as usize),
+            _ => None,
+        })
+    }
+
+    /// Get the message encoded as it is sent over the network.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Request(request) => request.to_bytes(),
+            Self::Response(response) => response.to_bytes(),
+        }
+    }
+
     fn set_body(&mut self, body: &[u8]) {
         match self {
             Self::Request(request) => request.set_body(body),
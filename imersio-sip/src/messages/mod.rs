@@ -3,3 +3,41 @@
 pub mod message;
 pub mod request;
 pub mod response;
+
+use std::fmt::Write;
+use std::str::from_utf8;
+
+use crate::Header;
+
+/// Replace the header of the same kind as the given one, or add it if there is none.
+pub(crate) fn replace_header(headers: &mut Vec<Header>, new_header: Header) {
+    match headers
+        .iter_mut()
+        .find(|header| std::mem::discriminant(*header) == std::mem::discriminant(&new_header))
+    {
+        Some(header) => *header = new_header,
+        None => headers.push(new_header),
+    }
+}
+
+/// Serialize the start line and the headers of a message, each one followed by a CRLF, and the
+/// empty line ending them.
+pub(crate) fn head(start_line: std::fmt::Arguments<'_>, headers: &[Header]) -> String {
+    let mut head = format!("{start_line}\r\n");
+    for header in headers {
+        // Writing to a String cannot fail.
+        let _ = write!(head, "{header}\r\n");
+    }
+    head.push_str("\r\n");
+    head
+}
+
+/// Display a message from its serialized head and its body, showing only the size of a body
+/// that is not text.
+pub(crate) fn fmt(f: &mut std::fmt::Formatter<'_>, head: &str, body: &[u8]) -> std::fmt::Result {
+    f.write_str(head)?;
+    match from_utf8(body) {
+        Ok(body) => f.write_str(body),
+        Err(_) => write!(f, "[binary body of size {}]", body.len()),
+    }
+}
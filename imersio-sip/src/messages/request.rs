@@ -2,14 +2,50 @@
 //!
 //! TODO
 
-use itertools::join;
 use nom_language::error::convert_error;
-use std::str::from_utf8;
+use std::borrow::Cow;
+use std::net::SocketAddr;
 
 use crate::Method;
+use crate::MultipartBody;
+use crate::SipFrag;
 use crate::Uri;
 use crate::Version;
-use crate::{Header, SipError};
+use crate::sdp::SessionDescription;
+use crate::{AcceptEncodingHeader, ContentCoding};
+use crate::{
+    CSeqHeader, ContentLengthHeader, Diversion, DiversionHeader, DiversionParameter,
+    DiversionReason, Diversions, Header, HistoryInfo, HistoryInfoHeader, HistoryInfoParameter,
+    HistoryInfoTag, HistoryInfos, MaxForwardsHeader, PathHeader, Route, RouteHeader, Routes,
+    SipError, StatusCode, Via, ViaHeader, Vias,
+};
+
+/// Maximum size of a MESSAGE request sent over a transport that is not congestion controlled.
+///
+/// [[RFC3428, Section 7](https://datatracker.ietf.org/doc/html/rfc3428#section-7)]
+const MESSAGE_SIZE_LIMIT: usize = 1300;
+
+/// Representation of an issue in the usage of a method, found when checking a SIP request.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::Display)]
+pub enum RequestIssue {
+    /// An UPDATE or INFO request is sent outside of a dialog, as its To header has no tag.
+    ///
+    /// [[RFC3311, Section 5.1](https://datatracker.ietf.org/doc/html/rfc3311#section-5.1)]
+    /// [[RFC6086, Section 4.2.1](https://datatracker.ietf.org/doc/html/rfc6086#section-4.2.1)]
+    #[display("{_0} request sent outside of a dialog")]
+    OutsideOfDialog(Method),
+    /// A MESSAGE request contains a Contact header.
+    ///
+    /// [[RFC3428, Section 4](https://datatracker.ietf.org/doc/html/rfc3428#section-4)]
+    #[display("MESSAGE request with a Contact header")]
+    ContactInMessage,
+    /// A MESSAGE request exceeds the size allowed on a transport that is not congestion
+    /// controlled.
+    ///
+    /// [[RFC3428, Section 7](https://datatracker.ietf.org/doc/html/rfc3428#section-7)]
+    #[display("MESSAGE request of {_0} bytes exceeding {MESSAGE_SIZE_LIMIT} bytes")]
+    MessageTooLarge(usize),
+}
 
 /// Representation of a SIP request.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -22,6 +58,22 @@ pub struct Request {
 }
 
 impl Request {
+    pub(crate) fn new(
+        method: Method,
+        uri: Uri,
+        version: Version,
+        headers: Vec<Header>,
+        body: Vec<u8>,
+    ) -> Self {
+        Self {
+            method,
+            uri,
+            version,
+            headers,
+            body,
+        }
+    }
+
     /// Get a reference to the associated SIP method.
     pub fn method(&self) -> &Method {
         &self.method
@@ -32,6 +84,11 @@ impl Request {
         &self.uri
     }
 
+    /// Set the SIP URI of the SIP request, eg. when a proxy retargets it.
+    pub fn set_uri(&mut self, uri: Uri) {
+        self.uri = uri;
+    }
+
     /// Get a reference to the associated SIP version.
     pub fn version(&self) -> &Version {
         &self.version
@@ -42,6 +99,445 @@ impl Request {
         &self.headers
     }
 
+    /// Get a mutable reference to the list of headers of the SIP request.
+    pub fn headers_mut(&mut self) -> &mut Vec<Header> {
+        &mut self.headers
+    }
+
+    /// Record the source address the request has been received from in its top Via.
+    ///
+    /// [[RFC3261, Section 18.2.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.2.1)]
+    /// [[RFC3581, Section 4](https://datatracker.ietf.org/doc/html/rfc3581#section-4)]
+    pub fn set_received(&mut self, source: SocketAddr) -> Result<(), SipError> {
+        let header = self
+            .headers
+            .iter_mut()
+            .find(|header| matches!(header, Header::Via(_)))
+            .ok_or_else(|| SipError::InvalidRequest("Request without Via header".to_string()))?;
+        if let Header::Via(via_header) = header {
+            let mut vias = via_header.vias().to_vec();
+            if let Some(via) = vias.first_mut() {
+                via.set_received(source);
+            }
+            *header = Header::Via(ViaHeader::from(vias));
+        }
+        Ok(())
+    }
+
+    /// Get the vias of the SIP request, gathered from all its Via headers in order, the top via
+    /// first.
+    pub fn vias(&self) -> Vias {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::Via(header) => Some(header.vias().iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<Via>>()
+            .into()
+    }
+
+    /// Insert a via above the existing ones, as done by a proxy forwarding the request.
+    ///
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    pub fn push_via(&mut self, via: Via) {
+        let header = Header::Via(ViaHeader::from(vec![via]));
+        match self
+            .headers
+            .iter()
+            .position(|header| matches!(header, Header::Via(_)))
+        {
+            Some(index) => self.headers.insert(index, header),
+            None => self.headers.insert(0, header),
+        }
+    }
+
+    /// Get the value of the Max-Forwards header of the SIP request, if it has one.
+    pub fn max_forwards(&self) -> Option<u8> {
+        self.headers.iter().find_map(|header| match header {
+            Header::MaxForwards(header) => Some(header.max_forwards()),
+            _ => None,
+        })
+    }
+
+    /// Decrement the Max-Forwards header of a request being forwarded, adding one with a value
+    /// of 70 if there is none.
+    ///
+    /// It fails if the Max-Forwards header has reached zero, the request must then not be
+    /// forwarded.
+    ///
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    pub fn decrement_max_forwards(&mut self) -> Result<(), SipError> {
+        let max_forwards = match self.max_forwards() {
+            Some(0) => {
+                return Err(SipError::InvalidRequest(
+                    "Max-Forwards has reached zero".to_string(),
+                ));
+            }
+            Some(max_forwards) => max_forwards - 1,
+            None => 70,
+        };
+        let header = Header::MaxForwards(MaxForwardsHeader::from(max_forwards));
+        match self
+            .headers
+            .iter_mut()
+            .find(|header| matches!(header, Header::MaxForwards(_)))
+        {
+            Some(existing) => *existing = header,
+            None => self.headers.push(header),
+        }
+        Ok(())
+    }
+
+    /// Build the CANCEL request of the SIP request, that must be an INVITE.
+    ///
+    /// The CANCEL has the same Request-URI, Call-ID, From, To, CSeq number and Route headers as
+    /// the request, and a single Via equal to the top Via of the request.
+    ///
+    /// [[RFC3261, Section 9.1](https://datatracker.ietf.org/doc/html/rfc3261#section-9.1)]
+    pub fn cancel(&self) -> Result<Self, SipError> {
+        if self.method != Method::Invite {
+            return Err(SipError::InvalidRequest(format!(
+                "Cannot cancel a {} request",
+                self.method
+            )));
+        }
+        let mut has_via = false;
+        let mut headers: Vec<Header> = self
+            .headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::Via(header) if !has_via => {
+                    has_via = true;
+                    Some(Header::Via(vec![header.vias().first()?.clone()].into()))
+                }
+                Header::Route(_)
+                | Header::MaxForwards(_)
+                | Header::From(_)
+                | Header::To(_)
+                | Header::CallId(_) => Some(header.clone()),
+                Header::CSeq(header) => Some(Header::CSeq(CSeqHeader::from((
+                    header.cseq(),
+                    Method::Cancel,
+                )))),
+                _ => None,
+            })
+            .collect();
+        headers.push(Header::ContentLength(ContentLengthHeader::from(0)));
+        Ok(Self::new(
+            Method::Cancel,
+            self.uri.clone(),
+            self.version,
+            headers,
+            vec![],
+        ))
+    }
+
+    /// Get the route set of the SIP request, gathered from all its Route headers in order.
+    pub fn routes(&self) -> Routes {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::Route(header) => Some(header.routes().iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<Route>>()
+            .into()
+    }
+
+    /// Remove the first entry of the route set of the SIP request, returning it.
+    ///
+    /// [[RFC3261, Section 16.4](https://datatracker.ietf.org/doc/html/rfc3261#section-16.4)]
+    pub fn pop_route(&mut self) -> Option<Route> {
+        let index = self.headers.iter().position(
+            |header| matches!(header, Header::Route(header) if !header.routes().is_empty()),
+        )?;
+        let Header::Route(header) = &self.headers[index] else {
+            return None;
+        };
+        let mut routes = header.routes().to_vec();
+        let route = routes.remove(0);
+        if routes.is_empty() {
+            self.headers.remove(index);
+        } else {
+            self.headers[index] = Header::Route(RouteHeader::builder().routes(routes).build());
+        }
+        Some(route)
+    }
+
+    /// Push entries on top of the route set of the SIP request, eg. the path vector stored by a
+    /// registrar for the contact the request is forwarded to.
+    ///
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    /// [[RFC3327, Section 5.3](https://datatracker.ietf.org/doc/html/rfc3327#section-5.3)]
+    pub fn push_routes<I: IntoIterator<Item = Route>>(&mut self, routes: I) {
+        let header = RouteHeader::builder().routes(routes).build();
+        if header.routes().is_empty() {
+            return;
+        }
+        let header = Header::Route(header);
+        match self
+            .headers
+            .iter()
+            .position(|header| matches!(header, Header::Route(_)))
+        {
+            Some(index) => self.headers.insert(index, header),
+            None => self.headers.push(header),
+        }
+    }
+
+    /// Get the path vector of the SIP request, gathered from all its Path headers in order.
+    ///
+    /// [[RFC3327, Section 5.3](https://datatracker.ietf.org/doc/html/rfc3327#section-5.3)]
+    pub fn path(&self) -> Routes {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::Path(header) => Some(header.routes().iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<Route>>()
+            .into()
+    }
+
+    /// Insert a Path entry in a REGISTER request.
+    ///
+    /// The entry is put above the ones inserted by the previous proxies, so that it ends up first
+    /// in the path vector stored by the registrar.
+    ///
+    /// [[RFC3327, Section 5.2](https://datatracker.ietf.org/doc/html/rfc3327#section-5.2)]
+    pub fn insert_path<R: Into<Route>>(&mut self, route: R) -> Result<(), SipError> {
+        if self.method != Method::Register {
+            return Err(SipError::InvalidRequest(format!(
+                "Path cannot be inserted in a {} request",
+                self.method
+            )));
+        }
+        let header = Header::Path(PathHeader::builder().route(route).build());
+        match self
+            .headers
+            .iter()
+            .position(|header| matches!(header, Header::Path(_)))
+        {
+            Some(index) => self.headers.insert(index, header),
+            None => self.headers.push(header),
+        }
+        Ok(())
+    }
+
+    /// Get the History-Info entries of the SIP request, gathered from all its History-Info headers
+    /// in order.
+    pub fn history_info(&self) -> HistoryInfos {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::HistoryInfo(header) => Some(header.entries().iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<HistoryInfo>>()
+            .into()
+    }
+
+    /// Add a History-Info entry for the new target of the request, when a proxy retargets it.
+    ///
+    /// It must be called before updating the Request-URI. If the request does not contain any
+    /// History-Info entry yet, an entry for its current Request-URI is added first. The new entry
+    /// is indexed as a child of the entry of the current Request-URI, that is the one of the
+    /// retargeting proxy, and tagged with the given `HistoryInfoTag`. When the retargeting is
+    /// caused by the failure of a previous target, the index of the entry of this target and the
+    /// status code of its response are given, and the status code is embedded as a Reason in
+    /// this entry.
+    ///
+    /// [[RFC7044, Section 10.3](https://datatracker.ietf.org/doc/html/rfc7044#section-10.3)]
+    pub fn add_history_info(
+        &mut self,
+        target: Uri,
+        tag: HistoryInfoTag,
+        cause: Option<(&str, &StatusCode)>,
+    ) -> Result<(), SipError> {
+        let mut entries: Vec<HistoryInfo> = self.history_info().to_vec();
+        if entries.is_empty() {
+            entries.push(HistoryInfo::new(
+                self.uri.clone().into(),
+                vec![HistoryInfoParameter::Index("1".to_string())],
+            ));
+        }
+        let parent = entries
+            .iter()
+            .rposition(|entry| *entry.uri() == self.uri)
+            .unwrap_or(entries.len() - 1);
+        let parent_index = entries[parent].index().unwrap_or("1").to_string();
+        if let Some((failed_index, status_code)) = cause {
+            entries
+                .iter_mut()
+                .find(|entry| entry.index() == Some(failed_index))
+                .ok_or_else(|| {
+                    SipError::InvalidMessageHeader(format!(
+                        "no History-Info entry with index `{failed_index}`"
+                    ))
+                })?
+                .set_cause(status_code)?;
+        }
+        let prefix = format!("{parent_index}.");
+        let child = entries
+            .iter()
+            .filter_map(|entry| entry.index()?.strip_prefix(&prefix)?.parse::<u32>().ok())
+            .max()
+            .unwrap_or_default()
+            + 1;
+        entries.push(HistoryInfo::new(
+            target.into(),
+            vec![
+                HistoryInfoParameter::Index(format!("{parent_index}.{child}")),
+                tag.to_parameter(parent_index),
+            ],
+        ));
+
+        let header = Header::HistoryInfo(HistoryInfoHeader::builder().entries(entries).build());
+        let position = self
+            .headers
+            .iter()
+            .position(|header| matches!(header, Header::HistoryInfo(_)));
+        self.headers
+            .retain(|header| !matches!(header, Header::HistoryInfo(_)));
+        match position {
+            Some(index) => self.headers.insert(index, header),
+            None => self.headers.push(header),
+        }
+        Ok(())
+    }
+
+    /// Get the Diversion entries of the SIP request, gathered from all its Diversion headers in
+    /// order, the most recent diversion first.
+    pub fn diversions(&self) -> Diversions {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::Diversion(header) => Some(header.entries().iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<Diversion>>()
+            .into()
+    }
+
+    /// Insert a Diversion entry for the current Request-URI of the request, when a proxy diverts
+    /// it.
+    ///
+    /// It must be called before updating the Request-URI. The entry is put above the existing
+    /// ones, as the most recent diversion.
+    ///
+    /// [[RFC5806, Section 4](https://datatracker.ietf.org/doc/html/rfc5806#section-4)]
+    pub fn insert_diversion(&mut self, reason: DiversionReason) {
+        let header = Header::Diversion(
+            DiversionHeader::builder()
+                .entry(Diversion::new(
+                    self.uri.clone().into(),
+                    vec![
+                        DiversionParameter::Reason(reason),
+                        DiversionParameter::Counter("1".to_string()),
+                    ],
+                ))
+                .build(),
+        );
+        match self
+            .headers
+            .iter()
+            .position(|header| matches!(header, Header::Diversion(_)))
+        {
+            Some(index) => self.headers.insert(index, header),
+            None => self.headers.push(header),
+        }
+    }
+
+    /// Get the telephone number of the originator of the request, as used in the `orig` claim
+    /// of a PASSporT.
+    ///
+    /// The first telephone number of the P-Asserted-Identity headers is preferred over the one of
+    /// the From header.
+    ///
+    /// [[RFC8224, Section 5.1](https://datatracker.ietf.org/doc/html/rfc8224#section-5.1)]
+    pub fn orig_telephone_number(&self) -> Option<String> {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::PAssertedIdentity(header) => Some(
+                    header
+                        .identities()
+                        .iter()
+                        .map(|identity| identity.uri())
+                        .collect::<Vec<_>>(),
+                ),
+                _ => None,
+            })
+            .flatten()
+            .find_map(Uri::telephone_number)
+            .or_else(|| {
+                self.headers.iter().find_map(|header| match header {
+                    Header::From(header) => header.address().uri().telephone_number(),
+                    _ => None,
+                })
+            })
+    }
+
+    /// Get the telephone numbers of the destination of the request, as used in the `dest` claim
+    /// of a PASSporT, from its To header.
+    ///
+    /// [[RFC8224, Section 5.1](https://datatracker.ietf.org/doc/html/rfc8224#section-5.1)]
+    pub fn dest_telephone_numbers(&self) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::To(header) => header.address().uri().telephone_number(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Check the usage of the method of the SIP request, and get the issues that have been found.
+    ///
+    /// UPDATE and INFO requests must be sent inside a dialog, and MESSAGE requests must not
+    /// contain a Contact header. MESSAGE requests larger than 1300 bytes are flagged as well, as
+    /// they must only be sent over a congestion controlled transport.
+    pub fn check(&self) -> Vec<RequestIssue> {
+        let mut issues = vec![];
+        match self.method {
+            Method::Update | Method::Info => {
+                let has_to_tag = self.headers.iter().any(|header| match header {
+                    Header::To(header) => header.tag().is_some(),
+                    _ => false,
+                });
+                if !has_to_tag {
+                    issues.push(RequestIssue::OutsideOfDialog(self.method.clone()));
+                }
+            }
+            Method::Message => {
+                if self
+                    .headers
+                    .iter()
+                    .any(|header| matches!(header, Header::Contact(_)))
+                {
+                    issues.push(RequestIssue::ContactInMessage);
+                }
+                let size = self.size();
+                if size > MESSAGE_SIZE_LIMIT {
+                    issues.push(RequestIssue::MessageTooLarge(size));
+                }
+            }
+            _ => {}
+        }
+        issues
+    }
+
+    /// Get the size in bytes of the SIP request once serialized.
+    pub fn size(&self) -> usize {
+        self.head().len() + self.body.len()
+    }
+
     /// Get a reference to the associated body.
     #[inline]
     pub fn body(&self) -> &[u8] {
@@ -51,22 +547,87 @@ impl Request {
     pub(crate) fn set_body(&mut self, body: &[u8]) {
         self.body = body.to_vec();
     }
+
+    /// Get the request encoded as it is sent over the network.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head().into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Get the request line and the headers of the request, as they are sent over the network.
+    fn head(&self) -> String {
+        super::head(
+            format_args!("{} {} {}", self.method, self.uri, self.version),
+            &self.headers,
+        )
+    }
+
+    /// Get the body of the request, decoded according to its Content-Encoding header.
+    ///
+    /// The `gzip` and `deflate` content codings are only supported with the `compression`
+    /// feature. Decoding fails if the decoded body is larger than
+    /// [`ContentCoding::DEFAULT_DECODED_SIZE_LIMIT`].
+    pub fn decoded_body(&self) -> Result<Cow<'_, [u8]>, SipError> {
+        self.decoded_body_with_limit(ContentCoding::DEFAULT_DECODED_SIZE_LIMIT)
+    }
+
+    /// Get the body of the request, decoded according to its Content-Encoding header, failing if
+    /// the decoded body is larger than the given number of bytes.
+    pub fn decoded_body_with_limit(&self, limit: usize) -> Result<Cow<'_, [u8]>, SipError> {
+        crate::compression::decode(&self.headers, &self.body, limit)
+    }
+
+    /// Compress the body of the request with the content coding preferred by the peer according
+    /// to its Accept-Encoding header, updating the Content-Encoding and Content-Length headers.
+    ///
+    /// Get the content coding that has been applied, if any.
+    pub fn encode_body(
+        &mut self,
+        accept_encoding: &AcceptEncodingHeader,
+    ) -> Result<Option<ContentCoding>, SipError> {
+        crate::compression::encode(&mut self.headers, &mut self.body, accept_encoding)
+    }
+
+    /// Get the session description contained in the body of the request, if its Content-Type is
+    /// `application/sdp`.
+    pub fn sdp(&self) -> Result<Option<SessionDescription>, SipError> {
+        crate::sdp::from_body(&self.headers, &self.decoded_body()?)
+    }
+
+    /// Set a session description as the body of the request, updating the Content-Type and
+    /// Content-Length headers.
+    pub fn set_sdp(&mut self, session_description: &SessionDescription) {
+        crate::sdp::set_body(&mut self.headers, &mut self.body, session_description);
+    }
+
+    /// Get the multipart body of the request, if its Content-Type is `multipart/*`.
+    pub fn multipart(&self) -> Result<Option<MultipartBody>, SipError> {
+        crate::multipart::from_body(&self.headers, &self.decoded_body()?)
+    }
+
+    /// Set a multipart body as the body of the request, updating the Content-Type and
+    /// Content-Length headers.
+    pub fn set_multipart(&mut self, multipart: &MultipartBody) {
+        crate::multipart::set_body(&mut self.headers, &mut self.body, multipart);
+    }
+
+    /// Get the SIP message fragment contained in the body of the request, if its Content-Type is
+    /// `message/sipfrag`.
+    pub fn sipfrag(&self) -> Result<Option<SipFrag>, SipError> {
+        crate::sipfrag::from_body(&self.headers, &self.decoded_body()?)
+    }
+
+    /// Set a SIP message fragment as the body of the request, updating the Content-Type and
+    /// Content-Length headers.
+    pub fn set_sipfrag(&mut self, sipfrag: &SipFrag) {
+        crate::sipfrag::set_body(&mut self.headers, &mut self.body, sipfrag);
+    }
 }
 
 impl std::fmt::Display for Request {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {} {}\r\n{}\r\n{}",
-            self.method(),
-            self.uri(),
-            self.version(),
-            join(self.headers(), "\r\n"),
-            match from_utf8(self.body()) {
-                Ok(body) => body.to_string(),
-                Err(_) => format!("[binary body of size {}]", self.body().len()),
-            }
-        )
+        super::fmt(f, &self.head(), &self.body)
     }
 }
 
@@ -107,7 +668,7 @@ pub(crate) mod parser {
         uris::uri::parser::request_uri,
     };
 
-    fn request_line(input: &str) -> ParserResult<&str, (Method, Uri, Version)> {
+    pub(crate) fn request_line(input: &str) -> ParserResult<&str, (Method, Uri, Version)> {
         context(
             "request_line",
             map(
@@ -143,6 +704,7 @@ pub(crate) mod parser {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{Host, Transport};
     use claims::{assert_err, assert_ok};
 
     #[test]
@@ -166,4 +728,438 @@ mod test {
             "INVITE sip:alice@atlanta.com@gateway.com SIP/2.0\r\n\r\n"
         ));
     }
+
+    #[test]
+    fn test_request_insert_path() {
+        let mut req = Request::try_from(
+            "REGISTER sip:example.com SIP/2.0\r\n\
+Path: <sip:p1.example.com;lr>\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_ok!(req.insert_path(Uri::try_from("sip:p2.example.com;lr").unwrap()));
+        assert_eq!(req.headers().len(), 2);
+        assert_eq!(
+            req.headers().first().unwrap().to_string(),
+            "Path: <sip:p2.example.com;lr>"
+        );
+        let path = req.path();
+        assert_eq!(path.len(), 2);
+        let mut routes = path.iter();
+        assert_eq!(
+            routes.next().unwrap().name_address().uri(),
+            Uri::try_from("sip:p2.example.com;lr").unwrap()
+        );
+        assert_eq!(
+            routes.next().unwrap().name_address().uri(),
+            Uri::try_from("sip:p1.example.com;lr").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_request_insert_path_in_non_register_request() {
+        let mut req = Request::try_from("INVITE sip:alice@atlanta.com SIP/2.0\r\n\r\n").unwrap();
+        assert_err!(req.insert_path(Uri::try_from("sip:p1.example.com;lr").unwrap()));
+        assert_eq!(req.headers().len(), 0);
+    }
+
+    #[test]
+    fn test_request_add_history_info() {
+        let mut req = Request::try_from("INVITE sip:bob@example.com SIP/2.0\r\n\r\n").unwrap();
+        assert_ok!(req.add_history_info(
+            Uri::try_from("sip:bob@192.0.2.4").unwrap(),
+            HistoryInfoTag::Rc,
+            None
+        ));
+        // The first target fails, the proxy retargets the request it received to a second one.
+        assert_err!(req.add_history_info(
+            Uri::try_from("sip:voicemail@example.com").unwrap(),
+            HistoryInfoTag::Mp,
+            Some(("1.2", &StatusCode::BUSY_HERE))
+        ));
+        assert_ok!(req.add_history_info(
+            Uri::try_from("sip:voicemail@example.com").unwrap(),
+            HistoryInfoTag::Mp,
+            Some(("1.1", &StatusCode::BUSY_HERE))
+        ));
+        req.set_uri(Uri::try_from("sip:voicemail@example.com").unwrap());
+
+        assert_eq!(req.headers().len(), 1);
+        let entries = req.history_info();
+        assert_eq!(entries.len(), 3);
+        let mut entries = entries.iter();
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.index(), Some("1"));
+        assert_eq!(entry.tag(), None);
+        assert_eq!(entry.cause(), None);
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.index(), Some("1.1"));
+        assert_eq!(entry.tag(), Some((HistoryInfoTag::Rc, "1")));
+        assert_eq!(entry.cause(), Some(486));
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.index(), Some("1.2"));
+        assert_eq!(entry.tag(), Some((HistoryInfoTag::Mp, "1")));
+        assert_eq!(entry.cause(), None);
+        assert_eq!(
+            req.headers().first().unwrap().to_string(),
+            "History-Info: <sip:bob@example.com>;index=1, \
+            <sip:bob@192.0.2.4?Reason=SIP%3bcause%3d486>;index=1.1;rc=1, \
+            <sip:voicemail@example.com>;index=1.2;mp=1"
+        );
+    }
+
+    #[test]
+    fn test_request_add_history_info_sibling() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@192.0.2.4 SIP/2.0\r\n\
+History-Info: <sip:bob@example.com>;index=1, <sip:bob@192.0.2.4>;index=1.1;rc=1\r\n\
+\r\n",
+        )
+        .unwrap();
+        req.set_uri(Uri::try_from("sip:bob@example.com").unwrap());
+        assert_ok!(req.add_history_info(
+            Uri::try_from("sip:bob@192.0.2.5").unwrap(),
+            HistoryInfoTag::Rc,
+            None
+        ));
+        let entries = req.history_info();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries.last().unwrap().index(), Some("1.2"));
+    }
+
+    #[test]
+    fn test_request_insert_diversion() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@example.com SIP/2.0\r\n\
+Diversion: <sip:alice@example.com>;reason=unconditional\r\n\
+\r\n",
+        )
+        .unwrap();
+        req.insert_diversion(DiversionReason::UserBusy);
+        assert_eq!(req.headers().len(), 2);
+        assert_eq!(
+            req.headers().first().unwrap().to_string(),
+            "Diversion: <sip:bob@example.com>;reason=user-busy;counter=1"
+        );
+        let diversions = req.diversions();
+        assert_eq!(diversions.len(), 2);
+        assert_eq!(
+            diversions.first().unwrap().reason(),
+            Some(&DiversionReason::UserBusy)
+        );
+        assert_eq!(
+            diversions.last().unwrap().reason(),
+            Some(&DiversionReason::Unconditional)
+        );
+    }
+
+    #[test]
+    fn test_request_check_update_and_info_outside_of_dialog() {
+        let req = Request::try_from(
+            "INFO sip:bob@example.com SIP/2.0\r\n\
+To: <sip:bob@example.com>\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            req.check(),
+            vec![RequestIssue::OutsideOfDialog(Method::Info)]
+        );
+        let req = Request::try_from(
+            "UPDATE sip:bob@example.com SIP/2.0\r\n\
+To: <sip:bob@example.com>;tag=a6c85cf\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert!(req.check().is_empty());
+    }
+
+    #[test]
+    fn test_request_check_message() {
+        let req = Request::try_from(
+            "MESSAGE sip:bob@example.com SIP/2.0\r\n\
+Contact: <sip:alice@192.0.2.4>\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_eq!(req.check(), vec![RequestIssue::ContactInMessage]);
+
+        let mut req = Request::try_from("MESSAGE sip:bob@example.com SIP/2.0\r\n\r\n").unwrap();
+        req.set_body("a".repeat(1300).as_bytes());
+        let size = req.size();
+        assert_eq!(
+            size,
+            1300 + "MESSAGE sip:bob@example.com SIP/2.0\r\n\r\n".len()
+        );
+        assert_eq!(req.check(), vec![RequestIssue::MessageTooLarge(size)]);
+        assert_eq!(
+            RequestIssue::MessageTooLarge(size).to_string(),
+            format!("MESSAGE request of {size} bytes exceeding 1300 bytes")
+        );
+    }
+
+    #[test]
+    fn test_request_sdp() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@example.com SIP/2.0\r\n\
+Content-Type: Application/SDP\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_eq!(req.sdp().unwrap(), None);
+        req.set_body(
+            b"v=0\r\no=alice 1 1 IN IP4 192.0.2.4\r\ns=-\r\nc=IN IP4 192.0.2.4\r\nt=0 0\r\n\
+m=audio 49170 RTP/AVP 0\r\n",
+        );
+        let mut sdp = req.sdp().unwrap().unwrap();
+        assert_eq!(sdp.media_descriptions().first().unwrap().port(), 49170);
+
+        sdp.media_descriptions_mut()
+            .first_mut()
+            .unwrap()
+            .set_port(5004);
+        req.set_sdp(&sdp);
+        assert_eq!(req.headers().len(), 2);
+        assert_eq!(
+            req.headers().last().unwrap().to_string(),
+            format!("Content-Length: {}", req.body().len())
+        );
+        assert_eq!(req.sdp().unwrap(), Some(sdp));
+
+        req.set_body(b"v=1\r\n");
+        assert_err!(req.sdp());
+    }
+
+    #[test]
+    fn test_request_sdp_with_other_content_type() {
+        let mut req = Request::try_from(
+            "MESSAGE sip:bob@example.com SIP/2.0\r\n\
+Content-Type: text/plain\r\n\
+\r\n",
+        )
+        .unwrap();
+        req.set_body(b"v=0");
+        assert_eq!(req.sdp().unwrap(), None);
+    }
+
+    #[test]
+    fn test_request_set_received() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.com:5060;branch=z9hG4bKnashds7\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_ok!(req.set_received("192.0.2.4:5060".parse().unwrap()));
+        assert_eq!(req.headers().len(), 2);
+        assert_eq!(
+            req.headers()[0].to_string(),
+            "Via: SIP/2.0/UDP bobspc.biloxi.com:5060;branch=z9hG4bKnashds7;received=192.0.2.4"
+        );
+        assert_eq!(
+            req.headers()[1].to_string(),
+            "Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds"
+        );
+    }
+
+    #[test]
+    fn test_request_set_received_with_rport() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP 10.1.1.1:4540;rport;branch=z9hG4bKkjshdyff\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_ok!(req.set_received("192.0.2.1:9988".parse().unwrap()));
+        assert_eq!(
+            req.headers().first().unwrap().to_string(),
+            "Via: SIP/2.0/UDP 10.1.1.1:4540;rport=9988;branch=z9hG4bKkjshdyff;received=192.0.2.1"
+        );
+
+        let mut req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP 192.0.2.1:5060;branch=z9hG4bKkjshdyff\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_ok!(req.set_received("192.0.2.1:5060".parse().unwrap()));
+        assert_eq!(
+            req.headers().first().unwrap().to_string(),
+            "Via: SIP/2.0/UDP 192.0.2.1:5060;branch=z9hG4bKkjshdyff"
+        );
+    }
+
+    #[test]
+    fn test_request_set_received_without_via() {
+        let mut req = Request::try_from("INVITE sip:bob@biloxi.com SIP/2.0\r\n\r\n").unwrap();
+        assert_err!(req.set_received("192.0.2.1:5060".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_request_push_via() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Max-Forwards: 70\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+\r\n",
+        )
+        .unwrap();
+        req.push_via(Via::with_branch(
+            Transport::Tcp,
+            Host::try_from("bigbox3.site3.atlanta.com").unwrap(),
+            Some(5060),
+            "z9hG4bK77ef4c2312983.1",
+        ));
+        assert_eq!(
+            req.headers()[1].to_string(),
+            "Via: SIP/2.0/TCP bigbox3.site3.atlanta.com:5060;branch=z9hG4bK77ef4c2312983.1"
+        );
+        let vias = req.vias();
+        assert_eq!(vias.len(), 2);
+        assert_eq!(
+            vias.first().unwrap().branch(),
+            Some("z9hG4bK77ef4c2312983.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_request_decrement_max_forwards() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Max-Forwards: 1\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_eq!(req.max_forwards(), Some(1));
+        assert_ok!(req.decrement_max_forwards());
+        assert_eq!(req.max_forwards(), Some(0));
+        assert_eq!(req.headers()[0].to_string(), "Max-Forwards: 0");
+        assert_err!(req.decrement_max_forwards());
+
+        let mut req = Request::try_from("INVITE sip:bob@biloxi.com SIP/2.0\r\n\r\n").unwrap();
+        assert_eq!(req.max_forwards(), None);
+        assert_ok!(req.decrement_max_forwards());
+        assert_eq!(req.max_forwards(), Some(70));
+    }
+
+    #[test]
+    fn test_request_pop_route() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Route: <sip:p1.example.com;lr>, <sip:p2.example.com;lr>\r\n\
+Route: <sip:p3.example.com;lr>\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_eq!(req.routes().len(), 3);
+        let route = req.pop_route().unwrap();
+        assert_eq!(
+            route.name_address().uri(),
+            Uri::try_from("sip:p1.example.com;lr").unwrap()
+        );
+        assert_eq!(
+            req.headers()[0].to_string(),
+            "Route: <sip:p2.example.com;lr>"
+        );
+        assert!(req.pop_route().is_some());
+        assert_eq!(req.headers().len(), 1);
+        assert!(req.pop_route().is_some());
+        assert!(req.headers().is_empty());
+        assert!(req.pop_route().is_none());
+    }
+
+    #[test]
+    fn test_request_push_routes() {
+        let mut req = Request::try_from(
+            "INVITE sip:bob@192.0.2.4 SIP/2.0\r\n\
+Max-Forwards: 70\r\n\
+\r\n",
+        )
+        .unwrap();
+        req.push_routes(vec![]);
+        assert!(req.routes().is_empty());
+        let path = Request::try_from(
+            "REGISTER sip:biloxi.com SIP/2.0\r\n\
+Path: <sip:p2.example.com;lr>, <sip:p1.example.com;lr>\r\n\
+\r\n",
+        )
+        .unwrap()
+        .path();
+        req.push_routes(path.iter().cloned());
+        assert_eq!(
+            req.headers()[1].to_string(),
+            "Route: <sip:p2.example.com;lr>, <sip:p1.example.com;lr>"
+        );
+        req.push_routes(vec![Route::from(
+            Uri::try_from("sip:p0.example.com;lr").unwrap(),
+        )]);
+        assert_eq!(req.routes().len(), 3);
+        assert_eq!(
+            req.routes()[0].name_address().uri(),
+            Uri::try_from("sip:p0.example.com;lr").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_request_cancel() {
+        let req = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 69\r\n\
+Route: <sip:p1.example.com;lr>\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Length: 0\r\n\
+\r\n",
+        )
+        .unwrap();
+        let cancel = req.cancel().unwrap();
+        assert_eq!(
+            cancel.to_string(),
+            "CANCEL sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Max-Forwards: 69\r\n\
+Route: <sip:p1.example.com;lr>\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 CANCEL\r\n\
+Content-Length: 0\r\n\
+\r\n"
+        );
+        assert_err!(cancel.cancel());
+    }
+
+    #[test]
+    fn test_request_to_bytes() {
+        let value = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Content-Length: 3\r\n\
+\r\n\
+v=0";
+        let mut req = Request::try_from(
+            "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Content-Length: 3\r\n\
+\r\n",
+        )
+        .unwrap();
+        req.set_body(b"v=0");
+        assert_eq!(req.to_bytes(), value.as_bytes());
+        assert_eq!(req.to_string(), value);
+        assert_eq!(req.size(), value.len());
+
+        // A request without headers is serialized without an extra empty line.
+        let value = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\r\n";
+        req.headers_mut().clear();
+        req.set_body(b"");
+        assert_eq!(req.to_bytes(), value.as_bytes());
+        assert_eq!(req.to_string(), value);
+        assert_eq!(req.size(), value.len());
+    }
 }
@@ -2,13 +2,16 @@
 //!
 //! TODO
 
-use itertools::join;
 use nom_language::error::convert_error;
-use std::str::from_utf8;
+use std::borrow::Cow;
 
+use crate::MultipartBody;
 use crate::Reason;
+use crate::SipFrag;
 use crate::Version;
-use crate::{Header, SipError};
+use crate::sdp::SessionDescription;
+use crate::{AcceptEncodingHeader, ContentCoding};
+use crate::{ContentLengthHeader, Header, Request, SipError, Via, ViaHeader, Vias};
 
 /// Representation of a SIP response.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -20,12 +23,62 @@ pub struct Response {
 }
 
 impl Response {
+    pub(crate) fn new(
+        reason: Reason,
+        version: Version,
+        headers: Vec<Header>,
+        body: Vec<u8>,
+    ) -> Self {
+        Self {
+            reason,
+            version,
+            headers,
+            body,
+        }
+    }
+
+    /// Create a response to the given request, copying the headers that must be the same in the
+    /// response: Via, From, To, Call-ID, CSeq and Timestamp.
+    ///
+    /// The To header is copied as is, its tag must be added with `set_to_tag` for the responses
+    /// other than 100 Trying.
+    ///
+    /// [[RFC3261, Section 8.2.6](https://datatracker.ietf.org/doc/html/rfc3261#section-8.2.6)]
+    pub fn from_request(request: &Request, reason: Reason) -> Self {
+        let mut headers: Vec<Header> = request
+            .headers()
+            .iter()
+            .filter(|header| {
+                matches!(
+                    header,
+                    Header::Via(_)
+                        | Header::From(_)
+                        | Header::To(_)
+                        | Header::CallId(_)
+                        | Header::CSeq(_)
+                        | Header::Timestamp(_)
+                )
+            })
+            .cloned()
+            .collect();
+        headers.push(Header::ContentLength(ContentLengthHeader::from(0)));
+        Self::new(reason, *request.version(), headers, vec![])
+    }
+
     /// Get a reference to the associated `Reason`.
     #[inline]
     pub fn reason(&self) -> &Reason {
         &self.reason
     }
 
+    /// Change the reason of the response, eg. when a proxy converts a 503 response it forwards
+    /// into a 500 response.
+    ///
+    /// [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+    pub fn set_reason(&mut self, reason: Reason) {
+        self.reason = reason;
+    }
+
     /// Get a reference to the associated SIP `Version`.
     #[inline]
     pub fn version(&self) -> &Version {
@@ -37,6 +90,57 @@ impl Response {
         &self.headers
     }
 
+    /// Get a mutable reference to the headers contained in the response.
+    pub fn headers_mut(&mut self) -> &mut Vec<Header> {
+        &mut self.headers
+    }
+
+    /// Add a tag to the To header of the response, if it has none.
+    ///
+    /// [[RFC3261, Section 8.2.6.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.2.6.2)]
+    pub fn set_to_tag(&mut self, tag: &str) {
+        for header in self.headers.iter_mut() {
+            if let Header::To(to) = header {
+                *header = Header::To(to.with_tag(tag));
+            }
+        }
+    }
+
+    /// Get the vias of the response, gathered from all its Via headers in order, the top via
+    /// first.
+    pub fn vias(&self) -> Vias {
+        self.headers
+            .iter()
+            .filter_map(|header| match header {
+                Header::Via(header) => Some(header.vias().iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<Via>>()
+            .into()
+    }
+
+    /// Remove the top via of the response, returning it, as done by a proxy forwarding the
+    /// response.
+    ///
+    /// [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+    pub fn pop_via(&mut self) -> Option<Via> {
+        let index = self.headers.iter().position(
+            |header| matches!(header, Header::Via(header) if !header.vias().is_empty()),
+        )?;
+        let Header::Via(header) = &self.headers[index] else {
+            return None;
+        };
+        let mut vias = header.vias().to_vec();
+        let via = vias.remove(0);
+        if vias.is_empty() {
+            self.headers.remove(index);
+        } else {
+            self.headers[index] = Header::Via(ViaHeader::from(vias));
+        }
+        Some(via)
+    }
+
     /// Get a reference to the associated body.
     #[inline]
     pub fn body(&self) -> &[u8] {
@@ -46,21 +150,87 @@ impl Response {
     pub(crate) fn set_body(&mut self, body: &[u8]) {
         self.body = body.to_vec();
     }
+
+    /// Get the response encoded as it is sent over the network.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head().into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Get the status line and the headers of the response, as they are sent over the network.
+    fn head(&self) -> String {
+        super::head(
+            format_args!("{} {}", self.version, self.reason),
+            &self.headers,
+        )
+    }
+
+    /// Get the body of the response, decoded according to its Content-Encoding header.
+    ///
+    /// The `gzip` and `deflate` content codings are only supported with the `compression`
+    /// feature. Decoding fails if the decoded body is larger than
+    /// [`ContentCoding::DEFAULT_DECODED_SIZE_LIMIT`].
+    pub fn decoded_body(&self) -> Result<Cow<'_, [u8]>, SipError> {
+        self.decoded_body_with_limit(ContentCoding::DEFAULT_DECODED_SIZE_LIMIT)
+    }
+
+    /// Get the body of the response, decoded according to its Content-Encoding header, failing if
+    /// the decoded body is larger than the given number of bytes.
+    pub fn decoded_body_with_limit(&self, limit: usize) -> Result<Cow<'_, [u8]>, SipError> {
+        crate::compression::decode(&self.headers, &self.body, limit)
+    }
+
+    /// Compress the body of the response with the content coding preferred by the peer according
+    /// to its Accept-Encoding header, updating the Content-Encoding and Content-Length headers.
+    ///
+    /// Get the content coding that has been applied, if any.
+    pub fn encode_body(
+        &mut self,
+        accept_encoding: &AcceptEncodingHeader,
+    ) -> Result<Option<ContentCoding>, SipError> {
+        crate::compression::encode(&mut self.headers, &mut self.body, accept_encoding)
+    }
+
+    /// Get the session description contained in the body of the response, if its Content-Type is
+    /// `application/sdp`.
+    pub fn sdp(&self) -> Result<Option<SessionDescription>, SipError> {
+        crate::sdp::from_body(&self.headers, &self.decoded_body()?)
+    }
+
+    /// Set a session description as the body of the response, updating the Content-Type and
+    /// Content-Length headers.
+    pub fn set_sdp(&mut self, session_description: &SessionDescription) {
+        crate::sdp::set_body(&mut self.headers, &mut self.body, session_description);
+    }
+
+    /// Get the multipart body of the response, if its Content-Type is `multipart/*`.
+    pub fn multipart(&self) -> Result<Option<MultipartBody>, SipError> {
+        crate::multipart::from_body(&self.headers, &self.decoded_body()?)
+    }
+
+    /// Set a multipart body as the body of the response, updating the Content-Type and
+    /// Content-Length headers.
+    pub fn set_multipart(&mut self, multipart: &MultipartBody) {
+        crate::multipart::set_body(&mut self.headers, &mut self.body, multipart);
+    }
+
+    /// Get the SIP message fragment contained in the body of the response, if its Content-Type is
+    /// `message/sipfrag`.
+    pub fn sipfrag(&self) -> Result<Option<SipFrag>, SipError> {
+        crate::sipfrag::from_body(&self.headers, &self.decoded_body()?)
+    }
+
+    /// Set a SIP message fragment as the body of the response, updating the Content-Type and
+    /// Content-Length headers.
+    pub fn set_sipfrag(&mut self, sipfrag: &SipFrag) {
+        crate::sipfrag::set_body(&mut self.headers, &mut self.body, sipfrag);
+    }
 }
 
 impl std::fmt::Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {}\r\n{}\r\n{}",
-            self.version(),
-            self.reason(),
-            join(self.headers(), "\r\n"),
-            match from_utf8(self.body()) {
-                Ok(body) => body.to_string(),
-                Err(_) => format!("[binary body of size {}]", self.body().len()),
-            }
-        )
+        super::fmt(f, &self.head(), &self.body)
     }
 }
 
@@ -100,7 +270,7 @@ pub(crate) mod parser {
         parser::{ParserResult, sp},
     };
 
-    fn status_line(input: &str) -> ParserResult<&str, (Version, Reason)> {
+    pub(crate) fn status_line(input: &str) -> ParserResult<&str, (Version, Reason)> {
         context(
             "status_line",
             map((sip_version, sp, reason), |(version, _, reason)| {
@@ -167,4 +337,108 @@ mod test {
         assert_err!(Response::try_from("Hello world!"));
         assert_err!(Response::try_from("SIP/1.0 200 OK\r\n\r\n"));
     }
+
+    #[test]
+    fn test_response_from_request() {
+        let request = Request::try_from(
+            "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8;received=192.0.2.1\r\n\
+Max-Forwards: 69\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Length: 0\r\n\
+\r\n",
+        )
+        .unwrap();
+        let mut response = Response::from_request(&request, Reason::LOOP_DETECTED);
+        response.set_to_tag("a6c85cf");
+        assert_eq!(
+            String::from_utf8(response.to_bytes()).unwrap(),
+            "SIP/2.0 482 Loop Detected\r\n\
+Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8;received=192.0.2.1\r\n\
+To: Bob <sip:bob@biloxi.com>;tag=a6c85cf\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Content-Length: 0\r\n\
+\r\n"
+        );
+    }
+
+    #[test]
+    fn test_response_to_bytes() {
+        let value = "SIP/2.0 200 OK\r\nContent-Length: 0\r\n\r\n";
+        let mut response = Response::try_from(value).unwrap();
+        assert_eq!(response.to_bytes(), value.as_bytes());
+        assert_eq!(response.to_string(), value);
+
+        // A response without headers is serialized without an extra empty line.
+        let value = "SIP/2.0 200 OK\r\n\r\n";
+        response.headers_mut().clear();
+        assert_eq!(response.to_bytes(), value.as_bytes());
+        assert_eq!(response.to_string(), value);
+    }
+
+    #[test]
+    fn test_response_pop_via() {
+        let mut response = Response::try_from(
+            "SIP/2.0 200 OK\r\n\
+Via: SIP/2.0/UDP server10.biloxi.com;branch=z9hG4bK4b43c2ff8.1, SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+Content-Length: 0\r\n\
+\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.vias().len(), 3);
+        let via = response.pop_via().unwrap();
+        assert_eq!(via.branch(), Some("z9hG4bK4b43c2ff8.1".to_string()));
+        assert_eq!(
+            response.headers()[0].to_string(),
+            "Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1"
+        );
+        assert!(response.pop_via().is_some());
+        assert!(response.pop_via().is_some());
+        assert!(response.pop_via().is_none());
+        assert_eq!(response.headers().len(), 1);
+    }
+
+    #[test]
+    fn test_response_set_reason() {
+        let mut response = Response::try_from(
+            "SIP/2.0 503 Service Unavailable\r\n\
+Retry-After: 120\r\n\
+Content-Length: 0\r\n\
+\r\n",
+        )
+        .unwrap();
+        response.set_reason(Reason::SERVER_INTERNAL_ERROR);
+        response
+            .headers_mut()
+            .retain(|header| !matches!(header, Header::RetryAfter(_)));
+        assert_eq!(
+            response.to_string(),
+            "SIP/2.0 500 Server Internal Error\r\nContent-Length: 0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_response_sdp() {
+        let mut response = Response::try_from("SIP/2.0 200 OK\r\n\r\n").unwrap();
+        assert_eq!(response.sdp().unwrap(), None);
+        let sdp = crate::sdp::SessionDescription::try_from(
+            "v=0\r\no=bob 1 1 IN IP4 192.0.2.5\r\ns=-\r\nt=0 0\r\nm=audio 0 RTP/AVP 0\r\n",
+        )
+        .unwrap();
+        response.set_sdp(&sdp);
+        assert_eq!(
+            response.headers().first().unwrap().to_string(),
+            "Content-Type: application/sdp"
+        );
+        assert_eq!(response.sdp().unwrap(), Some(sdp));
+    }
 }
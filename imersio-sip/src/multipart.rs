@@ -0,0 +1,622 @@
+//! Multipart message bodies.
+//!
+//! This module provides the parsing and the generation of `multipart/*` bodies, used to carry
+//! several body parts, eg. a session description and a location object, in a single SIP message.
+//!
+//! [[RFC2046, Section 5.1](https://datatracker.ietf.org/doc/html/rfc2046#section-5.1)]
+//! [[RFC5621](https://datatracker.ietf.org/doc/html/rfc5621)]
+
+use crate::common::wrapped_string::WrappedString;
+use crate::headers::HeaderAccessor;
+use crate::sdp::SessionDescription;
+use crate::{
+    ContentDispositionHeader, ContentLengthHeader, ContentTypeHeader, Handling, Header,
+    MediaParameter, MediaRange, MediaType, SipError, TokenString,
+};
+
+/// Representation of the subtype of a multipart body.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum MultipartType {
+    /// The body parts are independent and bundled in a particular order.
+    Mixed,
+    /// Each body part is an alternative version of the same information.
+    Alternative,
+    /// The body parts are related, the root one referencing the others.
+    Related,
+    /// Any other multipart subtype.
+    Other(String),
+}
+
+impl MultipartType {
+    fn new(subtype: &str) -> Self {
+        match subtype.to_ascii_lowercase().as_str() {
+            "mixed" => Self::Mixed,
+            "alternative" => Self::Alternative,
+            "related" => Self::Related,
+            _ => Self::Other(subtype.to_string()),
+        }
+    }
+
+    /// Get the value of the multipart subtype.
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Mixed => "mixed",
+            Self::Alternative => "alternative",
+            Self::Related => "related",
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for MultipartType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+/// Representation of a body part of a multipart body, with its own headers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BodyPart {
+    headers: Vec<Header>,
+    body: Vec<u8>,
+}
+
+impl BodyPart {
+    /// Create a `BodyPart` from its headers and its content.
+    pub fn new(headers: Vec<Header>, body: &[u8]) -> Self {
+        Self {
+            headers,
+            body: body.to_vec(),
+        }
+    }
+
+    /// Get a reference to the headers of the body part.
+    pub fn headers(&self) -> &Vec<Header> {
+        &self.headers
+    }
+
+    /// Get a reference to the content of the body part.
+    pub fn body(&self) -> &[u8] {
+        self.body.as_slice()
+    }
+
+    /// Get the media type of the body part, if it has a Content-Type header.
+    ///
+    /// A body part without a Content-Type header is `text/plain`.
+    pub fn content_type(&self) -> Option<&MediaType> {
+        self.headers.iter().find_map(|header| match header {
+            Header::ContentType(header) => Some(header.media_type()),
+            _ => None,
+        })
+    }
+
+    /// Get a reference to the Content-Disposition header of the body part, if any.
+    pub fn content_disposition(&self) -> Option<&ContentDispositionHeader> {
+        self.headers.iter().find_map(|header| match header {
+            Header::ContentDisposition(header) => Some(header),
+            _ => None,
+        })
+    }
+
+    /// Get the value of the Content-ID header of the body part, if any.
+    ///
+    /// [[RFC2045, Section 7](https://datatracker.ietf.org/doc/html/rfc2045#section-7)]
+    pub fn content_id(&self) -> Option<&str> {
+        self.headers.iter().find_map(|header| match header {
+            Header::ExtensionHeader(header) if header.name().eq_ignore_ascii_case("Content-ID") => {
+                Some(header.value())
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the handling of the body part, from the `handling` parameter of its
+    /// Content-Disposition header, `required` by default.
+    ///
+    /// [[RFC5621, Section 3.1](https://datatracker.ietf.org/doc/html/rfc5621#section-3.1)]
+    pub fn handling(&self) -> Handling {
+        self.content_disposition()
+            .and_then(|header| {
+                header
+                    .parameters()
+                    .iter()
+                    .find_map(|parameter| parameter.handling())
+            })
+            .cloned()
+            .unwrap_or(Handling::Required)
+    }
+
+    /// Tell whether the body part has the given media type.
+    pub fn is(&self, r#type: &str, subtype: &str) -> bool {
+        match self.content_type() {
+            Some(media_type) => {
+                let media_range = media_type.media_range();
+                media_range.r#type().eq_ignore_ascii_case(r#type)
+                    && media_range.subtype().eq_ignore_ascii_case(subtype)
+            }
+            None => r#type.eq_ignore_ascii_case("text") && subtype.eq_ignore_ascii_case("plain"),
+        }
+    }
+
+    /// Get the multipart body contained in the body part, if it is itself a multipart body.
+    pub fn multipart(&self) -> Result<Option<MultipartBody>, SipError> {
+        match self.content_type() {
+            Some(media_type) => MultipartBody::from_body(media_type, &self.body),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the session description contained in the body part, if it is an `application/sdp`
+    /// body part.
+    pub fn sdp(&self) -> Result<Option<SessionDescription>, SipError> {
+        if self.is("application", "sdp") {
+            SessionDescription::try_from(self.body()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl From<&SessionDescription> for BodyPart {
+    fn from(value: &SessionDescription) -> Self {
+        Self::new(
+            vec![Header::ContentType(ContentTypeHeader::from(media_type(
+                "application",
+                "sdp",
+                vec![],
+            )))],
+            value.to_string().as_bytes(),
+        )
+    }
+}
+
+/// Representation of a multipart body.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MultipartBody {
+    r#type: MultipartType,
+    boundary: String,
+    parts: Vec<BodyPart>,
+}
+
+impl MultipartBody {
+    /// Create an empty `MultipartBody`.
+    ///
+    /// The boundary must not appear in the content of any of the body parts.
+    pub fn new<S: Into<String>>(r#type: MultipartType, boundary: S) -> Self {
+        Self {
+            r#type,
+            boundary: boundary.into(),
+            parts: vec![],
+        }
+    }
+
+    /// Parse a body as a multipart body if its media type is `multipart/*`.
+    pub fn from_body(media_type: &MediaType, body: &[u8]) -> Result<Option<Self>, SipError> {
+        let media_range = media_type.media_range();
+        if !media_range.r#type().eq_ignore_ascii_case("multipart") {
+            return Ok(None);
+        }
+        let boundary = media_type
+            .parameters()
+            .iter()
+            .find(|parameter| parameter.key().eq_ignore_ascii_case("boundary"))
+            .map(|parameter| match parameter.value() {
+                WrappedString::Quoted(value) => value.clone(),
+                WrappedString::NotWrapped(value) => value.to_string(),
+            })
+            .ok_or_else(|| SipError::InvalidMultipartBody("missing boundary".to_string()))?;
+        let mut multipart = Self::new(MultipartType::new(media_range.subtype()), boundary);
+        multipart.parts = parser::parts(&multipart.boundary, body)?;
+        Ok(Some(multipart))
+    }
+
+    /// Get the subtype of the multipart body.
+    pub fn r#type(&self) -> &MultipartType {
+        &self.r#type
+    }
+
+    /// Get the boundary delimiting the body parts.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Get a reference to the body parts.
+    pub fn parts(&self) -> &Vec<BodyPart> {
+        &self.parts
+    }
+
+    /// Add a body part.
+    pub fn add_part(&mut self, part: BodyPart) {
+        self.parts.push(part);
+    }
+
+    /// Get the media type of the multipart body, to put in the Content-Type header of the
+    /// message.
+    pub fn media_type(&self) -> MediaType {
+        let boundary = if !self.boundary.is_empty()
+            && self
+                .boundary
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-.!%*_+`'~".contains(c))
+        {
+            WrappedString::new_not_wrapped(TokenString::new(self.boundary.as_str()))
+        } else {
+            WrappedString::new_quoted(self.boundary.as_str())
+        };
+        media_type(
+            "multipart",
+            self.r#type.value(),
+            vec![MediaParameter::new(TokenString::new("boundary"), boundary)],
+        )
+    }
+
+    /// Get the serialized multipart body.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for part in &self.parts {
+            bytes.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            for header in &part.headers {
+                bytes.extend_from_slice(format!("{}\r\n", header).as_bytes());
+            }
+            bytes.extend_from_slice(b"\r\n");
+            bytes.extend_from_slice(&part.body);
+            bytes.extend_from_slice(b"\r\n");
+        }
+        bytes.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        bytes
+    }
+
+    /// Get the first body part having the given media type, looking into the nested multipart
+    /// bodies as well.
+    pub fn find(&self, r#type: &str, subtype: &str) -> Option<BodyPart> {
+        self.parts.iter().find_map(|part| {
+            if part.is(r#type, subtype) {
+                Some(part.clone())
+            } else {
+                part.multipart()
+                    .ok()
+                    .flatten()
+                    .and_then(|multipart| multipart.find(r#type, subtype))
+            }
+        })
+    }
+
+    /// Get the body parts whose handling is required but that are not supported, according to
+    /// the given predicate. Nested multipart bodies are checked recursively.
+    ///
+    /// For a `multipart/alternative` body, the body parts are only reported if none of them is
+    /// supported. A UAS receiving a request with such body parts rejects it with a 415
+    /// (Unsupported Media Type) response.
+    ///
+    /// [[RFC5621, Section 9](https://datatracker.ietf.org/doc/html/rfc5621#section-9)]
+    pub fn unsupported_required_parts<F>(&self, is_supported: F) -> Vec<&BodyPart>
+    where
+        F: Fn(&BodyPart) -> bool + Copy,
+    {
+        let supported = |part: &BodyPart| match part.multipart() {
+            Ok(Some(multipart)) => multipart
+                .unsupported_required_parts(is_supported)
+                .is_empty(),
+            Ok(None) => is_supported(part),
+            Err(_) => false,
+        };
+        if self.r#type.is_alternative() && self.parts.iter().any(supported) {
+            return vec![];
+        }
+        self.parts
+            .iter()
+            .filter(|part| part.handling().is_required() && !supported(part))
+            .collect()
+    }
+}
+
+/// Parse the body of a SIP message as a multipart body if its Content-Type is `multipart/*`.
+pub(crate) fn from_body(
+    headers: &[Header],
+    body: &[u8],
+) -> Result<Option<MultipartBody>, SipError> {
+    match headers.iter().find_map(|header| match header {
+        Header::ContentType(header) => Some(header.media_type()),
+        _ => None,
+    }) {
+        Some(media_type) => MultipartBody::from_body(media_type, body),
+        None => Ok(None),
+    }
+}
+
+/// Set a multipart body as the body of a SIP message, updating its Content-Type and
+/// Content-Length headers.
+pub(crate) fn set_body(headers: &mut Vec<Header>, body: &mut Vec<u8>, multipart: &MultipartBody) {
+    *body = multipart.to_bytes();
+    set_content_headers(headers, multipart.media_type(), body.len());
+}
+
+/// Replace the Content-Type and Content-Length headers of a SIP message.
+pub(crate) fn set_content_headers(headers: &mut Vec<Header>, media_type: MediaType, length: usize) {
+    crate::messages::replace_header(
+        headers,
+        Header::ContentType(ContentTypeHeader::from(media_type)),
+    );
+    crate::messages::replace_header(
+        headers,
+        Header::ContentLength(ContentLengthHeader::from(length as u32)),
+    );
+}
+
+pub(crate) fn media_type(
+    r#type: &str,
+    subtype: &str,
+    parameters: Vec<MediaParameter>,
+) -> MediaType {
+    MediaType::new(
+        MediaRange::new(TokenString::new(r#type), TokenString::new(subtype)),
+        parameters,
+    )
+}
+
+mod parser {
+    use std::str::from_utf8;
+
+    use super::BodyPart;
+    use crate::{Header, SipError};
+
+    fn invalid(message: &str) -> SipError {
+        SipError::InvalidMultipartBody(message.to_string())
+    }
+
+    /// Find the positions of the boundary delimiters, ie. the `--boundary` at the beginning of
+    /// the body or at the beginning of a line.
+    fn delimiters(boundary: &str, body: &[u8]) -> Vec<usize> {
+        let delimiter = format!("--{boundary}");
+        let delimiter = delimiter.as_bytes();
+        (0..body.len())
+            .filter(|&index| {
+                (index == 0 || body[index - 1] == b'\n')
+                    && body[index..].strip_prefix(delimiter).is_some_and(|rest| {
+                        rest.starts_with(b"--")
+                            || rest
+                                .iter()
+                                .find(|c| !matches!(c, b' ' | b'\t'))
+                                .is_none_or(|c| matches!(c, b'\r' | b'\n'))
+                    })
+            })
+            .collect()
+    }
+
+    fn strip_line_ending(content: &[u8]) -> &[u8] {
+        let content = content.strip_suffix(b"\n").unwrap_or(content);
+        content.strip_suffix(b"\r").unwrap_or(content)
+    }
+
+    pub(super) fn parts(boundary: &str, body: &[u8]) -> Result<Vec<BodyPart>, SipError> {
+        let delimiter_len = boundary.len() + 2;
+        let delimiters = delimiters(boundary, body);
+        let mut parts = vec![];
+        for (index, start) in delimiters.iter().enumerate() {
+            let after_delimiter = &body[start + delimiter_len..];
+            if after_delimiter.starts_with(b"--") {
+                return Ok(parts);
+            }
+            let Some(end) = delimiters.get(index + 1) else {
+                break;
+            };
+            let content = &body[start + delimiter_len..*end];
+            let content = match content.iter().position(|&c| c == b'\n') {
+                Some(position) => &content[position + 1..],
+                None => return Err(invalid("invalid boundary delimiter line")),
+            };
+            parts.push(part(content)?);
+        }
+        Err(invalid("missing close delimiter"))
+    }
+
+    fn part(content: &[u8]) -> Result<BodyPart, SipError> {
+        let (head, body) = if content.starts_with(b"\r\n") {
+            (&content[..0], &content[2..])
+        } else if content.starts_with(b"\n") {
+            (&content[..0], &content[1..])
+        } else if let Some(position) = content.windows(4).position(|w| w == b"\r\n\r\n") {
+            (&content[..position], &content[position + 4..])
+        } else if let Some(position) = content.windows(2).position(|w| w == b"\n\n") {
+            (&content[..position], &content[position + 2..])
+        } else {
+            return Err(invalid("body part without an empty line after its headers"));
+        };
+        let head = from_utf8(head).map_err(|_| invalid("body part headers not UTF-8 encoded"))?;
+        let mut lines: Vec<String> = vec![];
+        for line in head.split('\n').map(|line| line.trim_end_matches('\r')) {
+            match lines.last_mut() {
+                Some(last) if line.starts_with([' ', '\t']) => {
+                    last.push(' ');
+                    last.push_str(line.trim_start());
+                }
+                _ if line.is_empty() => (),
+                _ => lines.push(line.to_string()),
+            }
+        }
+        let headers = lines
+            .iter()
+            .map(|line| Header::try_from(line.as_str()))
+            .collect::<Result<Vec<Header>, SipError>>()?;
+        Ok(BodyPart::new(headers, strip_line_ending(body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Request;
+    use claims::{assert_err, assert_none, assert_ok};
+
+    const BODY: &str = "--boundary1\r\n\
+        Content-Type: application/sdp\r\n\
+        \r\n\
+        v=0\r\n\
+        o=alice 1 1 IN IP4 192.0.2.4\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        m=audio 49170 RTP/AVP 0\r\n\
+        \r\n\
+        --boundary1\r\n\
+        Content-Type: application/pidf+xml\r\n\
+        Content-ID: <target123@atlanta.example.com>\r\n\
+        Content-Disposition: by-reference;handling=optional\r\n\
+        \r\n\
+        <?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n\
+        <presence entity=\"pres:alice@atlanta.example.com\"/>\r\n\
+        --boundary1--\r\n";
+
+    fn request(content_type: &str, body: &str) -> Request {
+        let mut request = Request::try_from(
+            format!("INVITE sip:bob@example.com SIP/2.0\r\nContent-Type: {content_type}\r\n\r\n")
+                .as_str(),
+        )
+        .unwrap();
+        request.set_body(body.as_bytes());
+        request
+    }
+
+    #[test]
+    fn test_parse_multipart_body() {
+        let request = request("multipart/mixed;boundary=boundary1", BODY);
+        let multipart = request.multipart().unwrap().unwrap();
+        assert!(multipart.r#type().is_mixed());
+        assert_eq!(multipart.boundary(), "boundary1");
+        assert_eq!(multipart.parts().len(), 2);
+        let sdp = multipart.parts().first().unwrap();
+        assert!(sdp.is("application", "sdp"));
+        assert!(sdp.handling().is_required());
+        assert_eq!(sdp.sdp().unwrap().unwrap().origin().username(), "alice");
+        let pidf = multipart.parts().last().unwrap();
+        assert_eq!(pidf.content_id(), Some("<target123@atlanta.example.com>"));
+        assert!(pidf.handling().is_optional());
+        assert!(pidf.body().ends_with(b"\"/>"));
+
+        assert_eq!(request.sdp().unwrap().unwrap().origin().username(), "alice");
+        assert_eq!(multipart.to_bytes(), BODY.as_bytes());
+    }
+
+    #[test]
+    fn test_parse_multipart_body_with_preamble_and_quoted_boundary() {
+        let request = request(
+            "multipart/alternative; boundary=\"Simple:Boundary\"",
+            "This is the preamble.\r\n\
+            --Simple:Boundary\r\n\
+            \r\n\
+            plain text\r\n\
+            --Simple:Boundary  \r\n\
+            Content-Type: text/html\r\n\
+            \r\n\
+            <p>html</p>\r\n\
+            --Simple:Boundary--\r\n\
+            This is the epilogue.\r\n",
+        );
+        let multipart = request.multipart().unwrap().unwrap();
+        assert!(multipart.r#type().is_alternative());
+        assert_eq!(multipart.parts().len(), 2);
+        assert!(multipart.parts()[0].is("text", "plain"));
+        assert_eq!(multipart.parts()[0].body(), b"plain text");
+        assert_eq!(multipart.parts()[1].body(), b"<p>html</p>");
+        assert_eq!(
+            multipart.media_type().to_string(),
+            "multipart/alternative;boundary=\"Simple:Boundary\""
+        );
+    }
+
+    #[test]
+    fn test_invalid_multipart_bodies() {
+        assert_none!(request("application/sdp", BODY).multipart().unwrap());
+        assert_err!(request("multipart/mixed", BODY).multipart());
+        assert_err!(
+            request(
+                "multipart/mixed;boundary=boundary1",
+                "--boundary1\r\n\r\ntext\r\n"
+            )
+            .multipart()
+        );
+        assert_err!(
+            request(
+                "multipart/mixed;boundary=boundary1",
+                "--boundary1\r\nContent-Type: text/plain\r\ntext\r\n--boundary1--\r\n"
+            )
+            .multipart()
+        );
+    }
+
+    #[test]
+    fn test_build_multipart_body() {
+        let sdp = SessionDescription::try_from(
+            "v=0\r\no=bob 1 1 IN IP4 192.0.2.5\r\ns=-\r\nt=0 0\r\nm=audio 5004 RTP/AVP 0\r\n",
+        )
+        .unwrap();
+        let mut multipart = MultipartBody::new(MultipartType::Mixed, "unique-boundary-1");
+        multipart.add_part(BodyPart::from(&sdp));
+        multipart.add_part(BodyPart::new(
+            vec![
+                Header::try_from("Content-Type: application/rs-metadata+xml").unwrap(),
+                Header::try_from("Content-Disposition: recording-session").unwrap(),
+            ],
+            b"<recording xmlns='urn:ietf:params:xml:ns:recording:1'/>",
+        ));
+        let mut request = request("text/plain", "");
+        request.set_multipart(&multipart);
+        assert_eq!(
+            request.headers().first().unwrap().to_string(),
+            "Content-Type: multipart/mixed;boundary=unique-boundary-1"
+        );
+        assert_eq!(
+            request.headers().last().unwrap().to_string(),
+            format!("Content-Length: {}", request.body().len())
+        );
+        assert_eq!(request.multipart().unwrap().unwrap(), multipart);
+        assert_eq!(request.sdp().unwrap().unwrap(), sdp);
+    }
+
+    #[test]
+    fn test_unsupported_required_parts() {
+        let request = request("multipart/mixed;boundary=boundary1", BODY);
+        let multipart = request.multipart().unwrap().unwrap();
+        assert!(
+            multipart
+                .unsupported_required_parts(|part| part.is("application", "sdp"))
+                .is_empty()
+        );
+        let unsupported = multipart.unsupported_required_parts(|_| false);
+        assert_eq!(unsupported.len(), 1);
+        assert!(unsupported[0].is("application", "sdp"));
+
+        let mut alternative = MultipartBody::new(MultipartType::Alternative, "alt");
+        alternative.add_part(BodyPart::new(vec![], b"text"));
+        alternative.add_part(BodyPart::new(
+            vec![Header::try_from("Content-Type: text/html").unwrap()],
+            b"<p>text</p>",
+        ));
+        assert!(
+            alternative
+                .unsupported_required_parts(|part| part.is("text", "html"))
+                .is_empty()
+        );
+        assert_eq!(
+            alternative
+                .unsupported_required_parts(|part| part.is("application", "sdp"))
+                .len(),
+            2
+        );
+
+        let mut nested = MultipartBody::new(MultipartType::Mixed, "outer");
+        nested.add_part(BodyPart::new(
+            vec![Header::ContentType(ContentTypeHeader::from(
+                alternative.media_type(),
+            ))],
+            &alternative.to_bytes(),
+        ));
+        assert_ok!(nested.parts()[0].multipart());
+        assert_eq!(nested.unsupported_required_parts(|_| false).len(), 1);
+        assert!(
+            nested
+                .unsupported_required_parts(|part| part.is("text", "plain"))
+                .is_empty()
+        );
+        assert_eq!(nested.find("text", "html").unwrap().body(), b"<p>text</p>");
+    }
+}
@@ -0,0 +1,420 @@
+//! Content negotiation.
+//!
+//! The `ContentNegotiator` selects the media type, language and content coding of the body of a
+//! response according to the Accept, Accept-Language and Accept-Encoding headers of a request,
+//! and tells whether the request has to be rejected with a 406 (Not Acceptable) or a 415
+//! (Unsupported Media Type) response.
+//!
+//! [[RFC3261, Section 20.1](https://datatracker.ietf.org/doc/html/rfc3261#section-20.1)]
+//! [[RFC3261, Section 21.4.13](https://datatracker.ietf.org/doc/html/rfc3261#section-21.4.13)]
+
+use crate::{
+    AcceptEncoding, AcceptEncodingHeader, AcceptHeader, AcceptLanguage, AcceptLanguageHeader,
+    AcceptRange, ContentEncoding, Header, MediaRange, MediaType, Request, StatusCode, TokenString,
+};
+
+/// Representation of the content supported by a user agent, used to negotiate the bodies of
+/// the responses to the requests it receives.
+#[derive(Clone, Debug)]
+pub struct ContentNegotiator {
+    media_types: Vec<MediaType>,
+    languages: Vec<String>,
+    encodings: Vec<String>,
+}
+
+impl ContentNegotiator {
+    /// Create a `ContentNegotiator` supporting the given media types, by order of preference.
+    ///
+    /// Only the `identity` content coding is supported, and no language is required.
+    pub fn new(media_types: Vec<MediaType>) -> Self {
+        Self {
+            media_types,
+            languages: vec![],
+            encodings: vec![],
+        }
+    }
+
+    /// Set the supported languages, by order of preference.
+    pub fn with_languages(mut self, languages: &[&str]) -> Self {
+        self.languages = languages.iter().map(|l| l.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Set the supported content codings besides `identity`, by order of preference.
+    pub fn with_encodings(mut self, encodings: &[&str]) -> Self {
+        self.encodings = encodings.iter().map(|e| e.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Get a reference to the supported media types.
+    pub fn media_types(&self) -> &Vec<MediaType> {
+        &self.media_types
+    }
+
+    /// Get a reference to the supported languages.
+    pub fn languages(&self) -> &Vec<String> {
+        &self.languages
+    }
+
+    /// Get a reference to the supported content codings besides `identity`.
+    pub fn encodings(&self) -> &Vec<String> {
+        &self.encodings
+    }
+
+    /// Select the media type of the body of the response to the given request.
+    ///
+    /// The most specific range of the Accept headers matching a media type gives its q-value,
+    /// and the media type with the highest q-value is selected. Without any Accept header,
+    /// only `application/sdp` is acceptable.
+    pub fn media_type(&self, request: &Request) -> Option<&MediaType> {
+        let ranges: Vec<&AcceptRange> = request
+            .headers()
+            .iter()
+            .filter_map(|header| match header {
+                Header::Accept(header) => Some(header.ranges().iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let has_accept = request
+            .headers()
+            .iter()
+            .any(|header| matches!(header, Header::Accept(_)));
+        best(&self.media_types, |media_type| {
+            let media_range = media_type.media_range();
+            if !has_accept {
+                return is_default_media_range(media_range).then_some(1.0);
+            }
+            ranges
+                .iter()
+                .filter_map(|range| {
+                    media_range_specificity(range.media_range(), media_range)
+                        .map(|specificity| (specificity, range.q().unwrap_or(1.0)))
+                })
+                .max_by_key(|(specificity, _)| *specificity)
+                .map(|(_, q)| q)
+        })
+    }
+
+    /// Select the language of the body of the response to the given request.
+    ///
+    /// A language range matches the languages it is equal to or a prefix of. Without any
+    /// Accept-Language header, the preferred supported language is selected.
+    pub fn language(&self, request: &Request) -> Option<&str> {
+        let languages: Vec<&AcceptLanguage> = request
+            .headers()
+            .iter()
+            .filter_map(|header| match header {
+                Header::AcceptLanguage(header) => Some(header.languages().iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        if languages.is_empty() {
+            return self.languages.first().map(String::as_str);
+        }
+        best(&self.languages, |language| {
+            languages
+                .iter()
+                .filter_map(|range| {
+                    language_range_specificity(range.language(), language)
+                        .map(|specificity| (specificity, range.q().unwrap_or(1.0)))
+                })
+                .max_by_key(|(specificity, _)| *specificity)
+                .map(|(_, q)| q)
+        })
+        .map(String::as_str)
+    }
+
+    /// Select the content coding of the body of the response to the given request.
+    ///
+    /// The `identity` content coding is acceptable unless it is explicitly refused, and the
+    /// other content codings are preferred to it when they have the same q-value, as with
+    /// `ContentCoding::select`.
+    pub fn encoding(&self, request: &Request) -> Option<&str> {
+        let encodings: Vec<&AcceptEncoding> = request
+            .headers()
+            .iter()
+            .filter_map(|header| match header {
+                Header::AcceptEncoding(header) => Some(header.encodings().iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        crate::compression::select(&encodings, self.encodings.iter().map(String::as_str)).or_else(
+            || crate::compression::is_identity_acceptable(&encodings).then_some("identity"),
+        )
+    }
+
+    /// Tell whether the given request must be rejected with a 406 (Not Acceptable) response,
+    /// ie. no supported media type or content coding is acceptable for the body of the
+    /// response.
+    pub fn is_not_acceptable(&self, request: &Request) -> bool {
+        self.media_type(request).is_none() || self.encoding(request).is_none()
+    }
+
+    /// Tell whether the given request must be rejected with a 415 (Unsupported Media Type)
+    /// response, ie. the media type, the content codings or the languages of its body are not
+    /// supported.
+    pub fn is_unsupported_media_type(&self, request: &Request) -> bool {
+        request.headers().iter().any(|header| match header {
+            Header::ContentType(header) => !self.media_types.iter().any(|media_type| {
+                media_range_specificity(media_type.media_range(), header.media_type().media_range())
+                    == Some(3)
+            }),
+            Header::ContentEncoding(header) => header.encodings().iter().any(|encoding| {
+                let encoding = encoding.to_string();
+                !encoding.eq_ignore_ascii_case("identity")
+                    && !self.encodings.contains(&encoding.to_ascii_lowercase())
+            }),
+            Header::ContentLanguage(header) if !self.languages.is_empty() => {
+                header.languages().iter().any(|language| {
+                    !self
+                        .languages
+                        .contains(&language.to_string().to_ascii_lowercase())
+                })
+            }
+            _ => false,
+        })
+    }
+
+    /// Get the status code of the response rejecting the given request because of its content,
+    /// if any.
+    pub fn check(&self, request: &Request) -> Option<StatusCode> {
+        if self.is_unsupported_media_type(request) {
+            Some(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+        } else if self.is_not_acceptable(request) {
+            Some(StatusCode::NOT_ACCEPTABLE)
+        } else {
+            None
+        }
+    }
+
+    /// Get the Accept header listing the supported media types, to be added to a 415
+    /// (Unsupported Media Type) response.
+    pub fn accept_header(&self) -> AcceptHeader {
+        let ranges: Vec<AcceptRange> = self
+            .media_types
+            .iter()
+            .map(|media_type| AcceptRange::new(media_type.media_range().clone(), vec![]))
+            .collect();
+        AcceptHeader::from(ranges)
+    }
+
+    /// Get the Accept-Encoding header listing the supported content codings, to be added to a
+    /// 415 (Unsupported Media Type) response.
+    pub fn accept_encoding_header(&self) -> AcceptEncodingHeader {
+        let encodings: Vec<AcceptEncoding> = self
+            .encodings
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once("identity"))
+            .map(|encoding| {
+                AcceptEncoding::new(ContentEncoding::new(TokenString::new(encoding)), vec![])
+            })
+            .collect();
+        AcceptEncodingHeader::from(encodings)
+    }
+
+    /// Get the Accept-Language header listing the supported languages, to be added to a 415
+    /// (Unsupported Media Type) response.
+    pub fn accept_language_header(&self) -> AcceptLanguageHeader {
+        let languages: Vec<AcceptLanguage> = self
+            .languages
+            .iter()
+            .map(|language| AcceptLanguage::new(language.as_str(), vec![]))
+            .collect();
+        AcceptLanguageHeader::from(languages)
+    }
+
+    /// Get the headers to be added to a 415 (Unsupported Media Type) response.
+    pub fn unsupported_media_type_headers(&self) -> Vec<Header> {
+        let mut headers = vec![
+            Header::Accept(self.accept_header()),
+            Header::AcceptEncoding(self.accept_encoding_header()),
+        ];
+        if !self.languages.is_empty() {
+            headers.push(Header::AcceptLanguage(self.accept_language_header()));
+        }
+        headers
+    }
+}
+
+/// Get the item with the highest non-zero q-value, the first one winning the ties.
+fn best<T, F>(items: &[T], q: F) -> Option<&T>
+where
+    F: Fn(&T) -> Option<f32>,
+{
+    items
+        .iter()
+        .filter_map(|item| q(item).map(|q| (item, q)))
+        .filter(|(_, q)| *q > 0.0)
+        .fold(None, |best: Option<(&T, f32)>, (item, q)| match best {
+            Some((_, best_q)) if best_q >= q => best,
+            _ => Some((item, q)),
+        })
+        .map(|(item, _)| item)
+}
+
+fn is_default_media_range(media_range: &MediaRange) -> bool {
+    media_range.r#type().eq_ignore_ascii_case("application")
+        && media_range.subtype().eq_ignore_ascii_case("sdp")
+}
+
+/// Get the specificity of a media range from an Accept header matching a media range, from 1
+/// for `*/*` to 3 for an exact match.
+fn media_range_specificity(range: &MediaRange, media_range: &MediaRange) -> Option<u8> {
+    match (range.r#type(), range.subtype()) {
+        ("*", "*") => Some(1),
+        (r#type, "*") if r#type.eq_ignore_ascii_case(media_range.r#type()) => Some(2),
+        (r#type, subtype)
+            if r#type.eq_ignore_ascii_case(media_range.r#type())
+                && subtype.eq_ignore_ascii_case(media_range.subtype()) =>
+        {
+            Some(3)
+        }
+        _ => None,
+    }
+}
+
+/// Get the specificity of a language range matching a language, ie. the number of subtags of
+/// the range, or 0 for `*`.
+fn language_range_specificity(range: &str, language: &str) -> Option<usize> {
+    if range == "*" {
+        return Some(0);
+    }
+    let range = range.to_ascii_lowercase();
+    let language = language.to_ascii_lowercase();
+    (language == range || language.starts_with(&format!("{range}-")))
+        .then(|| range.split('-').count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_some_eq};
+    use itertools::join;
+
+    fn request(headers: &str) -> Request {
+        Request::try_from(format!("OPTIONS sip:bob@biloxi.com SIP/2.0\r\n{headers}\r\n").as_str())
+            .unwrap()
+    }
+
+    fn negotiator() -> ContentNegotiator {
+        ContentNegotiator::new(vec![
+            MediaType::try_from("application/sdp").unwrap(),
+            MediaType::try_from("application/pidf+xml").unwrap(),
+            MediaType::try_from("text/plain").unwrap(),
+        ])
+        .with_languages(&["en", "fr-CA"])
+        .with_encodings(&["gzip"])
+    }
+
+    #[test]
+    fn test_media_type_negotiation() {
+        let negotiator = negotiator();
+        assert_some_eq!(
+            negotiator.media_type(&request("")),
+            &MediaType::try_from("application/sdp").unwrap()
+        );
+        assert_some_eq!(
+            negotiator.media_type(&request(
+                "Accept: application/sdp;q=0.5, application/pidf+xml\r\n"
+            )),
+            &MediaType::try_from("application/pidf+xml").unwrap()
+        );
+        assert_some_eq!(
+            negotiator.media_type(&request("Accept: text/*;q=0.9, */*;q=0.1\r\n")),
+            &MediaType::try_from("text/plain").unwrap()
+        );
+        assert_some_eq!(
+            negotiator.media_type(&request("Accept: application/*, application/sdp;q=0\r\n")),
+            &MediaType::try_from("application/pidf+xml").unwrap()
+        );
+        assert_none!(negotiator.media_type(&request("Accept: image/png\r\n")));
+        assert_none!(negotiator.media_type(&request("Accept: \r\n")));
+    }
+
+    #[test]
+    fn test_language_negotiation() {
+        let negotiator = negotiator();
+        assert_some_eq!(negotiator.language(&request("")), "en");
+        assert_some_eq!(
+            negotiator.language(&request("Accept-Language: fr;q=0.8, en;q=0.7\r\n")),
+            "fr-ca"
+        );
+        assert_some_eq!(
+            negotiator.language(&request("Accept-Language: *;q=0.5, en;q=0.1\r\n")),
+            "fr-ca"
+        );
+        assert_none!(negotiator.language(&request("Accept-Language: de\r\n")));
+    }
+
+    #[test]
+    fn test_encoding_negotiation() {
+        let negotiator = negotiator();
+        assert_some_eq!(negotiator.encoding(&request("")), "identity");
+        assert_some_eq!(
+            negotiator.encoding(&request("Accept-Encoding: gzip\r\n")),
+            "gzip"
+        );
+        assert_some_eq!(
+            negotiator.encoding(&request("Accept-Encoding: gzip, identity\r\n")),
+            "gzip"
+        );
+        assert_some_eq!(
+            negotiator.encoding(&request("Accept-Encoding: gzip;q=0.5, identity\r\n")),
+            "identity"
+        );
+        assert_some_eq!(
+            negotiator.encoding(&request("Accept-Encoding: gzip;q=0.5, identity;q=0\r\n")),
+            "gzip"
+        );
+        assert_none!(negotiator.encoding(&request("Accept-Encoding: br, identity;q=0\r\n")));
+        assert_none!(negotiator.encoding(&request("Accept-Encoding: *;q=0\r\n")));
+    }
+
+    #[test]
+    fn test_check() {
+        let negotiator = negotiator();
+        assert_none!(negotiator.check(&request("Content-Type: application/sdp\r\n")));
+        assert_some_eq!(
+            negotiator.check(&request("Accept: image/*\r\n")),
+            StatusCode::NOT_ACCEPTABLE
+        );
+        assert_some_eq!(
+            negotiator.check(&request("Content-Type: application/isup\r\n")),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_some_eq!(
+            negotiator.check(&request(
+                "Content-Type: application/sdp\r\nContent-Encoding: br\r\n"
+            )),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_some_eq!(
+            negotiator.check(&request(
+                "Content-Type: text/plain\r\nContent-Language: de\r\n"
+            )),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        assert_none!(negotiator.check(&request(
+            "Content-Type: text/plain\r\nContent-Encoding: gzip\r\nContent-Language: fr-ca\r\n"
+        )));
+    }
+
+    #[test]
+    fn test_unsupported_media_type_headers() {
+        let headers = negotiator().unsupported_media_type_headers();
+        assert_eq!(
+            join(&headers, "\r\n"),
+            "Accept: application/sdp, application/pidf+xml, text/plain\r\n\
+            Accept-Encoding: gzip, identity\r\n\
+            Accept-Language: en, fr-ca"
+        );
+        assert_eq!(
+            Header::try_from(headers[0].to_string().as_str()).unwrap(),
+            headers[0]
+        );
+    }
+}
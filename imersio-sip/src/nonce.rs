@@ -0,0 +1,400 @@
+//! Nonce management for Digest challenges.
+//!
+//! The `NonceService` generates the Digest challenges of a server. Its nonces are verifiable
+//! without keeping any state: they contain their creation time and an HMAC of this time, the
+//! address of the client and the realm. Only the nonce counts received for each nonce are kept,
+//! to detect replayed credentials.
+//!
+//! [[RFC3261, Section 22.4](https://datatracker.ietf.org/doc/html/rfc3261#section-22.4)]
+//! [[RFC2617, Section 3.2.1](https://datatracker.ietf.org/doc/html/rfc2617#section-3.2.1)]
+
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::common::wrapped_string::WrappedString;
+use crate::utils::constant_time_eq;
+use crate::{
+    Algorithm, AuthParameter, Challenge, Credentials, DigestSecret, MessageQop, MessageQops,
+    ProxyAuthenticateHeader, Request, SipError, Stale, WWWAuthenticateHeader,
+};
+
+/// The length of the hexadecimal timestamp at the start of a nonce.
+const TIMESTAMP_LENGTH: usize = 16;
+
+/// Representation of the outcome of the verification of Digest credentials.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, derive_more::IsVariant)]
+pub enum AuthenticationOutcome {
+    /// The credentials are valid.
+    Authenticated,
+    /// The credentials are valid but their nonce has expired, so the request is to be
+    /// challenged again with the `stale` parameter set to `true`.
+    StaleNonce,
+    /// The credentials are invalid or replayed, so the request is to be challenged again.
+    Rejected,
+}
+
+/// Representation of a service generating and verifying the nonces of the Digest challenges
+/// of a realm.
+#[derive(Clone, Debug)]
+pub struct NonceService {
+    realm: String,
+    key: Vec<u8>,
+    opaque: String,
+    algorithm: Algorithm,
+    qops: Vec<MessageQop>,
+    lifetime: TimeDelta,
+    nonce_counts: HashMap<String, (u32, DateTime<Utc>)>,
+}
+
+impl NonceService {
+    /// The default lifetime of the nonces.
+    pub const DEFAULT_LIFETIME: TimeDelta = TimeDelta::minutes(5);
+
+    /// Create a `NonceService` for the given realm, with a random key.
+    ///
+    /// The challenges use the MD5 algorithm and the `auth` quality of protection by default.
+    pub fn new<S: Into<String>>(realm: S) -> Result<Self, SipError> {
+        let mut key = [0u8; 32];
+        getrandom::fill(&mut key).map_err(|e| SipError::InvalidDigest(e.to_string()))?;
+        Ok(Self::with_key(realm, &key))
+    }
+
+    /// Create a `NonceService` for the given realm, with the given key.
+    ///
+    /// The servers of a cluster sharing the same key accept the nonces generated by each other.
+    pub fn with_key<S: Into<String>>(realm: S, key: &[u8]) -> Self {
+        let realm = realm.into();
+        let opaque = hex(&hmac(key, format!("opaque:{realm}").as_bytes()))[..32].to_string();
+        Self {
+            realm,
+            key: key.to_vec(),
+            opaque,
+            algorithm: Algorithm::Md5,
+            qops: vec![MessageQop::Auth],
+            lifetime: Self::DEFAULT_LIFETIME,
+            nonce_counts: HashMap::new(),
+        }
+    }
+
+    /// Set the algorithm of the challenges.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the quality of protection options of the challenges.
+    pub fn with_qops(mut self, qops: Vec<MessageQop>) -> Self {
+        self.qops = qops;
+        self
+    }
+
+    /// Set the lifetime of the nonces.
+    pub fn with_lifetime(mut self, lifetime: TimeDelta) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Get the realm of the challenges.
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    /// Get the opaque value of the challenges.
+    pub fn opaque(&self) -> &str {
+        &self.opaque
+    }
+
+    /// Generate a nonce for a client at the given time.
+    pub fn nonce(&self, client: IpAddr, now: DateTime<Utc>) -> String {
+        let timestamp = format!("{:016x}", now.timestamp());
+        let mac = self.mac(&timestamp, client);
+        format!("{timestamp}{mac}")
+    }
+
+    /// Generate a Digest challenge for a client at the given time.
+    ///
+    /// The `stale` parameter is to be set when the previous credentials of the client were
+    /// rejected only because of the expiration of their nonce.
+    pub fn challenge(&self, client: IpAddr, now: DateTime<Utc>, stale: bool) -> Challenge {
+        let mut parameters = vec![
+            AuthParameter::Realm(WrappedString::new_quoted(self.realm.as_str())),
+            AuthParameter::Nonce(WrappedString::new_quoted(self.nonce(client, now))),
+            AuthParameter::Opaque(WrappedString::new_quoted(self.opaque.as_str())),
+            AuthParameter::Algorithm(self.algorithm.clone()),
+        ];
+        if !self.qops.is_empty() {
+            parameters.push(AuthParameter::QopOptions(MessageQops::from(
+                self.qops.clone(),
+            )));
+        }
+        if stale {
+            parameters.push(AuthParameter::Stale(Stale::from(true)));
+        }
+        Challenge::Digest(parameters.into())
+    }
+
+    /// Generate the WWW-Authenticate header of a 401 (Unauthorized) response.
+    pub fn www_authenticate_header(
+        &self,
+        client: IpAddr,
+        now: DateTime<Utc>,
+        stale: bool,
+    ) -> WWWAuthenticateHeader {
+        WWWAuthenticateHeader::from(self.challenge(client, now, stale))
+    }
+
+    /// Generate the Proxy-Authenticate header of a 407 (Proxy Authentication Required)
+    /// response.
+    pub fn proxy_authenticate_header(
+        &self,
+        client: IpAddr,
+        now: DateTime<Utc>,
+        stale: bool,
+    ) -> ProxyAuthenticateHeader {
+        ProxyAuthenticateHeader::from(self.challenge(client, now, stale))
+    }
+
+    /// Verify the Digest credentials received from a client in the given request.
+    ///
+    /// The credentials are rejected if they do not match the realm, the opaque value or the
+    /// algorithm of the challenges, if their nonce has not been generated for the client, if
+    /// their response does not match the secret of the user, or if their nonce count has
+    /// already been used. Valid credentials with an expired nonce are told apart to be
+    /// challenged again with the `stale` parameter.
+    pub fn verify(
+        &mut self,
+        credentials: &Credentials,
+        request: &Request,
+        secret: &DigestSecret,
+        client: IpAddr,
+        now: DateTime<Utc>,
+    ) -> Result<AuthenticationOutcome, SipError> {
+        if !credentials.is_digest()
+            || credentials.realm() != Some(self.realm.as_str())
+            || credentials
+                .opaque()
+                .is_some_and(|opaque| opaque != self.opaque)
+            || credentials.algorithm().unwrap_or(&Algorithm::Md5) != &self.algorithm
+            || credentials
+                .qop()
+                .is_some_and(|qop| !self.qops.contains(qop))
+            || (credentials.qop().is_none() && !self.qops.is_empty())
+        {
+            return Ok(AuthenticationOutcome::Rejected);
+        }
+        let Some(nonce) = credentials.nonce() else {
+            return Ok(AuthenticationOutcome::Rejected);
+        };
+        let Some(created_at) = self.verify_nonce(nonce, client) else {
+            return Ok(AuthenticationOutcome::Rejected);
+        };
+        if !secret.verify(credentials, request)? {
+            return Ok(AuthenticationOutcome::Rejected);
+        }
+        let expires_at = created_at + self.lifetime;
+        if expires_at <= now {
+            return Ok(AuthenticationOutcome::StaleNonce);
+        }
+        if let Some(nonce_count) = credentials.nonce_count() {
+            let Ok(nonce_count) = u32::from_str_radix(nonce_count, 16) else {
+                return Ok(AuthenticationOutcome::Rejected);
+            };
+            let last = self
+                .nonce_counts
+                .entry(nonce.to_string())
+                .or_insert((0, expires_at));
+            if nonce_count <= last.0 {
+                return Ok(AuthenticationOutcome::Rejected);
+            }
+            last.0 = nonce_count;
+        }
+        Ok(AuthenticationOutcome::Authenticated)
+    }
+
+    /// Remove the nonce counts kept for the nonces that have expired at the given time.
+    pub fn purge(&mut self, now: DateTime<Utc>) {
+        self.nonce_counts
+            .retain(|_, (_, expires_at)| *expires_at > now);
+    }
+
+    /// Get the creation time of a nonce, if it has been generated for the given client.
+    fn verify_nonce(&self, nonce: &str, client: IpAddr) -> Option<DateTime<Utc>> {
+        if nonce.len() <= TIMESTAMP_LENGTH || !nonce.is_char_boundary(TIMESTAMP_LENGTH) {
+            return None;
+        }
+        let (timestamp, mac) = nonce.split_at(TIMESTAMP_LENGTH);
+        if !constant_time_eq(mac.as_bytes(), self.mac(timestamp, client).as_bytes()) {
+            return None;
+        }
+        DateTime::from_timestamp(i64::from_str_radix(timestamp, 16).ok()?, 0)
+    }
+
+    fn mac(&self, timestamp: &str, client: IpAddr) -> String {
+        hex(&hmac(
+            &self.key,
+            format!("{timestamp}:{client}:{}", self.realm).as_bytes(),
+        ))
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take a key of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DigestClient;
+    use claims::assert_ok;
+
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+    const OTHER_CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 2));
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    fn register() -> Request {
+        Request::try_from(
+            "REGISTER sip:biloxi.com SIP/2.0\r\n\
+            To: Bob <sip:bob@biloxi.com>\r\n\
+            Call-ID: a84b4c76e66710\r\n\
+            CSeq: 1 REGISTER\r\n\
+            \r\n",
+        )
+        .unwrap()
+    }
+
+    fn service() -> NonceService {
+        NonceService::with_key("biloxi.com", b"secret key")
+    }
+
+    fn secret() -> DigestSecret {
+        DigestSecret::Password("zanzibar".to_string())
+    }
+
+    #[test]
+    fn test_challenge() {
+        let service = service();
+        let header = service.www_authenticate_header(CLIENT, now(), false);
+        let challenge = header.challenge();
+        assert_eq!(challenge.realm(), Some("biloxi.com"));
+        assert_eq!(challenge.opaque(), Some(service.opaque()));
+        assert_eq!(
+            challenge.nonce(),
+            Some(service.nonce(CLIENT, now()).as_str())
+        );
+        assert_eq!(challenge.algorithm(), Some(&Algorithm::Md5));
+        assert!(!challenge.has_stale());
+        assert_ok!(crate::Header::try_from(header.to_string().as_str()));
+
+        let challenge = service
+            .with_algorithm(Algorithm::Sha256)
+            .challenge(CLIENT, now(), true);
+        assert_eq!(challenge.algorithm(), Some(&Algorithm::Sha256));
+        assert_eq!(challenge.stale().map(|stale| **stale), Some(true));
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut service = service();
+        let request = register();
+        let challenge = service.challenge(CLIENT, now(), false);
+        let mut client = DigestClient::new("bob", "zanzibar");
+        let credentials = client.credentials(&challenge, &request).unwrap();
+        assert_eq!(
+            service
+                .verify(&credentials, &request, &secret(), CLIENT, now())
+                .unwrap(),
+            AuthenticationOutcome::Authenticated
+        );
+        // Replayed nonce count.
+        assert_eq!(
+            service
+                .verify(&credentials, &request, &secret(), CLIENT, now())
+                .unwrap(),
+            AuthenticationOutcome::Rejected
+        );
+        // Next nonce count.
+        let credentials = client.credentials(&challenge, &request).unwrap();
+        assert_eq!(
+            service
+                .verify(&credentials, &request, &secret(), CLIENT, now())
+                .unwrap(),
+            AuthenticationOutcome::Authenticated
+        );
+    }
+
+    #[test]
+    fn test_verify_rejected_and_stale() {
+        let mut service = service();
+        let request = register();
+        let challenge = service.challenge(CLIENT, now(), false);
+        let mut client = DigestClient::new("bob", "zanzibar");
+
+        let credentials = client.credentials(&challenge, &request).unwrap();
+        assert_eq!(
+            service
+                .verify(
+                    &credentials,
+                    &request,
+                    &DigestSecret::Password("wrong".to_string()),
+                    CLIENT,
+                    now()
+                )
+                .unwrap(),
+            AuthenticationOutcome::Rejected
+        );
+        assert_eq!(
+            service
+                .verify(&credentials, &request, &secret(), OTHER_CLIENT, now())
+                .unwrap(),
+            AuthenticationOutcome::Rejected
+        );
+        let later = now() + NonceService::DEFAULT_LIFETIME;
+        assert_eq!(
+            service
+                .verify(&credentials, &request, &secret(), CLIENT, later)
+                .unwrap(),
+            AuthenticationOutcome::StaleNonce
+        );
+
+        let mut other_service = NonceService::with_key("biloxi.com", b"other key");
+        assert_eq!(
+            other_service
+                .verify(&credentials, &request, &secret(), CLIENT, now())
+                .unwrap(),
+            AuthenticationOutcome::Rejected
+        );
+    }
+
+    #[test]
+    fn test_purge() {
+        let mut service = service();
+        let request = register();
+        let challenge = service.challenge(CLIENT, now(), false);
+        let credentials = DigestClient::new("bob", "zanzibar")
+            .credentials(&challenge, &request)
+            .unwrap();
+        assert!(
+            service
+                .verify(&credentials, &request, &secret(), CLIENT, now())
+                .unwrap()
+                .is_authenticated()
+        );
+        assert_eq!(service.nonce_counts.len(), 1);
+        service.purge(now());
+        assert_eq!(service.nonce_counts.len(), 1);
+        service.purge(now() + NonceService::DEFAULT_LIFETIME);
+        assert!(service.nonce_counts.is_empty());
+    }
+}
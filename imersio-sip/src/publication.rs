@@ -0,0 +1,468 @@
+//! Event state publication handling.
+//!
+//! This module provides the state kept by an event state compositor (ESC) for the event state
+//! published by event publication agents (EPA) using PUBLISH requests.
+//!
+//! [[RFC3903, Section 6](https://datatracker.ietf.org/doc/html/rfc3903#section-6)]
+
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::HashMap;
+
+use crate::headers::HeaderAccessor;
+use crate::{
+    ExpiresHeader, Header, Method, MinExpiresHeader, Reason, Request, SipETagHeader, TokenString,
+    Uri,
+};
+
+/// Representation of a piece of event state published for a resource.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Publication {
+    resource: Uri,
+    event: Option<String>,
+    entity_tag: TokenString,
+    body: Vec<u8>,
+    expires_at: DateTime<Utc>,
+}
+
+impl Publication {
+    /// Get a reference to the URI of the resource the event state has been published for.
+    pub fn resource(&self) -> &Uri {
+        &self.resource
+    }
+
+    /// Get the event package of the event state, taken from the Event header of the PUBLISH
+    /// request.
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Get the entity-tag currently identifying the event state.
+    pub fn entity_tag(&self) -> &str {
+        self.entity_tag.as_str()
+    }
+
+    /// Get a reference to the published event state.
+    pub fn body(&self) -> &[u8] {
+        self.body.as_slice()
+    }
+
+    /// Get the time at which the event state expires.
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    /// Tell whether the event state has expired at the given time.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Representation of the outcome of the processing of a PUBLISH request.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::IsVariant)]
+pub enum PublicationOutcome {
+    /// The event state has been created, refreshed or modified.
+    Accepted {
+        /// The new entity-tag of the event state.
+        entity_tag: TokenString,
+        /// The expiration interval granted for the event state.
+        expires: TimeDelta,
+    },
+    /// The event state has been removed.
+    Removed {
+        /// The entity-tag of the removed event state.
+        entity_tag: TokenString,
+    },
+    /// The PUBLISH request has been rejected.
+    Rejected {
+        /// The reason of the rejection.
+        reason: Reason,
+        /// The minimum expiration interval, for a 423 (Interval Too Brief) response.
+        min_expires: Option<TimeDelta>,
+    },
+}
+
+impl PublicationOutcome {
+    /// Get the reason of the response to send for the PUBLISH request.
+    pub fn reason(&self) -> Reason {
+        match self {
+            Self::Accepted { .. } | Self::Removed { .. } => Reason::OK,
+            Self::Rejected { reason, .. } => reason.clone(),
+        }
+    }
+
+    /// Get the headers to include in the response to send for the PUBLISH request.
+    ///
+    /// A 2xx response contains the SIP-ETag and Expires headers, and a 423 response contains the
+    /// Min-Expires header.
+    pub fn headers(&self) -> Vec<Header> {
+        match self {
+            Self::Accepted {
+                entity_tag,
+                expires,
+            } => vec![
+                Header::SipETag(SipETagHeader::from(entity_tag.clone())),
+                Header::Expires(ExpiresHeader::from(*expires)),
+            ],
+            Self::Removed { entity_tag } => vec![
+                Header::SipETag(SipETagHeader::from(entity_tag.clone())),
+                Header::Expires(ExpiresHeader::from(TimeDelta::zero())),
+            ],
+            Self::Rejected { min_expires, .. } => min_expires
+                .iter()
+                .map(|min_expires| Header::MinExpires(MinExpiresHeader::from(*min_expires)))
+                .collect(),
+        }
+    }
+
+    fn rejected(reason: Reason) -> Self {
+        Self::Rejected {
+            reason,
+            min_expires: None,
+        }
+    }
+}
+
+/// Representation of the event state stored by an event state compositor.
+///
+/// Each piece of event state is identified by an entity-tag, that changes each time the event
+/// state is refreshed or modified.
+#[derive(Clone, Debug)]
+pub struct PublicationStore {
+    default_expires: TimeDelta,
+    min_expires: TimeDelta,
+    max_expires: TimeDelta,
+    publications: HashMap<String, Publication>,
+    counter: u64,
+}
+
+impl PublicationStore {
+    /// Create a `PublicationStore`.
+    ///
+    /// The `default_expires` interval is used when the PUBLISH request does not contain an
+    /// Expires header, shorter intervals than `min_expires` are rejected, and longer intervals than
+    /// `max_expires` are reduced.
+    pub fn new(default_expires: TimeDelta, min_expires: TimeDelta, max_expires: TimeDelta) -> Self {
+        Self {
+            default_expires,
+            min_expires,
+            max_expires,
+            publications: HashMap::new(),
+            counter: 0,
+        }
+    }
+
+    /// Get the event state identified by the given entity-tag.
+    pub fn get(&self, entity_tag: &str) -> Option<&Publication> {
+        self.publications.get(entity_tag)
+    }
+
+    /// Get the event state published for a resource and an event package that has not expired at
+    /// the given time.
+    pub fn publications<'a>(
+        &'a self,
+        resource: &'a Uri,
+        event: Option<&'a str>,
+        now: DateTime<Utc>,
+    ) -> impl Iterator<Item = &'a Publication> {
+        self.publications.values().filter(move |publication| {
+            publication.resource() == resource
+                && same_event(publication.event(), event)
+                && !publication.is_expired(now)
+        })
+    }
+
+    /// Remove the event state that has expired at the given time, and return it.
+    pub fn remove_expired(&mut self, now: DateTime<Utc>) -> Vec<Publication> {
+        let expired: Vec<String> = self
+            .publications
+            .iter()
+            .filter(|(_, publication)| publication.is_expired(now))
+            .map(|(entity_tag, _)| entity_tag.clone())
+            .collect();
+        expired
+            .iter()
+            .filter_map(|entity_tag| self.publications.remove(entity_tag))
+            .collect()
+    }
+
+    /// Process a PUBLISH request received at the given time, and update the event state
+    /// accordingly.
+    ///
+    /// A request without a SIP-If-Match header creates a new piece of event state. A request with
+    /// a SIP-If-Match header refreshes the identified event state if it has no body, modifies it
+    /// if it has a body, and removes it if its Expires header is 0. Authorization and event
+    /// package checks are left to the caller.
+    ///
+    /// [[RFC3903, Section 6](https://datatracker.ietf.org/doc/html/rfc3903#section-6)]
+    pub fn process(&mut self, request: &Request, now: DateTime<Utc>) -> PublicationOutcome {
+        if request.method() != &Method::Publish {
+            return PublicationOutcome::rejected(Reason::METHOD_NOT_ALLOWED);
+        }
+        self.remove_expired(now);
+
+        let resource = request.uri();
+        let event = event_package(request);
+        let if_match = request.headers().iter().find_map(|header| match header {
+            Header::SipIfMatch(header) => Some(header.entity_tag().to_string()),
+            _ => None,
+        });
+        let existing = match &if_match {
+            Some(entity_tag) => match self.publications.get(entity_tag) {
+                Some(publication)
+                    if publication.resource() == resource
+                        && same_event(publication.event(), event.as_deref()) =>
+                {
+                    Some(entity_tag.clone())
+                }
+                _ => return PublicationOutcome::rejected(Reason::CONDITIONAL_REQUEST_FAILED),
+            },
+            None => None,
+        };
+
+        let expires = request
+            .headers()
+            .iter()
+            .find_map(|header| match header {
+                Header::Expires(header) => Some(header.expires()),
+                _ => None,
+            })
+            .unwrap_or(self.default_expires);
+        if expires != TimeDelta::zero() && expires < self.min_expires {
+            return PublicationOutcome::Rejected {
+                reason: Reason::INTERVAL_TOO_BRIEF,
+                min_expires: Some(self.min_expires),
+            };
+        }
+        let expires = expires.min(self.max_expires);
+
+        match existing {
+            Some(entity_tag) => {
+                let mut publication = self.publications.remove(&entity_tag).unwrap();
+                if expires == TimeDelta::zero() {
+                    return PublicationOutcome::Removed {
+                        entity_tag: publication.entity_tag,
+                    };
+                }
+                if !request.body().is_empty() {
+                    publication.body = request.body().to_vec();
+                }
+                publication.entity_tag = self.next_entity_tag(now);
+                publication.expires_at = now + expires;
+                self.accept(publication, expires)
+            }
+            None => {
+                if request.body().is_empty() {
+                    return PublicationOutcome::rejected(Reason::BAD_REQUEST);
+                }
+                let publication = Publication {
+                    resource: resource.clone(),
+                    event,
+                    entity_tag: self.next_entity_tag(now),
+                    body: request.body().to_vec(),
+                    expires_at: now + expires,
+                };
+                if expires == TimeDelta::zero() {
+                    return PublicationOutcome::Accepted {
+                        entity_tag: publication.entity_tag,
+                        expires,
+                    };
+                }
+                self.accept(publication, expires)
+            }
+        }
+    }
+
+    fn accept(&mut self, publication: Publication, expires: TimeDelta) -> PublicationOutcome {
+        let entity_tag = publication.entity_tag.clone();
+        self.publications
+            .insert(entity_tag.to_string(), publication);
+        PublicationOutcome::Accepted {
+            entity_tag,
+            expires,
+        }
+    }
+
+    fn next_entity_tag(&mut self, now: DateTime<Utc>) -> TokenString {
+        self.counter += 1;
+        TokenString::new(format!("{:x}.{:x}", now.timestamp_micros(), self.counter))
+    }
+}
+
+impl Default for PublicationStore {
+    fn default() -> Self {
+        Self::new(
+            TimeDelta::hours(1),
+            TimeDelta::minutes(1),
+            TimeDelta::days(1),
+        )
+    }
+}
+
+fn event_package(request: &Request) -> Option<String> {
+    request.headers().iter().find_map(|header| match header {
+        Header::ExtensionHeader(header)
+            if header.name().eq_ignore_ascii_case("Event")
+                || header.name().eq_ignore_ascii_case("o") =>
+        {
+            header
+                .value()
+                .split(';')
+                .next()
+                .map(|event| event.trim().to_ascii_lowercase())
+        }
+        _ => None,
+    })
+}
+
+fn same_event(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+
+    fn publish(extra_headers: &str, body: &str) -> Request {
+        let message = format!(
+            "PUBLISH sip:presentity@example.com SIP/2.0\r\n\
+            Via: SIP/2.0/UDP pua.example.com;branch=z9hG4bK652hsge\r\n\
+            To: <sip:presentity@example.com>\r\n\
+            From: <sip:presentity@example.com>;tag=1234wxyz\r\n\
+            Call-ID: 81818181@pua.example.com\r\n\
+            CSeq: 1 PUBLISH\r\n\
+            Max-Forwards: 70\r\n\
+            Event: presence\r\n\
+            {extra_headers}\
+            Content-Length: {}\r\n\
+            \r\n\
+            {body}",
+            body.len()
+        );
+        match Message::try_from(message.as_bytes()).unwrap() {
+            Message::Request(request) => request,
+            Message::Response(_) => panic!("Not a request"),
+        }
+    }
+
+    fn accepted_entity_tag(outcome: &PublicationOutcome) -> String {
+        match outcome {
+            PublicationOutcome::Accepted { entity_tag, .. } => entity_tag.to_string(),
+            _ => panic!("Not accepted: {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn test_publication_lifecycle() {
+        let mut store = PublicationStore::default();
+        let now = Utc::now();
+
+        let outcome = store.process(&publish("Expires: 3600\r\n", "open"), now);
+        assert_eq!(outcome.reason(), Reason::OK);
+        assert_eq!(outcome.headers().len(), 2);
+        let first_tag = accepted_entity_tag(&outcome);
+        assert_eq!(store.get(&first_tag).unwrap().body(), b"open");
+        assert_eq!(store.get(&first_tag).unwrap().event(), Some("presence"));
+
+        let outcome = store.process(&publish(&format!("SIP-If-Match: {first_tag}\r\n"), ""), now);
+        let refreshed_tag = accepted_entity_tag(&outcome);
+        assert_ne!(refreshed_tag, first_tag);
+        assert!(store.get(&first_tag).is_none());
+        assert_eq!(store.get(&refreshed_tag).unwrap().body(), b"open");
+
+        let outcome = store.process(
+            &publish(&format!("SIP-If-Match: {refreshed_tag}\r\n"), "closed"),
+            now,
+        );
+        let modified_tag = accepted_entity_tag(&outcome);
+        assert_eq!(store.get(&modified_tag).unwrap().body(), b"closed");
+
+        let outcome = store.process(
+            &publish(
+                &format!("SIP-If-Match: {modified_tag}\r\nExpires: 0\r\n"),
+                "",
+            ),
+            now,
+        );
+        assert!(outcome.is_removed());
+        assert!(store.get(&modified_tag).is_none());
+    }
+
+    #[test]
+    fn test_publication_with_unknown_entity_tag() {
+        let mut store = PublicationStore::default();
+        let outcome = store.process(&publish("SIP-If-Match: unknown\r\n", ""), Utc::now());
+        assert_eq!(outcome.reason(), Reason::CONDITIONAL_REQUEST_FAILED);
+        assert!(outcome.headers().is_empty());
+    }
+
+    #[test]
+    fn test_publication_with_expired_entity_tag() {
+        let mut store = PublicationStore::default();
+        let now = Utc::now();
+        let outcome = store.process(&publish("Expires: 60\r\n", "open"), now);
+        let entity_tag = accepted_entity_tag(&outcome);
+        let outcome = store.process(
+            &publish(&format!("SIP-If-Match: {entity_tag}\r\n"), ""),
+            now + TimeDelta::seconds(61),
+        );
+        assert_eq!(outcome.reason(), Reason::CONDITIONAL_REQUEST_FAILED);
+    }
+
+    #[test]
+    fn test_publication_with_too_brief_interval() {
+        let mut store = PublicationStore::default();
+        let outcome = store.process(&publish("Expires: 10\r\n", "open"), Utc::now());
+        assert_eq!(outcome.reason(), Reason::INTERVAL_TOO_BRIEF);
+        assert_eq!(
+            outcome.headers(),
+            vec![Header::MinExpires(MinExpiresHeader::from(
+                TimeDelta::minutes(1)
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_publication_with_reduced_interval() {
+        let mut store = PublicationStore::default();
+        let outcome = store.process(&publish("Expires: 604800\r\n", "open"), Utc::now());
+        assert!(matches!(
+            outcome,
+            PublicationOutcome::Accepted { expires, .. } if expires == TimeDelta::days(1)
+        ));
+    }
+
+    #[test]
+    fn test_initial_publication_without_body() {
+        let mut store = PublicationStore::default();
+        let outcome = store.process(&publish("", ""), Utc::now());
+        assert_eq!(outcome.reason(), Reason::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_publications_for_resource() {
+        let mut store = PublicationStore::default();
+        let now = Utc::now();
+        store.process(&publish("", "open"), now);
+        store.process(&publish("", "closed"), now);
+        let resource = Uri::try_from("sip:presentity@example.com").unwrap();
+        assert_eq!(
+            store.publications(&resource, Some("presence"), now).count(),
+            2
+        );
+        assert_eq!(
+            store.publications(&resource, Some("dialog"), now).count(),
+            0
+        );
+        assert_eq!(
+            store
+                .publications(&resource, Some("presence"), now + TimeDelta::hours(2))
+                .count(),
+            0
+        );
+    }
+}
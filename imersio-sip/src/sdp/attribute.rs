@@ -0,0 +1,410 @@
+use crate::SipError;
+
+/// Representation of an attribute (`a=` line) of a session or media description.
+///
+/// The `rtpmap`, `fmtp` and direction attributes are typed, any other attribute (eg. `ice-ufrag`,
+/// `candidate` or `crypto`) is kept as is.
+///
+/// [[RFC8866, Section 5.13](https://datatracker.ietf.org/doc/html/rfc8866#section-5.13)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Attribute {
+    /// A `rtpmap` attribute.
+    RtpMap(RtpMap),
+    /// A `fmtp` attribute.
+    Fmtp(Fmtp),
+    /// A `sendrecv`, `sendonly`, `recvonly` or `inactive` attribute.
+    Direction(Direction),
+    /// Any other attribute, either a property attribute or a value attribute.
+    Other {
+        /// The name of the attribute.
+        name: String,
+        /// The value of the attribute, if any.
+        value: Option<String>,
+    },
+}
+
+impl Attribute {
+    /// Create a generic attribute.
+    pub fn new<S: Into<String>>(name: S, value: Option<S>) -> Self {
+        Self::Other {
+            name: name.into(),
+            value: value.map(Into::into),
+        }
+    }
+
+    /// Get the name of the attribute.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::RtpMap(_) => "rtpmap",
+            Self::Fmtp(_) => "fmtp",
+            Self::Direction(direction) => direction.value(),
+            Self::Other { name, .. } => name,
+        }
+    }
+
+    /// Get the value of the attribute, if any.
+    pub fn value(&self) -> Option<String> {
+        match self {
+            Self::RtpMap(rtpmap) => Some(rtpmap.to_string()),
+            Self::Fmtp(fmtp) => Some(fmtp.to_string()),
+            Self::Direction(_) => None,
+            Self::Other { value, .. } => value.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value() {
+            Some(value) => write!(f, "{}:{}", self.name(), value),
+            None => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+impl TryFrom<&str> for Attribute {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.split(':').next().is_none_or(str::is_empty) {
+            return Err(SipError::InvalidSdp(format!("invalid attribute `{value}`")));
+        }
+        match value.split_once(':') {
+            Some((name, attribute_value)) if name.eq_ignore_ascii_case("rtpmap") => {
+                Ok(Self::RtpMap(RtpMap::try_from(attribute_value)?))
+            }
+            Some((name, attribute_value)) if name.eq_ignore_ascii_case("fmtp") => {
+                Ok(Self::Fmtp(Fmtp::try_from(attribute_value)?))
+            }
+            Some((name, attribute_value)) => Ok(Self::new(name, Some(attribute_value))),
+            None => match Direction::try_from(value) {
+                Ok(direction) => Ok(Self::Direction(direction)),
+                Err(_) => Ok(Self::new(value, None)),
+            },
+        }
+    }
+}
+
+/// Representation of a `rtpmap` attribute, mapping an RTP payload type to an encoding.
+///
+/// [[RFC8866, Section 6.6](https://datatracker.ietf.org/doc/html/rfc8866#section-6.6)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RtpMap {
+    payload_type: u8,
+    encoding_name: String,
+    clock_rate: u32,
+    encoding_parameters: Option<String>,
+}
+
+impl RtpMap {
+    /// Create a `RtpMap`.
+    pub fn new<S: Into<String>>(
+        payload_type: u8,
+        encoding_name: S,
+        clock_rate: u32,
+        encoding_parameters: Option<S>,
+    ) -> Self {
+        Self {
+            payload_type,
+            encoding_name: encoding_name.into(),
+            clock_rate,
+            encoding_parameters: encoding_parameters.map(Into::into),
+        }
+    }
+
+    /// Get the payload type of the mapping.
+    pub fn payload_type(&self) -> u8 {
+        self.payload_type
+    }
+
+    /// Get the encoding name of the mapping.
+    pub fn encoding_name(&self) -> &str {
+        &self.encoding_name
+    }
+
+    /// Get the clock rate of the mapping.
+    pub fn clock_rate(&self) -> u32 {
+        self.clock_rate
+    }
+
+    /// Get the encoding parameters of the mapping, eg. the number of audio channels.
+    pub fn encoding_parameters(&self) -> Option<&str> {
+        self.encoding_parameters.as_deref()
+    }
+
+    /// Tell whether the mapping describes the same codec as another one, whatever the payload
+    /// types.
+    pub fn same_codec(&self, other: &RtpMap) -> bool {
+        self.encoding_name
+            .eq_ignore_ascii_case(&other.encoding_name)
+            && self.clock_rate == other.clock_rate
+            && self.encoding_parameters.as_deref().unwrap_or("1")
+                == other.encoding_parameters.as_deref().unwrap_or("1")
+    }
+}
+
+impl std::fmt::Display for RtpMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}/{}",
+            self.payload_type, self.encoding_name, self.clock_rate
+        )?;
+        if let Some(encoding_parameters) = &self.encoding_parameters {
+            write!(f, "/{}", encoding_parameters)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&str> for RtpMap {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("rtpmap", value, parser::rtpmap)
+    }
+}
+
+/// Representation of a `fmtp` attribute, giving format specific parameters.
+///
+/// [[RFC8866, Section 6.15](https://datatracker.ietf.org/doc/html/rfc8866#section-6.15)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Fmtp {
+    format: String,
+    parameters: String,
+}
+
+impl Fmtp {
+    /// Create a `Fmtp`.
+    pub fn new<S: Into<String>>(format: S, parameters: S) -> Self {
+        Self {
+            format: format.into(),
+            parameters: parameters.into(),
+        }
+    }
+
+    /// Get the format the parameters apply to.
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// Get the format specific parameters.
+    pub fn parameters(&self) -> &str {
+        &self.parameters
+    }
+
+    /// Get the value of a parameter, when the parameters are a `;` separated list of `key=value`
+    /// pairs.
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters.split(';').find_map(|parameter| {
+            parameter
+                .split_once('=')
+                .filter(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.trim())
+        })
+    }
+}
+
+impl std::fmt::Display for Fmtp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.format, self.parameters)
+    }
+}
+
+impl TryFrom<&str> for Fmtp {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("fmtp", value, parser::fmtp)
+    }
+}
+
+/// Representation of the direction of a media stream.
+///
+/// [[RFC8866, Section 6.7](https://datatracker.ietf.org/doc/html/rfc8866#section-6.7)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum Direction {
+    /// Media is both sent and received.
+    #[default]
+    SendRecv,
+    /// Media is only sent.
+    SendOnly,
+    /// Media is only received.
+    RecvOnly,
+    /// Media is neither sent nor received.
+    Inactive,
+}
+
+impl Direction {
+    /// Get the attribute name of the direction.
+    pub fn value(&self) -> &'static str {
+        match self {
+            Self::SendRecv => "sendrecv",
+            Self::SendOnly => "sendonly",
+            Self::RecvOnly => "recvonly",
+            Self::Inactive => "inactive",
+        }
+    }
+
+    /// Get the direction as seen from the remote party.
+    pub fn reverse(&self) -> Self {
+        match self {
+            Self::SendOnly => Self::RecvOnly,
+            Self::RecvOnly => Self::SendOnly,
+            direction => *direction,
+        }
+    }
+
+    /// Tell whether media is sent in this direction.
+    pub fn sends(&self) -> bool {
+        matches!(self, Self::SendRecv | Self::SendOnly)
+    }
+
+    /// Tell whether media is received in this direction.
+    pub fn receives(&self) -> bool {
+        matches!(self, Self::SendRecv | Self::RecvOnly)
+    }
+
+    /// Build a direction from whether media is sent and received.
+    pub fn from_flags(sends: bool, receives: bool) -> Self {
+        match (sends, receives) {
+            (true, true) => Self::SendRecv,
+            (true, false) => Self::SendOnly,
+            (false, true) => Self::RecvOnly,
+            (false, false) => Self::Inactive,
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl TryFrom<&str> for Direction {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "sendrecv" => Ok(Self::SendRecv),
+            "sendonly" => Ok(Self::SendOnly),
+            "recvonly" => Ok(Self::RecvOnly),
+            "inactive" => Ok(Self::Inactive),
+            _ => Err(SipError::InvalidSdp(format!("invalid direction `{value}`"))),
+        }
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::{take_till1, take_while1},
+        character::complete::char,
+        combinator::{map, opt, rest},
+        error::context,
+        sequence::preceded,
+    };
+
+    use super::{Fmtp, RtpMap};
+    use crate::parser::ParserResult;
+    use crate::sdp::parser::{field, integer, space};
+
+    pub(crate) fn rtpmap(input: &str) -> ParserResult<&str, RtpMap> {
+        context(
+            "rtpmap",
+            map(
+                (
+                    integer,
+                    space,
+                    take_till1(|c| c == '/'),
+                    char('/'),
+                    integer,
+                    opt(preceded(char('/'), take_while1(|c: char| c != ' '))),
+                ),
+                |(payload_type, _, encoding_name, _, clock_rate, encoding_parameters)| {
+                    RtpMap::new(payload_type, encoding_name, clock_rate, encoding_parameters)
+                },
+            ),
+        )
+        .parse(input)
+    }
+
+    pub(crate) fn fmtp(input: &str) -> ParserResult<&str, Fmtp> {
+        context(
+            "fmtp",
+            map((field, space, rest), |(format, _, parameters)| {
+                Fmtp::new(format, parameters)
+            }),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn test_rtpmap_attribute() {
+        let attribute = Attribute::try_from("rtpmap:97 opus/48000/2").unwrap();
+        let Attribute::RtpMap(rtpmap) = &attribute else {
+            panic!("Not a rtpmap attribute");
+        };
+        assert_eq!(rtpmap.payload_type(), 97);
+        assert_eq!(rtpmap.encoding_name(), "opus");
+        assert_eq!(rtpmap.clock_rate(), 48000);
+        assert_eq!(rtpmap.encoding_parameters(), Some("2"));
+        assert_eq!(attribute.to_string(), "rtpmap:97 opus/48000/2");
+        assert!(rtpmap.same_codec(&RtpMap::new(111, "OPUS", 48000, Some("2"))));
+        assert!(!rtpmap.same_codec(&RtpMap::new(97, "opus", 48000, None)));
+        assert_err!(Attribute::try_from("rtpmap:97 opus"));
+    }
+
+    #[test]
+    fn test_fmtp_attribute() {
+        let attribute = Attribute::try_from("fmtp:101 0-15").unwrap();
+        assert_eq!(attribute, Attribute::Fmtp(Fmtp::new("101", "0-15")));
+        let fmtp = Fmtp::try_from("96 profile-level-id=42e01f; packetization-mode=1").unwrap();
+        assert_eq!(fmtp.parameter("packetization-mode"), Some("1"));
+        assert_eq!(fmtp.parameter("max-fs"), None);
+        assert_err!(Attribute::try_from("fmtp:101"));
+    }
+
+    #[test]
+    fn test_direction_attribute() {
+        assert_eq!(
+            Attribute::try_from("recvonly").unwrap(),
+            Attribute::Direction(Direction::RecvOnly)
+        );
+        assert_eq!(Direction::SendOnly.reverse(), Direction::RecvOnly);
+        assert_eq!(Direction::Inactive.reverse(), Direction::Inactive);
+        assert_eq!(Direction::from_flags(true, false), Direction::SendOnly);
+    }
+
+    #[test]
+    fn test_generic_attributes() {
+        let attribute =
+            Attribute::try_from("candidate:1 1 UDP 2130706431 192.0.2.10 49170 typ host").unwrap();
+        assert_eq!(attribute.name(), "candidate");
+        assert_eq!(
+            attribute.value().as_deref(),
+            Some("1 1 UDP 2130706431 192.0.2.10 49170 typ host")
+        );
+        let attribute = Attribute::try_from(
+            "crypto:1 AES_CM_128_HMAC_SHA1_80 inline:PS1uQCVeeCFCanVmcjkpPywjNWhcYD0mXXtxaVBR",
+        )
+        .unwrap();
+        assert_eq!(
+            attribute.to_string(),
+            "crypto:1 AES_CM_128_HMAC_SHA1_80 inline:PS1uQCVeeCFCanVmcjkpPywjNWhcYD0mXXtxaVBR"
+        );
+        assert_eq!(
+            assert_ok!(Attribute::try_from("ice-lite")),
+            Attribute::new("ice-lite", None)
+        );
+        assert_err!(Attribute::try_from(""));
+        assert_err!(Attribute::try_from(":value"));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::SipError;
+
+/// Representation of the bandwidth information (`b=` line) of a session or media description.
+///
+/// [[RFC8866, Section 5.8](https://datatracker.ietf.org/doc/html/rfc8866#section-5.8)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Bandwidth {
+    bandwidth_type: String,
+    bandwidth: u64,
+}
+
+impl Bandwidth {
+    /// Create a `Bandwidth`.
+    pub fn new<S: Into<String>>(bandwidth_type: S, bandwidth: u64) -> Self {
+        Self {
+            bandwidth_type: bandwidth_type.into(),
+            bandwidth,
+        }
+    }
+
+    /// Get the type of the bandwidth, eg. `CT` or `AS`.
+    pub fn bandwidth_type(&self) -> &str {
+        &self.bandwidth_type
+    }
+
+    /// Get the value of the bandwidth, in kilobits per second for the `CT` and `AS` types.
+    pub fn bandwidth(&self) -> u64 {
+        self.bandwidth
+    }
+}
+
+impl std::fmt::Display for Bandwidth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.bandwidth_type, self.bandwidth)
+    }
+}
+
+impl TryFrom<&str> for Bandwidth {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("bandwidth", value, parser::bandwidth)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser, bytes::complete::take_till1, character::complete::char, combinator::map,
+        error::context, sequence::separated_pair,
+    };
+
+    use super::Bandwidth;
+    use crate::parser::ParserResult;
+    use crate::sdp::parser::integer;
+
+    pub(crate) fn bandwidth(input: &str) -> ParserResult<&str, Bandwidth> {
+        context(
+            "bandwidth",
+            map(
+                separated_pair(take_till1(|c| c == ':'), char(':'), integer),
+                |(bandwidth_type, bandwidth): (&str, u64)| {
+                    Bandwidth::new(bandwidth_type, bandwidth)
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_bandwidth() {
+        let bandwidth = Bandwidth::try_from("AS:64").unwrap();
+        assert_eq!(bandwidth.bandwidth_type(), "AS");
+        assert_eq!(bandwidth.bandwidth(), 64);
+        assert_eq!(bandwidth.to_string(), "AS:64");
+        assert_err!(Bandwidth::try_from("AS:high"));
+    }
+}
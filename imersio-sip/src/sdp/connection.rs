@@ -0,0 +1,97 @@
+use crate::SipError;
+
+/// Representation of the connection data (`c=` line) of a session or media description.
+///
+/// [[RFC8866, Section 5.7](https://datatracker.ietf.org/doc/html/rfc8866#section-5.7)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Connection {
+    net_type: String,
+    address_type: String,
+    address: String,
+}
+
+impl Connection {
+    /// Create a `Connection` with the `IN` network type.
+    pub fn new<S: Into<String>>(address_type: S, address: S) -> Self {
+        Self {
+            net_type: "IN".to_string(),
+            address_type: address_type.into(),
+            address: address.into(),
+        }
+    }
+
+    /// Get the network type of the connection.
+    pub fn net_type(&self) -> &str {
+        &self.net_type
+    }
+
+    /// Get the address type of the connection.
+    pub fn address_type(&self) -> &str {
+        &self.address_type
+    }
+
+    /// Get the address of the connection, including its TTL and number of addresses if any.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl std::fmt::Display for Connection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.net_type, self.address_type, self.address
+        )
+    }
+}
+
+impl TryFrom<&str> for Connection {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("connection", value, parser::connection)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{Parser, combinator::map, error::context};
+
+    use super::Connection;
+    use crate::parser::ParserResult;
+    use crate::sdp::parser::{field, space};
+
+    pub(crate) fn connection(input: &str) -> ParserResult<&str, Connection> {
+        context(
+            "connection",
+            map(
+                (field, space, field, space, field),
+                |(net_type, _, address_type, _, address)| Connection {
+                    net_type: net_type.to_string(),
+                    address_type: address_type.to_string(),
+                    address: address.to_string(),
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_connection() {
+        let connection = Connection::try_from("IN IP4 224.2.36.42/127").unwrap();
+        assert_eq!(connection.net_type(), "IN");
+        assert_eq!(connection.address_type(), "IP4");
+        assert_eq!(connection.address(), "224.2.36.42/127");
+        assert_eq!(
+            Connection::new("IP6", "2001:db8::2").to_string(),
+            "IN IP6 2001:db8::2"
+        );
+        assert_err!(Connection::try_from("IN IP4"));
+    }
+}
@@ -0,0 +1,330 @@
+use itertools::join;
+
+use crate::SipError;
+use crate::sdp::{Attribute, Bandwidth, Connection, Direction, Fmtp, RtpMap};
+
+/// Representation of a media description, starting with a `m=` line.
+///
+/// [[RFC8866, Section 5.14](https://datatracker.ietf.org/doc/html/rfc8866#section-5.14)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MediaDescription {
+    media: String,
+    port: u16,
+    port_count: Option<u16>,
+    proto: String,
+    formats: Vec<String>,
+    information: Option<String>,
+    connections: Vec<Connection>,
+    bandwidths: Vec<Bandwidth>,
+    key: Option<String>,
+    attributes: Vec<Attribute>,
+}
+
+impl MediaDescription {
+    /// Create a `MediaDescription` without any attribute.
+    pub fn new<S: Into<String>>(media: S, port: u16, proto: S, formats: Vec<String>) -> Self {
+        Self {
+            media: media.into(),
+            port,
+            port_count: None,
+            proto: proto.into(),
+            formats,
+            information: None,
+            connections: vec![],
+            bandwidths: vec![],
+            key: None,
+            attributes: vec![],
+        }
+    }
+
+    /// Get the media type, eg. `audio` or `video`.
+    pub fn media(&self) -> &str {
+        &self.media
+    }
+
+    /// Get the transport port of the media.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Set the transport port of the media.
+    pub fn set_port(&mut self, port: u16) {
+        self.port = port;
+    }
+
+    /// Get the number of ports of the media, if specified.
+    pub fn port_count(&self) -> Option<u16> {
+        self.port_count
+    }
+
+    /// Get the transport protocol of the media, eg. `RTP/AVP`.
+    pub fn proto(&self) -> &str {
+        &self.proto
+    }
+
+    /// Get a reference to the media formats, eg. the RTP payload types.
+    pub fn formats(&self) -> &Vec<String> {
+        &self.formats
+    }
+
+    /// Set the media formats.
+    pub fn set_formats(&mut self, formats: Vec<String>) {
+        self.formats = formats;
+    }
+
+    /// Get the media title (`i=` line), if any.
+    pub fn information(&self) -> Option<&str> {
+        self.information.as_deref()
+    }
+
+    /// Get a reference to the connection data (`c=` lines) of the media.
+    pub fn connections(&self) -> &Vec<Connection> {
+        &self.connections
+    }
+
+    /// Replace the connection data of the media.
+    pub fn set_connection(&mut self, connection: Connection) {
+        self.connections = vec![connection];
+    }
+
+    /// Get a reference to the bandwidth information (`b=` lines) of the media.
+    pub fn bandwidths(&self) -> &Vec<Bandwidth> {
+        &self.bandwidths
+    }
+
+    /// Get the encryption key (`k=` line) of the media, if any.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Get a reference to the attributes of the media.
+    pub fn attributes(&self) -> &Vec<Attribute> {
+        &self.attributes
+    }
+
+    /// Get a mutable reference to the attributes of the media.
+    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// Add an attribute to the media.
+    pub fn add_attribute(&mut self, attribute: Attribute) {
+        self.attributes.push(attribute);
+    }
+
+    /// Get the values of the attributes of the media having the given name.
+    pub fn attribute_values(&self, name: &str) -> Vec<String> {
+        self.attributes
+            .iter()
+            .filter(|attribute| attribute.name().eq_ignore_ascii_case(name))
+            .filter_map(Attribute::value)
+            .collect()
+    }
+
+    /// Get the `rtpmap` attribute of a format, if any.
+    pub fn rtpmap(&self, format: &str) -> Option<&RtpMap> {
+        self.rtpmaps()
+            .find(|rtpmap| rtpmap.payload_type().to_string() == format)
+    }
+
+    /// Get an iterator over the `rtpmap` attributes of the media.
+    pub fn rtpmaps(&self) -> impl Iterator<Item = &RtpMap> {
+        self.attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::RtpMap(rtpmap) => Some(rtpmap),
+                _ => None,
+            })
+    }
+
+    /// Get the `fmtp` attribute of a format, if any.
+    pub fn fmtp(&self, format: &str) -> Option<&Fmtp> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Fmtp(fmtp) if fmtp.format() == format => Some(fmtp),
+                _ => None,
+            })
+    }
+
+    /// Get the direction attribute of the media, if any.
+    pub fn direction(&self) -> Option<Direction> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Direction(direction) => Some(*direction),
+                _ => None,
+            })
+    }
+
+    /// Set the direction attribute of the media, replacing the existing one if any.
+    pub fn set_direction(&mut self, direction: Direction) {
+        match self
+            .attributes
+            .iter_mut()
+            .find(|attribute| matches!(attribute, Attribute::Direction(_)))
+        {
+            Some(attribute) => *attribute = Attribute::Direction(direction),
+            None => self.attributes.push(Attribute::Direction(direction)),
+        }
+    }
+
+    /// Tell whether the media stream is rejected or disabled, ie. its port is 0.
+    pub fn is_rejected(&self) -> bool {
+        self.port == 0
+    }
+
+    pub(crate) fn set_information(&mut self, information: &str) {
+        self.information = Some(information.to_string());
+    }
+
+    pub(crate) fn add_connection(&mut self, connection: Connection) {
+        self.connections.push(connection);
+    }
+
+    pub(crate) fn add_bandwidth(&mut self, bandwidth: Bandwidth) {
+        self.bandwidths.push(bandwidth);
+    }
+
+    pub(crate) fn set_key(&mut self, key: &str) {
+        self.key = Some(key.to_string());
+    }
+}
+
+impl std::fmt::Display for MediaDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "m={} {}", self.media, self.port)?;
+        if let Some(port_count) = self.port_count {
+            write!(f, "/{}", port_count)?;
+        }
+        write!(f, " {}", self.proto)?;
+        if !self.formats.is_empty() {
+            write!(f, " {}", join(&self.formats, " "))?;
+        }
+        write!(f, "\r\n")?;
+        if let Some(information) = &self.information {
+            write!(f, "i={}\r\n", information)?;
+        }
+        for connection in &self.connections {
+            write!(f, "c={}\r\n", connection)?;
+        }
+        for bandwidth in &self.bandwidths {
+            write!(f, "b={}\r\n", bandwidth)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "k={}\r\n", key)?;
+        }
+        for attribute in &self.attributes {
+            write!(f, "a={}\r\n", attribute)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&str> for MediaDescription {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("media", value, parser::media)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        character::complete::char,
+        combinator::{map, opt},
+        error::context,
+        multi::many0,
+        sequence::preceded,
+    };
+
+    use super::MediaDescription;
+    use crate::parser::ParserResult;
+    use crate::sdp::parser::{field, integer, space};
+
+    /// Parse the value of a `m=` line.
+    pub(crate) fn media(input: &str) -> ParserResult<&str, MediaDescription> {
+        context(
+            "media",
+            map(
+                (
+                    field,
+                    space,
+                    integer,
+                    opt(preceded(char('/'), integer)),
+                    space,
+                    field,
+                    many0(preceded(space, field)),
+                ),
+                |(media, _, port, port_count, _, proto, formats)| {
+                    let mut description = MediaDescription::new(
+                        media,
+                        port,
+                        proto,
+                        formats.into_iter().map(ToString::to_string).collect(),
+                    );
+                    description.port_count = port_count;
+                    description
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_media_line() {
+        let media = MediaDescription::try_from("video 49170/2 RTP/AVP 31 32").unwrap();
+        assert_eq!(media.media(), "video");
+        assert_eq!(media.port(), 49170);
+        assert_eq!(media.port_count(), Some(2));
+        assert_eq!(media.proto(), "RTP/AVP");
+        assert_eq!(media.formats(), &vec!["31".to_string(), "32".to_string()]);
+        assert_eq!(media.to_string(), "m=video 49170/2 RTP/AVP 31 32\r\n");
+        assert_err!(MediaDescription::try_from("audio 70000 RTP/AVP 0"));
+        assert_err!(MediaDescription::try_from("audio RTP/AVP 0"));
+    }
+
+    #[test]
+    fn test_media_direction() {
+        let mut media = MediaDescription::try_from("audio 0 RTP/AVP 0").unwrap();
+        assert!(media.is_rejected());
+        assert_eq!(media.direction(), None);
+        media.set_direction(Direction::SendOnly);
+        media.set_direction(Direction::Inactive);
+        media.set_port(5004);
+        assert!(!media.is_rejected());
+        assert_eq!(media.direction(), Some(Direction::Inactive));
+        assert_eq!(media.attributes().len(), 1);
+        assert_eq!(
+            media.to_string(),
+            "m=audio 5004 RTP/AVP 0\r\na=inactive\r\n"
+        );
+    }
+
+    #[test]
+    fn test_media_rtpmap_and_fmtp() {
+        let mut media = MediaDescription::try_from("audio 5004 RTP/AVP 0 101").unwrap();
+        media.add_attribute(Attribute::RtpMap(RtpMap::new(
+            101,
+            "telephone-event",
+            8000,
+            None,
+        )));
+        media.add_attribute(Attribute::Fmtp(Fmtp::new("101", "0-16")));
+        media.add_attribute(Attribute::new("ptime", Some("20")));
+        assert_eq!(
+            media.rtpmap("101").unwrap().encoding_name(),
+            "telephone-event"
+        );
+        assert!(media.rtpmap("0").is_none());
+        assert_eq!(media.fmtp("101").unwrap().parameters(), "0-16");
+        assert_eq!(media.attribute_values("ptime"), vec!["20".to_string()]);
+    }
+}
@@ -0,0 +1,138 @@
+//! SDP session descriptions carried in the body of SIP messages.
+//!
+//! A `SessionDescription` is parsed from an `application/sdp` body, can be inspected and
+//! rewritten, and is serialized back with its lines in the order defined by the SDP grammar.
+//! The `Negotiator` generates offers and answers from local capabilities, and `OfferAnswer`
+//! tracks the offer/answer exchanges of a dialog.
+//!
+//! # Examples
+//!
+//! ```
+//! use imersio_sip::sdp::{Direction, SessionDescription};
+//!
+//! let sdp = SessionDescription::try_from(
+//!     "v=0\r\n\
+//!     o=alice 2890844526 2890844526 IN IP4 192.0.2.10\r\n\
+//!     s=-\r\n\
+//!     c=IN IP4 192.0.2.10\r\n\
+//!     t=0 0\r\n\
+//!     m=audio 49170 RTP/AVP 0 8\r\n\
+//!     a=rtpmap:0 PCMU/8000\r\n\
+//!     a=rtpmap:8 PCMA/8000\r\n\
+//!     a=sendonly\r\n",
+//! )
+//! .unwrap();
+//! let audio = sdp.media_descriptions().first().unwrap();
+//! assert_eq!(audio.port(), 49170);
+//! assert_eq!(audio.rtpmap("8").unwrap().encoding_name(), "PCMA");
+//! assert_eq!(audio.direction(), Some(Direction::SendOnly));
+//! ```
+//!
+//! [[RFC8866](https://datatracker.ietf.org/doc/html/rfc8866)]
+
+mod attribute;
+mod bandwidth;
+mod connection;
+mod media_description;
+mod negotiator;
+mod offer_answer;
+mod origin;
+mod session_description;
+mod timing;
+
+pub use attribute::{Attribute, Direction, Fmtp, RtpMap};
+pub use bandwidth::Bandwidth;
+pub use connection::Connection;
+pub use media_description::MediaDescription;
+pub use negotiator::{Codec, MediaCapability, Negotiator};
+pub use offer_answer::{OfferAnswer, OfferAnswerEvent, OfferAnswerState, Side};
+pub use origin::Origin;
+pub use session_description::SessionDescription;
+pub use timing::Timing;
+
+use crate::multipart::{media_type, set_content_headers};
+use crate::{Header, MultipartBody, SipError};
+
+/// Parse the body of a SIP message as a session description if its Content-Type is
+/// `application/sdp`, or if it is a multipart body containing an `application/sdp` body part.
+pub(crate) fn from_body(
+    headers: &[Header],
+    body: &[u8],
+) -> Result<Option<SessionDescription>, SipError> {
+    let Some(media_type) = headers.iter().find_map(|header| match header {
+        Header::ContentType(header) => Some(header.media_type()),
+        _ => None,
+    }) else {
+        return Ok(None);
+    };
+    let media_range = media_type.media_range();
+    if media_range.r#type().eq_ignore_ascii_case("application")
+        && media_range.subtype().eq_ignore_ascii_case("sdp")
+    {
+        if body.is_empty() {
+            return Ok(None);
+        }
+        return SessionDescription::try_from(body).map(Some);
+    }
+    match MultipartBody::from_body(media_type, body)? {
+        Some(multipart) => match multipart.find("application", "sdp") {
+            Some(part) => part.sdp(),
+            None => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Set a session description as the body of a SIP message, updating its Content-Type and
+/// Content-Length headers.
+pub(crate) fn set_body(
+    headers: &mut Vec<Header>,
+    body: &mut Vec<u8>,
+    session_description: &SessionDescription,
+) {
+    *body = session_description.to_string().into_bytes();
+    set_content_headers(
+        headers,
+        media_type("application", "sdp", vec![]),
+        body.len(),
+    );
+}
+
+pub(crate) mod parser {
+    use nom::{
+        Parser,
+        bytes::complete::take_till1,
+        character::complete::{char, digit1},
+        combinator::{all_consuming, map_res},
+    };
+
+    use crate::SipError;
+    use crate::parser::ParserResult;
+
+    pub(crate) fn field(input: &str) -> ParserResult<&str, &str> {
+        take_till1(|c| c == ' ').parse(input)
+    }
+
+    pub(crate) fn space(input: &str) -> ParserResult<&str, char> {
+        char(' ').parse(input)
+    }
+
+    pub(crate) fn integer<T: std::str::FromStr>(input: &str) -> ParserResult<&str, T> {
+        map_res(digit1, str::parse).parse(input)
+    }
+
+    /// Parse the whole value of an SDP line with the given parser.
+    pub(crate) fn parse_value<'a, O, P>(
+        kind: &str,
+        value: &'a str,
+        parser: P,
+    ) -> Result<O, SipError>
+    where
+        P: Parser<&'a str, Output = O, Error = nom_language::error::VerboseError<&'a str>>,
+    {
+        all_consuming(parser)
+            .parse(value)
+            .map(|(_, output)| output)
+            .map_err(|_| SipError::InvalidSdp(format!("invalid {kind} `{value}`")))
+    }
+}
@@ -0,0 +1,454 @@
+use crate::sdp::{
+    Attribute, Connection, Direction, Fmtp, MediaDescription, Origin, RtpMap, SessionDescription,
+};
+use crate::{Request, Response, SipError};
+
+/// Static RTP payload types that can be offered without a `rtpmap` attribute.
+///
+/// [[RFC3551, Section 6](https://datatracker.ietf.org/doc/html/rfc3551#section-6)]
+const STATIC_PAYLOAD_TYPES: [(u8, &str, u32); 9] = [
+    (0, "PCMU", 8000),
+    (3, "GSM", 8000),
+    (4, "G723", 8000),
+    (8, "PCMA", 8000),
+    (9, "G722", 8000),
+    (18, "G729", 8000),
+    (26, "JPEG", 90000),
+    (31, "H261", 90000),
+    (34, "H263", 90000),
+];
+
+/// Representation of a codec supported locally.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Codec {
+    rtpmap: RtpMap,
+    fmtp: Option<Fmtp>,
+}
+
+impl Codec {
+    /// Create a `Codec` from its `rtpmap` attribute.
+    pub fn new(rtpmap: RtpMap) -> Self {
+        Self { rtpmap, fmtp: None }
+    }
+
+    /// Create a `Codec` from its `rtpmap` attribute and its required format parameters.
+    ///
+    /// An offered codec only matches if it has all the `key=value` parameters of `fmtp`.
+    pub fn with_fmtp(rtpmap: RtpMap, fmtp: Fmtp) -> Self {
+        Self {
+            rtpmap,
+            fmtp: Some(fmtp),
+        }
+    }
+
+    /// Get a reference to the `rtpmap` of the codec.
+    pub fn rtpmap(&self) -> &RtpMap {
+        &self.rtpmap
+    }
+
+    /// Get a reference to the required format parameters of the codec, if any.
+    pub fn fmtp(&self) -> Option<&Fmtp> {
+        self.fmtp.as_ref()
+    }
+
+    fn matches(&self, rtpmap: &RtpMap, fmtp: Option<&Fmtp>) -> bool {
+        if !self.rtpmap.same_codec(rtpmap) {
+            return false;
+        }
+        let Some(required) = &self.fmtp else {
+            return true;
+        };
+        required
+            .parameters()
+            .split(';')
+            .filter_map(|parameter| parameter.split_once('='))
+            .all(|(key, value)| {
+                fmtp.and_then(|fmtp| fmtp.parameter(key.trim()))
+                    .is_some_and(|offered| offered.eq_ignore_ascii_case(value.trim()))
+            })
+    }
+}
+
+/// Representation of the local capabilities for a type of media.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MediaCapability {
+    media: String,
+    proto: String,
+    port: u16,
+    codecs: Vec<Codec>,
+    direction: Direction,
+}
+
+impl MediaCapability {
+    /// Create a `MediaCapability` receiving the media on the given port, in both directions.
+    pub fn new<S: Into<String>>(media: S, proto: S, port: u16, codecs: Vec<Codec>) -> Self {
+        Self {
+            media: media.into(),
+            proto: proto.into(),
+            port,
+            codecs,
+            direction: Direction::SendRecv,
+        }
+    }
+
+    /// Restrict the direction in which the media can flow.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Get the media type, eg. `audio`.
+    pub fn media(&self) -> &str {
+        &self.media
+    }
+
+    /// Get the transport protocol, eg. `RTP/AVP`.
+    pub fn proto(&self) -> &str {
+        &self.proto
+    }
+
+    /// Get the local port on which the media is received.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Get a reference to the supported codecs, by order of preference.
+    pub fn codecs(&self) -> &Vec<Codec> {
+        &self.codecs
+    }
+
+    /// Get the direction in which the media can flow.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+}
+
+/// SDP offer/answer negotiator, generating offers and answers from the local capabilities.
+///
+/// [[RFC3264](https://datatracker.ietf.org/doc/html/rfc3264)]
+#[derive(Clone, Debug)]
+pub struct Negotiator {
+    origin: Origin,
+    connection: Connection,
+    capabilities: Vec<MediaCapability>,
+    generated: bool,
+}
+
+impl Negotiator {
+    /// Create a `Negotiator` using the given origin and connection data in the generated session
+    /// descriptions.
+    pub fn new(origin: Origin, connection: Connection, capabilities: Vec<MediaCapability>) -> Self {
+        Self {
+            origin,
+            connection,
+            capabilities,
+            generated: false,
+        }
+    }
+
+    /// Get a reference to the local capabilities.
+    pub fn capabilities(&self) -> &Vec<MediaCapability> {
+        &self.capabilities
+    }
+
+    /// Generate an offer with a media description for each local capability.
+    ///
+    /// [[RFC3264, Section 5](https://datatracker.ietf.org/doc/html/rfc3264#section-5)]
+    pub fn offer(&mut self) -> SessionDescription {
+        let mut offer = self.session_description();
+        for capability in &self.capabilities {
+            let mut media = MediaDescription::new(
+                capability.media.as_str(),
+                capability.port,
+                capability.proto.as_str(),
+                capability
+                    .codecs
+                    .iter()
+                    .map(|codec| codec.rtpmap.payload_type().to_string())
+                    .collect(),
+            );
+            for codec in &capability.codecs {
+                media.add_attribute(Attribute::RtpMap(codec.rtpmap.clone()));
+                if let Some(fmtp) = &codec.fmtp {
+                    media.add_attribute(Attribute::Fmtp(fmtp.clone()));
+                }
+            }
+            media.set_direction(capability.direction);
+            offer.add_media_description(media);
+        }
+        offer
+    }
+
+    /// Generate the answer to an offer.
+    ///
+    /// The answer has one media description for each one of the offer, in the same order. A
+    /// media description is rejected with a port 0 if it is rejected in the offer, if no local
+    /// capability is available for its media type, or if no offered codec is supported locally.
+    /// Each local capability is used for one media description at most.
+    ///
+    /// [[RFC3264, Section 6](https://datatracker.ietf.org/doc/html/rfc3264#section-6)]
+    pub fn answer(&mut self, offer: &SessionDescription) -> SessionDescription {
+        let mut answer = self.session_description();
+        *answer.timings_mut() = offer.timings().clone();
+        let mut used = vec![false; self.capabilities.len()];
+        for (index, offered) in offer.media_descriptions().iter().enumerate() {
+            let accepted = (!offered.is_rejected())
+                .then(|| {
+                    self.capabilities
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, capability)| {
+                            !used[*i]
+                                && capability.media.eq_ignore_ascii_case(offered.media())
+                                && capability.proto.eq_ignore_ascii_case(offered.proto())
+                        })
+                        .find_map(|(i, capability)| {
+                            let codecs = Self::intersect(capability, offered);
+                            (!codecs.is_empty()).then_some((i, capability, codecs))
+                        })
+                })
+                .flatten();
+            let media = match accepted {
+                Some((i, capability, codecs)) => {
+                    used[i] = true;
+                    let offered_direction = offer.media_direction(index);
+                    let mut media = MediaDescription::new(
+                        offered.media(),
+                        capability.port,
+                        offered.proto(),
+                        codecs.iter().map(|(format, _, _)| format.clone()).collect(),
+                    );
+                    for (_, rtpmap, fmtp) in codecs {
+                        if let Some(rtpmap) = rtpmap {
+                            media.add_attribute(Attribute::RtpMap(rtpmap));
+                        }
+                        if let Some(fmtp) = fmtp {
+                            media.add_attribute(Attribute::Fmtp(fmtp));
+                        }
+                    }
+                    media.set_direction(Direction::from_flags(
+                        offered_direction.receives() && capability.direction.sends(),
+                        offered_direction.sends() && capability.direction.receives(),
+                    ));
+                    media
+                }
+                None => MediaDescription::new(
+                    offered.media(),
+                    0,
+                    offered.proto(),
+                    offered.formats().clone(),
+                ),
+            };
+            answer.add_media_description(media);
+        }
+        answer
+    }
+
+    /// Generate the answer to the offer contained in a request, if any.
+    pub fn answer_request(
+        &mut self,
+        request: &Request,
+    ) -> Result<Option<SessionDescription>, SipError> {
+        Ok(request.sdp()?.map(|offer| self.answer(&offer)))
+    }
+
+    /// Generate the answer to the offer contained in a response, if any.
+    pub fn answer_response(
+        &mut self,
+        response: &Response,
+    ) -> Result<Option<SessionDescription>, SipError> {
+        Ok(response.sdp()?.map(|offer| self.answer(&offer)))
+    }
+
+    fn session_description(&mut self) -> SessionDescription {
+        if self.generated {
+            self.origin.increment_session_version();
+        }
+        self.generated = true;
+        let mut session_description = SessionDescription::new(self.origin.clone(), "-");
+        session_description.set_connection(Some(self.connection.clone()));
+        session_description
+    }
+
+    /// Get the offered formats supported locally, in the order of the offer, along with their
+    /// offered `rtpmap` and `fmtp` attributes.
+    fn intersect(
+        capability: &MediaCapability,
+        offered: &MediaDescription,
+    ) -> Vec<(String, Option<RtpMap>, Option<Fmtp>)> {
+        offered
+            .formats()
+            .iter()
+            .filter_map(|format| {
+                let rtpmap = offered.rtpmap(format).cloned();
+                let fmtp = offered.fmtp(format).cloned();
+                let effective_rtpmap = rtpmap.clone().or_else(|| {
+                    STATIC_PAYLOAD_TYPES
+                        .iter()
+                        .find(|(payload_type, _, _)| payload_type.to_string() == *format)
+                        .map(|(payload_type, name, clock_rate)| {
+                            RtpMap::new(*payload_type, *name, *clock_rate, None)
+                        })
+                })?;
+                capability
+                    .codecs
+                    .iter()
+                    .any(|codec| codec.matches(&effective_rtpmap, fmtp.as_ref()))
+                    .then(|| (format.clone(), rtpmap, fmtp))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiator() -> Negotiator {
+        Negotiator::new(
+            Origin::new("bob", "2808844564", 2808844564, "IP4", "192.0.2.5"),
+            Connection::new("IP4", "192.0.2.5"),
+            vec![
+                MediaCapability::new(
+                    "audio",
+                    "RTP/AVP",
+                    49172,
+                    vec![
+                        Codec::new(RtpMap::new(8, "PCMA", 8000, None)),
+                        Codec::new(RtpMap::new(0, "PCMU", 8000, None)),
+                        Codec::new(RtpMap::new(101, "telephone-event", 8000, None)),
+                    ],
+                ),
+                MediaCapability::new(
+                    "video",
+                    "RTP/AVP",
+                    49174,
+                    vec![Codec::with_fmtp(
+                        RtpMap::new(96, "H264", 90000, None),
+                        Fmtp::new("96", "packetization-mode=1"),
+                    )],
+                )
+                .with_direction(Direction::RecvOnly),
+            ],
+        )
+    }
+
+    fn offer(body: &str) -> SessionDescription {
+        SessionDescription::try_from(
+            format!(
+                "v=0\r\no=alice 2890844526 2890844526 IN IP4 192.0.2.4\r\ns=-\r\n\
+                c=IN IP4 192.0.2.4\r\nt=0 0\r\n{body}"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_answer_codec_intersection() {
+        let offer = offer(
+            "m=audio 49170 RTP/AVP 0 97 8 100\r\n\
+            a=rtpmap:97 iLBC/8000\r\n\
+            a=rtpmap:100 telephone-event/8000\r\n\
+            a=fmtp:100 0-15\r\n",
+        );
+        let answer = negotiator().answer(&offer);
+        assert_eq!(
+            answer.to_string(),
+            "v=0\r\no=bob 2808844564 2808844564 IN IP4 192.0.2.5\r\ns=-\r\n\
+            c=IN IP4 192.0.2.5\r\nt=0 0\r\n\
+            m=audio 49172 RTP/AVP 0 8 100\r\n\
+            a=rtpmap:100 telephone-event/8000\r\n\
+            a=fmtp:100 0-15\r\n\
+            a=sendrecv\r\n"
+        );
+    }
+
+    #[test]
+    fn test_answer_fmtp_mismatch_and_direction() {
+        let offer = offer(
+            "m=video 51372 RTP/AVP 98 99\r\n\
+            a=rtpmap:98 H264/90000\r\n\
+            a=fmtp:98 profile-level-id=42e01f;packetization-mode=0\r\n\
+            a=rtpmap:99 H264/90000\r\n\
+            a=fmtp:99 profile-level-id=42e01f;packetization-mode=1\r\n\
+            a=sendonly\r\n",
+        );
+        let answer = negotiator().answer(&offer);
+        let video = answer.media_descriptions().first().unwrap();
+        assert_eq!(video.port(), 49174);
+        assert_eq!(video.formats(), &vec!["99".to_string()]);
+        assert_eq!(video.direction(), Some(Direction::RecvOnly));
+
+        let offer = offer.to_string().replace("a=sendonly", "a=recvonly");
+        let answer = negotiator().answer(&SessionDescription::try_from(offer.as_str()).unwrap());
+        let video = answer.media_descriptions().first().unwrap();
+        assert_eq!(video.direction(), Some(Direction::Inactive));
+    }
+
+    #[test]
+    fn test_answer_rejections_keep_media_order() {
+        let offer = offer(
+            "m=video 0 RTP/AVP 31\r\n\
+            m=audio 49170 RTP/AVP 18\r\n\
+            m=audio 49180 RTP/AVP 0\r\n\
+            m=audio 49190 RTP/AVP 8\r\n\
+            m=application 5000 udp wb\r\n",
+        );
+        let answer = negotiator().answer(&offer);
+        let media: Vec<(&str, u16)> = answer
+            .media_descriptions()
+            .iter()
+            .map(|media| (media.media(), media.port()))
+            .collect();
+        assert_eq!(
+            media,
+            vec![
+                ("video", 0),
+                ("audio", 0),
+                ("audio", 49172),
+                ("audio", 0),
+                ("application", 0)
+            ]
+        );
+        assert_eq!(
+            answer.media_descriptions()[1].formats(),
+            &vec!["18".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_offer_and_session_version() {
+        let mut negotiator = negotiator();
+        let offer = negotiator.offer();
+        assert_eq!(offer.media_descriptions().len(), 2);
+        let audio = offer.media_descriptions().first().unwrap();
+        assert_eq!(audio.formats().join(" "), "8 0 101");
+        assert_eq!(
+            audio.rtpmap("101").unwrap().encoding_name(),
+            "telephone-event"
+        );
+        let video = offer.media_descriptions().last().unwrap();
+        assert_eq!(
+            video.fmtp("96").unwrap().parameter("packetization-mode"),
+            Some("1")
+        );
+        assert_eq!(video.direction(), Some(Direction::RecvOnly));
+        assert_eq!(offer.origin().session_version(), 2808844564);
+        let answer = negotiator.answer(&offer);
+        assert_eq!(answer.origin().session_version(), 2808844565);
+    }
+
+    #[test]
+    fn test_answer_request() {
+        let mut request = Request::try_from(
+            "INVITE sip:bob@example.com SIP/2.0\r\nContent-Type: application/sdp\r\n\r\n",
+        )
+        .unwrap();
+        let mut negotiator = negotiator();
+        assert_eq!(negotiator.answer_request(&request).unwrap(), None);
+        request.set_sdp(&offer("m=audio 49170 RTP/AVP 8\r\n"));
+        let answer = negotiator.answer_request(&request).unwrap().unwrap();
+        assert_eq!(answer.media_descriptions().first().unwrap().port(), 49172);
+    }
+}
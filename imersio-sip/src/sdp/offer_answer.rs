@@ -0,0 +1,438 @@
+use crate::sdp::SessionDescription;
+use crate::{Header, Method, Request, Response, SipError};
+
+/// The party of a dialog that has sent a SIP message.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum Side {
+    /// The message has been sent by the local party.
+    Local,
+    /// The message has been received from the remote party.
+    Remote,
+}
+
+impl Side {
+    fn other(&self) -> Self {
+        match self {
+            Self::Local => Self::Remote,
+            Self::Remote => Self::Local,
+        }
+    }
+}
+
+/// Representation of the state of the offer/answer exchanges of a dialog.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::IsVariant)]
+pub enum OfferAnswerState {
+    /// No offer/answer exchange has completed and no offer is pending.
+    Initial,
+    /// An offer has been sent by the local party and is waiting for an answer.
+    OfferSent,
+    /// An offer has been received from the remote party and is waiting for an answer.
+    OfferReceived,
+    /// The last offer has been answered, and no new offer is pending.
+    Complete,
+}
+
+/// Representation of a session description found in a SIP message.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::IsVariant)]
+pub enum OfferAnswerEvent {
+    /// The message does not carry an offer or an answer.
+    None,
+    /// The message carries an offer.
+    Offer(SessionDescription),
+    /// The message carries the answer to the pending offer.
+    Answer(SessionDescription),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Transaction {
+    side: Side,
+    method: Method,
+    cseq: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PendingOffer {
+    side: Side,
+    transaction: Transaction,
+    in_response: bool,
+    offer: SessionDescription,
+}
+
+/// Tracking of the SDP offer/answer exchanges of a dialog, across the INVITE, reliable
+/// provisional responses, PRACK, UPDATE and ACK messages.
+///
+/// Each SIP message of the dialog, sent or received, is passed to the tracker, which tells
+/// whether it carries an offer or an answer, and rejects the ones breaking the offer/answer
+/// rules.
+///
+/// [[RFC3261, Section 13.2.1](https://datatracker.ietf.org/doc/html/rfc3261#section-13.2.1)]
+/// [[RFC3262, Section 5](https://datatracker.ietf.org/doc/html/rfc3262#section-5)]
+/// [[RFC3311, Section 5](https://datatracker.ietf.org/doc/html/rfc3311#section-5)]
+/// [[RFC6337, Section 2](https://datatracker.ietf.org/doc/html/rfc6337#section-2)]
+#[derive(Clone, Debug, Default)]
+pub struct OfferAnswer {
+    local: Option<SessionDescription>,
+    remote: Option<SessionDescription>,
+    pending: Option<PendingOffer>,
+    offerless_invite: Option<Transaction>,
+    answered_invite: Option<Transaction>,
+}
+
+impl OfferAnswer {
+    /// Create an `OfferAnswer` tracker for a new dialog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the current state of the offer/answer exchanges.
+    pub fn state(&self) -> OfferAnswerState {
+        match &self.pending {
+            Some(pending) if pending.side.is_local() => OfferAnswerState::OfferSent,
+            Some(_) => OfferAnswerState::OfferReceived,
+            None if self.local.is_some() && self.remote.is_some() => OfferAnswerState::Complete,
+            None => OfferAnswerState::Initial,
+        }
+    }
+
+    /// Get a reference to the last session description negotiated by the local party.
+    pub fn local_sdp(&self) -> Option<&SessionDescription> {
+        self.local.as_ref()
+    }
+
+    /// Get a reference to the last session description negotiated by the remote party.
+    pub fn remote_sdp(&self) -> Option<&SessionDescription> {
+        self.remote.as_ref()
+    }
+
+    /// Get a reference to the pending offer, if any.
+    pub fn pending_offer(&self) -> Option<&SessionDescription> {
+        self.pending.as_ref().map(|pending| &pending.offer)
+    }
+
+    /// Process a request of the dialog, sent by the given side.
+    pub fn process_request(
+        &mut self,
+        request: &Request,
+        side: Side,
+    ) -> Result<OfferAnswerEvent, SipError> {
+        let sdp = request.sdp()?;
+        let transaction = Transaction {
+            side,
+            method: request.method().clone(),
+            cseq: cseq(request.headers()).unwrap_or_default(),
+        };
+        match (request.method(), sdp) {
+            (Method::Invite, None) => {
+                self.offerless_invite = Some(transaction);
+                Ok(OfferAnswerEvent::None)
+            }
+            (Method::Invite | Method::Update, Some(sdp)) => self.offer(transaction, false, sdp),
+            (Method::Prack, Some(sdp)) => match &self.pending {
+                Some(pending) if pending.side != side && pending.in_response => {
+                    Ok(self.answer(side, sdp))
+                }
+                _ => self.offer(transaction, false, sdp),
+            },
+            (Method::Ack, sdp) => {
+                let expected = self.pending.as_ref().is_some_and(|pending| {
+                    pending.side != side
+                        && pending.in_response
+                        && pending.transaction.method == Method::Invite
+                });
+                match (expected, sdp) {
+                    (true, Some(sdp)) => Ok(self.answer(side, sdp)),
+                    (true, None) => Err(SipError::InvalidOfferAnswer(
+                        "ACK without the answer to the offer of the 2xx response".to_string(),
+                    )),
+                    (false, Some(_)) => Err(SipError::InvalidOfferAnswer(
+                        "ACK with an unexpected session description".to_string(),
+                    )),
+                    (false, None) => Ok(OfferAnswerEvent::None),
+                }
+            }
+            _ => Ok(OfferAnswerEvent::None),
+        }
+    }
+
+    /// Process a response of the dialog, sent by the given side.
+    pub fn process_response(
+        &mut self,
+        response: &Response,
+        side: Side,
+    ) -> Result<OfferAnswerEvent, SipError> {
+        let status = response.reason().status();
+        let Some(header) = response.headers().iter().find_map(|header| match header {
+            Header::CSeq(header) => Some(header),
+            _ => None,
+        }) else {
+            return Ok(OfferAnswerEvent::None);
+        };
+        let transaction = Transaction {
+            side: side.other(),
+            method: header.method().clone(),
+            cseq: header.cseq(),
+        };
+        if !matches!(
+            transaction.method,
+            Method::Invite | Method::Update | Method::Prack
+        ) || status.code() == 100
+        {
+            return Ok(OfferAnswerEvent::None);
+        }
+
+        if status.is_final() && !status.is_success() {
+            if self
+                .pending
+                .as_ref()
+                .is_some_and(|pending| pending.transaction == transaction)
+            {
+                self.pending = None;
+            }
+            if self.offerless_invite.as_ref() == Some(&transaction) {
+                self.offerless_invite = None;
+            }
+            return Ok(OfferAnswerEvent::None);
+        }
+
+        let awaiting_answer = self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.transaction == transaction && !pending.in_response);
+        match response.sdp()? {
+            Some(sdp) if awaiting_answer => {
+                if transaction.method == Method::Invite {
+                    self.answered_invite = Some(transaction);
+                }
+                Ok(self.answer(side, sdp))
+            }
+            Some(sdp)
+                if self.pending.is_none()
+                    && self.offerless_invite.as_ref() == Some(&transaction) =>
+            {
+                self.offerless_invite = None;
+                self.answered_invite = Some(transaction.clone());
+                self.offer(transaction, true, sdp)
+            }
+            Some(_) if self.answered_invite.as_ref() == Some(&transaction) => {
+                Ok(OfferAnswerEvent::None)
+            }
+            Some(_) => Err(SipError::InvalidOfferAnswer(format!(
+                "unexpected session description in {} response to {}",
+                status.code(),
+                transaction.method
+            ))),
+            None if awaiting_answer && status.is_success() => {
+                Err(SipError::InvalidOfferAnswer(format!(
+                    "2xx response to {} without the answer to its offer",
+                    transaction.method
+                )))
+            }
+            None => Ok(OfferAnswerEvent::None),
+        }
+    }
+
+    fn offer(
+        &mut self,
+        transaction: Transaction,
+        in_response: bool,
+        offer: SessionDescription,
+    ) -> Result<OfferAnswerEvent, SipError> {
+        if self.pending.is_some() {
+            return Err(SipError::InvalidOfferAnswer(format!(
+                "new offer in {} while an offer is pending",
+                transaction.method
+            )));
+        }
+        let side = if in_response {
+            transaction.side.other()
+        } else {
+            transaction.side
+        };
+        self.pending = Some(PendingOffer {
+            side,
+            transaction,
+            in_response,
+            offer: offer.clone(),
+        });
+        Ok(OfferAnswerEvent::Offer(offer))
+    }
+
+    fn answer(&mut self, side: Side, answer: SessionDescription) -> OfferAnswerEvent {
+        if let Some(pending) = self.pending.take() {
+            let (local, remote) = match side {
+                Side::Local => (answer.clone(), pending.offer),
+                Side::Remote => (pending.offer, answer.clone()),
+            };
+            self.local = Some(local);
+            self.remote = Some(remote);
+        }
+        OfferAnswerEvent::Answer(answer)
+    }
+}
+
+fn cseq(headers: &[Header]) -> Option<u32> {
+    headers.iter().find_map(|header| match header {
+        Header::CSeq(header) => Some(header.cseq()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use claims::{assert_err, assert_ok};
+
+    fn sdp(user: &str, version: u64) -> String {
+        format!(
+            "v=0\r\no={user} 1 {version} IN IP4 192.0.2.1\r\ns=-\r\nc=IN IP4 192.0.2.1\r\n\
+            t=0 0\r\nm=audio 49170 RTP/AVP 0\r\n"
+        )
+    }
+
+    fn message(start_line: &str, cseq: &str, body: Option<String>) -> Message {
+        let mut message = format!("{start_line}\r\nCSeq: {cseq}\r\n");
+        if body.is_some() {
+            message.push_str("Content-Type: application/sdp\r\n");
+        }
+        message.push_str("\r\n");
+        if let Some(body) = body {
+            message.push_str(&body);
+        }
+        Message::try_from(message.as_bytes()).unwrap()
+    }
+
+    fn request(method: &str, cseq: u32, body: Option<String>) -> Request {
+        match message(
+            &format!("{method} sip:bob@example.com SIP/2.0"),
+            &format!("{cseq} {method}"),
+            body,
+        ) {
+            Message::Request(request) => request,
+            _ => unreachable!(),
+        }
+    }
+
+    fn response(status: &str, method: &str, cseq: u32, body: Option<String>) -> Response {
+        match message(
+            &format!("SIP/2.0 {status}"),
+            &format!("{cseq} {method}"),
+            body,
+        ) {
+            Message::Response(response) => response,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_offer_in_invite_answer_in_reliable_provisional_response() {
+        let mut oa = OfferAnswer::new();
+        assert!(oa.state().is_initial());
+        let event = oa.process_request(&request("INVITE", 1, Some(sdp("alice", 1))), Side::Local);
+        assert!(assert_ok!(event).is_offer());
+        assert!(oa.state().is_offer_sent());
+
+        let event = oa.process_response(&response("100 Trying", "INVITE", 1, None), Side::Remote);
+        assert!(assert_ok!(event).is_none());
+        let event = oa.process_response(
+            &response("183 Session Progress", "INVITE", 1, Some(sdp("bob", 1))),
+            Side::Remote,
+        );
+        assert!(assert_ok!(event).is_answer());
+        assert!(oa.state().is_complete());
+        assert_eq!(oa.remote_sdp().unwrap().origin().username(), "bob");
+        assert_eq!(oa.local_sdp().unwrap().origin().username(), "alice");
+
+        // PRACK with a new offer, answered in its 200 response.
+        let event = oa.process_request(&request("PRACK", 2, Some(sdp("alice", 2))), Side::Local);
+        assert!(assert_ok!(event).is_offer());
+        let event = oa.process_response(
+            &response("200 OK", "PRACK", 2, Some(sdp("bob", 2))),
+            Side::Remote,
+        );
+        assert!(assert_ok!(event).is_answer());
+
+        // The 200 response to the INVITE repeats the answer.
+        let event = oa.process_response(
+            &response("200 OK", "INVITE", 1, Some(sdp("bob", 1))),
+            Side::Remote,
+        );
+        assert!(assert_ok!(event).is_none());
+        assert_eq!(oa.remote_sdp().unwrap().origin().session_version(), 2);
+        let event = oa.process_request(&request("ACK", 1, None), Side::Local);
+        assert!(assert_ok!(event).is_none());
+        assert!(oa.state().is_complete());
+    }
+
+    #[test]
+    fn test_offerless_invite() {
+        let mut oa = OfferAnswer::new();
+        let event = oa.process_request(&request("INVITE", 1, None), Side::Remote);
+        assert!(assert_ok!(event).is_none());
+        let event = oa.process_response(
+            &response("200 OK", "INVITE", 1, Some(sdp("bob", 1))),
+            Side::Local,
+        );
+        assert!(assert_ok!(event).is_offer());
+        assert!(oa.state().is_offer_sent());
+        assert_err!(oa.process_request(&request("ACK", 1, None), Side::Remote));
+        let event = oa.process_request(&request("ACK", 1, Some(sdp("alice", 1))), Side::Remote);
+        assert!(assert_ok!(event).is_answer());
+        assert!(oa.state().is_complete());
+        assert_eq!(oa.local_sdp().unwrap().origin().username(), "bob");
+        assert_eq!(oa.remote_sdp().unwrap().origin().username(), "alice");
+    }
+
+    #[test]
+    fn test_offer_in_reliable_provisional_response_answer_in_prack() {
+        let mut oa = OfferAnswer::new();
+        assert_ok!(oa.process_request(&request("INVITE", 1, None), Side::Local));
+        let event = oa.process_response(
+            &response("180 Ringing", "INVITE", 1, Some(sdp("bob", 1))),
+            Side::Remote,
+        );
+        assert!(assert_ok!(event).is_offer());
+        assert!(oa.state().is_offer_received());
+        let event = oa.process_request(&request("PRACK", 2, Some(sdp("alice", 1))), Side::Local);
+        assert!(assert_ok!(event).is_answer());
+        assert!(oa.state().is_complete());
+    }
+
+    #[test]
+    fn test_update_glare_and_rejection() {
+        let mut oa = OfferAnswer::new();
+        assert_ok!(oa.process_request(&request("INVITE", 1, Some(sdp("alice", 1))), Side::Local));
+        assert_ok!(oa.process_response(
+            &response("200 OK", "INVITE", 1, Some(sdp("bob", 1))),
+            Side::Remote
+        ));
+        assert_ok!(oa.process_request(&request("ACK", 1, None), Side::Local));
+
+        let event = oa.process_request(&request("UPDATE", 2, Some(sdp("alice", 2))), Side::Local);
+        assert!(assert_ok!(event).is_offer());
+        assert_err!(oa.process_request(&request("UPDATE", 1, Some(sdp("bob", 2))), Side::Remote));
+        let event = oa.process_response(
+            &response("488 Not Acceptable Here", "UPDATE", 2, None),
+            Side::Remote,
+        );
+        assert!(assert_ok!(event).is_none());
+        assert!(oa.state().is_complete());
+        assert_eq!(oa.local_sdp().unwrap().origin().session_version(), 1);
+
+        assert_ok!(oa.process_request(&request("UPDATE", 3, Some(sdp("alice", 2))), Side::Local));
+        assert_err!(oa.process_response(&response("200 OK", "UPDATE", 3, None), Side::Remote));
+    }
+
+    #[test]
+    fn test_unexpected_session_descriptions() {
+        let mut oa = OfferAnswer::new();
+        assert_err!(oa.process_request(&request("ACK", 1, Some(sdp("alice", 1))), Side::Local));
+        assert_err!(oa.process_response(
+            &response("200 OK", "UPDATE", 1, Some(sdp("bob", 1))),
+            Side::Remote
+        ));
+        let event = oa.process_request(&request("MESSAGE", 1, Some(sdp("alice", 1))), Side::Local);
+        assert!(assert_ok!(event).is_none());
+        assert!(oa.state().is_initial());
+    }
+}
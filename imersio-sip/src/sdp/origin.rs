@@ -0,0 +1,163 @@
+use crate::SipError;
+
+/// Representation of the origin (`o=` line) of a session description.
+///
+/// [[RFC8866, Section 5.2](https://datatracker.ietf.org/doc/html/rfc8866#section-5.2)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Origin {
+    username: String,
+    session_id: String,
+    session_version: u64,
+    net_type: String,
+    address_type: String,
+    address: String,
+}
+
+impl Origin {
+    /// Create an `Origin` with the `IN` network type.
+    pub fn new<S: Into<String>>(
+        username: S,
+        session_id: S,
+        session_version: u64,
+        address_type: S,
+        address: S,
+    ) -> Self {
+        Self {
+            username: username.into(),
+            session_id: session_id.into(),
+            session_version,
+            net_type: "IN".to_string(),
+            address_type: address_type.into(),
+            address: address.into(),
+        }
+    }
+
+    /// Get the username of the origin.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Get the session id of the origin.
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Get the session version of the origin.
+    pub fn session_version(&self) -> u64 {
+        self.session_version
+    }
+
+    /// Increment the session version of the origin, as needed when the session description is
+    /// modified.
+    pub fn increment_session_version(&mut self) {
+        self.session_version += 1;
+    }
+
+    /// Get the network type of the origin.
+    pub fn net_type(&self) -> &str {
+        &self.net_type
+    }
+
+    /// Get the address type of the origin.
+    pub fn address_type(&self) -> &str {
+        &self.address_type
+    }
+
+    /// Get the address of the origin.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Set the address type and the address of the origin.
+    pub fn set_address<S: Into<String>>(&mut self, address_type: S, address: S) {
+        self.address_type = address_type.into();
+        self.address = address.into();
+    }
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.username,
+            self.session_id,
+            self.session_version,
+            self.net_type,
+            self.address_type,
+            self.address
+        )
+    }
+}
+
+impl TryFrom<&str> for Origin {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("origin", value, parser::origin)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{Parser, combinator::map, error::context};
+
+    use super::Origin;
+    use crate::parser::ParserResult;
+    use crate::sdp::parser::{field, integer, space};
+
+    pub(crate) fn origin(input: &str) -> ParserResult<&str, Origin> {
+        context(
+            "origin",
+            map(
+                (
+                    field, space, field, space, integer, space, field, space, field, space, field,
+                ),
+                |(
+                    username,
+                    _,
+                    session_id,
+                    _,
+                    session_version,
+                    _,
+                    net_type,
+                    _,
+                    address_type,
+                    _,
+                    address,
+                )| {
+                    Origin {
+                        username: username.to_string(),
+                        session_id: session_id.to_string(),
+                        session_version,
+                        net_type: net_type.to_string(),
+                        address_type: address_type.to_string(),
+                        address: address.to_string(),
+                    }
+                },
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_origin() {
+        let mut origin = Origin::try_from("jdoe 2890844526 2890842807 IN IP4 10.47.16.5").unwrap();
+        assert_eq!(origin.username(), "jdoe");
+        assert_eq!(origin.session_id(), "2890844526");
+        assert_eq!(origin.session_version(), 2890842807);
+        assert_eq!(origin.address_type(), "IP4");
+        origin.increment_session_version();
+        origin.set_address("IP6", "2001:db8::1");
+        assert_eq!(
+            origin.to_string(),
+            "jdoe 2890844526 2890842808 IN IP6 2001:db8::1"
+        );
+        assert_err!(Origin::try_from("jdoe 2890844526 IN IP4 10.47.16.5"));
+    }
+}
@@ -0,0 +1,437 @@
+use crate::SipError;
+use crate::sdp::{Attribute, Bandwidth, Connection, MediaDescription, Origin, Timing};
+
+/// Representation of an SDP session description.
+///
+/// The lines are accepted in any order inside the session and the media sections, and are
+/// always serialized in the order defined by the SDP grammar, with CRLF line endings.
+///
+/// [[RFC8866, Section 5](https://datatracker.ietf.org/doc/html/rfc8866#section-5)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SessionDescription {
+    origin: Origin,
+    session_name: String,
+    information: Option<String>,
+    uri: Option<String>,
+    emails: Vec<String>,
+    phones: Vec<String>,
+    connection: Option<Connection>,
+    bandwidths: Vec<Bandwidth>,
+    timings: Vec<Timing>,
+    time_zones: Option<String>,
+    key: Option<String>,
+    attributes: Vec<Attribute>,
+    media_descriptions: Vec<MediaDescription>,
+}
+
+impl SessionDescription {
+    /// Create a `SessionDescription` for an unbounded session, without any media.
+    pub fn new<S: Into<String>>(origin: Origin, session_name: S) -> Self {
+        Self {
+            origin,
+            session_name: session_name.into(),
+            information: None,
+            uri: None,
+            emails: vec![],
+            phones: vec![],
+            connection: None,
+            bandwidths: vec![],
+            timings: vec![Timing::default()],
+            time_zones: None,
+            key: None,
+            attributes: vec![],
+            media_descriptions: vec![],
+        }
+    }
+
+    /// Get the protocol version of the session description, which is always 0.
+    pub fn version(&self) -> u8 {
+        0
+    }
+
+    /// Get a reference to the origin of the session description.
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    /// Get a mutable reference to the origin of the session description.
+    pub fn origin_mut(&mut self) -> &mut Origin {
+        &mut self.origin
+    }
+
+    /// Get the session name.
+    pub fn session_name(&self) -> &str {
+        &self.session_name
+    }
+
+    /// Get the session information (`i=` line), if any.
+    pub fn information(&self) -> Option<&str> {
+        self.information.as_deref()
+    }
+
+    /// Get the URI of the description (`u=` line), if any.
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    /// Get a reference to the email addresses (`e=` lines).
+    pub fn emails(&self) -> &Vec<String> {
+        &self.emails
+    }
+
+    /// Get a reference to the phone numbers (`p=` lines).
+    pub fn phones(&self) -> &Vec<String> {
+        &self.phones
+    }
+
+    /// Get the session level connection data, if any.
+    pub fn connection(&self) -> Option<&Connection> {
+        self.connection.as_ref()
+    }
+
+    /// Set the session level connection data.
+    pub fn set_connection(&mut self, connection: Option<Connection>) {
+        self.connection = connection;
+    }
+
+    /// Get a reference to the session level bandwidth information.
+    pub fn bandwidths(&self) -> &Vec<Bandwidth> {
+        &self.bandwidths
+    }
+
+    /// Get a reference to the timings of the session.
+    pub fn timings(&self) -> &Vec<Timing> {
+        &self.timings
+    }
+
+    /// Get a mutable reference to the timings of the session.
+    pub fn timings_mut(&mut self) -> &mut Vec<Timing> {
+        &mut self.timings
+    }
+
+    /// Get the time zone adjustments (`z=` line), if any.
+    pub fn time_zones(&self) -> Option<&str> {
+        self.time_zones.as_deref()
+    }
+
+    /// Get the session level encryption key (`k=` line), if any.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Get a reference to the session level attributes.
+    pub fn attributes(&self) -> &Vec<Attribute> {
+        &self.attributes
+    }
+
+    /// Get a mutable reference to the session level attributes.
+    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// Get a reference to the media descriptions.
+    pub fn media_descriptions(&self) -> &Vec<MediaDescription> {
+        &self.media_descriptions
+    }
+
+    /// Get a mutable reference to the media descriptions.
+    pub fn media_descriptions_mut(&mut self) -> &mut Vec<MediaDescription> {
+        &mut self.media_descriptions
+    }
+
+    /// Add a media description.
+    pub fn add_media_description(&mut self, media_description: MediaDescription) {
+        self.media_descriptions.push(media_description);
+    }
+
+    /// Get the connection data applying to a media description, ie. its own one or the session
+    /// level one.
+    pub fn media_connection(&self, index: usize) -> Option<&Connection> {
+        self.media_descriptions
+            .get(index)
+            .and_then(|media| media.connections().first())
+            .or(self.connection.as_ref())
+    }
+
+    /// Get the direction applying to a media description, ie. its own one, the session level one,
+    /// or `sendrecv` by default.
+    pub fn media_direction(&self, index: usize) -> crate::sdp::Direction {
+        self.media_descriptions
+            .get(index)
+            .and_then(MediaDescription::direction)
+            .or_else(|| {
+                self.attributes
+                    .iter()
+                    .find_map(|attribute| match attribute {
+                        Attribute::Direction(direction) => Some(*direction),
+                        _ => None,
+                    })
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for SessionDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v=0\r\n")?;
+        write!(f, "o={}\r\n", self.origin)?;
+        write!(f, "s={}\r\n", self.session_name)?;
+        if let Some(information) = &self.information {
+            write!(f, "i={}\r\n", information)?;
+        }
+        if let Some(uri) = &self.uri {
+            write!(f, "u={}\r\n", uri)?;
+        }
+        for email in &self.emails {
+            write!(f, "e={}\r\n", email)?;
+        }
+        for phone in &self.phones {
+            write!(f, "p={}\r\n", phone)?;
+        }
+        if let Some(connection) = &self.connection {
+            write!(f, "c={}\r\n", connection)?;
+        }
+        for bandwidth in &self.bandwidths {
+            write!(f, "b={}\r\n", bandwidth)?;
+        }
+        for timing in &self.timings {
+            write!(f, "{}", timing)?;
+        }
+        if let Some(time_zones) = &self.time_zones {
+            write!(f, "z={}\r\n", time_zones)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "k={}\r\n", key)?;
+        }
+        for attribute in &self.attributes {
+            write!(f, "a={}\r\n", attribute)?;
+        }
+        for media_description in &self.media_descriptions {
+            write!(f, "{}", media_description)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&str> for SessionDescription {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = |message: String| SipError::InvalidSdp(message);
+        let mut lines = value
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.split_once('=')
+                    .filter(|(kind, _)| kind.len() == 1)
+                    .ok_or_else(|| invalid(format!("invalid line `{line}`")))
+            });
+
+        match lines.next().transpose()? {
+            Some(("v", "0")) => (),
+            Some(("v", version)) => return Err(invalid(format!("invalid version `{version}`"))),
+            _ => return Err(invalid("missing version".to_string())),
+        }
+
+        let mut origin = None;
+        let mut session_name = None;
+        let mut information = None;
+        let mut uri = None;
+        let mut emails = vec![];
+        let mut phones = vec![];
+        let mut connection = None;
+        let mut bandwidths = vec![];
+        let mut timings: Vec<Timing> = vec![];
+        let mut time_zones = None;
+        let mut key = None;
+        let mut attributes = vec![];
+        let mut media_descriptions: Vec<MediaDescription> = vec![];
+
+        for line in lines {
+            let (kind, line_value) = line?;
+            if kind != "m" {
+                if let Some(media) = media_descriptions.last_mut() {
+                    match kind {
+                        "i" => media.set_information(line_value),
+                        "c" => media.add_connection(Connection::try_from(line_value)?),
+                        "b" => media.add_bandwidth(Bandwidth::try_from(line_value)?),
+                        "k" => media.set_key(line_value),
+                        "a" => media.add_attribute(Attribute::try_from(line_value)?),
+                        _ => {
+                            return Err(invalid(format!(
+                                "unexpected line `{kind}={line_value}` in media description"
+                            )));
+                        }
+                    }
+                    continue;
+                }
+            }
+            match kind {
+                "o" if origin.is_none() => origin = Some(Origin::try_from(line_value)?),
+                "s" if session_name.is_none() => session_name = Some(line_value.to_string()),
+                "i" if information.is_none() => information = Some(line_value.to_string()),
+                "u" if uri.is_none() => uri = Some(line_value.to_string()),
+                "e" => emails.push(line_value.to_string()),
+                "p" => phones.push(line_value.to_string()),
+                "c" if connection.is_none() => connection = Some(Connection::try_from(line_value)?),
+                "b" => bandwidths.push(Bandwidth::try_from(line_value)?),
+                "t" => timings.push(Timing::try_from(line_value)?),
+                "r" => match timings.last_mut() {
+                    Some(timing) => timing.add_repeat_time(line_value),
+                    None => return Err(invalid("repeat time without timing".to_string())),
+                },
+                "z" if time_zones.is_none() => time_zones = Some(line_value.to_string()),
+                "k" if key.is_none() => key = Some(line_value.to_string()),
+                "a" => attributes.push(Attribute::try_from(line_value)?),
+                "m" => media_descriptions.push(MediaDescription::try_from(line_value)?),
+                _ => {
+                    return Err(invalid(format!(
+                        "unexpected line `{kind}={line_value}` in session description"
+                    )));
+                }
+            }
+        }
+
+        Ok(Self {
+            origin: origin.ok_or_else(|| invalid("missing origin".to_string()))?,
+            session_name: session_name
+                .ok_or_else(|| invalid("missing session name".to_string()))?,
+            information,
+            uri,
+            emails,
+            phones,
+            connection,
+            bandwidths,
+            timings: if timings.is_empty() {
+                vec![Timing::default()]
+            } else {
+                timings
+            },
+            time_zones,
+            key,
+            attributes,
+            media_descriptions,
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for SessionDescription {
+    type Error = SipError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let value = std::str::from_utf8(value)
+            .map_err(|_| SipError::InvalidSdp("not valid UTF-8".to_string()))?;
+        Self::try_from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdp::Direction;
+    use claims::assert_err;
+
+    const OFFER: &str = "v=0\r\n\
+        o=jdoe 2890844526 2890842807 IN IP4 10.47.16.5\r\n\
+        s=SDP Seminar\r\n\
+        i=A Seminar on the session description protocol\r\n\
+        u=http://www.example.com/seminars/sdp.pdf\r\n\
+        e=j.doe@example.com (Jane Doe)\r\n\
+        c=IN IP4 224.2.17.12/127\r\n\
+        t=2873397496 2873404696\r\n\
+        a=recvonly\r\n\
+        m=audio 49170 RTP/AVP 0\r\n\
+        m=video 51372 RTP/AVP 99\r\n\
+        c=IN IP6 2001:db8::2\r\n\
+        a=rtpmap:99 h263-1998/90000\r\n\
+        a=candidate:1 1 UDP 2130706431 10.47.16.5 51372 typ host\r\n";
+
+    #[test]
+    fn test_session_description_round_trip() {
+        let sdp = SessionDescription::try_from(OFFER).unwrap();
+        assert_eq!(sdp.version(), 0);
+        assert_eq!(sdp.origin().username(), "jdoe");
+        assert_eq!(sdp.session_name(), "SDP Seminar");
+        assert_eq!(sdp.emails().len(), 1);
+        assert_eq!(sdp.timings().first().unwrap().start_time(), 2873397496);
+        assert_eq!(sdp.media_descriptions().len(), 2);
+        assert_eq!(sdp.to_string(), OFFER);
+        assert_eq!(
+            SessionDescription::try_from(sdp.to_string().as_bytes()).unwrap(),
+            sdp
+        );
+    }
+
+    #[test]
+    fn test_session_description_media_inheritance() {
+        let sdp = SessionDescription::try_from(OFFER).unwrap();
+        assert_eq!(
+            sdp.media_connection(0).unwrap().address(),
+            "224.2.17.12/127"
+        );
+        assert_eq!(sdp.media_connection(1).unwrap().address(), "2001:db8::2");
+        assert_eq!(sdp.media_direction(0), Direction::RecvOnly);
+        assert_eq!(
+            sdp.media_descriptions()[1]
+                .rtpmap("99")
+                .unwrap()
+                .clock_rate(),
+            90000
+        );
+    }
+
+    #[test]
+    fn test_session_description_lenient_parsing() {
+        let sdp = SessionDescription::try_from(
+            "v=0\n\
+            s=-\n\
+            o=- 1 1 IN IP4 192.0.2.1\n\
+            m=audio 5004 RTP/AVP 0\n\
+            a=sendrecv\n\
+            c=IN IP4 192.0.2.1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            sdp.to_string(),
+            "v=0\r\no=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\nt=0 0\r\nm=audio 5004 RTP/AVP 0\r\n\
+            c=IN IP4 192.0.2.1\r\na=sendrecv\r\n"
+        );
+    }
+
+    #[test]
+    fn test_session_description_rewriting() {
+        let mut sdp = SessionDescription::try_from(OFFER).unwrap();
+        sdp.origin_mut().increment_session_version();
+        sdp.set_connection(Some(Connection::new("IP4", "198.51.100.1")));
+        let video = sdp.media_descriptions_mut().get_mut(1).unwrap();
+        video.set_port(0);
+        video.set_direction(Direction::Inactive);
+        let sdp = SessionDescription::try_from(sdp.to_string().as_str()).unwrap();
+        assert_eq!(sdp.origin().session_version(), 2890842808);
+        assert_eq!(sdp.connection().unwrap().address(), "198.51.100.1");
+        assert!(sdp.media_descriptions()[1].is_rejected());
+        assert_eq!(sdp.media_direction(1), Direction::Inactive);
+    }
+
+    #[test]
+    fn test_invalid_session_descriptions() {
+        assert_err!(SessionDescription::try_from(""));
+        assert_err!(SessionDescription::try_from(
+            "v=1\r\no=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\n"
+        ));
+        assert_err!(SessionDescription::try_from(
+            "o=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\n"
+        ));
+        assert_err!(SessionDescription::try_from("v=0\r\ns=-\r\n"));
+        assert_err!(SessionDescription::try_from(
+            "v=0\r\no=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\nm=audio 0 RTP/AVP 0\r\nt=0 0\r\n"
+        ));
+        assert_err!(SessionDescription::try_from(
+            "v=0\r\no=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\nx=unknown\r\n"
+        ));
+        assert_err!(SessionDescription::try_from(
+            "v=0\r\no=- 1 1 IN IP4 192.0.2.1\r\ns=-\r\ngarbage\r\n"
+        ));
+    }
+}
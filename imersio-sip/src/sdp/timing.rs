@@ -0,0 +1,104 @@
+use crate::SipError;
+
+/// Representation of the timing (`t=` line and its `r=` lines) of a session description.
+///
+/// [[RFC8866, Section 5.9](https://datatracker.ietf.org/doc/html/rfc8866#section-5.9)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Timing {
+    start_time: u64,
+    stop_time: u64,
+    repeat_times: Vec<String>,
+}
+
+impl Timing {
+    /// Create a `Timing`.
+    pub fn new(start_time: u64, stop_time: u64) -> Self {
+        Self {
+            start_time,
+            stop_time,
+            repeat_times: vec![],
+        }
+    }
+
+    /// Get the start time, as an NTP timestamp, or 0 for an unbounded session.
+    pub fn start_time(&self) -> u64 {
+        self.start_time
+    }
+
+    /// Get the stop time, as an NTP timestamp, or 0 for an unbounded session.
+    pub fn stop_time(&self) -> u64 {
+        self.stop_time
+    }
+
+    /// Get a reference to the values of the `r=` lines of the timing.
+    pub fn repeat_times(&self) -> &Vec<String> {
+        &self.repeat_times
+    }
+
+    pub(crate) fn add_repeat_time(&mut self, repeat_time: &str) {
+        self.repeat_times.push(repeat_time.to_string());
+    }
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl std::fmt::Display for Timing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "t={} {}\r\n", self.start_time, self.stop_time)?;
+        for repeat_time in &self.repeat_times {
+            write!(f, "r={}\r\n", repeat_time)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&str> for Timing {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        super::parser::parse_value("timing", value, parser::timing)
+    }
+}
+
+pub(crate) mod parser {
+    use nom::{Parser, combinator::map, error::context, sequence::separated_pair};
+
+    use super::Timing;
+    use crate::parser::ParserResult;
+    use crate::sdp::parser::{integer, space};
+
+    pub(crate) fn timing(input: &str) -> ParserResult<&str, Timing> {
+        context(
+            "timing",
+            map(
+                separated_pair(integer, space, integer),
+                |(start_time, stop_time)| Timing::new(start_time, stop_time),
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+
+    #[test]
+    fn test_timing() {
+        let mut timing = Timing::try_from("2873397496 2873404696").unwrap();
+        assert_eq!(timing.start_time(), 2873397496);
+        assert_eq!(timing.stop_time(), 2873404696);
+        timing.add_repeat_time("604800 3600 0 90000");
+        assert_eq!(
+            timing.to_string(),
+            "t=2873397496 2873404696\r\nr=604800 3600 0 90000\r\n"
+        );
+        assert_eq!(Timing::default().to_string(), "t=0 0\r\n");
+        assert_err!(Timing::try_from("now 0"));
+    }
+}
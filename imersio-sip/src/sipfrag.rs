@@ -0,0 +1,407 @@
+//! SIP message fragments.
+//!
+//! This module provides the `message/sipfrag` bodies, made of an optional start line, optional
+//! headers and an optional body. They are notably carried by the NOTIFY requests reporting the
+//! progress of a REFER request.
+//!
+//! [[RFC3420](https://datatracker.ietf.org/doc/html/rfc3420)]
+//! [[RFC3515, Section 2.4.5](https://datatracker.ietf.org/doc/html/rfc3515#section-2.4.5)]
+
+use itertools::join;
+use nom_language::error::convert_error;
+use std::str::from_utf8;
+
+use crate::{Header, Method, Reason, SipError, StatusCode, Uri, Version};
+
+/// Representation of the start line of a SIP message fragment.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::IsVariant)]
+pub enum StartLine {
+    /// A request line.
+    Request {
+        /// The method of the request.
+        method: Method,
+        /// The Request-URI of the request.
+        uri: Uri,
+        /// The SIP version of the request.
+        version: Version,
+    },
+    /// A status line.
+    Status {
+        /// The SIP version of the response.
+        version: Version,
+        /// The status code and reason phrase of the response.
+        reason: Reason,
+    },
+}
+
+impl std::fmt::Display for StartLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request {
+                method,
+                uri,
+                version,
+            } => write!(f, "{} {} {}", method, uri, version),
+            Self::Status { version, reason } => write!(f, "{} {}", version, reason),
+        }
+    }
+}
+
+/// Representation of a SIP message fragment, ie. a `message/sipfrag` body.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SipFrag {
+    start_line: Option<StartLine>,
+    headers: Vec<Header>,
+    body: Vec<u8>,
+}
+
+impl SipFrag {
+    /// Get a `SipFrag` builder.
+    pub fn builder() -> SipFragBuilder {
+        SipFragBuilder::default()
+    }
+
+    /// Get a reference to the start line of the fragment, if any.
+    pub fn start_line(&self) -> Option<&StartLine> {
+        self.start_line.as_ref()
+    }
+
+    /// Get a reference to the reason of the fragment, if it starts with a status line.
+    pub fn reason(&self) -> Option<&Reason> {
+        match &self.start_line {
+            Some(StartLine::Status { reason, .. }) => Some(reason),
+            _ => None,
+        }
+    }
+
+    /// Get a reference to the status code of the fragment, if it starts with a status line.
+    pub fn status(&self) -> Option<&StatusCode> {
+        self.reason().map(Reason::status)
+    }
+
+    /// Get a reference to the method of the fragment, if it starts with a request line.
+    pub fn method(&self) -> Option<&Method> {
+        match &self.start_line {
+            Some(StartLine::Request { method, .. }) => Some(method),
+            _ => None,
+        }
+    }
+
+    /// Get a reference to the headers of the fragment.
+    pub fn headers(&self) -> &Vec<Header> {
+        &self.headers
+    }
+
+    /// Get a reference to the body of the fragment.
+    pub fn body(&self) -> &[u8] {
+        self.body.as_slice()
+    }
+
+    /// Get the bytes of the fragment, to be used as a `message/sipfrag` body.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        if let Some(start_line) = &self.start_line {
+            bytes.extend_from_slice(format!("{}\r\n", start_line).as_bytes());
+        }
+        for header in &self.headers {
+            bytes.extend_from_slice(format!("{}\r\n", header).as_bytes());
+        }
+        if !self.body.is_empty() {
+            bytes.extend_from_slice(b"\r\n");
+            bytes.extend_from_slice(&self.body);
+        }
+        bytes
+    }
+}
+
+impl std::fmt::Display for SipFrag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(start_line) = &self.start_line {
+            write!(f, "{}\r\n", start_line)?;
+        }
+        if !self.headers.is_empty() {
+            write!(f, "{}\r\n", join(&self.headers, "\r\n"))?;
+        }
+        if !self.body.is_empty() {
+            write!(
+                f,
+                "\r\n{}",
+                match from_utf8(&self.body) {
+                    Ok(body) => body.to_string(),
+                    Err(_) => format!("[binary body of size {}]", self.body.len()),
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for SipFrag {
+    type Error = SipError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (head, body) = match value.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(position) => (&value[..position + 2], &value[position + 4..]),
+            None if value.starts_with(b"\r\n") => (&value[..0], &value[2..]),
+            None => (value, &value[value.len()..]),
+        };
+        let head = from_utf8(head)
+            .map_err(|_| SipError::InvalidSipFrag("fragment head is not UTF-8 encoded".into()))?;
+        let head = if head.is_empty() || head.ends_with("\r\n") {
+            head.to_string()
+        } else {
+            format!("{head}\r\n")
+        };
+        match parser::sipfrag_head(&head) {
+            Ok((rest, (start_line, headers))) => {
+                if !rest.is_empty() {
+                    Err(SipError::RemainingUnparsedData(rest.to_string()))
+                } else {
+                    Ok(Self {
+                        start_line,
+                        headers,
+                        body: body.to_vec(),
+                    })
+                }
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                Err(SipError::InvalidSipFrag(convert_error(head.as_str(), e)))
+            }
+            Err(nom::Err::Incomplete(_)) => Err(SipError::InvalidSipFrag(format!(
+                "Incomplete sipfrag `{}`",
+                head
+            ))),
+        }
+    }
+}
+
+impl TryFrom<&str> for SipFrag {
+    type Error = SipError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_bytes())
+    }
+}
+
+/// Representation of a builder of `SipFrag`.
+#[derive(Clone, Debug, Default)]
+pub struct SipFragBuilder {
+    start_line: Option<StartLine>,
+    headers: Vec<Header>,
+    body: Vec<u8>,
+}
+
+impl SipFragBuilder {
+    /// Set a status line with the given reason, eg. `Reason::OK` for `SIP/2.0 200 OK`.
+    pub fn status(&mut self, reason: Reason) -> &mut Self {
+        self.start_line = Some(StartLine::Status {
+            version: Version::Sip2,
+            reason,
+        });
+        self
+    }
+
+    /// Set a request line with the given method and Request-URI.
+    pub fn request(&mut self, method: Method, uri: Uri) -> &mut Self {
+        self.start_line = Some(StartLine::Request {
+            method,
+            uri,
+            version: Version::Sip2,
+        });
+        self
+    }
+
+    /// Add a header.
+    pub fn header(&mut self, header: Header) -> &mut Self {
+        self.headers.push(header);
+        self
+    }
+
+    /// Set the body.
+    pub fn body(&mut self, body: &[u8]) -> &mut Self {
+        self.body = body.to_vec();
+        self
+    }
+
+    /// Build the `SipFrag`.
+    pub fn build(&self) -> SipFrag {
+        SipFrag {
+            start_line: self.start_line.clone(),
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+        }
+    }
+}
+
+/// Parse the body of a SIP message as a SIP message fragment if its Content-Type is
+/// `message/sipfrag`.
+pub(crate) fn from_body(headers: &[Header], body: &[u8]) -> Result<Option<SipFrag>, SipError> {
+    let is_sipfrag = headers.iter().any(|header| match header {
+        Header::ContentType(header) => {
+            let media_range = header.media_type().media_range();
+            media_range.r#type().eq_ignore_ascii_case("message")
+                && media_range.subtype().eq_ignore_ascii_case("sipfrag")
+        }
+        _ => false,
+    });
+    if !is_sipfrag {
+        return Ok(None);
+    }
+    SipFrag::try_from(body).map(Some)
+}
+
+/// Set a SIP message fragment as the body of a SIP message, updating its Content-Type and
+/// Content-Length headers.
+pub(crate) fn set_body(headers: &mut Vec<Header>, body: &mut Vec<u8>, sipfrag: &SipFrag) {
+    *body = sipfrag.to_bytes();
+    crate::multipart::set_content_headers(
+        headers,
+        crate::multipart::media_type("message", "sipfrag", vec![]),
+        body.len(),
+    );
+}
+
+mod parser {
+    use nom::{
+        Parser,
+        branch::alt,
+        character::complete::crlf,
+        combinator::{map, opt},
+        error::context,
+        multi::many0,
+        sequence::terminated,
+    };
+
+    use super::StartLine;
+    use crate::{
+        Header,
+        headers::header::parser::message_header,
+        messages::{request::parser::request_line, response::parser::status_line},
+        parser::ParserResult,
+    };
+
+    fn start_line(input: &str) -> ParserResult<&str, StartLine> {
+        context(
+            "start_line",
+            alt((
+                map(request_line, |(method, uri, version)| StartLine::Request {
+                    method,
+                    uri,
+                    version,
+                }),
+                map(status_line, |(version, reason)| StartLine::Status {
+                    version,
+                    reason,
+                }),
+            )),
+        )
+        .parse(input)
+    }
+
+    pub(super) fn sipfrag_head(
+        input: &str,
+    ) -> ParserResult<&str, (Option<StartLine>, Vec<Header>)> {
+        context(
+            "sipfrag",
+            (
+                opt(terminated(start_line, crlf)),
+                many0(terminated(message_header, crlf)),
+            ),
+        )
+        .parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Request;
+    use claims::{assert_err, assert_none};
+
+    #[test]
+    fn test_status_line_only() {
+        let sipfrag = SipFrag::try_from("SIP/2.0 100 Trying").unwrap();
+        assert_eq!(sipfrag.status(), Some(&StatusCode::TRYING));
+        assert!(sipfrag.headers().is_empty());
+        assert!(sipfrag.body().is_empty());
+        let sipfrag = SipFrag::try_from("SIP/2.0 603 Declined\r\n").unwrap();
+        assert_eq!(sipfrag.status(), Some(&StatusCode::DECLINE));
+        assert_eq!(sipfrag.reason().unwrap().phrase(), "Declined");
+        assert_none!(sipfrag.method());
+    }
+
+    #[test]
+    fn test_full_fragment() {
+        let sipfrag = SipFrag::try_from(
+            "INVITE sip:alice@atlanta.com SIP/2.0\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Length: 11\r\n\
+            \r\n\
+            Hello Alice",
+        )
+        .unwrap();
+        assert_eq!(sipfrag.method(), Some(&Method::Invite));
+        assert_none!(sipfrag.status());
+        assert_eq!(sipfrag.headers().len(), 2);
+        assert_eq!(sipfrag.body(), b"Hello Alice");
+        assert_eq!(
+            sipfrag.to_string(),
+            "INVITE sip:alice@atlanta.com SIP/2.0\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Length: 11\r\n\
+            \r\n\
+            Hello Alice"
+        );
+    }
+
+    #[test]
+    fn test_fragment_without_start_line() {
+        let sipfrag =
+            SipFrag::try_from("From: <sip:alice@atlanta.com>;tag=1928301774\r\n").unwrap();
+        assert_none!(sipfrag.start_line());
+        assert_eq!(sipfrag.headers().len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_fragments() {
+        assert_err!(SipFrag::try_from("SIP/2.0 OK"));
+        assert_err!(SipFrag::try_from("Hello world!"));
+        assert_err!(SipFrag::try_from(&b"SIP/2.0 200 \xff\xfe"[..]));
+    }
+
+    #[test]
+    fn test_builder() {
+        let sipfrag = SipFrag::builder().status(Reason::OK).build();
+        assert_eq!(sipfrag.to_string(), "SIP/2.0 200 OK\r\n");
+        let sipfrag = SipFrag::builder()
+            .request(Method::Bye, Uri::try_from("sip:bob@biloxi.com").unwrap())
+            .header(Header::try_from("Call-ID: a84b4c76e66710").unwrap())
+            .build();
+        assert_eq!(
+            sipfrag.to_string(),
+            "BYE sip:bob@biloxi.com SIP/2.0\r\nCall-ID: a84b4c76e66710\r\n"
+        );
+        assert_eq!(
+            SipFrag::try_from(sipfrag.to_string().as_str()).unwrap(),
+            sipfrag
+        );
+    }
+
+    #[test]
+    fn test_notify_sipfrag_body() {
+        let mut notify =
+            Request::try_from("NOTIFY sip:alice@atlanta.com SIP/2.0\r\nEvent: refer\r\n\r\n")
+                .unwrap();
+        assert_none!(notify.sipfrag().unwrap());
+        notify.set_sipfrag(&SipFrag::builder().status(Reason::RINGING).build());
+        assert_eq!(notify.body(), b"SIP/2.0 180 Ringing\r\n");
+        assert_eq!(
+            notify.headers()[1].to_string(),
+            "Content-Type: message/sipfrag"
+        );
+        assert_eq!(
+            notify.sipfrag().unwrap().unwrap().status(),
+            Some(&StatusCode::RINGING)
+        );
+    }
+}
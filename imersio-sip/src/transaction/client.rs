@@ -0,0 +1,587 @@
+//! Client transactions, sending a request and receiving its responses.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::transaction::timer::Timers;
+use crate::transaction::{
+    Clock, Timer, TimerSettings, TransactionEvent, TransactionKey, TransactionState, cseq,
+};
+use crate::{CSeqHeader, ContentLengthHeader, Header, Method, Request, Response, SipError};
+
+/// Representation of a client transaction, INVITE or non-INVITE depending on the method of its
+/// request.
+///
+/// The transaction is started with `start`, then fed with the responses received for the
+/// request, the transport errors and the expiration of its timers. Each of these returns the
+/// events to handle by the owner of the transaction.
+///
+/// [[RFC3261, Section 17.1](https://datatracker.ietf.org/doc/html/rfc3261#section-17.1)]
+/// [[RFC6026, Section 7.2](https://datatracker.ietf.org/doc/html/rfc6026#section-7.2)]
+#[derive(Debug)]
+pub struct ClientTransaction {
+    key: TransactionKey,
+    request: Request,
+    reliable: bool,
+    settings: TimerSettings,
+    clock: Arc<dyn Clock>,
+    state: TransactionState,
+    started: bool,
+    timers: Timers,
+    retransmit_interval: Duration,
+    ack: Option<Request>,
+}
+
+impl ClientTransaction {
+    /// Create a client transaction for the given request, to send over a reliable transport or
+    /// not.
+    ///
+    /// The top Via of the request must have a branch generated according to RFC 3261. An ACK
+    /// does not create a transaction.
+    pub fn new(
+        request: Request,
+        reliable: bool,
+        settings: TimerSettings,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, SipError> {
+        if request.method() == &Method::Ack {
+            return Err(SipError::InvalidTransaction(
+                "An ACK does not create a client transaction".to_string(),
+            ));
+        }
+        if cseq(request.headers()).is_none() {
+            return Err(SipError::InvalidRequest(
+                "Request without CSeq header".to_string(),
+            ));
+        }
+        let key = TransactionKey::client(&request)?;
+        let state = if request.method() == &Method::Invite {
+            TransactionState::Calling
+        } else {
+            TransactionState::Trying
+        };
+        Ok(Self {
+            key,
+            request,
+            reliable,
+            settings,
+            clock,
+            state,
+            started: false,
+            timers: Timers::default(),
+            retransmit_interval: settings.t1(),
+            ack: None,
+        })
+    }
+
+    /// Get the key identifying the transaction.
+    pub fn key(&self) -> &TransactionKey {
+        &self.key
+    }
+
+    /// Get the request of the transaction.
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// Get the current state of the transaction.
+    pub fn state(&self) -> TransactionState {
+        self.state
+    }
+
+    /// Tell whether the transaction is an INVITE client transaction.
+    pub fn is_invite(&self) -> bool {
+        self.request.method() == &Method::Invite
+    }
+
+    /// Tell whether the transaction is over and can be removed.
+    pub fn is_terminated(&self) -> bool {
+        self.state == TransactionState::Terminated
+    }
+
+    /// Start the transaction, sending its request and starting its timers.
+    ///
+    /// Starting an already started transaction does nothing.
+    pub fn start(&mut self) -> Vec<TransactionEvent> {
+        if self.started {
+            return vec![];
+        }
+        self.started = true;
+        let (retransmit, timeout) = if self.is_invite() {
+            (Timer::A, Timer::B)
+        } else {
+            (Timer::E, Timer::F)
+        };
+        if !self.reliable {
+            self.start_timer(retransmit, self.retransmit_interval);
+        }
+        self.start_timer(timeout, self.duration(timeout));
+        vec![TransactionEvent::SendRequest(self.request.clone())]
+    }
+
+    /// Get the time at which the next timer of the transaction expires, if any.
+    pub fn next_timeout(&self) -> Option<std::time::Instant> {
+        self.timers.next()
+    }
+
+    /// Handle the timers of the transaction that expired.
+    pub fn handle_timers(&mut self) -> Vec<TransactionEvent> {
+        let mut events = vec![];
+        while let Some(timer) = self.timers.pop_expired(self.clock.now()) {
+            events.extend(self.handle_timer(timer));
+        }
+        events
+    }
+
+    /// Handle a response received for the request of the transaction.
+    ///
+    /// The responses that are retransmissions are absorbed, the other ones are passed to the
+    /// transaction user.
+    pub fn receive_response(&mut self, response: Response) -> Vec<TransactionEvent> {
+        if self.is_invite() {
+            self.receive_invite_response(response)
+        } else {
+            self.receive_non_invite_response(response)
+        }
+    }
+
+    /// Handle the failure of the transport to send the request of the transaction.
+    pub fn transport_error(&mut self) -> Vec<TransactionEvent> {
+        match self.state {
+            TransactionState::Calling | TransactionState::Trying | TransactionState::Proceeding => {
+                let mut events = vec![TransactionEvent::TransportError];
+                events.extend(self.terminate());
+                events
+            }
+            _ => vec![],
+        }
+    }
+
+    fn receive_invite_response(&mut self, response: Response) -> Vec<TransactionEvent> {
+        let reason = response.reason();
+        match self.state {
+            TransactionState::Calling | TransactionState::Proceeding => {
+                if reason.is_provisional() {
+                    self.state = TransactionState::Proceeding;
+                    self.timers.stop(Timer::A);
+                    self.timers.stop(Timer::B);
+                    vec![TransactionEvent::Response(response)]
+                } else if reason.is_success() {
+                    self.state = TransactionState::Accepted;
+                    self.timers.clear();
+                    self.start_timer(Timer::M, self.duration(Timer::M));
+                    vec![TransactionEvent::Response(response)]
+                } else {
+                    let ack = self.ack(&response);
+                    self.ack = Some(ack.clone());
+                    self.state = TransactionState::Completed;
+                    self.timers.clear();
+                    let mut events = vec![
+                        TransactionEvent::Response(response),
+                        TransactionEvent::SendRequest(ack),
+                    ];
+                    events.extend(self.wait(Timer::D));
+                    events
+                }
+            }
+            // The retransmissions of the 2xx responses are handled by the transaction user.
+            TransactionState::Accepted if reason.is_success() => {
+                vec![TransactionEvent::Response(response)]
+            }
+            TransactionState::Completed if reason.is_final() && !reason.is_success() => self
+                .ack
+                .clone()
+                .map(TransactionEvent::SendRequest)
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn receive_non_invite_response(&mut self, response: Response) -> Vec<TransactionEvent> {
+        match self.state {
+            TransactionState::Trying | TransactionState::Proceeding => {
+                if response.reason().is_provisional() {
+                    self.state = TransactionState::Proceeding;
+                    vec![TransactionEvent::Response(response)]
+                } else {
+                    self.state = TransactionState::Completed;
+                    self.timers.clear();
+                    let mut events = vec![TransactionEvent::Response(response)];
+                    events.extend(self.wait(Timer::K));
+                    events
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    fn handle_timer(&mut self, timer: Timer) -> Vec<TransactionEvent> {
+        match (timer, self.state) {
+            (Timer::A, TransactionState::Calling) => {
+                self.retransmit_interval *= 2;
+                self.start_timer(Timer::A, self.retransmit_interval);
+                vec![TransactionEvent::SendRequest(self.request.clone())]
+            }
+            (Timer::E, TransactionState::Trying | TransactionState::Proceeding) => {
+                self.retransmit_interval = if self.state == TransactionState::Trying {
+                    (self.retransmit_interval * 2).min(self.settings.t2())
+                } else {
+                    self.settings.t2()
+                };
+                self.start_timer(Timer::E, self.retransmit_interval);
+                vec![TransactionEvent::SendRequest(self.request.clone())]
+            }
+            (Timer::B, TransactionState::Calling)
+            | (Timer::F, TransactionState::Trying | TransactionState::Proceeding) => {
+                let mut events = vec![TransactionEvent::Timeout];
+                events.extend(self.terminate());
+                events
+            }
+            (Timer::D, TransactionState::Completed)
+            | (Timer::K, TransactionState::Completed)
+            | (Timer::M, TransactionState::Accepted) => self.terminate(),
+            _ => vec![],
+        }
+    }
+
+    /// Build the ACK of a non-2xx final response to the INVITE of the transaction.
+    ///
+    /// [[RFC3261, Section 17.1.1.3](https://datatracker.ietf.org/doc/html/rfc3261#section-17.1.1.3)]
+    fn ack(&self, response: &Response) -> Request {
+        let mut has_via = false;
+        let mut headers: Vec<Header> = self
+            .request
+            .headers()
+            .iter()
+            .filter_map(|header| match header {
+                Header::Via(header) if !has_via => {
+                    has_via = true;
+                    Some(Header::Via(vec![header.vias().first()?.clone()].into()))
+                }
+                Header::Route(_) | Header::MaxForwards(_) | Header::From(_) | Header::CallId(_) => {
+                    Some(header.clone())
+                }
+                Header::To(_) => response
+                    .headers()
+                    .iter()
+                    .find(|header| matches!(header, Header::To(_)))
+                    .or(Some(header))
+                    .cloned(),
+                Header::CSeq(header) => {
+                    Some(Header::CSeq(CSeqHeader::from((header.cseq(), Method::Ack))))
+                }
+                _ => None,
+            })
+            .collect();
+        headers.push(Header::ContentLength(ContentLengthHeader::from(0)));
+        Request::new(
+            Method::Ack,
+            self.request.uri().clone(),
+            *self.request.version(),
+            headers,
+            vec![],
+        )
+    }
+
+    /// Start a wait timer, terminating the transaction at once if its duration is zero.
+    fn wait(&mut self, timer: Timer) -> Vec<TransactionEvent> {
+        let duration = self.duration(timer);
+        if duration.is_zero() {
+            self.terminate()
+        } else {
+            self.start_timer(timer, duration);
+            vec![]
+        }
+    }
+
+    fn terminate(&mut self) -> Vec<TransactionEvent> {
+        self.state = TransactionState::Terminated;
+        self.timers.clear();
+        vec![TransactionEvent::Terminated]
+    }
+
+    fn duration(&self, timer: Timer) -> Duration {
+        self.settings.duration(timer, self.reliable)
+    }
+
+    fn start_timer(&mut self, timer: Timer, duration: Duration) {
+        self.timers.start(timer, self.clock.now() + duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::ManualClock;
+
+    const INVITE: &str = "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Max-Forwards: 70\r\n\
+Route: <sip:proxy.atlanta.com;lr>\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    const OPTIONS: &str = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+To: Carol <sip:carol@chicago.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    fn response(code: u16, method: &str) -> Response {
+        Response::try_from(
+            format!(
+                "SIP/2.0 {code} Whatever\r\n\
+                 Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+                 To: Bob <sip:bob@biloxi.com>;tag=a6c85cf\r\n\
+                 From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+                 Call-ID: a84b4c76e66710\r\n\
+                 CSeq: 314159 {method}\r\n\
+                 Content-Length: 0\r\n\
+                 \r\n"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    fn transaction(request: &str, reliable: bool) -> (ClientTransaction, ManualClock) {
+        let clock = ManualClock::new();
+        let transaction = ClientTransaction::new(
+            Request::try_from(request).unwrap(),
+            reliable,
+            TimerSettings::default(),
+            Arc::new(clock.clone()),
+        )
+        .unwrap();
+        (transaction, clock)
+    }
+
+    fn sent_requests(events: &[TransactionEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| matches!(event, TransactionEvent::SendRequest(_)))
+            .count()
+    }
+
+    #[test]
+    fn test_invite_retransmissions_and_timeout() {
+        let (mut transaction, clock) = transaction(INVITE, false);
+        assert_eq!(transaction.state(), TransactionState::Calling);
+        assert_eq!(sent_requests(&transaction.start()), 1);
+        assert!(transaction.start().is_empty());
+
+        // Timer A fires after 0.5s, 1s, 2s, 4s, 8s and 16s.
+        let mut retransmissions = 0;
+        for interval in [500, 1000, 2000, 4000, 8000, 16000] {
+            clock.advance(Duration::from_millis(interval - 1));
+            assert!(transaction.handle_timers().is_empty());
+            clock.advance(Duration::from_millis(1));
+            retransmissions += sent_requests(&transaction.handle_timers());
+        }
+        assert_eq!(retransmissions, 6);
+
+        // Timer B fires after 32s.
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Timeout, TransactionEvent::Terminated]
+        );
+        assert!(transaction.is_terminated());
+        assert_eq!(transaction.next_timeout(), None);
+    }
+
+    #[test]
+    fn test_invite_reliable() {
+        let (mut transaction, clock) = transaction(INVITE, true);
+        transaction.start();
+        assert_eq!(
+            transaction.next_timeout(),
+            Some(clock.now() + Duration::from_secs(32))
+        );
+        clock.advance(Duration::from_secs(16));
+        assert!(transaction.handle_timers().is_empty());
+
+        // Timer D is zero over a reliable transport.
+        let events = transaction.receive_response(response(486, "INVITE"));
+        assert_eq!(sent_requests(&events), 1);
+        assert_eq!(events.last(), Some(&TransactionEvent::Terminated));
+    }
+
+    #[test]
+    fn test_invite_non_2xx_final_response() {
+        let (mut transaction, clock) = transaction(INVITE, false);
+        transaction.start();
+        let events = transaction.receive_response(response(180, "INVITE"));
+        assert_eq!(
+            events,
+            vec![TransactionEvent::Response(response(180, "INVITE"))]
+        );
+        assert_eq!(transaction.state(), TransactionState::Proceeding);
+
+        // No retransmission nor timeout in the Proceeding state.
+        clock.advance(Duration::from_secs(60));
+        assert!(transaction.handle_timers().is_empty());
+
+        let events = transaction.receive_response(response(486, "INVITE"));
+        assert_eq!(transaction.state(), TransactionState::Completed);
+        assert_eq!(
+            events[0],
+            TransactionEvent::Response(response(486, "INVITE"))
+        );
+        let TransactionEvent::SendRequest(ack) = &events[1] else {
+            panic!("No ACK sent");
+        };
+        assert_eq!(
+            String::from_utf8(ack.to_bytes()).unwrap(),
+            "ACK sip:bob@biloxi.com SIP/2.0\r\n\
+             Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+             Max-Forwards: 70\r\n\
+             Route: <sip:proxy.atlanta.com;lr>\r\n\
+             To: Bob <sip:bob@biloxi.com>;tag=a6c85cf\r\n\
+             From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+             Call-ID: a84b4c76e66710\r\n\
+             CSeq: 314159 ACK\r\n\
+             Content-Length: 0\r\n\
+             \r\n"
+        );
+
+        // The retransmissions of the final response are absorbed and acknowledged again.
+        assert_eq!(
+            transaction.receive_response(response(486, "INVITE")),
+            vec![TransactionEvent::SendRequest(ack.clone())]
+        );
+        assert!(
+            transaction
+                .receive_response(response(180, "INVITE"))
+                .is_empty()
+        );
+
+        // Timer D fires after 32s.
+        clock.advance(Duration::from_secs(32));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_invite_2xx_response() {
+        let (mut transaction, clock) = transaction(INVITE, false);
+        transaction.start();
+        let events = transaction.receive_response(response(200, "INVITE"));
+        assert_eq!(
+            events,
+            vec![TransactionEvent::Response(response(200, "INVITE"))]
+        );
+        assert_eq!(transaction.state(), TransactionState::Accepted);
+
+        // The retransmissions of the 2xx response are passed to the transaction user.
+        assert_eq!(
+            transaction.receive_response(response(200, "INVITE")),
+            vec![TransactionEvent::Response(response(200, "INVITE"))]
+        );
+        assert!(
+            transaction
+                .receive_response(response(486, "INVITE"))
+                .is_empty()
+        );
+
+        // Timer M fires after 32s.
+        clock.advance(Duration::from_secs(32));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_non_invite_transaction() {
+        let (mut transaction, clock) = transaction(OPTIONS, false);
+        assert_eq!(transaction.state(), TransactionState::Trying);
+        transaction.start();
+
+        // Timer E fires after 0.5s, 1s, 2s, 4s then every T2.
+        for interval in [500, 1000, 2000, 4000, 4000] {
+            clock.advance(Duration::from_millis(interval));
+            assert_eq!(sent_requests(&transaction.handle_timers()), 1);
+        }
+        assert_eq!(
+            transaction.next_timeout(),
+            Some(clock.now() + Duration::from_secs(4))
+        );
+
+        transaction.receive_response(response(100, "OPTIONS"));
+        assert_eq!(transaction.state(), TransactionState::Proceeding);
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(sent_requests(&transaction.handle_timers()), 1);
+
+        let events = transaction.receive_response(response(200, "OPTIONS"));
+        assert_eq!(
+            events,
+            vec![TransactionEvent::Response(response(200, "OPTIONS"))]
+        );
+        assert_eq!(transaction.state(), TransactionState::Completed);
+        assert!(
+            transaction
+                .receive_response(response(200, "OPTIONS"))
+                .is_empty()
+        );
+
+        // Timer K fires after T4.
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_non_invite_timeout() {
+        let (mut transaction, clock) = transaction(OPTIONS, true);
+        transaction.start();
+        clock.advance(Duration::from_secs(31));
+        assert!(transaction.handle_timers().is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Timeout, TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_transport_error() {
+        let (mut transaction, _) = transaction(OPTIONS, false);
+        transaction.start();
+        assert_eq!(
+            transaction.transport_error(),
+            vec![
+                TransactionEvent::TransportError,
+                TransactionEvent::Terminated
+            ]
+        );
+        assert!(transaction.transport_error().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_transaction() {
+        let clock = Arc::new(ManualClock::new());
+        let ack = Request::try_from(INVITE.replace("INVITE", "ACK").as_str()).unwrap();
+        assert!(matches!(
+            ClientTransaction::new(ack, false, TimerSettings::default(), clock.clone()),
+            Err(SipError::InvalidTransaction(_))
+        ));
+        let request =
+            Request::try_from(INVITE.replace("z9hG4bKnashds8", "776asdhds").as_str()).unwrap();
+        assert!(ClientTransaction::new(request, false, TimerSettings::default(), clock).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! Clocks giving the current time to the transactions.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A source of the current time, used to run the timers of the transactions.
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    /// Get the current time.
+    fn now(&self) -> Instant;
+}
+
+/// A clock giving the time of the system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock whose time only changes when it is advanced, to test the timers deterministically.
+///
+/// The clones of a manual clock share the same time.
+///
+/// # Example
+///
+/// ```
+/// use imersio_sip::transaction::{Clock, ManualClock};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(1));
+/// assert_eq!(clock.now() - start, Duration::from_secs(1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// Create a manual clock, starting at the current time of the system.
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Advance the time of the clock by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
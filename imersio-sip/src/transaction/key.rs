@@ -0,0 +1,195 @@
+//! Identification of the transactions the messages belong to.
+
+use crate::transaction::{cseq, top_via};
+use crate::{Host, Method, Request, Response, SipError};
+
+/// The value starting the branch parameters generated according to RFC 3261.
+///
+/// [[RFC3261, Section 8.1.1.7](https://datatracker.ietf.org/doc/html/rfc3261#section-8.1.1.7)]
+pub(crate) const MAGIC_COOKIE: &str = "z9hG4bK";
+
+/// The key identifying a transaction, used to match the messages with the transaction they
+/// belong to.
+///
+/// Only the branch parameters generated according to RFC 3261 are supported, the matching rules
+/// of RFC 2543 are not.
+///
+/// [[RFC3261, Section 17.1.3](https://datatracker.ietf.org/doc/html/rfc3261#section-17.1.3)]
+/// [[RFC3261, Section 17.2.3](https://datatracker.ietf.org/doc/html/rfc3261#section-17.2.3)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TransactionKey {
+    branch: String,
+    sent_by: Option<(Host, Option<u16>)>,
+    method: Method,
+}
+
+impl TransactionKey {
+    /// Get the key of the client transaction sending the given request.
+    pub fn client(request: &Request) -> Result<Self, SipError> {
+        Ok(Self {
+            branch: branch(request.headers())
+                .ok_or_else(|| SipError::InvalidRequest(no_branch()))?,
+            sent_by: None,
+            method: request.method().clone(),
+        })
+    }
+
+    /// Get the key of the client transaction the given received response belongs to, that is
+    /// the branch of its top Via and the method of its CSeq.
+    pub fn response(response: &Response) -> Result<Self, SipError> {
+        let branch =
+            branch(response.headers()).ok_or_else(|| SipError::InvalidResponse(no_branch()))?;
+        let method = cseq(response.headers())
+            .ok_or_else(|| SipError::InvalidResponse("Response without CSeq header".to_string()))?
+            .method()
+            .clone();
+        Ok(Self {
+            branch,
+            sent_by: None,
+            method,
+        })
+    }
+
+    /// Get the key of the server transaction the given received request belongs to, that is
+    /// the branch and the sent-by of its top Via and its method, an ACK belonging to the INVITE
+    /// transaction it acknowledges.
+    pub fn server(request: &Request) -> Result<Self, SipError> {
+        let via = top_via(request.headers())
+            .ok_or_else(|| SipError::InvalidRequest("Request without Via header".to_string()))?;
+        let method = match request.method() {
+            Method::Ack => Method::Invite,
+            method => method.clone(),
+        };
+        Ok(Self {
+            branch: branch(request.headers())
+                .ok_or_else(|| SipError::InvalidRequest(no_branch()))?,
+            sent_by: Some((via.host().clone(), via.port())),
+            method,
+        })
+    }
+
+    /// Get the key of the INVITE server transaction cancelled by the given received CANCEL
+    /// request, that has the same branch and sent-by in its top Via.
+    ///
+    /// [[RFC3261, Section 9.2](https://datatracker.ietf.org/doc/html/rfc3261#section-9.2)]
+    pub fn cancelled(cancel: &Request) -> Result<Self, SipError> {
+        if cancel.method() != &Method::Cancel {
+            return Err(SipError::InvalidRequest(format!(
+                "{} request is not a CANCEL",
+                cancel.method()
+            )));
+        }
+        Ok(Self {
+            method: Method::Invite,
+            ..Self::server(cancel)?
+        })
+    }
+
+    /// Get the branch parameter of the transaction.
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    /// Get the method of the transaction.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+}
+
+/// Get the branch of the top Via, if it has been generated according to RFC 3261.
+fn branch(headers: &[crate::Header]) -> Option<String> {
+    top_via(headers)?
+        .branch()
+        .filter(|branch| branch.starts_with(MAGIC_COOKIE) && branch.len() > MAGIC_COOKIE.len())
+}
+
+fn no_branch() -> String {
+    format!("No branch starting with `{MAGIC_COOKIE}` in the top Via")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, branch: &str, sent_by: &str) -> Request {
+        Request::try_from(
+            format!(
+                "{method} sip:bob@biloxi.com SIP/2.0\r\n\
+                 Via: SIP/2.0/UDP {sent_by};branch={branch}\r\n\
+                 Via: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+                 CSeq: 1 {method}\r\n\
+                 Content-Length: 0\r\n\
+                 \r\n"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_client_key() {
+        let invite = request("INVITE", "z9hG4bKnashds8", "pc33.atlanta.com");
+        let response = Response::try_from(
+            "SIP/2.0 180 Ringing\r\n\
+             Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8;received=192.0.2.1\r\n\
+             CSeq: 1 INVITE\r\n\
+             Content-Length: 0\r\n\
+             \r\n",
+        )
+        .unwrap();
+        let key = TransactionKey::client(&invite).unwrap();
+        assert_eq!(key.branch(), "z9hG4bKnashds8");
+        assert_eq!(key.method(), &Method::Invite);
+        assert_eq!(TransactionKey::response(&response).unwrap(), key);
+
+        let cancel = request("CANCEL", "z9hG4bKnashds8", "pc33.atlanta.com");
+        assert_ne!(TransactionKey::client(&cancel).unwrap(), key);
+    }
+
+    #[test]
+    fn test_server_key() {
+        let key = TransactionKey::server(&request("INVITE", "z9hG4bKnashds8", "pc33.atlanta.com"))
+            .unwrap();
+        assert_eq!(
+            TransactionKey::server(&request("ACK", "z9hG4bKnashds8", "PC33.atlanta.com")).unwrap(),
+            key
+        );
+        assert_ne!(
+            TransactionKey::server(&request("ACK", "z9hG4bKnashds8", "pc33.atlanta.com:5070"))
+                .unwrap(),
+            key
+        );
+        assert_ne!(
+            TransactionKey::server(&request("CANCEL", "z9hG4bKnashds8", "pc33.atlanta.com"))
+                .unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn test_cancelled_key() {
+        let key = TransactionKey::server(&request("INVITE", "z9hG4bKnashds8", "pc33.atlanta.com"))
+            .unwrap();
+        let cancel = request("CANCEL", "z9hG4bKnashds8", "pc33.atlanta.com");
+        assert_eq!(TransactionKey::cancelled(&cancel).unwrap(), key);
+        assert_ne!(TransactionKey::server(&cancel).unwrap(), key);
+        assert!(
+            TransactionKey::cancelled(&request("INVITE", "z9hG4bKnashds8", "pc33.atlanta.com"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_branch() {
+        assert!(TransactionKey::server(&request("INVITE", "z9hG4bK", "pc33.atlanta.com")).is_err());
+        assert!(TransactionKey::client(&request("INVITE", "1234", "pc33.atlanta.com")).is_err());
+        let response = Response::try_from(
+            "SIP/2.0 200 OK\r\n\
+             Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+             Content-Length: 0\r\n\
+             \r\n",
+        )
+        .unwrap();
+        assert!(TransactionKey::response(&response).is_err());
+    }
+}
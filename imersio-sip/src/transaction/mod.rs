@@ -0,0 +1,135 @@
+//! SIP transactions, absorbing the retransmissions of the requests and responses.
+//!
+//! The transactions do not perform any I/O. They are fed with the messages received by the
+//! transport layer, with the transport errors and with the expiration of their timers, and they
+//! return the `TransactionEvent`s telling the messages to send and the ones to pass to the
+//! transaction user. The current time is read from a `Clock`, that can be a `ManualClock` to test
+//! the timers deterministically.
+//!
+//! The INVITE transactions handle the 2xx responses as updated by RFC 6026: they stay in the
+//! Accepted state to let the transaction user handle the retransmissions of the 2xx responses and
+//! of their ACKs.
+//!
+//! [[RFC3261, Section 17](https://datatracker.ietf.org/doc/html/rfc3261#section-17)]
+//! [[RFC6026, Section 7](https://datatracker.ietf.org/doc/html/rfc6026#section-7)]
+//!
+//! # Examples
+//!
+//! ```
+//! use imersio_sip::Request;
+//! use imersio_sip::transaction::{
+//!     ClientTransaction, ManualClock, TimerSettings, TransactionEvent, TransactionState,
+//! };
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let request = Request::try_from(
+//!     "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+//!     Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+//!     From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+//!     To: Carol <sip:carol@chicago.com>\r\n\
+//!     Call-ID: a84b4c76e66710\r\n\
+//!     CSeq: 63104 OPTIONS\r\n\
+//!     Content-Length: 0\r\n\
+//!     \r\n",
+//! )
+//! .unwrap();
+//! let clock = ManualClock::new();
+//! let mut transaction = ClientTransaction::new(
+//!     request.clone(),
+//!     false,
+//!     TimerSettings::default(),
+//!     Arc::new(clock.clone()),
+//! )
+//! .unwrap();
+//! assert_eq!(
+//!     transaction.start(),
+//!     vec![TransactionEvent::SendRequest(request.clone())]
+//! );
+//!
+//! // The request is retransmitted after T1.
+//! clock.advance(Duration::from_millis(500));
+//! assert_eq!(
+//!     transaction.handle_timers(),
+//!     vec![TransactionEvent::SendRequest(request)]
+//! );
+//!
+//! // The transaction times out after 64*T1 without response.
+//! clock.advance(Duration::from_secs(32));
+//! let events = transaction.handle_timers();
+//! assert!(events.contains(&TransactionEvent::Timeout));
+//! assert_eq!(transaction.state(), TransactionState::Terminated);
+//! ```
+
+mod client;
+mod clock;
+mod key;
+mod server;
+mod timer;
+
+pub use client::ClientTransaction;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use key::TransactionKey;
+pub use server::ServerTransaction;
+pub use timer::{Timer, TimerSettings};
+
+use crate::{CSeqHeader, Header, Request, Response, Via};
+
+/// Representation of the state of a transaction.
+///
+/// [[RFC3261, Section 17](https://datatracker.ietf.org/doc/html/rfc3261#section-17)]
+/// [[RFC6026, Section 7](https://datatracker.ietf.org/doc/html/rfc6026#section-7)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, derive_more::Display)]
+pub enum TransactionState {
+    /// An INVITE client transaction waiting for a response.
+    Calling,
+    /// A non-INVITE transaction waiting for a response.
+    Trying,
+    /// A provisional response has been received or sent.
+    Proceeding,
+    /// A final response, that is not a 2xx response to an INVITE, has been received or sent.
+    Completed,
+    /// The ACK of a final response to an INVITE has been received.
+    Confirmed,
+    /// A 2xx response to an INVITE has been received or sent.
+    Accepted,
+    /// The transaction is over and can be removed.
+    Terminated,
+}
+
+/// Representation of an event produced by a transaction, that is an action for its owner.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TransactionEvent {
+    /// A request to send with the transport: the request of a client transaction, one of its
+    /// retransmissions, or the ACK of a non-2xx final response to an INVITE.
+    SendRequest(Request),
+    /// A response to send with the transport, or to retransmit.
+    SendResponse(Response),
+    /// A response received by a client transaction, to pass to the transaction user.
+    Response(Response),
+    /// An ACK of a 2xx response received by an INVITE server transaction, to pass to the
+    /// transaction user.
+    Ack(Request),
+    /// No final response has been received, or no ACK of a final response to an INVITE.
+    Timeout,
+    /// The transport failed to send a message of the transaction.
+    TransportError,
+    /// The transaction is over and can be removed.
+    Terminated,
+}
+
+/// Get the top Via of the given headers.
+pub(crate) fn top_via(headers: &[Header]) -> Option<&Via> {
+    headers.iter().find_map(|header| match header {
+        Header::Via(header) => header.vias().first(),
+        _ => None,
+    })
+}
+
+/// Get the CSeq header among the given headers.
+pub(crate) fn cseq(headers: &[Header]) -> Option<&CSeqHeader> {
+    headers.iter().find_map(|header| match header {
+        Header::CSeq(header) => Some(header),
+        _ => None,
+    })
+}
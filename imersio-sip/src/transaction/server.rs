@@ -0,0 +1,526 @@
+//! Server transactions, receiving a request and sending its responses.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::transaction::timer::Timers;
+use crate::transaction::{
+    Clock, Timer, TimerSettings, TransactionEvent, TransactionKey, TransactionState,
+};
+use crate::{Method, Reason, Request, Response, SipError};
+
+/// Representation of a server transaction, INVITE or non-INVITE depending on the method of its
+/// request.
+///
+/// The transaction is started with `start`, then fed with the retransmissions of its request,
+/// the ACKs, the responses of the transaction user, the transport errors and the expiration of
+/// its timers. Each of these returns the events to handle by the owner of the transaction.
+///
+/// [[RFC3261, Section 17.2](https://datatracker.ietf.org/doc/html/rfc3261#section-17.2)]
+/// [[RFC6026, Section 7.1](https://datatracker.ietf.org/doc/html/rfc6026#section-7.1)]
+#[derive(Debug)]
+pub struct ServerTransaction {
+    key: TransactionKey,
+    request: Request,
+    reliable: bool,
+    settings: TimerSettings,
+    clock: Arc<dyn Clock>,
+    state: TransactionState,
+    started: bool,
+    timers: Timers,
+    retransmit_interval: Duration,
+    last_response: Option<Response>,
+}
+
+impl ServerTransaction {
+    /// Create a server transaction for the given received request, received over a reliable
+    /// transport or not.
+    ///
+    /// The top Via of the request must have a branch generated according to RFC 3261. An ACK
+    /// does not create a transaction.
+    pub fn new(
+        request: Request,
+        reliable: bool,
+        settings: TimerSettings,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, SipError> {
+        if request.method() == &Method::Ack {
+            return Err(SipError::InvalidTransaction(
+                "An ACK does not create a server transaction".to_string(),
+            ));
+        }
+        let key = TransactionKey::server(&request)?;
+        let state = if request.method() == &Method::Invite {
+            TransactionState::Proceeding
+        } else {
+            TransactionState::Trying
+        };
+        Ok(Self {
+            key,
+            request,
+            reliable,
+            settings,
+            clock,
+            state,
+            started: false,
+            timers: Timers::default(),
+            retransmit_interval: settings.t1(),
+            last_response: None,
+        })
+    }
+
+    /// Get the key identifying the transaction.
+    pub fn key(&self) -> &TransactionKey {
+        &self.key
+    }
+
+    /// Get the request of the transaction.
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// Get the current state of the transaction.
+    pub fn state(&self) -> TransactionState {
+        self.state
+    }
+
+    /// Tell whether the transaction is an INVITE server transaction.
+    pub fn is_invite(&self) -> bool {
+        self.request.method() == &Method::Invite
+    }
+
+    /// Tell whether the transaction is over and can be removed.
+    pub fn is_terminated(&self) -> bool {
+        self.state == TransactionState::Terminated
+    }
+
+    /// Start the transaction. An INVITE server transaction sends a 100 Trying response at once,
+    /// to quench the retransmissions of the INVITE.
+    ///
+    /// Starting an already started transaction does nothing.
+    ///
+    /// [[RFC3261, Section 17.2.1](https://datatracker.ietf.org/doc/html/rfc3261#section-17.2.1)]
+    pub fn start(&mut self) -> Vec<TransactionEvent> {
+        if self.started || !self.is_invite() {
+            return vec![];
+        }
+        self.started = true;
+        let trying = Response::from_request(&self.request, Reason::TRYING);
+        self.last_response = Some(trying.clone());
+        vec![TransactionEvent::SendResponse(trying)]
+    }
+
+    /// Get the time at which the next timer of the transaction expires, if any.
+    pub fn next_timeout(&self) -> Option<std::time::Instant> {
+        self.timers.next()
+    }
+
+    /// Handle the timers of the transaction that expired.
+    pub fn handle_timers(&mut self) -> Vec<TransactionEvent> {
+        let mut events = vec![];
+        while let Some(timer) = self.timers.pop_expired(self.clock.now()) {
+            events.extend(self.handle_timer(timer));
+        }
+        events
+    }
+
+    /// Handle a request received for the transaction, that is a retransmission of its request
+    /// or an ACK of a final response to its INVITE.
+    ///
+    /// The retransmissions are absorbed, resending the last response if any. The ACKs of the
+    /// 2xx responses are passed to the transaction user.
+    pub fn receive_request(&mut self, request: Request) -> Vec<TransactionEvent> {
+        if request.method() == &Method::Ack {
+            return match self.state {
+                TransactionState::Completed if self.is_invite() => {
+                    self.state = TransactionState::Confirmed;
+                    self.timers.clear();
+                    self.wait(Timer::I)
+                }
+                TransactionState::Accepted => vec![TransactionEvent::Ack(request)],
+                _ => vec![],
+            };
+        }
+        match self.state {
+            TransactionState::Proceeding | TransactionState::Completed => self
+                .last_response
+                .clone()
+                .map(TransactionEvent::SendResponse)
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Send a response of the transaction user for the request of the transaction.
+    ///
+    /// Fails if the transaction is not in a state allowing to send the response, for example
+    /// after a final response has been sent.
+    pub fn send_response(&mut self, response: Response) -> Result<Vec<TransactionEvent>, SipError> {
+        let reason = response.reason().clone();
+        let mut events = vec![TransactionEvent::SendResponse(response.clone())];
+        match self.state {
+            TransactionState::Trying | TransactionState::Proceeding if reason.is_provisional() => {
+                self.state = TransactionState::Proceeding;
+            }
+            TransactionState::Proceeding if self.is_invite() && reason.is_success() => {
+                self.state = TransactionState::Accepted;
+                self.start_timer(Timer::L, self.duration(Timer::L));
+            }
+            TransactionState::Proceeding if self.is_invite() && reason.is_final() => {
+                self.state = TransactionState::Completed;
+                if !self.reliable {
+                    self.start_timer(Timer::G, self.retransmit_interval);
+                }
+                self.start_timer(Timer::H, self.duration(Timer::H));
+            }
+            TransactionState::Trying | TransactionState::Proceeding
+                if !self.is_invite() && reason.is_final() =>
+            {
+                self.state = TransactionState::Completed;
+                events.extend(self.wait(Timer::J));
+            }
+            // The retransmissions of the 2xx responses are sent by the transaction user.
+            TransactionState::Accepted if reason.is_success() => {}
+            state => {
+                return Err(SipError::InvalidTransaction(format!(
+                    "Cannot send a {} response in the {} state",
+                    reason.status().code(),
+                    state
+                )));
+            }
+        }
+        self.last_response = Some(response);
+        Ok(events)
+    }
+
+    /// Handle the failure of the transport to send a response of the transaction.
+    pub fn transport_error(&mut self) -> Vec<TransactionEvent> {
+        if self.is_terminated() {
+            return vec![];
+        }
+        let mut events = vec![TransactionEvent::TransportError];
+        events.extend(self.terminate());
+        events
+    }
+
+    fn handle_timer(&mut self, timer: Timer) -> Vec<TransactionEvent> {
+        match (timer, self.state) {
+            (Timer::G, TransactionState::Completed) => {
+                self.retransmit_interval = (self.retransmit_interval * 2).min(self.settings.t2());
+                self.start_timer(Timer::G, self.retransmit_interval);
+                self.last_response
+                    .clone()
+                    .map(TransactionEvent::SendResponse)
+                    .into_iter()
+                    .collect()
+            }
+            (Timer::H, TransactionState::Completed) => {
+                let mut events = vec![TransactionEvent::Timeout];
+                events.extend(self.terminate());
+                events
+            }
+            (Timer::I, TransactionState::Confirmed)
+            | (Timer::J, TransactionState::Completed)
+            | (Timer::L, TransactionState::Accepted) => self.terminate(),
+            _ => vec![],
+        }
+    }
+
+    /// Start a wait timer, terminating the transaction at once if its duration is zero.
+    fn wait(&mut self, timer: Timer) -> Vec<TransactionEvent> {
+        let duration = self.duration(timer);
+        if duration.is_zero() {
+            self.terminate()
+        } else {
+            self.start_timer(timer, duration);
+            vec![]
+        }
+    }
+
+    fn terminate(&mut self) -> Vec<TransactionEvent> {
+        self.state = TransactionState::Terminated;
+        self.timers.clear();
+        vec![TransactionEvent::Terminated]
+    }
+
+    fn duration(&self, timer: Timer) -> Duration {
+        self.settings.duration(timer, self.reliable)
+    }
+
+    fn start_timer(&mut self, timer: Timer, duration: Duration) {
+        self.timers.start(timer, self.clock.now() + duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::ManualClock;
+
+    const INVITE: &str = "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    const REGISTER: &str = "REGISTER sip:registrar.biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.com:5060;branch=z9hG4bKnashds7\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Bob <sip:bob@biloxi.com>;tag=456248\r\n\
+Call-ID: 843817637684230@998sdasdh09\r\n\
+CSeq: 1826 REGISTER\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    fn response(code: u16, method: &str) -> Response {
+        Response::try_from(
+            format!(
+                "SIP/2.0 {code} Whatever\r\n\
+                 Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+                 To: Bob <sip:bob@biloxi.com>;tag=a6c85cf\r\n\
+                 From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+                 Call-ID: a84b4c76e66710\r\n\
+                 CSeq: 314159 {method}\r\n\
+                 Content-Length: 0\r\n\
+                 \r\n"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    fn ack() -> Request {
+        Request::try_from(
+            "ACK sip:bob@biloxi.com SIP/2.0\r\n\
+             Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+             To: Bob <sip:bob@biloxi.com>;tag=a6c85cf\r\n\
+             From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+             Call-ID: a84b4c76e66710\r\n\
+             CSeq: 314159 ACK\r\n\
+             Content-Length: 0\r\n\
+             \r\n",
+        )
+        .unwrap()
+    }
+
+    fn transaction(request: &str, reliable: bool) -> (ServerTransaction, ManualClock) {
+        let clock = ManualClock::new();
+        let transaction = ServerTransaction::new(
+            Request::try_from(request).unwrap(),
+            reliable,
+            TimerSettings::default(),
+            Arc::new(clock.clone()),
+        )
+        .unwrap();
+        (transaction, clock)
+    }
+
+    #[test]
+    fn test_invite_trying() {
+        let (mut transaction, _) = transaction(INVITE, false);
+        assert_eq!(transaction.state(), TransactionState::Proceeding);
+        let events = transaction.start();
+        let [TransactionEvent::SendResponse(trying)] = events.as_slice() else {
+            panic!("No 100 Trying sent");
+        };
+        assert_eq!(
+            String::from_utf8(trying.to_bytes()).unwrap(),
+            "SIP/2.0 100 Trying\r\n\
+             Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+             To: Bob <sip:bob@biloxi.com>\r\n\
+             From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+             Call-ID: a84b4c76e66710\r\n\
+             CSeq: 314159 INVITE\r\n\
+             Content-Length: 0\r\n\
+             \r\n"
+        );
+        assert!(transaction.start().is_empty());
+
+        // A retransmission of the INVITE resends the last provisional response.
+        let invite = Request::try_from(INVITE).unwrap();
+        assert_eq!(
+            transaction.receive_request(invite.clone()),
+            vec![TransactionEvent::SendResponse(trying.clone())]
+        );
+        transaction.send_response(response(180, "INVITE")).unwrap();
+        assert_eq!(
+            transaction.receive_request(invite),
+            vec![TransactionEvent::SendResponse(response(180, "INVITE"))]
+        );
+    }
+
+    #[test]
+    fn test_invite_non_2xx_final_response() {
+        let (mut transaction, clock) = transaction(INVITE, false);
+        transaction.start();
+        assert_eq!(
+            transaction.send_response(response(486, "INVITE")).unwrap(),
+            vec![TransactionEvent::SendResponse(response(486, "INVITE"))]
+        );
+        assert_eq!(transaction.state(), TransactionState::Completed);
+
+        // Timer G fires after 0.5s, 1s, 2s, 4s then every T2.
+        for interval in [500, 1000, 2000, 4000, 4000] {
+            clock.advance(Duration::from_millis(interval));
+            assert_eq!(
+                transaction.handle_timers(),
+                vec![TransactionEvent::SendResponse(response(486, "INVITE"))]
+            );
+        }
+
+        // The ACK stops the retransmissions and Timer I absorbs its retransmissions.
+        assert!(transaction.receive_request(ack()).is_empty());
+        assert_eq!(transaction.state(), TransactionState::Confirmed);
+        assert!(transaction.receive_request(ack()).is_empty());
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_invite_ack_timeout() {
+        let (mut transaction, clock) = transaction(INVITE, true);
+        transaction.start();
+        transaction.send_response(response(486, "INVITE")).unwrap();
+        clock.advance(Duration::from_secs(31));
+        assert!(transaction.handle_timers().is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Timeout, TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_invite_reliable_ack() {
+        let (mut transaction, _) = transaction(INVITE, true);
+        transaction.send_response(response(486, "INVITE")).unwrap();
+        assert_eq!(
+            transaction.receive_request(ack()),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_invite_2xx_response() {
+        let (mut transaction, clock) = transaction(INVITE, false);
+        transaction.start();
+        transaction.send_response(response(200, "INVITE")).unwrap();
+        assert_eq!(transaction.state(), TransactionState::Accepted);
+        assert_eq!(
+            transaction.next_timeout(),
+            Some(clock.now() + Duration::from_secs(32))
+        );
+
+        // The 2xx retransmissions come from the transaction user, the ACKs are passed to it.
+        assert_eq!(
+            transaction.send_response(response(200, "INVITE")).unwrap(),
+            vec![TransactionEvent::SendResponse(response(200, "INVITE"))]
+        );
+        assert!(
+            transaction
+                .receive_request(Request::try_from(INVITE).unwrap())
+                .is_empty()
+        );
+        assert_eq!(
+            transaction.receive_request(ack()),
+            vec![TransactionEvent::Ack(ack())]
+        );
+        assert!(matches!(
+            transaction.send_response(response(486, "INVITE")),
+            Err(SipError::InvalidTransaction(_))
+        ));
+
+        clock.advance(Duration::from_secs(32));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_non_invite_transaction() {
+        let (mut transaction, clock) = transaction(REGISTER, false);
+        assert!(transaction.start().is_empty());
+        assert_eq!(transaction.state(), TransactionState::Trying);
+
+        // The retransmissions are absorbed while no response has been sent.
+        let register = Request::try_from(REGISTER).unwrap();
+        assert!(transaction.receive_request(register.clone()).is_empty());
+
+        transaction
+            .send_response(response(100, "REGISTER"))
+            .unwrap();
+        assert_eq!(transaction.state(), TransactionState::Proceeding);
+        transaction
+            .send_response(response(200, "REGISTER"))
+            .unwrap();
+        assert_eq!(transaction.state(), TransactionState::Completed);
+        assert_eq!(
+            transaction.receive_request(register),
+            vec![TransactionEvent::SendResponse(response(200, "REGISTER"))]
+        );
+        assert!(
+            transaction
+                .send_response(response(200, "REGISTER"))
+                .is_err()
+        );
+
+        // Timer J fires after 64*T1.
+        clock.advance(Duration::from_secs(32));
+        assert_eq!(
+            transaction.handle_timers(),
+            vec![TransactionEvent::Terminated]
+        );
+    }
+
+    #[test]
+    fn test_non_invite_reliable() {
+        let (mut transaction, _) = transaction(REGISTER, true);
+        assert_eq!(
+            transaction
+                .send_response(response(200, "REGISTER"))
+                .unwrap(),
+            vec![
+                TransactionEvent::SendResponse(response(200, "REGISTER")),
+                TransactionEvent::Terminated
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transport_error() {
+        let (mut transaction, _) = transaction(INVITE, false);
+        transaction.start();
+        assert_eq!(
+            transaction.transport_error(),
+            vec![
+                TransactionEvent::TransportError,
+                TransactionEvent::Terminated
+            ]
+        );
+        assert!(transaction.transport_error().is_empty());
+        assert!(transaction.send_response(response(180, "INVITE")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_transaction() {
+        assert!(matches!(
+            ServerTransaction::new(
+                ack(),
+                false,
+                TimerSettings::default(),
+                Arc::new(ManualClock::new())
+            ),
+            Err(SipError::InvalidTransaction(_))
+        ));
+    }
+}
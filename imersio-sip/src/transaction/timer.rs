@@ -0,0 +1,210 @@
+//! Timers of the transactions.
+
+use std::time::{Duration, Instant};
+
+/// The timers of the transactions.
+///
+/// [[RFC3261, Appendix A](https://datatracker.ietf.org/doc/html/rfc3261#appendix-A)]
+/// [[RFC6026, Section 8.11](https://datatracker.ietf.org/doc/html/rfc6026#section-8.11)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::Display)]
+pub enum Timer {
+    /// Retransmission of the request of an INVITE client transaction.
+    A,
+    /// Timeout of an INVITE client transaction.
+    B,
+    /// Wait time for the retransmissions of the final response of an INVITE client transaction.
+    D,
+    /// Retransmission of the request of a non-INVITE client transaction.
+    E,
+    /// Timeout of a non-INVITE client transaction.
+    F,
+    /// Retransmission of the final response of an INVITE server transaction.
+    G,
+    /// Wait time for the ACK of the final response of an INVITE server transaction.
+    H,
+    /// Wait time for the retransmissions of the ACK received by an INVITE server transaction.
+    I,
+    /// Wait time for the retransmissions of the request of a non-INVITE server transaction.
+    J,
+    /// Wait time for the retransmissions of the final response of a non-INVITE client
+    /// transaction.
+    K,
+    /// Wait time for the retransmissions of the INVITE of an accepted INVITE server transaction.
+    L,
+    /// Wait time for the retransmissions of the 2xx response of an accepted INVITE client
+    /// transaction.
+    M,
+}
+
+/// The timer values of the transactions, derived from T1, T2 and T4.
+///
+/// [[RFC3261, Section 17.1.1.1](https://datatracker.ietf.org/doc/html/rfc3261#section-17.1.1.1)]
+/// [[RFC3261, Appendix A](https://datatracker.ietf.org/doc/html/rfc3261#appendix-A)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimerSettings {
+    t1: Duration,
+    t2: Duration,
+    t4: Duration,
+}
+
+impl TimerSettings {
+    /// Default estimate of the round-trip time.
+    pub const DEFAULT_T1: Duration = Duration::from_millis(500);
+    /// Default maximum retransmission interval of the non-INVITE requests and of the INVITE
+    /// responses.
+    pub const DEFAULT_T2: Duration = Duration::from_secs(4);
+    /// Default maximum duration a message remains in the network.
+    pub const DEFAULT_T4: Duration = Duration::from_secs(5);
+
+    /// Wait time for the response retransmissions of an INVITE client transaction over an
+    /// unreliable transport.
+    const TIMER_D: Duration = Duration::from_secs(32);
+
+    /// Create timer settings from the given T1, T2 and T4 values.
+    pub fn new(t1: Duration, t2: Duration, t4: Duration) -> Self {
+        Self { t1, t2, t4 }
+    }
+
+    /// Get the estimate of the round-trip time.
+    pub fn t1(&self) -> Duration {
+        self.t1
+    }
+
+    /// Get the maximum retransmission interval of the non-INVITE requests and of the INVITE
+    /// responses.
+    pub fn t2(&self) -> Duration {
+        self.t2
+    }
+
+    /// Get the maximum duration a message remains in the network.
+    pub fn t4(&self) -> Duration {
+        self.t4
+    }
+
+    /// Get the initial duration of the given timer, depending on whether the transport is
+    /// reliable.
+    ///
+    /// The retransmission timers are not used over reliable transports, and the wait times for
+    /// the retransmissions are zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use imersio_sip::transaction::{Timer, TimerSettings};
+    /// use std::time::Duration;
+    ///
+    /// let settings = TimerSettings::default();
+    /// assert_eq!(settings.duration(Timer::B, false), Duration::from_secs(32));
+    /// assert_eq!(settings.duration(Timer::K, false), Duration::from_secs(5));
+    /// assert_eq!(settings.duration(Timer::K, true), Duration::ZERO);
+    /// ```
+    pub fn duration(&self, timer: Timer, reliable: bool) -> Duration {
+        match timer {
+            Timer::A | Timer::E | Timer::G => self.t1,
+            Timer::B | Timer::F | Timer::H | Timer::L | Timer::M => 64 * self.t1,
+            Timer::D if reliable => Duration::ZERO,
+            Timer::D => Self::TIMER_D.max(64 * self.t1),
+            Timer::I | Timer::K if reliable => Duration::ZERO,
+            Timer::I | Timer::K => self.t4,
+            Timer::J if reliable => Duration::ZERO,
+            Timer::J => 64 * self.t1,
+        }
+    }
+}
+
+impl Default for TimerSettings {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_T1, Self::DEFAULT_T2, Self::DEFAULT_T4)
+    }
+}
+
+/// The running timers of a transaction, with their expiration time.
+#[derive(Debug, Default)]
+pub(crate) struct Timers(Vec<(Timer, Instant)>);
+
+impl Timers {
+    /// Start a timer expiring at the given time, restarting it if it is already running.
+    pub(crate) fn start(&mut self, timer: Timer, expires_at: Instant) {
+        self.stop(timer);
+        self.0.push((timer, expires_at));
+    }
+
+    /// Stop a timer.
+    pub(crate) fn stop(&mut self, timer: Timer) {
+        self.0.retain(|(running, _)| *running != timer);
+    }
+
+    /// Stop all the timers.
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Get the time at which the next timer expires.
+    pub(crate) fn next(&self) -> Option<Instant> {
+        self.0.iter().map(|(_, expires_at)| *expires_at).min()
+    }
+
+    /// Remove the timer that expired first at the given time, if any.
+    pub(crate) fn pop_expired(&mut self, now: Instant) -> Option<Timer> {
+        let index = self
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, expires_at))| *expires_at <= now)
+            .min_by_key(|(_, (_, expires_at))| *expires_at)
+            .map(|(index, _)| index)?;
+        Some(self.0.remove(index).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_durations() {
+        let settings = TimerSettings::default();
+        assert_eq!(
+            settings.duration(Timer::A, false),
+            Duration::from_millis(500)
+        );
+        assert_eq!(settings.duration(Timer::D, false), Duration::from_secs(32));
+        assert_eq!(settings.duration(Timer::D, true), Duration::ZERO);
+        assert_eq!(settings.duration(Timer::H, true), Duration::from_secs(32));
+        assert_eq!(settings.duration(Timer::I, false), Duration::from_secs(5));
+        assert_eq!(settings.duration(Timer::J, false), Duration::from_secs(32));
+        assert_eq!(settings.duration(Timer::J, true), Duration::ZERO);
+
+        let settings = TimerSettings::new(
+            Duration::from_secs(1),
+            Duration::from_secs(8),
+            Duration::from_secs(10),
+        );
+        assert_eq!(settings.duration(Timer::D, false), Duration::from_secs(64));
+        assert_eq!(settings.duration(Timer::M, true), Duration::from_secs(64));
+    }
+
+    #[test]
+    fn test_timers() {
+        let now = Instant::now();
+        let mut timers = Timers::default();
+        assert_eq!(timers.next(), None);
+        timers.start(Timer::B, now + Duration::from_secs(32));
+        timers.start(Timer::A, now + Duration::from_millis(500));
+        assert_eq!(timers.next(), Some(now + Duration::from_millis(500)));
+        assert_eq!(timers.pop_expired(now), None);
+        timers.start(Timer::A, now + Duration::from_secs(1));
+        assert_eq!(
+            timers.pop_expired(now + Duration::from_secs(40)),
+            Some(Timer::A)
+        );
+        assert_eq!(
+            timers.pop_expired(now + Duration::from_secs(40)),
+            Some(Timer::B)
+        );
+        assert_eq!(timers.pop_expired(now + Duration::from_secs(40)), None);
+        timers.start(Timer::A, now);
+        timers.clear();
+        assert_eq!(timers.next(), None);
+    }
+}
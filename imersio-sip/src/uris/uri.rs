@@ -119,6 +119,44 @@ impl Uri {
             Uri::Absolute(_) => None,
         }
     }
+    /// Get the telephone number designated by the URI, in the canonical form used by STIR.
+    ///
+    /// It is the number of a tel URI, or the user part of a SIP URI having the `user=phone`
+    /// parameter or only containing a telephone number. Visual separators and the leading `+`
+    /// are removed.
+    ///
+    /// [[RFC8224, Section 8.3](https://datatracker.ietf.org/doc/html/rfc8224#section-8.3)]
+    pub fn telephone_number(&self) -> Option<String> {
+        let number = match self {
+            Uri::Sip(uri) => {
+                let user = uri.userinfo()?.user();
+                let user = user.split(';').next().unwrap_or_default();
+                if self.parameter("user").is_some_and(|user| user == "phone")
+                    || user.starts_with('+')
+                    || user.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    user
+                } else {
+                    return None;
+                }
+            }
+            Uri::Absolute(uri) if uri.scheme().as_str().eq_ignore_ascii_case("tel") => {
+                uri.opaque_part().split(';').next().unwrap_or_default()
+            }
+            Uri::Absolute(_) => return None,
+        };
+        let number = number
+            .strip_prefix('+')
+            .unwrap_or(number)
+            .chars()
+            .filter(|c| !matches!(c, '-' | '.' | '(' | ')'))
+            .collect::<String>();
+        (!number.is_empty()
+            && number
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '*' | '#')))
+        .then_some(number)
+    }
 }
 
 impl std::fmt::Display for Uri {
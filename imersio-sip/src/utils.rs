@@ -26,3 +26,9 @@ where
     let second_values: HashSet<_> = second.into_iter().collect();
     first_values == second_values
 }
+
+/// Compare two byte strings in a time not depending on the position of their first difference,
+/// not to leak the expected value of a secret through the time taken to reject a guess.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
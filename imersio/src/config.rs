@@ -1,9 +1,10 @@
+use crate::transport::{is_supported, wss};
 use crate::{DEFAULT_SIP_PORT, DEFAULT_SIPS_PORT};
 use imersio_sip::{Host, SipUri, Transport, UriScheme};
 use serde::Deserialize;
 use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -29,6 +30,18 @@ pub(crate) struct ProxyConfig {
     pub(crate) log_level: LogLevel,
     #[serde(default = "default_transports")]
     pub(crate) transports: HashSet<SipUri>,
+    #[serde(default = "default_max_connections")]
+    pub(crate) max_connections: usize,
+    #[serde(default = "default_connection_idle_timeout")]
+    pub(crate) connection_idle_timeout: u64,
+    #[serde(default)]
+    pub(crate) tls: Option<TlsConfig>,
+    #[serde(default = "default_timer_c")]
+    pub(crate) timer_c: u64,
+    #[serde(default)]
+    pub(crate) routes: Vec<RouteConfig>,
+    #[serde(default)]
+    pub(crate) registrar: RegistrarConfig,
 }
 
 fn default_transports() -> HashSet<SipUri> {
@@ -38,6 +51,143 @@ fn default_transports() -> HashSet<SipUri> {
     ])
 }
 
+fn default_max_connections() -> usize {
+    1024
+}
+
+fn default_connection_idle_timeout() -> u64 {
+    300
+}
+
+/// Timer C must be greater than 3 minutes.
+///
+/// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+fn default_timer_c() -> u64 {
+    200
+}
+
+/// A route of the proxy, giving the targets of the requests for a Request-URI.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RouteConfig {
+    /// The Request-URI matched by the route: its host, and its user if it has one.
+    pub(crate) uri: SipUri,
+    pub(crate) targets: Vec<TargetConfig>,
+    #[serde(default)]
+    pub(crate) forking: Forking,
+    /// Timer C of the route, instead of the one of the proxy.
+    #[serde(default)]
+    pub(crate) timer_c: Option<u64>,
+}
+
+impl RouteConfig {
+    /// Tell whether the route applies to a request with the given Request-URI.
+    pub(crate) fn matches(&self, uri: &SipUri) -> bool {
+        let user = |uri: &SipUri| uri.userinfo().map(|userinfo| userinfo.user().to_string());
+        self.uri.host() == uri.host() && (user(&self.uri).is_none() || user(&self.uri) == user(uri))
+    }
+}
+
+/// A target of a route, with its preference as in the `q` parameter of a Contact.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TargetConfig {
+    pub(crate) uri: SipUri,
+    #[serde(default)]
+    pub(crate) q: Option<f32>,
+}
+
+/// How the targets of a request are tried.
+///
+/// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase")]
+pub(crate) enum Forking {
+    /// All the targets are tried at once.
+    #[default]
+    Parallel,
+    /// The targets are tried one after the other by decreasing `q`, the targets with the same
+    /// `q` being tried at once.
+    Sequential,
+}
+
+/// The registrar of the proxy, accepting the REGISTER requests for its domains and for the
+/// addresses of the proxy.
+///
+/// [[RFC3261, Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3)]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RegistrarConfig {
+    #[serde(default)]
+    pub(crate) domains: Vec<String>,
+    /// Expiration interval of the bindings, in seconds, when the REGISTER request gives none.
+    #[serde(default = "default_expires")]
+    pub(crate) default_expires: u64,
+    /// Shortest expiration interval accepted, in seconds, shorter ones getting a 423 response.
+    #[serde(default = "default_min_expires")]
+    pub(crate) min_expires: u64,
+    /// Longest expiration interval granted, in seconds, longer ones being reduced.
+    #[serde(default = "default_max_expires")]
+    pub(crate) max_expires: u64,
+    #[serde(default)]
+    pub(crate) store: StoreConfig,
+}
+
+impl Default for RegistrarConfig {
+    fn default() -> Self {
+        Self {
+            domains: vec![],
+            default_expires: default_expires(),
+            min_expires: default_min_expires(),
+            max_expires: default_max_expires(),
+            store: StoreConfig::default(),
+        }
+    }
+}
+
+fn default_expires() -> u64 {
+    3600
+}
+
+fn default_min_expires() -> u64 {
+    60
+}
+
+fn default_max_expires() -> u64 {
+    86400
+}
+
+/// Where the registrar stores its bindings.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, tag = "type", rename_all = "lowercase")]
+pub(crate) enum StoreConfig {
+    /// The bindings are kept in memory, and lost when the proxy stops.
+    #[default]
+    Memory,
+    /// The bindings are kept in a file, and restored when the proxy starts.
+    File { path: PathBuf },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TlsConfig {
+    pub(crate) certificate: PathBuf,
+    pub(crate) private_key: PathBuf,
+    #[serde(default)]
+    pub(crate) ca_certificates: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) client_auth: ClientAuth,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase")]
+pub(crate) enum ClientAuth {
+    #[default]
+    None,
+    Optional,
+    Required,
+}
+
 #[derive(Debug, Default, Clone, Copy, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "lowercase")]
 pub(crate) enum LogLevel {
@@ -181,14 +331,19 @@ where
                     insert_transport(&mut result, sip_uri);
                 }
             },
-            UriScheme::Sips => {
-                let mut builder = sip_uri.into_builder();
-                if port.is_none() {
-                    builder.port(DEFAULT_SIPS_PORT);
+            UriScheme::Sips => match sip_uri.transport() {
+                Some(transport) if transport == wss() => {
+                    insert_transport(&mut result, sip_uri);
                 }
-                let sip_uri = builder.transport_parameter(Transport::Tls).build();
-                insert_transport(&mut result, sip_uri);
-            }
+                _ => {
+                    let mut builder = sip_uri.into_builder();
+                    if port.is_none() {
+                        builder.port(DEFAULT_SIPS_PORT);
+                    }
+                    let sip_uri = builder.transport_parameter(Transport::Tls).build();
+                    insert_transport(&mut result, sip_uri);
+                }
+            },
             _ => unreachable!(),
         }
     }
@@ -196,10 +351,137 @@ where
     result
 }
 
+/// Get the configured transports that cannot be enabled, so that they are reported at startup
+/// instead of being ignored.
+pub(crate) fn unsupported_transports(transports: &[TransportsByIpType]) -> Vec<&SipUri> {
+    transports
+        .iter()
+        .flat_map(|transport| [&transport.ipv4, &transport.ipv6])
+        .flatten()
+        .filter(|sip_uri| {
+            !sip_uri
+                .transport()
+                .is_some_and(|transport| is_supported(&transport))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::config::transports_by_ip_type;
+    use crate::config::{
+        ClientAuth, Config, Forking, StoreConfig, transports_by_ip_type, unsupported_transports,
+    };
     use imersio_sip::SipUri;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_tls_settings() {
+        let config: Config = toml::from_str("[proxy]\n").unwrap();
+        assert!(config.proxy.tls.is_none());
+
+        let config: Config = toml::from_str(
+            "[proxy.tls]\n\
+             certificate = \"/etc/imersio/cert.pem\"\n\
+             private_key = \"/etc/imersio/key.pem\"\n\
+             client_auth = \"required\"\n",
+        )
+        .unwrap();
+        let tls = config.proxy.tls.unwrap();
+        assert_eq!(tls.certificate.to_str(), Some("/etc/imersio/cert.pem"));
+        assert_eq!(tls.private_key.to_str(), Some("/etc/imersio/key.pem"));
+        assert_eq!(tls.ca_certificates, None);
+        assert_eq!(tls.client_auth, ClientAuth::Required);
+
+        assert!(toml::from_str::<Config>("[proxy.tls]\ncertificate = \"cert.pem\"\n").is_err());
+    }
+
+    #[test]
+    fn test_connection_settings() {
+        let config: Config = toml::from_str("[proxy]\n").unwrap();
+        assert_eq!(config.proxy.max_connections, 1024);
+        assert_eq!(config.proxy.connection_idle_timeout, 300);
+
+        let config: Config =
+            toml::from_str("[proxy]\nmax_connections = 10\nconnection_idle_timeout = 60\n")
+                .unwrap();
+        assert_eq!(config.proxy.max_connections, 10);
+        assert_eq!(config.proxy.connection_idle_timeout, 60);
+    }
+
+    #[test]
+    fn test_routes() {
+        let config: Config = toml::from_str("[proxy]\n").unwrap();
+        assert_eq!(config.proxy.timer_c, 200);
+        assert!(config.proxy.routes.is_empty());
+
+        let config: Config = toml::from_str(
+            "[proxy]\n\
+             timer_c = 300\n\
+             [[proxy.routes]]\n\
+             uri = \"sip:bob@biloxi.com\"\n\
+             forking = \"sequential\"\n\
+             timer_c = 190\n\
+             targets = [\n\
+                 { uri = \"sip:bob@192.0.2.4\", q = 0.7 },\n\
+                 { uri = \"sip:bob@192.0.2.5\" },\n\
+             ]\n\
+             [[proxy.routes]]\n\
+             uri = \"sip:atlanta.com\"\n\
+             targets = [{ uri = \"sip:proxy.atlanta.com;transport=tcp\" }]\n",
+        )
+        .unwrap();
+        assert_eq!(config.proxy.timer_c, 300);
+        let routes = config.proxy.routes;
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].forking, Forking::Sequential);
+        assert_eq!(routes[0].timer_c, Some(190));
+        assert_eq!(routes[0].targets.len(), 2);
+        assert_eq!(routes[0].targets[0].q, Some(0.7));
+        assert_eq!(routes[0].targets[1].q, None);
+        assert!(routes[0].matches(&"sip:bob@BILOXI.com".parse().unwrap()));
+        assert!(!routes[0].matches(&"sip:carol@biloxi.com".parse().unwrap()));
+        assert_eq!(routes[1].forking, Forking::Parallel);
+        assert!(routes[1].matches(&"sip:alice@atlanta.com".parse().unwrap()));
+
+        assert!(toml::from_str::<Config>("[[proxy.routes]]\nuri = \"sip:biloxi.com\"\n").is_err());
+    }
+
+    #[test]
+    fn test_registrar() {
+        let config: Config = toml::from_str("[proxy]\n").unwrap();
+        let registrar = config.proxy.registrar;
+        assert!(registrar.domains.is_empty());
+        assert_eq!(registrar.default_expires, 3600);
+        assert_eq!(registrar.min_expires, 60);
+        assert_eq!(registrar.max_expires, 86400);
+        assert_eq!(registrar.store, StoreConfig::Memory);
+
+        let config: Config = toml::from_str(
+            "[proxy.registrar]\n\
+             domains = [\"biloxi.com\", \"atlanta.com\"]\n\
+             min_expires = 120\n",
+        )
+        .unwrap();
+        let registrar = config.proxy.registrar;
+        assert_eq!(registrar.domains, vec!["biloxi.com", "atlanta.com"]);
+        assert_eq!(registrar.default_expires, 3600);
+        assert_eq!(registrar.min_expires, 120);
+
+        let config: Config = toml::from_str(
+            "[proxy.registrar]\n\
+             store = { type = \"file\", path = \"/var/lib/imersio/bindings.json\" }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.proxy.registrar.store,
+            StoreConfig::File {
+                path: PathBuf::from("/var/lib/imersio/bindings.json")
+            }
+        );
+        assert!(
+            toml::from_str::<Config>("[proxy.registrar]\nstore = { type = \"redis\" }\n").is_err()
+        );
+    }
 
     #[test]
     fn test_transports_by_ip_type_unspecified() {
@@ -338,6 +620,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_transports_by_ip_type_websocket() {
+        let transports: Vec<SipUri> = vec![
+            "sip:127.0.0.1:8080;transport=ws".parse().unwrap(),
+            "sips:127.0.0.1;transport=wss".parse().unwrap(),
+        ];
+
+        let transports = transports_by_ip_type(transports);
+        assert_eq!(transports.len(), 2);
+        assert!(
+            transports.iter().any(|transport| transport.ipv4
+                == Some("sip:127.0.0.1:8080;transport=ws".parse().unwrap()))
+        );
+        assert!(transports.iter().any(
+            |transport| transport.ipv4 == Some("sips:127.0.0.1;transport=wss".parse().unwrap())
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_transports() {
+        let transports = transports_by_ip_type(vec![
+            "sip:0.0.0.0:5060".parse().unwrap(),
+            "sip:127.0.0.1:5060;transport=dccp".parse().unwrap(),
+            "sip:[::1]:5060;transport=sctp".parse().unwrap(),
+        ]);
+        let unsupported: Vec<String> = unsupported_transports(&transports)
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        if cfg!(target_os = "linux") {
+            assert_eq!(unsupported, vec!["sip:127.0.0.1:5060;transport=dccp"]);
+        } else {
+            assert_eq!(
+                unsupported,
+                vec![
+                    "sip:127.0.0.1:5060;transport=dccp",
+                    "sip:[::1]:5060;transport=sctp"
+                ]
+            );
+        }
+    }
+
     #[test]
     fn test_transports_by_ip_type_sip_and_sips_uris() {
         let transports: Vec<SipUri> = vec![
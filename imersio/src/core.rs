@@ -0,0 +1,99 @@
+//! Core of the proxy, handling the SIP messages received by the transport layer.
+
+use imersio_sip::Message;
+use imersio_sip::transaction::SystemClock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep_until};
+use tracing::debug;
+
+use crate::config::RouteConfig;
+use crate::proxy::{Completion, StatefulProxy};
+use crate::registrar::Registrar;
+use crate::transport::{IncomingMessage, Transports};
+
+/// Number of received messages that can be waiting to be handled by the core.
+pub(crate) const INCOMING_QUEUE_SIZE: usize = 1024;
+
+/// Number of reports of the operations performed in the background that can be waiting to be
+/// handled by the core.
+const COMPLETION_QUEUE_SIZE: usize = 1024;
+
+/// Interval between two removals of the expired bindings of the registrar.
+const BINDINGS_EXPIRATION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The core dispatcher, handling the messages received by all the transports.
+#[derive(Debug)]
+pub(crate) struct Core {
+    proxy: StatefulProxy,
+    completions: mpsc::Receiver<Completion>,
+}
+
+impl Core {
+    /// Create the core, using the given transports to send the outgoing messages, the given
+    /// registrar to answer the REGISTER requests, and the given routes and Timer C to forward the
+    /// requests.
+    pub(crate) fn new(
+        transports: Transports,
+        routes: Vec<RouteConfig>,
+        registrar: Registrar,
+        timer_c: Duration,
+    ) -> Self {
+        let (completion_sender, completions) = mpsc::channel(COMPLETION_QUEUE_SIZE);
+        Self {
+            proxy: StatefulProxy::new(
+                transports,
+                routes,
+                registrar,
+                timer_c,
+                Arc::new(SystemClock),
+                completion_sender,
+            ),
+            completions,
+        }
+    }
+
+    /// Handle the received messages, the timers of the proxy and the completions of the operations
+    /// it performs in the background until all the transports are stopped, removing the expired
+    /// bindings of the registrar periodically.
+    pub(crate) async fn run(mut self, mut incoming: mpsc::Receiver<IncomingMessage>) {
+        let mut expiration = interval(BINDINGS_EXPIRATION_INTERVAL);
+        loop {
+            let timeout = self.proxy.next_timeout();
+            let deadline = tokio::time::Instant::from_std(timeout.unwrap_or_else(Instant::now));
+            select! {
+                message = incoming.recv() => match message {
+                    Some(message) => self.dispatch(message),
+                    None => break,
+                },
+                Some(completion) = self.completions.recv() => self.proxy.complete(completion),
+                () = sleep_until(deadline), if timeout.is_some() => self.proxy.handle_timers(),
+                _ = expiration.tick() => self.proxy.remove_expired_bindings(),
+            }
+        }
+    }
+
+    /// Handle a received message.
+    fn dispatch(&mut self, incoming: IncomingMessage) {
+        match &incoming.message {
+            Message::Request(request) => debug!(
+                "Received {} request for {} from {} on {} over {}",
+                request.method(),
+                request.uri(),
+                incoming.remote,
+                incoming.local,
+                incoming.transport
+            ),
+            Message::Response(response) => debug!(
+                "Received {} response from {} on {} over {}",
+                response.reason(),
+                incoming.remote,
+                incoming.local,
+                incoming.transport
+            ),
+        }
+        self.proxy.handle(incoming);
+    }
+}
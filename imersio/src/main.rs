@@ -1,17 +1,33 @@
 use clap::Parser;
 use imersio_sip::{SipUri, Transport};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::process::ExitCode;
-use tokio::net::{TcpListener, UdpSocket};
+use std::time::Duration;
 use tokio::select;
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
 mod config;
+mod core;
+mod proxy;
+mod registrar;
+mod transport;
 
+use crate::core::{Core, INCOMING_QUEUE_SIZE};
 use config::Config;
+use registrar::Registrar;
+#[cfg(target_os = "linux")]
+use transport::SctpTransport;
+use transport::{
+    ConnectionTable, IncomingMessage, TcpTransport, TlsContext, TlsTransport, Transports,
+    UdpTransport, WebSocketTransport, is_websocket, wss,
+};
 
 const DEFAULT_SIP_PORT: u16 = 5060;
 const DEFAULT_SIPS_PORT: u16 = 5061;
+const DEFAULT_WS_PORT: u16 = 80;
+const DEFAULT_WSS_PORT: u16 = 443;
 const DEFAULT_CONFIG_PATH: &str = "/etc/imersio/imersio.toml";
 
 #[derive(Parser)]
@@ -58,15 +74,38 @@ async fn main() -> ExitCode {
 
 #[tracing::instrument]
 async fn run(config: Config) -> Result<(), std::io::Error> {
-    let transports = config::transports_by_ip_type(config.proxy.transports);
-    for transport_by_ip_type in transports {
+    let (incoming_sender, incoming_receiver) = mpsc::channel(INCOMING_QUEUE_SIZE);
+    let connections = ConnectionTable::new(
+        config.proxy.max_connections,
+        Duration::from_secs(config.proxy.connection_idle_timeout),
+        incoming_sender.clone(),
+    );
+    let tls_context = config.proxy.tls.as_ref().map(TlsContext::new).transpose()?;
+    let registrar = Registrar::new(config.proxy.registrar)?;
+    let mut transports = Transports::new(connections, tls_context);
+    let transports_by_ip_type = config::transports_by_ip_type(config.proxy.transports);
+    let unsupported = config::unsupported_transports(&transports_by_ip_type);
+    if !unsupported.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "Unsupported transports: {}",
+                unsupported
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ));
+    }
+    for transport_by_ip_type in transports_by_ip_type {
         let mut bind_done_for_ipv6 = false;
         if let Some(sip_uri) = transport_by_ip_type.ipv6 {
-            enable_transport(&sip_uri).await?;
+            enable_transport(&sip_uri, &mut transports, &incoming_sender).await?;
             bind_done_for_ipv6 = true;
         }
         if let Some(sip_uri) = transport_by_ip_type.ipv4 {
-            match enable_transport(&sip_uri).await {
+            match enable_transport(&sip_uri, &mut transports, &incoming_sender).await {
                 Ok(()) => (),
                 Err(_) if bind_done_for_ipv6 => {
                     warn!("Transport {} already bound to IPv6, skipping IPv4", sip_uri);
@@ -76,6 +115,15 @@ async fn run(config: Config) -> Result<(), std::io::Error> {
         }
     }
 
+    drop(incoming_sender);
+    let core = Core::new(
+        transports,
+        config.proxy.routes,
+        registrar,
+        Duration::from_secs(config.proxy.timer_c),
+    );
+    tokio::spawn(core.run(incoming_receiver));
+
     wait_for_signal().await?;
 
     info!("Shutting down...");
@@ -123,29 +171,68 @@ async fn wait_for_signal() -> Result<(), std::io::Error> {
     Ok(())
 }
 
-async fn enable_transport(uri: &SipUri) -> Result<(), std::io::Error> {
+async fn enable_transport(
+    uri: &SipUri,
+    transports: &mut Transports,
+    incoming: &mpsc::Sender<IncomingMessage>,
+) -> Result<(), std::io::Error> {
     debug!("Enabling transport {}", uri);
-    if uri.is_secure() {
-        unimplemented!();
-    } else {
-        let ip = uri.host().ip().unwrap();
-        let port = uri.port().unwrap();
-        match uri.transport() {
-            Some(Transport::Udp) => {
-                let _socket = UdpSocket::bind((*ip, port)).await?;
-                // TODO
-            }
-            Some(Transport::Tcp) => {
-                let listener = TcpListener::bind((*ip, port)).await?;
-                tokio::spawn(async move {
-                    loop {
-                        let _result = listener.accept().await;
-                        // TODO
-                    }
-                });
-            }
-            _ => (),
+    let ip = uri.host().ip().unwrap();
+    let port = uri.port().unwrap_or(match uri.transport() {
+        Some(Transport::Tls) => DEFAULT_SIPS_PORT,
+        Some(transport) if transport == wss() => DEFAULT_WSS_PORT,
+        Some(transport) if is_websocket(&transport) => DEFAULT_WS_PORT,
+        _ => DEFAULT_SIP_PORT,
+    });
+    match uri.transport() {
+        Some(Transport::Udp) => {
+            let udp = UdpTransport::bind(SocketAddr::new(*ip, port)).await?;
+            udp.spawn(incoming.clone());
+            transports.add_udp(udp);
+        }
+        Some(Transport::Tcp) => {
+            let tcp = TcpTransport::bind(SocketAddr::new(*ip, port)).await?;
+            transports.add_tcp(tcp.local_addr());
+            tcp.spawn(transports.connections().clone());
+        }
+        Some(Transport::Tls) => {
+            let tls_context = tls_context(uri, transports)?;
+            let tls = TlsTransport::bind(SocketAddr::new(*ip, port), tls_context).await?;
+            transports.add_tls(tls.local_addr());
+            tls.spawn(transports.connections().clone());
+        }
+        Some(transport) if is_websocket(&transport) => {
+            let tls_context = if transport == wss() {
+                Some(tls_context(uri, transports)?)
+            } else {
+                None
+            };
+            let websocket =
+                WebSocketTransport::bind(SocketAddr::new(*ip, port), tls_context).await?;
+            transports.add_websocket(websocket.transport(), websocket.local_addr());
+            websocket.spawn(transports.connections().clone());
+        }
+        #[cfg(target_os = "linux")]
+        Some(Transport::Sctp) => {
+            let sctp = SctpTransport::bind(SocketAddr::new(*ip, port)).await?;
+            sctp.spawn(incoming.clone());
+            transports.add_sctp(sctp);
+        }
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("Unsupported transport {uri}"),
+            ));
         }
-        Ok(())
     }
+    Ok(())
+}
+
+fn tls_context(uri: &SipUri, transports: &Transports) -> Result<TlsContext, std::io::Error> {
+    transports.tls_context().cloned().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Transport {uri} requires a certificate and a private key in [proxy.tls]"),
+        )
+    })
 }
@@ -0,0 +1,866 @@
+//! Response contexts of the stateful proxy, gathering the responses of the branches a request
+//! has been forked to, and choosing the response to forward to the client.
+//!
+//! [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+
+use imersio_sip::{Header, Method, Reason, Request, Response, Route, Uri};
+use std::time::{Duration, Instant};
+
+use super::{MAGIC_COOKIE, NextHop, StableHasher, local_response, loop_hash, transaction_hash};
+use crate::config::Forking;
+
+/// The 4xx responses preferred to the other ones, as the client may be able to retry the request
+/// successfully.
+///
+/// [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+const PREFERRED_CLIENT_ERRORS: [u16; 5] = [401, 407, 415, 420, 484];
+
+/// A target of a request, with its preference as in the `q` parameter of a Contact.
+///
+/// [[RFC3261, Section 16.5](https://datatracker.ietf.org/doc/html/rfc3261#section-16.5)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Target {
+    pub(crate) uri: Uri,
+    pub(crate) q: f32,
+    /// The route set to preload in the request forwarded to the target.
+    pub(crate) path: Vec<Route>,
+    /// The next hop of the request forwarded to the target, when it is not determined by its
+    /// Request-URI or its route set.
+    pub(crate) next_hop: Option<NextHop>,
+}
+
+impl Target {
+    /// Create a target, with a preference of 1 when it has no `q`.
+    pub(crate) fn new(uri: Uri, q: Option<f32>) -> Self {
+        Self {
+            uri,
+            q: q.unwrap_or(1.0),
+            path: vec![],
+            next_hop: None,
+        }
+    }
+}
+
+/// An action of a response context, to perform by the stateful proxy.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ContextAction {
+    /// Forward a response to the client, with the server transaction.
+    Forward(Response),
+    /// Cancel the branch with the given branch parameter.
+    Cancel(String),
+    /// Forward the request to a target, in a new branch with the given branch parameter.
+    Fork { branch: String, target: Target },
+    /// Destroy the client transaction of the branch with the given branch parameter, that has
+    /// been cancelled but did not receive a final response in time.
+    Terminate(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BranchState {
+    /// No response has been received yet.
+    Trying,
+    /// A provisional response has been received.
+    Proceeding,
+    /// A final response has been received, or the branch failed.
+    Completed,
+}
+
+#[derive(Debug)]
+struct Branch {
+    id: String,
+    state: BranchState,
+    /// The branch is to be cancelled, as soon as it receives a provisional response.
+    cancelled: bool,
+    timer_c: Option<Instant>,
+    /// The time at which the branch is abandoned if it does not receive a final response after
+    /// being cancelled.
+    cancel_timeout: Option<Instant>,
+}
+
+impl Branch {
+    /// Cancel the branch, that received a provisional response, waiting for its final response
+    /// until the given time.
+    fn cancel(&mut self, timeout: Instant) -> ContextAction {
+        self.cancelled = true;
+        self.cancel_timeout = Some(timeout);
+        ContextAction::Cancel(self.id.clone())
+    }
+}
+
+/// The response context of a request handled by the stateful proxy.
+#[derive(Debug)]
+pub(crate) struct ResponseContext {
+    request: Request,
+    forking: Forking,
+    timer_c: Duration,
+    cancel_timeout: Duration,
+    targets: Vec<Target>,
+    branches: Vec<Branch>,
+    responses: Vec<Response>,
+    transaction_hash: String,
+    loop_hash: String,
+    final_sent: bool,
+    forking_stopped: bool,
+}
+
+impl ResponseContext {
+    /// Create the response context of a received request, to forward to the given targets.
+    ///
+    /// The targets are tried by decreasing `q`. A cancelled branch is abandoned when it does not
+    /// receive a final response within the given timeout, that is 64*T1.
+    pub(crate) fn new(
+        request: Request,
+        mut targets: Vec<Target>,
+        forking: Forking,
+        timer_c: Duration,
+        cancel_timeout: Duration,
+    ) -> Self {
+        targets.sort_by(|a, b| b.q.total_cmp(&a.q));
+        Self {
+            transaction_hash: transaction_hash(&request),
+            loop_hash: loop_hash(&request),
+            request,
+            forking,
+            timer_c,
+            cancel_timeout,
+            targets,
+            branches: vec![],
+            responses: vec![],
+            final_sent: false,
+            forking_stopped: false,
+        }
+    }
+
+    /// Get the request of the response context, as received.
+    pub(crate) fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// Start forwarding the request to its targets.
+    pub(crate) fn start(&mut self, now: Instant) -> Vec<ContextAction> {
+        let actions = self.fork(now);
+        if actions.is_empty() {
+            return self.complete();
+        }
+        actions
+    }
+
+    /// Handle a response received in a branch, with its Via removed.
+    ///
+    /// The provisional and the 2xx responses are forwarded at once, every 2xx response to an
+    /// INVITE being forwarded. The other final responses are kept until all the branches are
+    /// completed, to choose the best one.
+    ///
+    /// [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+    pub(crate) fn receive_response(
+        &mut self,
+        branch: &str,
+        response: Response,
+        now: Instant,
+    ) -> Vec<ContextAction> {
+        let timer_c = self.timer_c;
+        let cancel_timeout = now + self.cancel_timeout;
+        let final_sent = self.final_sent;
+        let Some(branch) = self.branches.iter_mut().find(|b| b.id == branch) else {
+            return vec![];
+        };
+        let reason = response.reason().clone();
+        if reason.is_provisional() {
+            if branch.state == BranchState::Completed || reason.status().code() == 100 {
+                return vec![];
+            }
+            let mut actions = vec![];
+            if branch.state == BranchState::Trying && branch.cancelled {
+                actions.push(branch.cancel(cancel_timeout));
+            }
+            branch.state = BranchState::Proceeding;
+            if branch.timer_c.is_some() {
+                branch.timer_c = Some(now + timer_c);
+            }
+            if !final_sent {
+                actions.push(ContextAction::Forward(response));
+            }
+            return actions;
+        }
+
+        branch.state = BranchState::Completed;
+        branch.timer_c = None;
+        branch.cancel_timeout = None;
+        if reason.is_success() {
+            let mut actions = vec![];
+            if self.is_invite() || !final_sent {
+                actions.push(ContextAction::Forward(response));
+            }
+            self.final_sent = true;
+            actions.extend(self.stop_forking(now));
+            return actions;
+        }
+        let mut actions = vec![];
+        if reason.is_global_failure() {
+            actions.extend(self.stop_forking(now));
+        }
+        self.responses.push(response);
+        actions.extend(self.check_completed(now));
+        actions
+    }
+
+    /// Handle the failure of a branch, that timed out or could not be sent, as if it received a
+    /// response with the given reason.
+    ///
+    /// [[RFC3261, Section 16.8](https://datatracker.ietf.org/doc/html/rfc3261#section-16.8)]
+    /// [[RFC3261, Section 16.9](https://datatracker.ietf.org/doc/html/rfc3261#section-16.9)]
+    pub(crate) fn branch_failed(
+        &mut self,
+        branch: &str,
+        reason: Reason,
+        now: Instant,
+    ) -> Vec<ContextAction> {
+        let response = local_response(&self.request, reason);
+        self.receive_response(branch, response, now)
+    }
+
+    /// Handle the CANCEL of the request: the pending branches are cancelled and no new branch is
+    /// created.
+    ///
+    /// [[RFC3261, Section 16.10](https://datatracker.ietf.org/doc/html/rfc3261#section-16.10)]
+    pub(crate) fn cancel(&mut self, now: Instant) -> Vec<ContextAction> {
+        self.stop_forking(now)
+    }
+
+    /// Give up on a cancelled branch that did not receive a final response, because its CANCEL
+    /// failed or it did not answer it in time: its client transaction is destroyed, and it is
+    /// handled as if it received a 408 response.
+    ///
+    /// [[RFC3261, Section 9.1](https://datatracker.ietf.org/doc/html/rfc3261#section-9.1)]
+    pub(crate) fn abandon(&mut self, branch: &str, now: Instant) -> Vec<ContextAction> {
+        if !self
+            .branches
+            .iter()
+            .any(|b| b.id == branch && b.state != BranchState::Completed)
+        {
+            return vec![];
+        }
+        let mut actions = vec![ContextAction::Terminate(branch.to_string())];
+        actions.extend(self.branch_failed(branch, Reason::REQUEST_TIMEOUT, now));
+        actions
+    }
+
+    /// Get the time at which the next Timer C, or the next timeout of a cancelled branch,
+    /// expires, if any.
+    pub(crate) fn next_timeout(&self) -> Option<Instant> {
+        self.branches
+            .iter()
+            .flat_map(|branch| [branch.timer_c, branch.cancel_timeout])
+            .flatten()
+            .min()
+    }
+
+    /// Handle the expiration of Timer C in the branches: a branch that received a provisional
+    /// response is cancelled, the other ones are handled as if they received a 408 response.
+    /// The cancelled branches that did not receive a final response in time are abandoned.
+    ///
+    /// [[RFC3261, Section 16.8](https://datatracker.ietf.org/doc/html/rfc3261#section-16.8)]
+    pub(crate) fn handle_timers(&mut self, now: Instant) -> Vec<ContextAction> {
+        let mut actions = vec![];
+        let mut timed_out = vec![];
+        let mut abandoned = vec![];
+        let cancel_timeout = now + self.cancel_timeout;
+        for branch in self.branches.iter_mut() {
+            if branch.timer_c.is_some_and(|timer_c| timer_c <= now) {
+                branch.timer_c = None;
+                if branch.state == BranchState::Proceeding {
+                    if !branch.cancelled {
+                        actions.push(branch.cancel(cancel_timeout));
+                    }
+                } else {
+                    timed_out.push(branch.id.clone());
+                }
+            }
+            if branch.cancel_timeout.is_some_and(|timeout| timeout <= now) {
+                branch.cancel_timeout = None;
+                abandoned.push(branch.id.clone());
+            }
+        }
+        for branch in timed_out {
+            actions.extend(self.branch_failed(&branch, Reason::REQUEST_TIMEOUT, now));
+        }
+        for branch in abandoned {
+            actions.extend(self.abandon(&branch, now));
+        }
+        actions
+    }
+
+    fn is_invite(&self) -> bool {
+        self.request.method() == &Method::Invite
+    }
+
+    /// Create the branches of the next targets to try: all of them when forking in parallel, the
+    /// ones with the highest `q` when forking sequentially.
+    fn fork(&mut self, now: Instant) -> Vec<ContextAction> {
+        if self.forking_stopped || self.targets.is_empty() {
+            return vec![];
+        }
+        let count = match self.forking {
+            Forking::Parallel => self.targets.len(),
+            Forking::Sequential => {
+                let q = self.targets[0].q;
+                self.targets
+                    .iter()
+                    .take_while(|target| target.q == q)
+                    .count()
+            }
+        };
+        let targets: Vec<Target> = self.targets.drain(..count).collect();
+        targets
+            .into_iter()
+            .map(|target| {
+                let branch = self.branch_id(&target);
+                self.branches.push(Branch {
+                    id: branch.clone(),
+                    state: BranchState::Trying,
+                    cancelled: false,
+                    timer_c: self.is_invite().then(|| now + self.timer_c),
+                    cancel_timeout: None,
+                });
+                ContextAction::Fork { branch, target }
+            })
+            .collect()
+    }
+
+    /// Compute the branch parameter of a new branch, unique in the response context and ending
+    /// with the value used to detect the loops.
+    ///
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    fn branch_id(&self, target: &Target) -> String {
+        let mut hasher = StableHasher::new();
+        hasher.add(&self.transaction_hash);
+        hasher.add(self.branches.len());
+        hasher.add(&target.uri);
+        format!("{MAGIC_COOKIE}{}.{}", hasher.finish(), self.loop_hash)
+    }
+
+    /// Stop creating new branches and cancel the pending ones, if the request is an INVITE.
+    fn stop_forking(&mut self, now: Instant) -> Vec<ContextAction> {
+        self.forking_stopped = true;
+        self.targets.clear();
+        if !self.is_invite() {
+            return vec![];
+        }
+        let cancel_timeout = now + self.cancel_timeout;
+        self.branches
+            .iter_mut()
+            .filter(|branch| branch.state != BranchState::Completed && !branch.cancelled)
+            .filter_map(|branch| {
+                // A branch without provisional response is cancelled once it receives one.
+                if branch.state == BranchState::Proceeding {
+                    Some(branch.cancel(cancel_timeout))
+                } else {
+                    branch.cancelled = true;
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Try the next targets once all the branches are completed, or forward the best response
+    /// if there are no more targets.
+    fn check_completed(&mut self, now: Instant) -> Vec<ContextAction> {
+        if self.final_sent
+            || self
+                .branches
+                .iter()
+                .any(|branch| branch.state != BranchState::Completed)
+        {
+            return vec![];
+        }
+        let actions = self.fork(now);
+        if !actions.is_empty() {
+            return actions;
+        }
+        self.complete()
+    }
+
+    fn complete(&mut self) -> Vec<ContextAction> {
+        self.final_sent = true;
+        vec![ContextAction::Forward(self.best_response())]
+    }
+
+    /// Choose the best of the final responses received by the branches: a 6xx response if any,
+    /// else a response of the lowest class, a 401, 407, 415, 420 or 484 response being preferred
+    /// among the 4xx ones. A 503 response is converted into a 500 response, and
+    /// the challenges of all the 401 and 407 responses are gathered in the chosen one.
+    ///
+    /// A 408 response is generated when no final response has been received.
+    ///
+    /// [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+    fn best_response(&self) -> Response {
+        let best = self
+            .responses
+            .iter()
+            .position(|response| response.reason().is_global_failure())
+            .or_else(|| {
+                self.responses
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, response)| {
+                        let code = response.reason().status().code();
+                        (code / 100, !PREFERRED_CLIENT_ERRORS.contains(&code))
+                    })
+                    .map(|(index, _)| index)
+            });
+        let Some(best) = best else {
+            return local_response(&self.request, Reason::REQUEST_TIMEOUT);
+        };
+        let mut response = self.responses[best].clone();
+        match response.reason().status().code() {
+            503 => response.set_reason(Reason::SERVER_INTERNAL_ERROR),
+            401 | 407 => {
+                let challenges: Vec<Header> = self
+                    .responses
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, other)| {
+                        *index != best && matches!(other.reason().status().code(), 401 | 407)
+                    })
+                    .flat_map(|(_, other)| other.headers())
+                    .filter(|header| {
+                        matches!(
+                            header,
+                            Header::WWWAuthenticate(_) | Header::ProxyAuthenticate(_)
+                        )
+                    })
+                    .cloned()
+                    .collect();
+                response.headers_mut().extend(challenges);
+            }
+            _ => (),
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVITE: &str = "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    fn target(uri: &str, q: Option<f32>) -> Target {
+        Target::new(Uri::try_from(uri).unwrap(), q)
+    }
+
+    fn response_context(request: &str, targets: Vec<Target>, forking: Forking) -> ResponseContext {
+        ResponseContext::new(
+            Request::try_from(request).unwrap(),
+            targets,
+            forking,
+            Duration::from_secs(200),
+            Duration::from_secs(32),
+        )
+    }
+
+    fn response(context: &ResponseContext, code: u16, extra_headers: &[&str]) -> Response {
+        let mut message = local_response(context.request(), Reason::OK)
+            .to_string()
+            .replacen("200 OK", &format!("{code} Whatever"), 1);
+        for header in extra_headers {
+            message = message.replacen("Content-Length", &format!("{header}\r\nContent-Length"), 1);
+        }
+        Response::try_from(message.as_str()).unwrap()
+    }
+
+    fn forks(actions: &[ContextAction]) -> Vec<(String, String)> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                ContextAction::Fork { branch, target } => {
+                    Some((branch.clone(), target.uri.to_string()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn forwarded(actions: &[ContextAction]) -> Vec<u16> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                ContextAction::Forward(response) => Some(response.reason().status().code()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn cancelled(actions: &[ContextAction]) -> Vec<String> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                ContextAction::Cancel(branch) => Some(branch.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_forking() {
+        let mut context = response_context(
+            INVITE,
+            vec![
+                target("sip:bob@192.0.2.4", Some(0.5)),
+                target("sip:bob@192.0.2.5", None),
+                target("sip:bob@192.0.2.6", Some(0.5)),
+            ],
+            Forking::Parallel,
+        );
+        let now = Instant::now();
+        let actions = context.start(now);
+        let branches = forks(&actions);
+        assert_eq!(
+            branches
+                .iter()
+                .map(|(_, uri)| uri.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "sip:bob@192.0.2.5",
+                "sip:bob@192.0.2.4",
+                "sip:bob@192.0.2.6"
+            ]
+        );
+        assert!(branches[0].0.starts_with(MAGIC_COOKIE));
+        assert_ne!(branches[0].0, branches[1].0);
+
+        // 100 Trying is not forwarded, the other provisional responses are.
+        let trying = response(&context, 100, &[]);
+        assert!(
+            context
+                .receive_response(&branches[0].0, trying, now)
+                .is_empty()
+        );
+        let ringing = response(&context, 180, &[]);
+        assert_eq!(
+            forwarded(&context.receive_response(&branches[0].0, ringing, now)),
+            vec![180]
+        );
+
+        let busy = response(&context, 486, &[]);
+        assert!(
+            context
+                .receive_response(&branches[1].0, busy, now)
+                .is_empty()
+        );
+
+        // The 2xx response is forwarded at once, the pending branches are cancelled.
+        let ok = response(&context, 200, &[]);
+        let actions = context.receive_response(&branches[2].0, ok.clone(), now);
+        assert_eq!(forwarded(&actions), vec![200]);
+        assert_eq!(cancelled(&actions), vec![branches[0].0.clone()]);
+
+        // Every 2xx response to an INVITE is forwarded.
+        let actions = context.receive_response(&branches[0].0, ok, now);
+        assert_eq!(forwarded(&actions), vec![200]);
+        assert!(cancelled(&actions).is_empty());
+    }
+
+    #[test]
+    fn test_sequential_forking() {
+        let mut context = response_context(
+            INVITE,
+            vec![
+                target("sip:bob@192.0.2.4", Some(0.1)),
+                target("sip:bob@192.0.2.5", Some(0.7)),
+                target("sip:bob@192.0.2.6", Some(0.7)),
+            ],
+            Forking::Sequential,
+        );
+        let now = Instant::now();
+        let branches = forks(&context.start(now));
+        assert_eq!(
+            branches
+                .iter()
+                .map(|(_, uri)| uri.as_str())
+                .collect::<Vec<_>>(),
+            vec!["sip:bob@192.0.2.5", "sip:bob@192.0.2.6"]
+        );
+
+        let unavailable = response(&context, 480, &[]);
+        assert!(
+            context
+                .receive_response(&branches[0].0, unavailable, now)
+                .is_empty()
+        );
+        let actions = context.branch_failed(&branches[1].0, Reason::SERVICE_UNAVAILABLE, now);
+        let next = forks(&actions);
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].1, "sip:bob@192.0.2.4");
+
+        // The best response is the one of the lowest class.
+        let moved = response(&context, 302, &[]);
+        assert_eq!(
+            forwarded(&context.receive_response(&next[0].0, moved, now)),
+            vec![302]
+        );
+    }
+
+    #[test]
+    fn test_best_response() {
+        let targets = || {
+            vec![
+                target("sip:bob@192.0.2.4", None),
+                target("sip:bob@192.0.2.5", None),
+                target("sip:bob@192.0.2.6", None),
+            ]
+        };
+        let now = Instant::now();
+
+        // A 6xx response is preferred, and cancels the pending branches.
+        let mut context = response_context(INVITE, targets(), Forking::Parallel);
+        let branches = forks(&context.start(now));
+        let ringing = response(&context, 180, &[]);
+        context.receive_response(&branches[0].0, ringing, now);
+        let not_found = response(&context, 404, &[]);
+        context.receive_response(&branches[1].0, not_found, now);
+        let decline = response(&context, 603, &[]);
+        let actions = context.receive_response(&branches[2].0, decline, now);
+        assert_eq!(cancelled(&actions), vec![branches[0].0.clone()]);
+        assert!(forwarded(&actions).is_empty());
+        let terminated = response(&context, 487, &[]);
+        assert_eq!(
+            forwarded(&context.receive_response(&branches[0].0, terminated, now)),
+            vec![603]
+        );
+
+        // A response of the lowest class is preferred, the branches that failed count.
+        let mut context = response_context(INVITE, targets(), Forking::Parallel);
+        let branches = forks(&context.start(now));
+        let unavailable = response(&context, 503, &[]);
+        context.receive_response(&branches[0].0, unavailable, now);
+        context.branch_failed(&branches[1].0, Reason::SERVICE_UNAVAILABLE, now);
+        let actions = context.branch_failed(&branches[2].0, Reason::NOT_FOUND, now);
+        assert_eq!(forwarded(&actions), vec![404]);
+
+        // A 503 response is converted into a 500 response.
+        let mut context = response_context(INVITE, targets(), Forking::Parallel);
+        let branches = forks(&context.start(now));
+        let mut actions = vec![];
+        for (branch, _) in &branches {
+            let unavailable = response(&context, 503, &[]);
+            actions = context.receive_response(branch, unavailable, now);
+        }
+        let [ContextAction::Forward(best)] = actions.as_slice() else {
+            panic!("No response forwarded");
+        };
+        assert_eq!(best.reason(), &Reason::SERVER_INTERNAL_ERROR);
+
+        // A 415 response is preferred to the other 4xx responses.
+        let mut context = response_context(INVITE, targets(), Forking::Parallel);
+        let branches = forks(&context.start(now));
+        let mut actions = vec![];
+        for ((branch, _), code) in branches.iter().zip([404, 415, 486]) {
+            let failure = response(&context, code, &[]);
+            actions = context.receive_response(branch, failure, now);
+        }
+        assert_eq!(forwarded(&actions), vec![415]);
+    }
+
+    #[test]
+    fn test_challenges_merged() {
+        let mut context = response_context(
+            INVITE,
+            vec![
+                target("sip:bob@192.0.2.4", None),
+                target("sip:bob@192.0.2.5", None),
+            ],
+            Forking::Parallel,
+        );
+        let now = Instant::now();
+        let branches = forks(&context.start(now));
+        let unauthorized = response(
+            &context,
+            401,
+            &[
+                "WWW-Authenticate: Digest realm=\"biloxi.com\", nonce=\"ea9c8e88df84f1cec4341ae6cbe5a359\"",
+            ],
+        );
+        context.receive_response(&branches[0].0, unauthorized, now);
+        let proxy_authentication_required = response(
+            &context,
+            407,
+            &[
+                "Proxy-Authenticate: Digest realm=\"atlanta.com\", nonce=\"wf84f1ceczx41ae6cbe5aea9c8e88d359\"",
+            ],
+        );
+        let actions = context.receive_response(&branches[1].0, proxy_authentication_required, now);
+        let [ContextAction::Forward(best)] = actions.as_slice() else {
+            panic!("No response forwarded");
+        };
+        assert_eq!(best.reason().status().code(), 401);
+        let challenges: Vec<&Header> = best
+            .headers()
+            .iter()
+            .filter(|header| {
+                matches!(
+                    header,
+                    Header::WWWAuthenticate(_) | Header::ProxyAuthenticate(_)
+                )
+            })
+            .collect();
+        assert_eq!(challenges.len(), 2);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut context = response_context(
+            INVITE,
+            vec![
+                target("sip:bob@192.0.2.4", Some(1.0)),
+                target("sip:bob@192.0.2.5", Some(1.0)),
+                target("sip:bob@192.0.2.6", Some(0.5)),
+            ],
+            Forking::Sequential,
+        );
+        let now = Instant::now();
+        let branches = forks(&context.start(now));
+        assert_eq!(branches.len(), 2);
+        let ringing = response(&context, 180, &[]);
+        context.receive_response(&branches[0].0, ringing, now);
+
+        // Only the branch that received a provisional response can be cancelled at once.
+        assert_eq!(cancelled(&context.cancel(now)), vec![branches[0].0.clone()]);
+        let ringing = response(&context, 180, &[]);
+        let actions = context.receive_response(&branches[1].0, ringing, now);
+        assert_eq!(cancelled(&actions), vec![branches[1].0.clone()]);
+        assert_eq!(forwarded(&actions), vec![180]);
+
+        // No other target is tried once the request is cancelled.
+        let terminated = response(&context, 487, &[]);
+        assert!(
+            context
+                .receive_response(&branches[0].0, terminated.clone(), now)
+                .is_empty()
+        );
+        assert_eq!(
+            forwarded(&context.receive_response(&branches[1].0, terminated, now)),
+            vec![487]
+        );
+    }
+
+    #[test]
+    fn test_cancel_timeout() {
+        let mut context = response_context(
+            INVITE,
+            vec![
+                target("sip:bob@192.0.2.4", None),
+                target("sip:bob@192.0.2.5", None),
+            ],
+            Forking::Parallel,
+        );
+        let now = Instant::now();
+        let branches = forks(&context.start(now));
+        for (branch, _) in &branches {
+            let ringing = response(&context, 180, &[]);
+            context.receive_response(branch, ringing, now);
+        }
+        let later = now + Duration::from_secs(10);
+        assert_eq!(
+            cancelled(&context.cancel(later)),
+            vec![branches[0].0.clone(), branches[1].0.clone()]
+        );
+
+        // The branch whose CANCEL failed is abandoned at once.
+        let actions = context.abandon(&branches[0].0, later);
+        assert_eq!(
+            actions,
+            vec![ContextAction::Terminate(branches[0].0.clone())]
+        );
+        assert!(context.abandon(&branches[0].0, later).is_empty());
+
+        // The other branch is abandoned when it does not receive a final response within 64*T1.
+        assert_eq!(
+            context.next_timeout(),
+            Some(later + Duration::from_secs(32))
+        );
+        let actions = context.handle_timers(later + Duration::from_secs(32));
+        assert_eq!(
+            actions.first(),
+            Some(&ContextAction::Terminate(branches[1].0.clone()))
+        );
+        assert_eq!(forwarded(&actions), vec![408]);
+        assert_eq!(context.next_timeout(), None);
+    }
+
+    #[test]
+    fn test_timer_c() {
+        let mut context = response_context(
+            INVITE,
+            vec![
+                target("sip:bob@192.0.2.4", None),
+                target("sip:bob@192.0.2.5", None),
+            ],
+            Forking::Parallel,
+        );
+        let now = Instant::now();
+        let branches = forks(&context.start(now));
+        assert_eq!(context.next_timeout(), Some(now + Duration::from_secs(200)));
+
+        // A provisional response restarts Timer C.
+        let later = now + Duration::from_secs(100);
+        let ringing = response(&context, 180, &[]);
+        context.receive_response(&branches[0].0, ringing, later);
+        assert!(
+            context
+                .handle_timers(now + Duration::from_secs(150))
+                .is_empty()
+        );
+
+        // The branch without provisional response behaves as if it received a 408 response.
+        let actions = context.handle_timers(now + Duration::from_secs(200));
+        assert!(actions.is_empty());
+        assert_eq!(
+            context.next_timeout(),
+            Some(later + Duration::from_secs(200))
+        );
+
+        // The branch with a provisional response is cancelled.
+        let actions = context.handle_timers(later + Duration::from_secs(200));
+        assert_eq!(cancelled(&actions), vec![branches[0].0.clone()]);
+        assert_eq!(
+            context.next_timeout(),
+            Some(later + Duration::from_secs(232))
+        );
+        // The 408 of the branch that timed out is the first response of the lowest class.
+        let terminated = response(&context, 487, &[]);
+        assert_eq!(
+            forwarded(&context.receive_response(&branches[0].0, terminated, later)),
+            vec![408]
+        );
+        assert_eq!(context.next_timeout(), None);
+    }
+
+    #[test]
+    fn test_non_invite() {
+        let mut context = response_context(
+            &INVITE.replace("INVITE", "OPTIONS"),
+            vec![
+                target("sip:bob@192.0.2.4", None),
+                target("sip:bob@192.0.2.5", None),
+            ],
+            Forking::Parallel,
+        );
+        let now = Instant::now();
+        let branches = forks(&context.start(now));
+        assert_eq!(context.next_timeout(), None);
+        let ok = response(&context, 200, &[]);
+        let actions = context.receive_response(&branches[0].0, ok.clone(), now);
+        assert_eq!(forwarded(&actions), vec![200]);
+        assert!(cancelled(&actions).is_empty());
+        assert!(context.receive_response(&branches[1].0, ok, now).is_empty());
+    }
+}
@@ -0,0 +1,826 @@
+//! Proxy core, forwarding the requests to their targets and the responses back to the clients.
+//!
+//! The stateless proxy forwards each request and each response on its own, without keeping any
+//! state about them. The branch of the Via added to a forwarded request is computed from the
+//! request, so that its retransmissions, and the CANCEL and the ACK of a non-2xx response of an
+//! INVITE, are forwarded with the same branch. The responses are forwarded along the Via headers.
+//!
+//! The stateful proxy handles the requests in server and client transactions, forking them to
+//! the targets of their route, and uses the stateless proxy for the messages that do not belong
+//! to any transaction.
+//!
+//! [[RFC3261, Section 16](https://datatracker.ietf.org/doc/html/rfc3261#section-16)]
+
+use imersio_sip::{
+    Header, Host, Message, Method, Reason, Request, Response, Transport, Uri, UriScheme, Via,
+};
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::debug;
+
+mod context;
+mod outbox;
+mod stateful;
+
+pub(crate) use outbox::Completion;
+pub(crate) use stateful::StatefulProxy;
+
+use crate::transport::{IncomingMessage, Transports, is_websocket, select_transport, wss};
+use crate::{DEFAULT_SIP_PORT, DEFAULT_SIPS_PORT, DEFAULT_WS_PORT, DEFAULT_WSS_PORT};
+
+/// The value starting the branch parameters generated according to RFC 3261.
+///
+/// [[RFC3261, Section 8.1.1.7](https://datatracker.ietf.org/doc/html/rfc3261#section-8.1.1.7)]
+const MAGIC_COOKIE: &str = "z9hG4bK";
+
+/// Maximum time to resolve the host of a next hop.
+const RESOLUTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The next hop a message is sent to, before the resolution of its host.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct NextHop {
+    pub(crate) transport: Transport,
+    pub(crate) host: Host,
+    pub(crate) port: u16,
+}
+
+/// The outcome of the processing of a received request by the proxy.
+#[derive(Debug)]
+pub(crate) enum RequestOutcome {
+    /// Forward the request to the next hop, adding a Via with the given branch.
+    Forward {
+        request: Box<Request>,
+        next_hop: NextHop,
+        branch: String,
+    },
+    /// Reply to the request with the given response.
+    Reply(Response),
+    /// Discard the request, for the given reason.
+    Discard(String),
+}
+
+/// The stateless proxy, forwarding the messages with the transports of the proxy.
+#[derive(Clone, Debug)]
+pub(crate) struct StatelessProxy {
+    transports: Transports,
+}
+
+impl StatelessProxy {
+    /// Create the stateless proxy, using the given transports to send the messages.
+    pub(crate) fn new(transports: Transports) -> Self {
+        Self { transports }
+    }
+
+    /// Handle a received message, forwarding it or replying to it.
+    pub(crate) async fn handle(&self, incoming: IncomingMessage) {
+        let result = match incoming.message {
+            Message::Request(request) => match self.process_request(request) {
+                RequestOutcome::Forward {
+                    request,
+                    next_hop,
+                    branch,
+                } => self.forward_request(*request, &next_hop, &branch).await,
+                RequestOutcome::Reply(response) => self.send_response(response).await,
+                RequestOutcome::Discard(reason) => {
+                    debug!("Discarding request from {}: {}", incoming.remote, reason);
+                    Ok(())
+                }
+            },
+            Message::Response(response) => match self.process_response(response) {
+                Ok(response) => self.send_response(response).await,
+                Err(reason) => {
+                    debug!("Discarding response from {}: {}", incoming.remote, reason);
+                    Ok(())
+                }
+            },
+        };
+        if let Err(err) = result {
+            debug!(
+                "Could not forward message received from {}: {}",
+                incoming.remote, err
+            );
+        }
+    }
+
+    /// Validate a received request and determine where to forward it.
+    ///
+    /// [[RFC3261, Section 16.3](https://datatracker.ietf.org/doc/html/rfc3261#section-16.3)]
+    /// [[RFC3261, Section 16.4](https://datatracker.ietf.org/doc/html/rfc3261#section-16.4)]
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    pub(crate) fn process_request(&self, mut request: Request) -> RequestOutcome {
+        if let Err(reason) = self.preprocess(&mut request) {
+            return self.reject(&request, reason);
+        }
+
+        let next_hop = match next_hop(&request) {
+            Ok(next_hop) => next_hop,
+            Err(reason) => return self.reject(&request, reason),
+        };
+        if self.transports.is_local(&next_hop.host, next_hop.port) {
+            // There is no location service to find the targets of a request for the proxy.
+            return self.reject(&request, Reason::TEMPORARILY_UNAVAILABLE);
+        }
+
+        let branch = format!(
+            "{MAGIC_COOKIE}{}.{}",
+            transaction_hash(&request),
+            loop_hash(&request)
+        );
+        if let Err(err) = request.decrement_max_forwards() {
+            return RequestOutcome::Discard(err.to_string());
+        }
+        RequestOutcome::Forward {
+            request: Box::new(request),
+            next_hop,
+            branch,
+        }
+    }
+
+    /// Check the Max-Forwards of a received request, remove the first entry of its route set if
+    /// it designates the proxy, and detect the loops.
+    ///
+    /// [[RFC3261, Section 16.3](https://datatracker.ietf.org/doc/html/rfc3261#section-16.3)]
+    /// [[RFC3261, Section 16.4](https://datatracker.ietf.org/doc/html/rfc3261#section-16.4)]
+    fn preprocess(&self, request: &mut Request) -> Result<(), Reason> {
+        if request.max_forwards() == Some(0) {
+            return Err(Reason::TOO_MANY_HOPS);
+        }
+
+        let routes = request.routes();
+        if let Some(route) = routes.first() {
+            if self.is_local_uri(route.name_address().uri()) {
+                request.pop_route();
+            }
+        }
+
+        let loop_hash = loop_hash(request);
+        let looped = request.vias().iter().any(|via| {
+            self.is_local_via(via)
+                && via
+                    .branch()
+                    .is_some_and(|branch| branch.ends_with(&format!(".{loop_hash}")))
+        });
+        if looped {
+            return Err(Reason::LOOP_DETECTED);
+        }
+        Ok(())
+    }
+
+    /// Check a received response and remove its top Via, that must be the one added by the
+    /// proxy when forwarding the request.
+    ///
+    /// [[RFC3261, Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)]
+    /// [[RFC3261, Section 16.11](https://datatracker.ietf.org/doc/html/rfc3261#section-16.11)]
+    pub(crate) fn process_response(&self, mut response: Response) -> Result<Response, String> {
+        let via = response
+            .pop_via()
+            .ok_or_else(|| "Response without Via header".to_string())?;
+        if !self.is_local_via(&via) {
+            return Err(format!("Top Via `{via}` not added by the proxy"));
+        }
+        if response.vias().is_empty() {
+            return Err("Response for the proxy itself".to_string());
+        }
+        Ok(response)
+    }
+
+    /// Build a response of the proxy itself to a request, or discard the request if it is an ACK,
+    /// that cannot be answered.
+    fn reject(&self, request: &Request, reason: Reason) -> RequestOutcome {
+        if request.method() == &Method::Ack {
+            return RequestOutcome::Discard(reason.to_string());
+        }
+        RequestOutcome::Reply(local_response(request, reason))
+    }
+
+    /// Forward a request to the next hop, adding a Via with the given branch.
+    ///
+    /// The transport of the Via is the one actually used, that is TCP when the request is too
+    /// large to be sent over UDP.
+    ///
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    async fn forward_request(
+        &self,
+        request: Request,
+        next_hop: &NextHop,
+        branch: &str,
+    ) -> Result<(), std::io::Error> {
+        let destination = resolve(&self.transports, next_hop).await?;
+        let (request, transport) = self.prepare_request(request, next_hop, destination, branch)?;
+        self.transports
+            .send(
+                &Message::Request(request),
+                transport,
+                destination,
+                &next_hop.host,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Add the Via of the proxy with the given branch to a request sent to the given
+    /// destination, returning the request with the transport to send it with.
+    fn prepare_request(
+        &self,
+        request: Request,
+        next_hop: &NextHop,
+        destination: SocketAddr,
+        branch: &str,
+    ) -> Result<(Request, Transport), std::io::Error> {
+        let mut transport = next_hop.transport.clone();
+        let mut message = self.add_via(request.clone(), &transport, destination, branch)?;
+        // Vias over UDP and TCP have the same size, so the selection does not change again.
+        let selected = select_transport(
+            &Message::Request(message.clone()),
+            message.to_bytes().len(),
+            transport.clone(),
+        );
+        if selected != transport {
+            transport = selected;
+            message = self.add_via(request, &transport, destination, branch)?;
+        }
+        Ok((message, transport))
+    }
+
+    fn add_via(
+        &self,
+        mut request: Request,
+        transport: &Transport,
+        destination: SocketAddr,
+        branch: &str,
+    ) -> Result<Request, std::io::Error> {
+        let local = self
+            .transports
+            .local_addr(transport, destination)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!("No {transport} transport available to reach {destination}"),
+                )
+            })?;
+        request.push_via(Via::with_branch(
+            transport.clone(),
+            Host::Ip(local.ip()),
+            Some(local.port()),
+            branch,
+        ));
+        Ok(request)
+    }
+
+    /// Send a response to the element designated by its top Via.
+    ///
+    /// [[RFC3261, Section 18.2.2](https://datatracker.ietf.org/doc/html/rfc3261#section-18.2.2)]
+    async fn send_response(&self, response: Response) -> Result<(), std::io::Error> {
+        let via = response.vias().first().cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Response without Via header",
+            )
+        })?;
+        let next_hop = response_next_hop(&via);
+        let destination = resolve(&self.transports, &next_hop).await?;
+        self.transports
+            .send(
+                &Message::Response(response),
+                next_hop.transport,
+                destination,
+                via.host(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Tell whether a Via has been added by the proxy.
+    fn is_local_via(&self, via: &Via) -> bool {
+        let port = via
+            .port()
+            .unwrap_or_else(|| default_port(via.protocol().transport()));
+        self.transports.is_local(via.host(), port)
+    }
+
+    /// Tell whether a URI designates the proxy.
+    fn is_local_uri(&self, uri: &Uri) -> bool {
+        match next_hop_for_uri(uri) {
+            Ok(next_hop) => self.transports.is_local(&next_hop.host, next_hop.port),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Resolve the host of a next hop to an address reachable by the transports.
+///
+/// Only the A and AAAA records of a host name are used, the NAPTR and SRV records are not.
+async fn resolve(
+    transports: &Transports,
+    next_hop: &NextHop,
+) -> Result<SocketAddr, std::io::Error> {
+    let addresses: Vec<SocketAddr> = match next_hop.host.ip() {
+        Some(ip) => vec![SocketAddr::new(*ip, next_hop.port)],
+        None => tokio::time::timeout(
+            RESOLUTION_TIMEOUT,
+            tokio::net::lookup_host((next_hop.host.to_string(), next_hop.port)),
+        )
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("Timeout resolving {}", next_hop.host),
+            )
+        })??
+        .collect(),
+    };
+    addresses
+        .iter()
+        .find(|address| {
+            transports
+                .local_addr(&next_hop.transport, **address)
+                .is_some()
+        })
+        .or(addresses.first())
+        .copied()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Could not resolve {}", next_hop.host),
+            )
+        })
+}
+
+/// Build a response of the proxy itself to a request.
+fn local_response(request: &Request, reason: Reason) -> Response {
+    let mut response = Response::from_request(request, reason);
+    // The tag must be the same for the retransmissions of the request.
+    response.set_to_tag(&transaction_hash(request));
+    response
+}
+
+/// Determine the next hop of a request, that is the first entry of its route set, or its
+/// Request-URI when it has no route set.
+///
+/// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+pub(crate) fn next_hop(request: &Request) -> Result<NextHop, Reason> {
+    match request.routes().first() {
+        Some(route) => next_hop_for_uri(route.name_address().uri()),
+        None => next_hop_for_uri(request.uri()),
+    }
+}
+
+/// Determine the transport, the host and the port to reach a SIP URI, using TLS for a `sips:`
+/// URI and UDP when there is no transport parameter.
+///
+/// [[RFC3263, Section 4](https://datatracker.ietf.org/doc/html/rfc3263#section-4)]
+fn next_hop_for_uri(uri: &Uri) -> Result<NextHop, Reason> {
+    let sip_uri = uri.as_sip_uri().ok_or(Reason::UNSUPPORTED_URI_SCHEME)?;
+    let transport = match sip_uri.transport() {
+        Some(transport) if sip_uri.scheme() == &UriScheme::SIPS && !is_websocket(&transport) => {
+            Transport::Tls
+        }
+        Some(transport) => transport,
+        None if sip_uri.scheme() == &UriScheme::SIPS => Transport::Tls,
+        None => Transport::Udp,
+    };
+    let port = sip_uri.port().unwrap_or_else(|| default_port(&transport));
+    Ok(NextHop {
+        transport,
+        host: sip_uri.host().clone(),
+        port,
+    })
+}
+
+/// Determine where to send a response, given its top Via: the source address of the request
+/// recorded in the `received` and `rport` parameters, or the sent-by of the Via.
+///
+/// [[RFC3261, Section 18.2.2](https://datatracker.ietf.org/doc/html/rfc3261#section-18.2.2)]
+/// [[RFC3581, Section 4](https://datatracker.ietf.org/doc/html/rfc3581#section-4)]
+pub(crate) fn response_next_hop(via: &Via) -> NextHop {
+    let transport = via.protocol().transport().clone();
+    let port = via
+        .rport()
+        .or(via.port())
+        .unwrap_or_else(|| default_port(&transport));
+    let host = match via.received() {
+        Some(ip) => Host::Ip(ip),
+        None => via.host().clone(),
+    };
+    NextHop {
+        transport,
+        host,
+        port,
+    }
+}
+
+fn default_port(transport: &Transport) -> u16 {
+    match transport {
+        Transport::Tls => DEFAULT_SIPS_PORT,
+        transport if *transport == wss() => DEFAULT_WSS_PORT,
+        transport if is_websocket(transport) => DEFAULT_WS_PORT,
+        _ => DEFAULT_SIP_PORT,
+    }
+}
+
+/// Hasher of the values identifying a request, giving the same hash whatever the build of the
+/// proxy, so that the branches it generates survive a restart or an upgrade.
+struct StableHasher(Sha256);
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(Sha256::new())
+    }
+
+    /// Add a value to the hash, separated from the next one.
+    fn add(&mut self, value: impl Display) {
+        self.0.update(value.to_string().as_bytes());
+        self.0.update([0]);
+    }
+
+    /// Get the first 64 bits of the hash, hex-encoded.
+    fn finish(self) -> String {
+        self.0.finalize()[..8]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// Compute a hash identifying the transaction of a request from its top Via, so that its
+/// retransmissions, its CANCEL and the ACK of a non-2xx response get the same branch.
+///
+/// When the top Via has no branch generated according to RFC 3261, the Call-ID and the CSeq
+/// number are used as well.
+fn transaction_hash(request: &Request) -> String {
+    let mut hasher = StableHasher::new();
+    let vias = request.vias();
+    if let Some(via) = vias.first() {
+        hasher.add(via.host());
+        hasher.add(via.port().unwrap_or_default());
+        match via.branch() {
+            Some(branch) if branch.starts_with(MAGIC_COOKIE) => hasher.add(branch),
+            branch => {
+                hasher.add(branch.unwrap_or_default());
+                hasher.add(request.uri());
+                for header in request.headers() {
+                    match header {
+                        Header::CallId(_) | Header::From(_) => hasher.add(header),
+                        Header::CSeq(header) => hasher.add(header.cseq()),
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Compute the part of the branch used to detect loops, from the values of the request that
+/// identify it, excluding its Via headers.
+///
+/// The To tag, the Proxy-Require and the Proxy-Authorization headers are left out, so that the
+/// CANCEL of a request and the ACK of a non-2xx response, that do not carry them as the request
+/// does, get the same branch as the request.
+///
+/// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+/// [[RFC3261, Section 17.1.1.3](https://datatracker.ietf.org/doc/html/rfc3261#section-17.1.1.3)]
+fn loop_hash(request: &Request) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.add(request.uri());
+    for header in request.headers() {
+        match header {
+            Header::From(header) => hasher.add(header.tag().unwrap_or_default()),
+            Header::CallId(_) | Header::Route(_) => hasher.add(header),
+            Header::CSeq(header) => hasher.add(header.cseq()),
+            _ => (),
+        }
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{ConnectionTable, UdpTransport};
+    use std::time::Duration;
+    use tokio::net::UdpSocket;
+
+    fn request(uri: &str, extra_headers: &str) -> Request {
+        Request::try_from(
+            format!(
+                "INVITE {uri} SIP/2.0\r\n\
+                 Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+                 Max-Forwards: 70\r\n\
+                 To: Bob <sip:bob@biloxi.com>\r\n\
+                 From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+                 Call-ID: a84b4c76e66710\r\n\
+                 CSeq: 314159 INVITE\r\n\
+                 {extra_headers}\
+                 Content-Length: 0\r\n\
+                 \r\n"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    fn proxy() -> StatelessProxy {
+        let (core, _) = tokio::sync::mpsc::channel(1);
+        let mut transports = Transports::new(
+            ConnectionTable::new(10, Duration::from_secs(30), core),
+            None,
+        );
+        transports.add_tcp("127.0.0.1:5060".parse().unwrap());
+        StatelessProxy::new(transports)
+    }
+
+    fn forward(outcome: RequestOutcome) -> (Request, NextHop, String) {
+        match outcome {
+            RequestOutcome::Forward {
+                request,
+                next_hop,
+                branch,
+            } => (*request, next_hop, branch),
+            outcome => panic!("Request not forwarded: {outcome:?}"),
+        }
+    }
+
+    fn reply(outcome: RequestOutcome) -> Response {
+        match outcome {
+            RequestOutcome::Reply(response) => response,
+            outcome => panic!("Request not rejected: {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn test_next_hop() {
+        assert_eq!(
+            next_hop(&request("sip:bob@biloxi.com", "")),
+            Ok(NextHop {
+                transport: Transport::Udp,
+                host: Host::try_from("biloxi.com").unwrap(),
+                port: 5060
+            })
+        );
+        assert_eq!(
+            next_hop(&request(
+                "sip:bob@biloxi.com",
+                "Route: <sips:proxy.biloxi.com;lr>\r\n"
+            )),
+            Ok(NextHop {
+                transport: Transport::Tls,
+                host: Host::try_from("proxy.biloxi.com").unwrap(),
+                port: 5061
+            })
+        );
+        assert_eq!(
+            next_hop(&request("sip:bob@192.0.2.4:5080;transport=tcp", "")),
+            Ok(NextHop {
+                transport: Transport::Tcp,
+                host: Host::Ip("192.0.2.4".parse().unwrap()),
+                port: 5080
+            })
+        );
+        assert_eq!(
+            next_hop(&request("tel:+12125551212", "")),
+            Err(Reason::UNSUPPORTED_URI_SCHEME)
+        );
+    }
+
+    #[test]
+    fn test_response_next_hop() {
+        let response = Response::try_from(
+            "SIP/2.0 200 OK\r\n\
+             Via: SIP/2.0/TCP pc33.atlanta.com;rport=9988;branch=z9hG4bKnashds8;received=192.0.2.1\r\n\
+             Via: SIP/2.0/UDP bobspc.biloxi.com:5070;branch=z9hG4bK776asdhds\r\n\
+             Content-Length: 0\r\n\
+             \r\n",
+        )
+        .unwrap();
+        let vias = response.vias();
+        assert_eq!(
+            response_next_hop(&vias[0]),
+            NextHop {
+                transport: Transport::Tcp,
+                host: Host::Ip("192.0.2.1".parse().unwrap()),
+                port: 9988
+            }
+        );
+        assert_eq!(
+            response_next_hop(&vias[1]),
+            NextHop {
+                transport: Transport::Udp,
+                host: Host::try_from("bobspc.biloxi.com").unwrap(),
+                port: 5070
+            }
+        );
+    }
+
+    #[test]
+    fn test_forward_request() {
+        let proxy = proxy();
+        let (forwarded, next_hop, branch) = forward(proxy.process_request(request(
+            "sip:bob@biloxi.com",
+            "Route: <sip:127.0.0.1;lr>, <sip:proxy.biloxi.com;lr>\r\n",
+        )));
+        assert_eq!(forwarded.max_forwards(), Some(69));
+        assert_eq!(forwarded.routes().len(), 1);
+        assert_eq!(next_hop.host, Host::try_from("proxy.biloxi.com").unwrap());
+        assert!(branch.starts_with(MAGIC_COOKIE));
+        // The branch does not depend on the build of the proxy.
+        assert_eq!(branch, "z9hG4bK90815e989176d139.f5faba20988f79c0");
+
+        // A retransmission gets the same branch.
+        let (_, _, retransmission_branch) = forward(proxy.process_request(request(
+            "sip:bob@biloxi.com",
+            "Route: <sip:127.0.0.1;lr>, <sip:proxy.biloxi.com;lr>\r\n",
+        )));
+        assert_eq!(retransmission_branch, branch);
+
+        // The CANCEL gets the same branch as the INVITE.
+        let cancel = Request::try_from(
+            request(
+                "sip:bob@biloxi.com",
+                "Route: <sip:127.0.0.1;lr>, <sip:proxy.biloxi.com;lr>\r\n",
+            )
+            .to_string()
+            .replace("INVITE", "CANCEL")
+            .as_str(),
+        )
+        .unwrap();
+        let (_, _, cancel_branch) = forward(proxy.process_request(cancel));
+        assert_eq!(cancel_branch, branch);
+
+        // The Proxy-Authorization header, not carried by the CANCEL and the ACK, does not change
+        // the branch.
+        let (_, _, authorized_branch) = forward(proxy.process_request(request(
+            "sip:bob@biloxi.com",
+            "Route: <sip:127.0.0.1;lr>, <sip:proxy.biloxi.com;lr>\r\n\
+             Proxy-Authorization: Digest username=\"alice\", realm=\"atlanta.com\", \
+             nonce=\"wf84f1ceczx41ae6cbe5aea9c8e88d359\", uri=\"sip:bob@biloxi.com\", \
+             response=\"42ce3cef44b22f50c6a6071bc8b6c8a1\"\r\n",
+        )));
+        assert_eq!(authorized_branch, branch);
+
+        // The ACK of a non-2xx response, with the To tag of the response, gets the same branch as
+        // the INVITE.
+        let ack = Request::try_from(
+            request(
+                "sip:bob@biloxi.com",
+                "Route: <sip:127.0.0.1;lr>, <sip:proxy.biloxi.com;lr>\r\n",
+            )
+            .to_string()
+            .replace("INVITE", "ACK")
+            .replace(
+                "<sip:bob@biloxi.com>\r\n",
+                "<sip:bob@biloxi.com>;tag=8321234356\r\n",
+            )
+            .as_str(),
+        )
+        .unwrap();
+        assert!(ack.to_string().contains(";tag=8321234356"));
+        let (_, _, ack_branch) = forward(proxy.process_request(ack));
+        assert_eq!(ack_branch, branch);
+    }
+
+    #[test]
+    fn test_reject_request() {
+        let proxy = proxy();
+        let no_more_hops = Request::try_from(
+            request("sip:bob@biloxi.com", "")
+                .to_string()
+                .replace("Max-Forwards: 70", "Max-Forwards: 0")
+                .as_str(),
+        )
+        .unwrap();
+        let response = reply(proxy.process_request(no_more_hops.clone()));
+        assert_eq!(response.reason(), &Reason::TOO_MANY_HOPS);
+
+        let response = reply(proxy.process_request(request("sip:bob@127.0.0.1", "")));
+        assert_eq!(response.reason(), &Reason::TEMPORARILY_UNAVAILABLE);
+        let to_tag = response.headers().iter().find_map(|header| match header {
+            Header::To(header) => header.tag().map(ToString::to_string),
+            _ => None,
+        });
+        assert!(to_tag.is_some());
+        let retransmission = reply(proxy.process_request(request("sip:bob@127.0.0.1", "")));
+        assert_eq!(retransmission, response);
+
+        let response = reply(proxy.process_request(request("tel:+12125551212", "")));
+        assert_eq!(response.reason(), &Reason::UNSUPPORTED_URI_SCHEME);
+
+        let ack =
+            Request::try_from(no_more_hops.to_string().replace("INVITE", "ACK").as_str()).unwrap();
+        assert!(matches!(
+            proxy.process_request(ack),
+            RequestOutcome::Discard(_)
+        ));
+    }
+
+    #[test]
+    fn test_loop_detection() {
+        let proxy = proxy();
+        let (mut forwarded, _, branch) =
+            forward(proxy.process_request(request("sip:bob@biloxi.com", "")));
+        forwarded.push_via(Via::with_branch(
+            Transport::Tcp,
+            Host::Ip("127.0.0.1".parse().unwrap()),
+            Some(5060),
+            &branch,
+        ));
+
+        // The request comes back unchanged: it is looping.
+        let response = reply(proxy.process_request(forwarded.clone()));
+        assert_eq!(response.reason(), &Reason::LOOP_DETECTED);
+
+        // The request comes back with another Request-URI: it is spiraling.
+        forwarded.set_uri(Uri::try_from("sip:bob@192.0.2.4").unwrap());
+        forward(proxy.process_request(forwarded));
+    }
+
+    #[test]
+    fn test_process_response() {
+        let proxy = proxy();
+        let response = Response::try_from(
+            "SIP/2.0 180 Ringing\r\n\
+             Via: SIP/2.0/TCP 127.0.0.1:5060;branch=z9hG4bK1234.5678\r\n\
+             Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+             Content-Length: 0\r\n\
+             \r\n",
+        )
+        .unwrap();
+        let forwarded = proxy.process_response(response).unwrap();
+        assert_eq!(forwarded.vias().len(), 1);
+
+        // The top Via is not the one of the proxy.
+        assert!(proxy.process_response(forwarded.clone()).is_err());
+    }
+
+    async fn receive(socket: &UdpSocket) -> Message {
+        let mut buffer = vec![0u8; 65536];
+        let (size, _) = tokio::time::timeout(Duration::from_secs(5), socket.recv_from(&mut buffer))
+            .await
+            .unwrap()
+            .unwrap();
+        Message::try_from(&buffer[..size]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forward_over_udp() {
+        let (core, _) = tokio::sync::mpsc::channel(1);
+        let mut transports = Transports::new(
+            ConnectionTable::new(10, Duration::from_secs(30), core),
+            None,
+        );
+        let udp = UdpTransport::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let proxy_address = udp.local_addr();
+        transports.add_udp(udp);
+        let proxy = StatelessProxy::new(transports);
+        let uac = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let uas = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let uac_address = uac.local_addr().unwrap();
+        let uas_address = uas.local_addr().unwrap();
+
+        // The response is sent back to the source port of the request thanks to rport.
+        let mut invite = Request::try_from(
+            request(&format!("sip:bob@{uas_address}"), "")
+                .to_string()
+                .replace("branch=", "rport;branch=")
+                .as_str(),
+        )
+        .unwrap();
+        invite.set_received(uac_address).unwrap();
+        proxy
+            .handle(IncomingMessage {
+                message: Message::Request(invite),
+                transport: Transport::Udp,
+                local: proxy_address,
+                remote: uac_address,
+            })
+            .await;
+        let Message::Request(forwarded) = receive(&uas).await else {
+            panic!("Request not forwarded");
+        };
+        let vias = forwarded.vias();
+        assert_eq!(vias.len(), 2);
+        assert_eq!(vias[0].host(), &Host::Ip(proxy_address.ip()));
+        assert_eq!(vias[0].port(), Some(proxy_address.port()));
+        assert_eq!(forwarded.max_forwards(), Some(69));
+
+        let mut response = Response::from_request(&forwarded, Reason::RINGING);
+        response.set_to_tag("a6c85cf");
+        proxy
+            .handle(IncomingMessage {
+                message: Message::Response(response),
+                transport: Transport::Udp,
+                local: proxy_address,
+                remote: uas_address,
+            })
+            .await;
+        let Message::Response(forwarded) = receive(&uac).await else {
+            panic!("Response not forwarded");
+        };
+        assert_eq!(forwarded.reason(), &Reason::RINGING);
+        assert_eq!(forwarded.vias().len(), 1);
+    }
+}
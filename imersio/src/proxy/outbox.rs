@@ -0,0 +1,260 @@
+//! Queues of the messages sent by the stateful proxy, one per next hop.
+//!
+//! Each queue is drained by its own task, resolving the host of the next hop and connecting to
+//! it if needed, so that a slow or unreachable next hop only delays the messages sent to it, and
+//! not the handling of the other messages and of the timers by the core. The messages sent to a
+//! next hop are sent in order.
+
+use imersio_sip::transaction::TransactionKey;
+use imersio_sip::{Host, Message, Request};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::timeout;
+use tracing::debug;
+
+use super::{NextHop, resolve};
+use crate::transport::Transports;
+
+/// Number of messages that can be waiting to be sent to a next hop.
+const QUEUE_SIZE: usize = 64;
+
+/// Time after which the task of a queue stops when no message is sent to its next hop.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Report of an operation performed in the background, handed back to the core.
+#[derive(Debug)]
+pub(crate) enum Completion {
+    /// The host of the next hop of a request to forward in a new client transaction has been
+    /// resolved, or could not be.
+    Resolved {
+        server: TransactionKey,
+        branch: String,
+        request: Box<Request>,
+        next_hop: NextHop,
+        destination: Result<SocketAddr, std::io::Error>,
+    },
+    /// A request of a client transaction could not be sent.
+    RequestFailed(TransactionKey),
+    /// A response of a server transaction could not be sent.
+    ResponseFailed(TransactionKey),
+}
+
+#[derive(Debug)]
+struct Outgoing {
+    message: Message,
+    destination: Option<SocketAddr>,
+    host: Host,
+    failure: Option<Completion>,
+}
+
+/// The queues of the messages to send, by next hop.
+#[derive(Debug)]
+pub(crate) struct Outbox {
+    transports: Transports,
+    queues: HashMap<NextHop, mpsc::Sender<Outgoing>>,
+    completions: mpsc::Sender<Completion>,
+}
+
+impl Outbox {
+    /// Create the outbox, sending the messages with the given transports and reporting the
+    /// failures to the given channel.
+    pub(crate) fn new(transports: Transports, completions: mpsc::Sender<Completion>) -> Self {
+        Self {
+            transports,
+            queues: HashMap::new(),
+            completions,
+        }
+    }
+
+    /// Get the channel the operations performed in the background are reported to.
+    pub(crate) fn completions(&self) -> &mpsc::Sender<Completion> {
+        &self.completions
+    }
+
+    /// Queue a message to send to a next hop, at the given destination or at the address its
+    /// host resolves to. The certificate of a new TLS connection is validated against the given
+    /// host, that also designates the WebSocket clients.
+    ///
+    /// The given failure is reported if the message cannot be sent, including when the queue of
+    /// the next hop is full.
+    pub(crate) fn send(
+        &mut self,
+        message: Message,
+        next_hop: NextHop,
+        destination: Option<SocketAddr>,
+        host: Host,
+        failure: Option<Completion>,
+    ) {
+        let mut outgoing = Outgoing {
+            message,
+            destination,
+            host,
+            failure,
+        };
+        if !self.queues.contains_key(&next_hop) {
+            // Forget the queues whose task stopped.
+            self.queues.retain(|_, queue| !queue.is_closed());
+        }
+        loop {
+            let queue = self
+                .queues
+                .entry(next_hop.clone())
+                .or_insert_with(|| spawn_queue(&self.transports, &self.completions, &next_hop));
+            match queue.try_send(outgoing) {
+                Ok(()) => return,
+                // The task of the queue stopped after being idle: a new one is started.
+                Err(TrySendError::Closed(returned)) => {
+                    self.queues.remove(&next_hop);
+                    outgoing = returned;
+                }
+                Err(TrySendError::Full(returned)) => {
+                    debug!("Too many messages waiting to be sent to {}", next_hop.host);
+                    if let Some(failure) = returned.failure {
+                        if self.completions.try_send(failure).is_err() {
+                            debug!("Could not report the failure to send a message");
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Start the task sending the messages of the queue of a next hop, returning the sender of the
+/// queue.
+fn spawn_queue(
+    transports: &Transports,
+    completions: &mpsc::Sender<Completion>,
+    next_hop: &NextHop,
+) -> mpsc::Sender<Outgoing> {
+    let (sender, mut receiver) = mpsc::channel(QUEUE_SIZE);
+    let (transports, completions, next_hop) =
+        (transports.clone(), completions.clone(), next_hop.clone());
+    tokio::spawn(async move {
+        loop {
+            match timeout(IDLE_TIMEOUT, receiver.recv()).await {
+                Ok(Some(outgoing)) => deliver(&transports, &completions, &next_hop, outgoing).await,
+                Ok(None) => break,
+                // The messages queued in the meantime are still received before the end.
+                Err(_) => receiver.close(),
+            }
+        }
+    });
+    sender
+}
+
+async fn deliver(
+    transports: &Transports,
+    completions: &mpsc::Sender<Completion>,
+    next_hop: &NextHop,
+    outgoing: Outgoing,
+) {
+    let destination = match outgoing.destination {
+        Some(destination) => Ok(destination),
+        None => resolve(transports, next_hop).await,
+    };
+    let result = match destination {
+        Ok(destination) => transports
+            .send(
+                &outgoing.message,
+                next_hop.transport.clone(),
+                destination,
+                &outgoing.host,
+            )
+            .await
+            .map(|_| ()),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        debug!("Could not send message to {}: {}", next_hop.host, err);
+        if let Some(failure) = outgoing.failure {
+            // The core stopped if the report cannot be handed to it.
+            let _ = completions.send(failure).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{ConnectionTable, UdpTransport};
+    use imersio_sip::Transport;
+    use tokio::net::UdpSocket;
+
+    fn message(cseq: u32) -> Message {
+        Message::try_from(
+            format!(
+                "OPTIONS sip:bob@biloxi.com SIP/2.0\r\n\
+                 Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+                 CSeq: {cseq} OPTIONS\r\n\
+                 Content-Length: 0\r\n\
+                 \r\n"
+            )
+            .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_outbox() {
+        let (core, _) = mpsc::channel(1);
+        let mut transports = Transports::new(
+            ConnectionTable::new(10, Duration::from_secs(30), core),
+            None,
+        );
+        transports.add_udp(
+            UdpTransport::bind("127.0.0.1:0".parse().unwrap())
+                .await
+                .unwrap(),
+        );
+        let (completions, mut completion_receiver) = mpsc::channel(1);
+        let mut outbox = Outbox::new(transports, completions);
+        let destination = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = destination.local_addr().unwrap();
+        let host = Host::Ip(address.ip());
+        let next_hop = NextHop {
+            transport: Transport::Udp,
+            host: host.clone(),
+            port: address.port(),
+        };
+
+        // The messages sent to a next hop are received in order.
+        for cseq in 1..=3 {
+            outbox.send(message(cseq), next_hop.clone(), None, host.clone(), None);
+        }
+        let mut buffer = vec![0u8; 65536];
+        for cseq in 1..=3 {
+            let (size, _) = timeout(Duration::from_secs(5), destination.recv_from(&mut buffer))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                Message::try_from(&buffer[..size]).unwrap().to_string(),
+                message(cseq).to_string()
+            );
+        }
+
+        // The failure to send a message is reported.
+        let key =
+            TransactionKey::server(&Request::try_from(message(1).to_string().as_str()).unwrap())
+                .unwrap();
+        outbox.send(
+            message(4),
+            NextHop {
+                transport: Transport::Tcp,
+                ..next_hop
+            },
+            Some(address),
+            host,
+            Some(Completion::RequestFailed(key.clone())),
+        );
+        let completion = timeout(Duration::from_secs(5), completion_receiver.recv())
+            .await
+            .unwrap();
+        assert!(matches!(completion, Some(Completion::RequestFailed(failed)) if failed == key));
+    }
+}
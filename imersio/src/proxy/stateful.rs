@@ -0,0 +1,1163 @@
+//! Transaction stateful proxy, forwarding the requests to their targets in client transactions
+//! and the best of their responses in the server transaction of the request.
+//!
+//! [[RFC3261, Section 16](https://datatracker.ietf.org/doc/html/rfc3261#section-16)]
+
+use imersio_sip::transaction::{
+    ClientTransaction, Clock, ServerTransaction, TimerSettings, TransactionEvent, TransactionKey,
+    TransactionState,
+};
+use imersio_sip::{Host, Message, Method, Reason, Request, Response, Transport, Uri};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use super::context::{ContextAction, ResponseContext, Target};
+use super::outbox::{Completion, Outbox};
+use super::{NextHop, StatelessProxy, local_response, next_hop, resolve, response_next_hop};
+use crate::config::{Forking, RouteConfig};
+use crate::registrar::{Binding, Registrar};
+use crate::transport::{IncomingMessage, Transports, is_reliable};
+
+/// A client transaction of the proxy, forwarding the request of a server transaction to one of
+/// its targets, or cancelling such a forwarded request.
+#[derive(Debug)]
+struct ClientBranch {
+    transaction: ClientTransaction,
+    /// The key of the server transaction the request is forwarded for.
+    server: TransactionKey,
+    transport: Transport,
+    destination: SocketAddr,
+    host: Host,
+}
+
+/// An action of the stateful proxy, that needs to perform I/O in the background.
+#[derive(Debug)]
+enum Action {
+    /// Forward the request of a server transaction in a new client transaction, with the given
+    /// branch.
+    Fork {
+        server: TransactionKey,
+        branch: String,
+        request: Request,
+        next_hop: NextHop,
+    },
+    /// Send a request of a client transaction.
+    SendRequest {
+        client: TransactionKey,
+        request: Request,
+        transport: Transport,
+        destination: SocketAddr,
+        host: Host,
+    },
+    /// Send a response, of a server transaction if it still exists.
+    SendResponse {
+        server: Option<TransactionKey>,
+        response: Response,
+    },
+}
+
+/// The transaction stateful proxy, forking the requests to the contacts registered for their
+/// Request-URI or to the targets of their route.
+///
+/// The REGISTER requests for the domains of the registrar are answered by the proxy itself.
+///
+/// The messages that do not belong to any transaction, that is the ACKs of the 2xx responses,
+/// the CANCELs of unknown requests and the responses of unknown client transactions, are
+/// forwarded by the stateless proxy.
+///
+/// The proxy never waits for the network: the messages are sent, and the host names are
+/// resolved, in the background, the failures being reported back as completions.
+#[derive(Debug)]
+pub(crate) struct StatefulProxy {
+    stateless: StatelessProxy,
+    outbox: Outbox,
+    routes: Vec<RouteConfig>,
+    registrar: Registrar,
+    timer_c: Duration,
+    settings: TimerSettings,
+    clock: Arc<dyn Clock>,
+    servers: HashMap<TransactionKey, ServerTransaction>,
+    clients: HashMap<TransactionKey, ClientBranch>,
+    contexts: HashMap<TransactionKey, ResponseContext>,
+}
+
+impl StatefulProxy {
+    /// Create the stateful proxy, using the given transports to send the messages, and the given
+    /// registrar and routes to find the targets of the requests, and reporting the operations
+    /// performed in the background to the given channel.
+    ///
+    /// The given Timer C applies to the routes not defining their own one.
+    pub(crate) fn new(
+        transports: Transports,
+        routes: Vec<RouteConfig>,
+        registrar: Registrar,
+        timer_c: Duration,
+        clock: Arc<dyn Clock>,
+        completions: mpsc::Sender<Completion>,
+    ) -> Self {
+        Self {
+            outbox: Outbox::new(transports.clone(), completions),
+            stateless: StatelessProxy::new(transports),
+            routes,
+            registrar,
+            timer_c,
+            settings: TimerSettings::default(),
+            clock,
+            servers: HashMap::new(),
+            clients: HashMap::new(),
+            contexts: HashMap::new(),
+        }
+    }
+
+    /// Handle a received message, in the transaction it belongs to, or statelessly if it does
+    /// not belong to any transaction.
+    pub(crate) fn handle(&mut self, incoming: IncomingMessage) {
+        let actions = match incoming.message.clone() {
+            Message::Request(request) => {
+                self.receive_request(request, &incoming.transport, incoming.remote)
+            }
+            Message::Response(response) => self.receive_response(response),
+        };
+        match actions {
+            Some(actions) => self.execute(actions),
+            None => {
+                let stateless = self.stateless.clone();
+                tokio::spawn(async move { stateless.handle(incoming).await });
+            }
+        }
+        self.cleanup();
+    }
+
+    /// Handle the report of an operation performed in the background.
+    pub(crate) fn complete(&mut self, completion: Completion) {
+        let actions = match completion {
+            Completion::Resolved {
+                server,
+                branch,
+                request,
+                next_hop,
+                destination,
+            } => self.forward(server, branch, *request, next_hop, destination),
+            Completion::RequestFailed(client) => match self.clients.get_mut(&client) {
+                Some(client_branch) => {
+                    let events = client_branch.transaction.transport_error();
+                    self.client_events(&client, events)
+                }
+                None => vec![],
+            },
+            Completion::ResponseFailed(server) => match self.servers.get_mut(&server) {
+                Some(transaction) => {
+                    let events = transaction.transport_error();
+                    self.server_events(&server, events)
+                }
+                None => vec![],
+            },
+        };
+        self.execute(actions);
+        self.cleanup();
+    }
+
+    /// Get the time at which the next timer of the proxy expires, if any.
+    pub(crate) fn next_timeout(&self) -> Option<Instant> {
+        self.servers
+            .values()
+            .filter_map(ServerTransaction::next_timeout)
+            .chain(
+                self.clients
+                    .values()
+                    .filter_map(|client| client.transaction.next_timeout()),
+            )
+            .chain(
+                self.contexts
+                    .values()
+                    .filter_map(ResponseContext::next_timeout),
+            )
+            .min()
+    }
+
+    /// Handle the timers of the transactions and of the response contexts that expired.
+    pub(crate) fn handle_timers(&mut self) {
+        let now = self.clock.now();
+        let expired = |timeout: Option<Instant>| timeout.is_some_and(|timeout| timeout <= now);
+        let mut actions = vec![];
+        let servers: Vec<TransactionKey> = self
+            .servers
+            .iter()
+            .filter(|(_, server)| expired(server.next_timeout()))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in servers {
+            if let Some(server) = self.servers.get_mut(&key) {
+                let events = server.handle_timers();
+                actions.extend(self.server_events(&key, events));
+            }
+        }
+        let clients: Vec<TransactionKey> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| expired(client.transaction.next_timeout()))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in clients {
+            if let Some(client) = self.clients.get_mut(&key) {
+                let events = client.transaction.handle_timers();
+                actions.extend(self.client_events(&key, events));
+            }
+        }
+        let contexts: Vec<TransactionKey> = self
+            .contexts
+            .iter()
+            .filter(|(_, context)| expired(context.next_timeout()))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in contexts {
+            if let Some(context) = self.contexts.get_mut(&key) {
+                let context_actions = context.handle_timers(now);
+                actions.extend(self.context_actions(&key, context_actions));
+            }
+        }
+        self.execute(actions);
+        self.cleanup();
+    }
+
+    /// Remove the bindings of the registrar that have expired.
+    pub(crate) fn remove_expired_bindings(&mut self) {
+        self.registrar.remove_expired(SystemTime::now());
+    }
+
+    /// Handle a received request, returning None if it is to be forwarded statelessly.
+    fn receive_request(
+        &mut self,
+        request: Request,
+        transport: &Transport,
+        remote: SocketAddr,
+    ) -> Option<Vec<Action>> {
+        // A request without a branch generated according to RFC 3261 is forwarded statelessly.
+        let key = TransactionKey::server(&request).ok()?;
+        if request.method() == &Method::Ack {
+            // The ACK of a 2xx response is forwarded statelessly, the one of another final
+            // response is absorbed by the server transaction.
+            let server = self
+                .servers
+                .get_mut(&key)
+                .filter(|server| server.state() != TransactionState::Accepted)?;
+            let events = server.receive_request(request);
+            return Some(self.server_events(&key, events));
+        }
+        if let Some(server) = self.servers.get_mut(&key) {
+            let events = server.receive_request(request);
+            return Some(self.server_events(&key, events));
+        }
+        if request.method() == &Method::Cancel {
+            return self.receive_cancel(key, request, transport);
+        }
+        Some(self.receive_new_request(key, request, transport, remote))
+    }
+
+    /// Handle a new request, creating its server transaction and forwarding it to its targets,
+    /// or answering it when it is a REGISTER for the registrar.
+    fn receive_new_request(
+        &mut self,
+        key: TransactionKey,
+        mut request: Request,
+        transport: &Transport,
+        remote: SocketAddr,
+    ) -> Vec<Action> {
+        let Some(mut actions) = self.create_server(&key, &request, transport) else {
+            return vec![];
+        };
+        if let Err(reason) = self.stateless.preprocess(&mut request) {
+            actions.extend(self.send_response(&key, local_response(&request, reason)));
+            return actions;
+        }
+        if request.method() == &Method::Register && self.is_registrar_for(request.uri()) {
+            let outcome =
+                self.registrar
+                    .register(&request, (transport.clone(), remote), SystemTime::now());
+            let mut response = local_response(&request, outcome.reason());
+            response.headers_mut().extend(outcome.headers());
+            actions.extend(self.send_response(&key, response));
+            return actions;
+        }
+        let (targets, forking, timer_c) = match self.targets(&request) {
+            Ok(targets) => targets,
+            Err(reason) => {
+                actions.extend(self.send_response(&key, local_response(&request, reason)));
+                return actions;
+            }
+        };
+        let mut context =
+            ResponseContext::new(request, targets, forking, timer_c, 64 * self.settings.t1());
+        let context_actions = context.start(self.clock.now());
+        self.contexts.insert(key.clone(), context);
+        actions.extend(self.context_actions(&key, context_actions));
+        actions
+    }
+
+    /// Handle a CANCEL, answering it and cancelling the pending branches of the INVITE it
+    /// cancels, returning None if the INVITE is unknown.
+    ///
+    /// [[RFC3261, Section 16.10](https://datatracker.ietf.org/doc/html/rfc3261#section-16.10)]
+    fn receive_cancel(
+        &mut self,
+        key: TransactionKey,
+        request: Request,
+        transport: &Transport,
+    ) -> Option<Vec<Action>> {
+        let invite = TransactionKey::cancelled(&request).ok()?;
+        if !self.servers.contains_key(&invite) {
+            return None;
+        }
+        let mut actions = self.create_server(&key, &request, transport)?;
+        actions.extend(self.send_response(&key, local_response(&request, Reason::OK)));
+        if let Some(context) = self.contexts.get_mut(&invite) {
+            let context_actions = context.cancel(self.clock.now());
+            actions.extend(self.context_actions(&invite, context_actions));
+        }
+        Some(actions)
+    }
+
+    fn create_server(
+        &mut self,
+        key: &TransactionKey,
+        request: &Request,
+        transport: &Transport,
+    ) -> Option<Vec<Action>> {
+        let mut server = match ServerTransaction::new(
+            request.clone(),
+            is_reliable(transport),
+            self.settings,
+            self.clock.clone(),
+        ) {
+            Ok(server) => server,
+            Err(err) => {
+                debug!("Could not create server transaction: {}", err);
+                return None;
+            }
+        };
+        let events = server.start();
+        self.servers.insert(key.clone(), server);
+        Some(self.server_events(key, events))
+    }
+
+    /// Tell whether the REGISTER requests for a Request-URI are handled by the registrar of the
+    /// proxy: when its domain is one of the registrar or an address of the proxy.
+    ///
+    /// [[RFC3261, Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3)]
+    fn is_registrar_for(&self, uri: &Uri) -> bool {
+        self.registrar.is_responsible_for(uri) || self.stateless.is_local_uri(uri)
+    }
+
+    /// Find the targets of a request: the contacts registered for its Request-URI, tried by
+    /// decreasing `q`, or the ones of the route matching its Request-URI, or its Request-URI when
+    /// no route matches or when it has a route set.
+    ///
+    /// [[RFC3261, Section 16.5](https://datatracker.ietf.org/doc/html/rfc3261#section-16.5)]
+    fn targets(&self, request: &Request) -> Result<(Vec<Target>, Forking, Duration), Reason> {
+        if request.routes().is_empty() {
+            let bindings = self
+                .registrar
+                .lookup(request.uri(), SystemTime::now())
+                .map_err(|err| {
+                    debug!(
+                        "Could not look up the bindings of {}: {}",
+                        request.uri(),
+                        err
+                    );
+                    Reason::SERVER_INTERNAL_ERROR
+                })?;
+            if !bindings.is_empty() {
+                let targets = bindings.iter().map(binding_target).collect();
+                return Ok((targets, Forking::Sequential, self.timer_c));
+            }
+            let route = request
+                .uri()
+                .as_sip_uri()
+                .and_then(|uri| self.routes.iter().find(|route| route.matches(uri)));
+            if let Some(route) = route {
+                let targets = route
+                    .targets
+                    .iter()
+                    .map(|target| Target::new(Uri::Sip(target.uri.clone()), target.q))
+                    .collect();
+                let timer_c = route
+                    .timer_c
+                    .map(Duration::from_secs)
+                    .unwrap_or(self.timer_c);
+                return Ok((targets, route.forking, timer_c));
+            }
+            if self.is_registrar_for(request.uri()) {
+                // No contact is registered for the Request-URI.
+                return Err(Reason::TEMPORARILY_UNAVAILABLE);
+            }
+        }
+        Ok((
+            vec![Target::new(request.uri().clone(), None)],
+            Forking::Parallel,
+            self.timer_c,
+        ))
+    }
+
+    /// Handle a received response, returning None if it is to be forwarded statelessly.
+    fn receive_response(&mut self, response: Response) -> Option<Vec<Action>> {
+        let key = TransactionKey::response(&response).ok()?;
+        let client = self.clients.get_mut(&key)?;
+        let events = client.transaction.receive_response(response);
+        Some(self.client_events(&key, events))
+    }
+
+    fn server_events(
+        &mut self,
+        key: &TransactionKey,
+        events: Vec<TransactionEvent>,
+    ) -> Vec<Action> {
+        let mut actions = vec![];
+        for event in events {
+            match event {
+                TransactionEvent::SendResponse(response) => actions.push(Action::SendResponse {
+                    server: Some(key.clone()),
+                    response,
+                }),
+                TransactionEvent::Terminated => {
+                    self.servers.remove(key);
+                }
+                _ => (),
+            }
+        }
+        actions
+    }
+
+    fn client_events(
+        &mut self,
+        key: &TransactionKey,
+        events: Vec<TransactionEvent>,
+    ) -> Vec<Action> {
+        let Some(client) = self.clients.get(key) else {
+            return vec![];
+        };
+        let (transport, destination, host) = (
+            client.transport.clone(),
+            client.destination,
+            client.host.clone(),
+        );
+        let mut actions = vec![];
+        for event in events {
+            match event {
+                TransactionEvent::SendRequest(request) => actions.push(Action::SendRequest {
+                    client: key.clone(),
+                    request,
+                    transport: transport.clone(),
+                    destination,
+                    host: host.clone(),
+                }),
+                TransactionEvent::Response(mut response) => {
+                    // The top Via is the one of the proxy, as the response matched the branch.
+                    response.pop_via();
+                    actions.extend(self.branch_response(key, response));
+                }
+                TransactionEvent::Timeout => {
+                    actions.extend(self.branch_failed(key, Reason::REQUEST_TIMEOUT));
+                }
+                TransactionEvent::TransportError => {
+                    actions.extend(self.branch_failed(key, Reason::SERVICE_UNAVAILABLE));
+                }
+                TransactionEvent::Terminated => {
+                    self.clients.remove(key);
+                }
+                _ => (),
+            }
+        }
+        actions
+    }
+
+    /// Pass a response received by a client transaction to its response context. The responses
+    /// to the CANCELs are absorbed.
+    fn branch_response(&mut self, key: &TransactionKey, response: Response) -> Vec<Action> {
+        let Some(server) = self.forwarded_for(key) else {
+            return vec![];
+        };
+        let now = self.clock.now();
+        match self.contexts.get_mut(&server) {
+            Some(context) => {
+                let context_actions = context.receive_response(key.branch(), response, now);
+                self.context_actions(&server, context_actions)
+            }
+            // A 2xx response received after the response context has been removed.
+            None if response.reason().is_success() => vec![Action::SendResponse {
+                server: None,
+                response,
+            }],
+            None => vec![],
+        }
+    }
+
+    /// Handle the failure of a client transaction. When it cancels a branch, the branch is
+    /// abandoned as it is not going to receive a final response.
+    ///
+    /// [[RFC3261, Section 9.1](https://datatracker.ietf.org/doc/html/rfc3261#section-9.1)]
+    fn branch_failed(&mut self, key: &TransactionKey, reason: Reason) -> Vec<Action> {
+        let Some(client) = self.clients.get(key) else {
+            return vec![];
+        };
+        let server = client.server.clone();
+        if client.transaction.request().method() != &Method::Cancel {
+            return self.fork_failed(&server, key.branch(), reason);
+        }
+        let now = self.clock.now();
+        match self.contexts.get_mut(&server) {
+            Some(context) => {
+                // The CANCEL has the branch of the request it cancels.
+                let context_actions = context.abandon(key.branch(), now);
+                self.context_actions(&server, context_actions)
+            }
+            None => vec![],
+        }
+    }
+
+    fn fork_failed(
+        &mut self,
+        server: &TransactionKey,
+        branch: &str,
+        reason: Reason,
+    ) -> Vec<Action> {
+        let now = self.clock.now();
+        match self.contexts.get_mut(server) {
+            Some(context) => {
+                let context_actions = context.branch_failed(branch, reason, now);
+                self.context_actions(server, context_actions)
+            }
+            None => vec![],
+        }
+    }
+
+    /// Get the key of the server transaction a client transaction forwards the request of, if
+    /// it is not a CANCEL.
+    fn forwarded_for(&self, key: &TransactionKey) -> Option<TransactionKey> {
+        self.clients
+            .get(key)
+            .filter(|client| client.transaction.request().method() != &Method::Cancel)
+            .map(|client| client.server.clone())
+    }
+
+    fn context_actions(
+        &mut self,
+        server: &TransactionKey,
+        context_actions: Vec<ContextAction>,
+    ) -> Vec<Action> {
+        let mut actions = vec![];
+        for context_action in context_actions {
+            match context_action {
+                ContextAction::Forward(response) => {
+                    actions.extend(self.send_response(server, response));
+                }
+                ContextAction::Cancel(branch) => {
+                    actions.extend(self.cancel_branch(server, &branch));
+                }
+                ContextAction::Fork { branch, target } => {
+                    actions.extend(self.fork(server, branch, target));
+                }
+                ContextAction::Terminate(branch) => {
+                    self.clients.retain(|key, client| {
+                        &client.server != server
+                            || !client.transaction.is_invite()
+                            || key.branch() != branch
+                    });
+                }
+            }
+        }
+        actions
+    }
+
+    /// Prepare the request forwarded to a target: its Request-URI is the URI of the target, the
+    /// path of the target is preloaded in its route set and its Max-Forwards is decremented.
+    ///
+    /// [[RFC3261, Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)]
+    fn fork(&mut self, server: &TransactionKey, branch: String, target: Target) -> Vec<Action> {
+        let Some(context) = self.contexts.get(server) else {
+            return vec![];
+        };
+        let mut request = context.request().clone();
+        request.set_uri(target.uri);
+        request.push_routes(target.path);
+        if request.decrement_max_forwards().is_err() {
+            return self.fork_failed(server, &branch, Reason::TOO_MANY_HOPS);
+        }
+        match target.next_hop.map_or_else(|| next_hop(&request), Ok) {
+            Ok(next_hop) => vec![Action::Fork {
+                server: server.clone(),
+                branch,
+                request,
+                next_hop,
+            }],
+            Err(reason) => self.fork_failed(server, &branch, reason),
+        }
+    }
+
+    /// Send a CANCEL for a forwarded INVITE, in a new client transaction sent to the same
+    /// destination.
+    ///
+    /// [[RFC3261, Section 9.1](https://datatracker.ietf.org/doc/html/rfc3261#section-9.1)]
+    fn cancel_branch(&mut self, server: &TransactionKey, branch: &str) -> Vec<Action> {
+        let Some(invite) = self.clients.values().find(|client| {
+            &client.server == server
+                && client.transaction.is_invite()
+                && client.transaction.key().branch() == branch
+        }) else {
+            return vec![];
+        };
+        let transaction = invite.transaction.request().cancel().and_then(|cancel| {
+            ClientTransaction::new(
+                cancel,
+                is_reliable(&invite.transport),
+                self.settings,
+                self.clock.clone(),
+            )
+        });
+        let mut transaction = match transaction {
+            Ok(transaction) => transaction,
+            Err(err) => {
+                debug!("Could not cancel branch {}: {}", branch, err);
+                return vec![];
+            }
+        };
+        let events = transaction.start();
+        let key = transaction.key().clone();
+        let client = ClientBranch {
+            transaction,
+            server: server.clone(),
+            transport: invite.transport.clone(),
+            destination: invite.destination,
+            host: invite.host.clone(),
+        };
+        self.clients.insert(key.clone(), client);
+        self.client_events(&key, events)
+    }
+
+    /// Send a response in a server transaction, or statelessly if the server transaction does
+    /// not exist anymore.
+    fn send_response(&mut self, server: &TransactionKey, response: Response) -> Vec<Action> {
+        let Some(transaction) = self.servers.get_mut(server) else {
+            return vec![Action::SendResponse {
+                server: None,
+                response,
+            }];
+        };
+        match transaction.send_response(response) {
+            Ok(events) => self.server_events(server, events),
+            Err(err) => {
+                debug!("Could not send response: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    /// Perform the actions, and the ones resulting from them, in order.
+    fn execute(&mut self, actions: Vec<Action>) {
+        let mut actions = VecDeque::from(actions);
+        while let Some(action) = actions.pop_front() {
+            actions.extend(self.perform(action));
+        }
+    }
+
+    fn perform(&mut self, action: Action) -> Vec<Action> {
+        match action {
+            Action::Fork {
+                server,
+                branch,
+                request,
+                next_hop,
+            } => match next_hop.host.ip() {
+                Some(ip) => {
+                    let destination = SocketAddr::new(*ip, next_hop.port);
+                    self.forward(server, branch, request, next_hop, Ok(destination))
+                }
+                None => {
+                    let transports = self.stateless.transports.clone();
+                    let completions = self.outbox.completions().clone();
+                    tokio::spawn(async move {
+                        let destination = resolve(&transports, &next_hop).await;
+                        let completion = Completion::Resolved {
+                            server,
+                            branch,
+                            request: Box::new(request),
+                            next_hop,
+                            destination,
+                        };
+                        // The core stopped if the completion cannot be handed to it.
+                        let _ = completions.send(completion).await;
+                    });
+                    vec![]
+                }
+            },
+            Action::SendRequest {
+                client,
+                request,
+                transport,
+                destination,
+                host,
+            } => {
+                let next_hop = NextHop {
+                    transport,
+                    host: host.clone(),
+                    port: destination.port(),
+                };
+                self.outbox.send(
+                    Message::Request(request),
+                    next_hop,
+                    Some(destination),
+                    host,
+                    Some(Completion::RequestFailed(client)),
+                );
+                vec![]
+            }
+            Action::SendResponse { server, response } => {
+                let Some(via) = response.vias().first().cloned() else {
+                    debug!("Could not send response without Via header");
+                    return vec![];
+                };
+                let next_hop = response_next_hop(&via);
+                let destination = next_hop
+                    .host
+                    .ip()
+                    .map(|ip| SocketAddr::new(*ip, next_hop.port));
+                self.outbox.send(
+                    Message::Response(response),
+                    next_hop,
+                    destination,
+                    via.host().clone(),
+                    server.map(Completion::ResponseFailed),
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// Forward the request of a server transaction to the resolved address of its next hop, in
+    /// a new client transaction.
+    fn forward(
+        &mut self,
+        server: TransactionKey,
+        branch: String,
+        request: Request,
+        next_hop: NextHop,
+        destination: Result<SocketAddr, std::io::Error>,
+    ) -> Vec<Action> {
+        let prepared = destination.and_then(|destination| {
+            self.stateless
+                .prepare_request(request, &next_hop, destination, &branch)
+                .map(|(request, transport)| (request, transport, destination))
+        });
+        match prepared {
+            Ok((request, transport, destination)) => {
+                self.create_client(server, request, transport, destination, next_hop.host)
+            }
+            Err(err) => {
+                debug!("Could not forward request to {}: {}", next_hop.host, err);
+                self.fork_failed(&server, &branch, Reason::SERVICE_UNAVAILABLE)
+            }
+        }
+    }
+
+    fn create_client(
+        &mut self,
+        server: TransactionKey,
+        request: Request,
+        transport: Transport,
+        destination: SocketAddr,
+        host: Host,
+    ) -> Vec<Action> {
+        let branch = request
+            .vias()
+            .first()
+            .and_then(|via| via.branch())
+            .unwrap_or_default();
+        let mut transaction = match ClientTransaction::new(
+            request,
+            is_reliable(&transport),
+            self.settings,
+            self.clock.clone(),
+        ) {
+            Ok(transaction) => transaction,
+            Err(err) => {
+                debug!("Could not create client transaction: {}", err);
+                return self.fork_failed(&server, &branch, Reason::SERVER_INTERNAL_ERROR);
+            }
+        };
+        let events = transaction.start();
+        let key = transaction.key().clone();
+        self.clients.insert(
+            key.clone(),
+            ClientBranch {
+                transaction,
+                server,
+                transport,
+                destination,
+                host,
+            },
+        );
+        self.client_events(&key, events)
+    }
+
+    /// Remove the response contexts whose server transaction and client transactions are all
+    /// terminated.
+    fn cleanup(&mut self) {
+        self.contexts.retain(|key, _| {
+            self.servers.contains_key(key) || self.clients.values().any(|c| &c.server == key)
+        });
+    }
+}
+
+/// Get the target of a request for a registered contact. The request is sent to the source
+/// address of the REGISTER request when it has been received directly from the user agent.
+fn binding_target(binding: &Binding) -> Target {
+    Target {
+        uri: binding.uri().clone(),
+        q: binding.contact.q().unwrap_or(1.0),
+        path: binding.path.clone(),
+        next_hop: binding
+            .received
+            .as_ref()
+            .map(|(transport, address)| NextHop {
+                transport: transport.clone(),
+                host: Host::Ip(address.ip()),
+                port: address.port(),
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{RegistrarConfig, TargetConfig};
+    use crate::transport::{ConnectionTable, UdpTransport};
+    use imersio_sip::transaction::ManualClock;
+    use tokio::net::UdpSocket;
+
+    struct Network {
+        proxy: StatefulProxy,
+        proxy_address: SocketAddr,
+        completions: mpsc::Receiver<Completion>,
+        clock: ManualClock,
+        uac: UdpSocket,
+        uas: Vec<UdpSocket>,
+    }
+
+    impl Network {
+        async fn new(targets: &[Option<f32>], forking: Forking) -> Self {
+            let (core, _) = tokio::sync::mpsc::channel(1);
+            let mut transports = Transports::new(
+                ConnectionTable::new(10, Duration::from_secs(30), core),
+                None,
+            );
+            let udp = UdpTransport::bind("127.0.0.1:0".parse().unwrap())
+                .await
+                .unwrap();
+            let proxy_address = udp.local_addr();
+            transports.add_udp(udp);
+            let mut uas = vec![];
+            for _ in targets {
+                uas.push(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+            }
+            let route = RouteConfig {
+                uri: "sip:bob@biloxi.com".parse().unwrap(),
+                targets: targets
+                    .iter()
+                    .zip(&uas)
+                    .map(|(q, uas)| TargetConfig {
+                        uri: format!("sip:bob@{}", uas.local_addr().unwrap())
+                            .parse()
+                            .unwrap(),
+                        q: *q,
+                    })
+                    .collect(),
+                forking,
+                timer_c: None,
+            };
+            let clock = ManualClock::new();
+            let (completions, completion_receiver) = mpsc::channel(16);
+            let registrar = Registrar::new(RegistrarConfig {
+                domains: vec!["biloxi.com".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+            let proxy = StatefulProxy::new(
+                transports,
+                vec![route],
+                registrar,
+                Duration::from_secs(200),
+                Arc::new(clock.clone()),
+                completions,
+            );
+            Self {
+                proxy,
+                proxy_address,
+                completions: completion_receiver,
+                clock,
+                uac: UdpSocket::bind("127.0.0.1:0").await.unwrap(),
+                uas,
+            }
+        }
+
+        /// Send a request from the UAC to the proxy.
+        fn send_request(&mut self, request: &str) {
+            let uac_address = self.uac.local_addr().unwrap();
+            self.send_request_from(uac_address, request);
+        }
+
+        /// Send a request from the given address to the proxy.
+        fn send_request_from(&mut self, source: SocketAddr, request: &str) {
+            // The responses are sent back to the source port of the request thanks to rport.
+            let mut request =
+                Request::try_from(request.replace("branch=", "rport;branch=").as_str()).unwrap();
+            request.set_received(source).unwrap();
+            self.proxy.handle(IncomingMessage {
+                message: Message::Request(request),
+                transport: Transport::Udp,
+                local: self.proxy_address,
+                remote: source,
+            });
+        }
+
+        /// Send a response to a forwarded request from a UAS to the proxy.
+        fn send_response(&mut self, uas: usize, request: &Request, reason: Reason) {
+            let mut response = Response::from_request(request, reason);
+            response.set_to_tag(&format!("uas{uas}"));
+            self.proxy.handle(IncomingMessage {
+                message: Message::Response(response),
+                transport: Transport::Udp,
+                local: self.proxy_address,
+                remote: self.uas[uas].local_addr().unwrap(),
+            });
+        }
+
+        /// Receive a message on the socket of the UAC, or of a UAS, handing the completions of the
+        /// operations performed in the background to the proxy meanwhile.
+        async fn receive(&mut self, uas: Option<usize>) -> Message {
+            let socket = match uas {
+                Some(uas) => &self.uas[uas],
+                None => &self.uac,
+            };
+            let mut buffer = vec![0u8; 65536];
+            let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+            loop {
+                tokio::select! {
+                    received = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)) => {
+                        let (size, _) = received.unwrap().unwrap();
+                        return Message::try_from(&buffer[..size]).unwrap();
+                    }
+                    Some(completion) = self.completions.recv() => self.proxy.complete(completion),
+                }
+            }
+        }
+
+        async fn uac_response(&mut self) -> Response {
+            match self.receive(None).await {
+                Message::Response(response) => response,
+                message => panic!("Unexpected message received by the UAC: {message}"),
+            }
+        }
+
+        async fn uas_request(&mut self, uas: usize) -> Request {
+            match self.receive(Some(uas)).await {
+                Message::Request(request) => request,
+                message => panic!("Unexpected message received by the UAS: {message}"),
+            }
+        }
+
+        async fn uas_response(&mut self, uas: usize) -> Response {
+            match self.receive(Some(uas)).await {
+                Message::Response(response) => response,
+                message => panic!("Unexpected message received by the UAS: {message}"),
+            }
+        }
+    }
+
+    const INVITE: &str = "INVITE sip:bob@biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    #[tokio::test]
+    async fn test_parallel_forking() {
+        let mut network = Network::new(&[None, None], Forking::Parallel).await;
+        network.send_request(INVITE);
+        assert_eq!(network.uac_response().await.reason(), &Reason::TRYING);
+        let first = network.uas_request(0).await;
+        let second = network.uas_request(1).await;
+        assert_eq!(
+            first.uri().to_string(),
+            format!("sip:bob@{}", network.uas[0].local_addr().unwrap())
+        );
+        assert_eq!(first.max_forwards(), Some(69));
+        assert_ne!(first.vias()[0].branch(), second.vias()[0].branch());
+
+        network.send_response(0, &first, Reason::RINGING);
+        assert_eq!(network.uac_response().await.reason(), &Reason::RINGING);
+
+        // The 486 response is acknowledged by the proxy, and kept.
+        network.send_response(1, &second, Reason::BUSY_HERE);
+        let ack = network.uas_request(1).await;
+        assert_eq!(ack.method(), &Method::Ack);
+        assert_eq!(ack.vias()[0].branch(), second.vias()[0].branch());
+
+        network.send_response(0, &first, Reason::OK);
+        let ok = network.uac_response().await;
+        assert_eq!(ok.reason(), &Reason::OK);
+        assert_eq!(ok.vias().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sequential_forking() {
+        let mut network = Network::new(&[Some(0.5), Some(1.0)], Forking::Sequential).await;
+        network.send_request(INVITE);
+        assert_eq!(network.uac_response().await.reason(), &Reason::TRYING);
+
+        // The target with the highest q is tried first.
+        let first = network.uas_request(1).await;
+        network.send_response(1, &first, Reason::TEMPORARILY_UNAVAILABLE);
+        assert_eq!(network.uas_request(1).await.method(), &Method::Ack);
+        let second = network.uas_request(0).await;
+        assert_eq!(second.method(), &Method::Invite);
+        network.send_response(0, &second, Reason::RINGING);
+        assert_eq!(network.uac_response().await.reason(), &Reason::RINGING);
+
+        // Timer C expires: the branch that received a provisional response is cancelled.
+        network.clock.advance(Duration::from_secs(200));
+        assert!(network.proxy.next_timeout() <= Some(network.clock.now()));
+        network.proxy.handle_timers();
+        let cancel = network.uas_request(0).await;
+        assert_eq!(cancel.method(), &Method::Cancel);
+        assert_eq!(cancel.vias()[0].branch(), second.vias()[0].branch());
+        network.send_response(0, &cancel, Reason::OK);
+        network.send_response(0, &second, Reason::REQUEST_TERMINATED);
+        assert_eq!(network.uas_request(0).await.method(), &Method::Ack);
+
+        // The best response is the first one of the lowest class.
+        assert_eq!(
+            network.uac_response().await.reason(),
+            &Reason::TEMPORARILY_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let mut network = Network::new(&[None], Forking::Parallel).await;
+        network.send_request(INVITE);
+        assert_eq!(network.uac_response().await.reason(), &Reason::TRYING);
+        let invite = network.uas_request(0).await;
+        network.send_response(0, &invite, Reason::RINGING);
+        assert_eq!(network.uac_response().await.reason(), &Reason::RINGING);
+
+        let cancel = Request::try_from(INVITE).unwrap().cancel().unwrap();
+        network.send_request(&cancel.to_string());
+        let ok = network.uac_response().await;
+        assert_eq!(ok.reason(), &Reason::OK);
+        assert_eq!(
+            ok.headers()
+                .iter()
+                .find(|header| matches!(header, imersio_sip::Header::CSeq(_)))
+                .map(ToString::to_string),
+            Some("CSeq: 314159 CANCEL".to_string())
+        );
+        let forwarded = network.uas_request(0).await;
+        assert_eq!(forwarded.method(), &Method::Cancel);
+
+        network.send_response(0, &forwarded, Reason::OK);
+        network.send_response(0, &invite, Reason::REQUEST_TERMINATED);
+        assert_eq!(network.uas_request(0).await.method(), &Method::Ack);
+        assert_eq!(
+            network.uac_response().await.reason(),
+            &Reason::REQUEST_TERMINATED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_without_final_response() {
+        let mut network = Network::new(&[None], Forking::Parallel).await;
+        network.send_request(INVITE);
+        assert_eq!(network.uac_response().await.reason(), &Reason::TRYING);
+        let invite = network.uas_request(0).await;
+        network.send_response(0, &invite, Reason::RINGING);
+        assert_eq!(network.uac_response().await.reason(), &Reason::RINGING);
+
+        let cancel = Request::try_from(INVITE).unwrap().cancel().unwrap();
+        network.send_request(&cancel.to_string());
+        assert_eq!(network.uac_response().await.reason(), &Reason::OK);
+        let forwarded = network.uas_request(0).await;
+        network.send_response(0, &forwarded, Reason::OK);
+
+        // The UAS never answers the INVITE: the branch is abandoned after 64*T1.
+        network.clock.advance(Duration::from_secs(32));
+        network.proxy.handle_timers();
+        assert_eq!(
+            network.uac_response().await.reason(),
+            &Reason::REQUEST_TIMEOUT
+        );
+        assert!(
+            network
+                .proxy
+                .clients
+                .values()
+                .all(|client| !client.transaction.is_invite())
+        );
+
+        // The response context is removed with the server transaction.
+        network.clock.advance(Duration::from_secs(32));
+        network.proxy.handle_timers();
+        assert!(network.proxy.contexts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registered_contact() {
+        let mut network = Network::new(&[None], Forking::Parallel).await;
+        let uas_address = network.uas[0].local_addr().unwrap();
+        let register = "REGISTER sip:biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP 192.0.2.99:5060;branch=z9hG4bKnashds7\r\n\
+Max-Forwards: 70\r\n\
+To: Carol <sip:carol@biloxi.com>\r\n\
+From: Carol <sip:carol@biloxi.com>;tag=456248\r\n\
+Call-ID: 843817637684230@998sdasdh09\r\n\
+CSeq: 1826 REGISTER\r\n\
+Contact: <sip:carol@192.0.2.99>\r\n\
+Expires: 7200\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        network.send_request_from(uas_address, register);
+        let ok = network.uas_response(0).await;
+        assert_eq!(ok.reason(), &Reason::OK);
+        assert_eq!(
+            ok.headers()
+                .iter()
+                .find(|header| matches!(header, imersio_sip::Header::Contact(_)))
+                .map(ToString::to_string),
+            Some("Contact: <sip:carol@192.0.2.99>;expires=7200".to_string())
+        );
+
+        // The request for the registered user is sent to the source of the REGISTER request.
+        network.send_request(&INVITE.replace("sip:bob@biloxi.com", "sip:carol@biloxi.com"));
+        assert_eq!(network.uac_response().await.reason(), &Reason::TRYING);
+        let invite = network.uas_request(0).await;
+        assert_eq!(invite.uri().to_string(), "sip:carol@192.0.2.99");
+
+        network.send_request(
+            &INVITE
+                .replace("sip:bob@biloxi.com", "sip:dave@biloxi.com")
+                .replace("z9hG4bKnashds8", "z9hG4bKnashds9"),
+        );
+        assert_eq!(network.uac_response().await.reason(), &Reason::TRYING);
+        assert_eq!(
+            network.uac_response().await.reason(),
+            &Reason::TEMPORARILY_UNAVAILABLE
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Binding store keeping the bindings in a JSON file, so that they survive the restarts of the
+//! proxy.
+
+use imersio_sip::{Contact, Header, Route, Transport};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use super::location::{Binding, BindingStore};
+use super::memory::MemoryStore;
+
+/// The binding store keeping the bindings in memory, and writing them to a file in the background
+/// after they change, so that the core never waits for the disk.
+///
+/// The changes made while the file is being written are written together afterwards. The file
+/// is replaced atomically once its new content is on the disk, so that it is never left
+/// half-written.
+#[derive(Debug)]
+pub(crate) struct FileStore {
+    bindings: MemoryStore,
+    updates: mpsc::UnboundedSender<Update>,
+    /// The task writing the file, that writes the pending changes before ending when the store
+    /// is dropped.
+    #[cfg(test)]
+    writer: tokio::task::JoinHandle<()>,
+}
+
+/// A change of the bindings, applied to the copy of the bindings of the writer.
+#[derive(Debug)]
+enum Update {
+    SetBindings(String, Vec<Binding>),
+    RemoveExpired(SystemTime),
+}
+
+impl FileStore {
+    /// Open the store, loading the bindings from the given file if it exists, and start the task
+    /// writing them.
+    pub(crate) fn open(path: &Path) -> Result<Self, std::io::Error> {
+        let mut bindings = MemoryStore::default();
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let stored: Vec<StoredBinding> = serde_json::from_str(&content).map_err(|e| {
+                    invalid_data(format!(
+                        "Could not parse bindings file '{}': {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                for stored in stored {
+                    let aor = stored.aor.clone();
+                    let mut aor_bindings = bindings.bindings(&aor)?;
+                    aor_bindings.push(stored.try_into()?);
+                    bindings.set_bindings(&aor, aor_bindings)?;
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => {
+                return Err(std::io::Error::new(
+                    err.kind(),
+                    format!("Could not read bindings file '{}': {}", path.display(), err),
+                ));
+            }
+        }
+        let (updates, receiver) = mpsc::unbounded_channel();
+        let _writer = tokio::spawn(write_behind(path.to_path_buf(), bindings.clone(), receiver));
+        Ok(Self {
+            bindings,
+            updates,
+            #[cfg(test)]
+            writer: _writer,
+        })
+    }
+
+    /// Stop the store, waiting for the last changes to be written.
+    #[cfg(test)]
+    pub(crate) async fn close(self) {
+        drop(self.updates);
+        self.writer.await.unwrap();
+    }
+
+    fn update(&self, update: Update) -> Result<(), std::io::Error> {
+        self.updates.send(update).map_err(|_| {
+            std::io::Error::new(
+                ErrorKind::BrokenPipe,
+                "The bindings file is not written anymore",
+            )
+        })
+    }
+}
+
+impl BindingStore for FileStore {
+    fn bindings(&self, aor: &str) -> Result<Vec<Binding>, std::io::Error> {
+        self.bindings.bindings(aor)
+    }
+
+    fn instance_bindings(
+        &self,
+        instance_id: &str,
+        reg_id: Option<u32>,
+    ) -> Result<Vec<(String, Binding)>, std::io::Error> {
+        self.bindings.instance_bindings(instance_id, reg_id)
+    }
+
+    fn set_bindings(&mut self, aor: &str, bindings: Vec<Binding>) -> Result<(), std::io::Error> {
+        self.bindings.set_bindings(aor, bindings.clone())?;
+        self.update(Update::SetBindings(aor.to_string(), bindings))
+    }
+
+    fn remove_expired(&mut self, now: SystemTime) -> Result<usize, std::io::Error> {
+        let removed = self.bindings.remove_expired(now)?;
+        if removed > 0 {
+            self.update(Update::RemoveExpired(now))?;
+        }
+        Ok(removed)
+    }
+}
+
+/// Apply the changes of the bindings to a copy of them, writing it to the file after each batch
+/// of changes, until the store is dropped.
+async fn write_behind(
+    path: PathBuf,
+    mut bindings: MemoryStore,
+    mut updates: mpsc::UnboundedReceiver<Update>,
+) {
+    let apply = |bindings: &mut MemoryStore, update: Update| match update {
+        Update::SetBindings(aor, aor_bindings) => bindings.set_bindings(&aor, aor_bindings),
+        Update::RemoveExpired(now) => bindings.remove_expired(now).map(|_| ()),
+    };
+    while let Some(update) = updates.recv().await {
+        // The memory store does not fail.
+        let _ = apply(&mut bindings, update);
+        while let Ok(update) = updates.try_recv() {
+            let _ = apply(&mut bindings, update);
+        }
+        let file = path.clone();
+        let written = tokio::task::spawn_blocking(move || {
+            let result = save(&file, &bindings);
+            (bindings, result)
+        })
+        .await;
+        match written {
+            Ok((written, result)) => {
+                bindings = written;
+                if let Err(err) = result {
+                    warn!(
+                        "Could not write bindings file '{}': {}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
+            Err(err) => {
+                warn!(
+                    "Could not write bindings file '{}': {}",
+                    path.display(),
+                    err
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Write the bindings to a file, replacing it atomically and durably.
+fn save(path: &Path, bindings: &MemoryStore) -> Result<(), std::io::Error> {
+    let stored: Vec<StoredBinding> = bindings
+        .iter()
+        .map(|(aor, binding)| StoredBinding::new(aor, binding))
+        .collect();
+    let content = serde_json::to_vec_pretty(&stored).map_err(std::io::Error::other)?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(&content)?;
+    // The new content must be on the disk before it replaces the previous one.
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)?;
+    sync_directory(path)
+}
+
+/// Make the renaming of a file durable, by syncing the directory containing it.
+#[cfg(unix)]
+fn sync_directory(path: &Path) -> Result<(), std::io::Error> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_directory(_path: &Path) -> Result<(), std::io::Error> {
+    Ok(())
+}
+
+/// A binding as written in the file.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct StoredBinding {
+    aor: String,
+    contact: String,
+    /// The expiration time, in seconds since the Unix epoch.
+    expires_at: u64,
+    call_id: String,
+    cseq: u32,
+    #[serde(default)]
+    path: Vec<String>,
+    #[serde(default)]
+    received: Option<StoredSource>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct StoredSource {
+    transport: String,
+    address: SocketAddr,
+}
+
+impl StoredBinding {
+    fn new(aor: &str, binding: &Binding) -> Self {
+        Self {
+            aor: aor.to_string(),
+            contact: encode_contact(&binding.contact),
+            expires_at: binding
+                .expires_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            call_id: binding.call_id.clone(),
+            cseq: binding.cseq,
+            path: binding.path.iter().map(ToString::to_string).collect(),
+            received: binding
+                .received
+                .as_ref()
+                .map(|(transport, address)| StoredSource {
+                    transport: transport.to_string(),
+                    address: *address,
+                }),
+        }
+    }
+}
+
+impl TryFrom<StoredBinding> for Binding {
+    type Error = std::io::Error;
+
+    fn try_from(value: StoredBinding) -> Result<Self, Self::Error> {
+        let contact = match Header::try_from(format!("Contact: {}", value.contact).as_str()) {
+            Ok(Header::Contact(header)) if header.contacts().len() == 1 => {
+                header.contacts()[0].clone()
+            }
+            _ => return Err(invalid_data(format!("Invalid contact: {}", value.contact))),
+        };
+        let path = value
+            .path
+            .iter()
+            .map(
+                |route| match Header::try_from(format!("Route: {route}").as_str()) {
+                    Ok(Header::Route(header)) if header.routes().len() == 1 => {
+                        Ok(header.routes()[0].clone())
+                    }
+                    _ => Err(invalid_data(format!("Invalid path: {route}"))),
+                },
+            )
+            .collect::<Result<Vec<Route>, _>>()?;
+        let received = value
+            .received
+            .map(|source| {
+                Transport::try_from(source.transport.as_str())
+                    .map(|transport| (transport, source.address))
+                    .map_err(|e| invalid_data(e.to_string()))
+            })
+            .transpose()?;
+        Ok(Binding::new(
+            contact,
+            UNIX_EPOCH + Duration::from_secs(value.expires_at),
+            value.call_id,
+            value.cseq,
+            path,
+            received,
+        ))
+    }
+}
+
+/// Encode a contact keeping the case and the quotes of the values of its parameters, that its
+/// normalized form does not preserve, eg. for the `+sip.instance` feature tag.
+fn encode_contact(contact: &Contact) -> String {
+    let mut value = contact.address().to_string();
+    for parameter in contact.parameters() {
+        value.push(';');
+        value.push_str(parameter.key());
+        match parameter.value() {
+            Some(parameter_value) if is_token(parameter_value) => {
+                value.push('=');
+                value.push_str(parameter_value);
+            }
+            Some(parameter_value) => {
+                value.push_str("=\"");
+                value.push_str(&parameter_value.replace('\\', "\\\\").replace('"', "\\\""));
+                value.push('"');
+            }
+            None => (),
+        }
+    }
+    value
+}
+
+/// [[RFC3261, Section 25.1](https://datatracker.ietf.org/doc/html/rfc3261#section-25.1)]
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-.!%*_+`'~".contains(c))
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registrar::location::expires;
+    use crate::registrar::location::tests::{binding, contacts};
+
+    struct TemporaryFile(PathBuf);
+
+    impl TemporaryFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "imersio-{}-{}-{name}.json",
+                std::process::id(),
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_nanos()
+            ));
+            Self(path)
+        }
+    }
+
+    impl Drop for TemporaryFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_store_survives_restarts() {
+        let file = TemporaryFile::new("bindings");
+        // Whole seconds, as stored in the file.
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let contacts = contacts(
+            r#""Bob" <sip:bob@192.0.2.4;transport=tcp>;q=0.7;+sip.instance="<urn:uuid:00000000-0000-1000-8000-000A95A0E128>";reg-id=1;audio"#,
+        );
+        let mut first = binding(&contacts[0], now + expires(3600));
+        first.path = vec![Route::from(
+            imersio_sip::Uri::try_from("sip:p1.example.com;lr").unwrap(),
+        )];
+        first.received = Some((Transport::Tcp, "192.0.2.4:49152".parse().unwrap()));
+
+        let mut store = FileStore::open(&file.0).unwrap();
+        assert!(store.bindings("sip:bob@biloxi.com").unwrap().is_empty());
+        store
+            .set_bindings("sip:bob@biloxi.com", vec![first.clone()])
+            .unwrap();
+        store.close().await;
+
+        let mut store = FileStore::open(&file.0).unwrap();
+        let bindings = store.bindings("sip:bob@biloxi.com").unwrap();
+        assert_eq!(bindings, vec![first]);
+        assert_eq!(
+            bindings[0].instance_id.as_deref(),
+            Some("urn:uuid:00000000-0000-1000-8000-000A95A0E128")
+        );
+        assert_eq!(
+            store
+                .instance_bindings("urn:uuid:00000000-0000-1000-8000-000A95A0E128", Some(1))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert_eq!(store.remove_expired(now + expires(3600)).unwrap(), 1);
+        store.close().await;
+        let store = FileStore::open(&file.0).unwrap();
+        assert!(store.bindings("sip:bob@biloxi.com").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_invalid_file() {
+        let file = TemporaryFile::new("invalid");
+        std::fs::write(&file.0, "[{\"aor\": \"sip:bob@biloxi.com\"}]").unwrap();
+        let err = FileStore::open(&file.0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,193 @@
+//! Location service, storing the bindings of the addresses-of-record to the contacts registered
+//! for them in a binding store.
+//!
+//! [[RFC3261, Section 10.2](https://datatracker.ietf.org/doc/html/rfc3261#section-10.2)]
+
+use imersio_sip::{Contact, Route, Transport, Uri};
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+/// A binding of an address-of-record to a contact.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Binding {
+    /// The contact, as given in the REGISTER request.
+    pub(crate) contact: Contact,
+    pub(crate) expires_at: SystemTime,
+    /// The Call-ID and the CSeq of the last REGISTER request that updated the binding.
+    pub(crate) call_id: String,
+    pub(crate) cseq: u32,
+    /// The path vector of the REGISTER request, preloaded as route set of the requests forwarded
+    /// to the contact.
+    ///
+    /// [[RFC3327, Section 5.3](https://datatracker.ietf.org/doc/html/rfc3327#section-5.3)]
+    pub(crate) path: Vec<Route>,
+    pub(crate) instance_id: Option<String>,
+    pub(crate) reg_id: Option<u32>,
+    /// The transport and the source address of the REGISTER request, when it has been received
+    /// directly from the user agent.
+    pub(crate) received: Option<(Transport, SocketAddr)>,
+}
+
+impl Binding {
+    /// Create a binding to a contact, identified by the instance and the registration
+    /// identifiers of the contact if it has some.
+    pub(crate) fn new(
+        contact: Contact,
+        expires_at: SystemTime,
+        call_id: String,
+        cseq: u32,
+        path: Vec<Route>,
+        received: Option<(Transport, SocketAddr)>,
+    ) -> Self {
+        Self {
+            instance_id: contact.instance_id(),
+            reg_id: contact.reg_id(),
+            contact,
+            expires_at,
+            call_id,
+            cseq,
+            path,
+            received,
+        }
+    }
+
+    /// Get the URI of the contact.
+    pub(crate) fn uri(&self) -> &Uri {
+        self.contact.address().uri()
+    }
+
+    /// Tell whether the binding is the one of a contact: the one of the same instance and flow
+    /// when the contact identifies its instance, or else the one of the same URI.
+    ///
+    /// [[RFC5626, Section 6](https://datatracker.ietf.org/doc/html/rfc5626#section-6)]
+    pub(crate) fn is_for(&self, contact: &Contact) -> bool {
+        match (&self.instance_id, contact.instance_id()) {
+            (Some(instance_id), Some(other)) => {
+                instance_id == &other && self.reg_id == contact.reg_id()
+            }
+            (None, None) => self.uri() == contact.address().uri(),
+            _ => false,
+        }
+    }
+
+    /// Get the remaining lifetime of the binding at the given time, in seconds.
+    pub(crate) fn remaining(&self, now: SystemTime) -> u32 {
+        let remaining = self.expires_at.duration_since(now).unwrap_or_default();
+        u32::try_from(remaining.as_secs()).unwrap_or(u32::MAX)
+    }
+
+    /// Tell whether the binding has expired at the given time.
+    pub(crate) fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// A store of the bindings of the location service.
+///
+/// The bindings of an address-of-record are read and written all at once, so that a store
+/// shared by several proxies, eg. in a key-value database, can update them atomically.
+///
+/// The store is used by the core handling all the messages, so it must not wait for I/O: a store
+/// keeping the bindings on a disk or in a remote database answers from a copy of them in memory,
+/// and writes the changes in the background, as the file store does.
+pub(crate) trait BindingStore: std::fmt::Debug + Send {
+    /// Get the bindings of an address-of-record, including the expired ones that have not been
+    /// removed yet.
+    fn bindings(&self, aor: &str) -> Result<Vec<Binding>, std::io::Error>;
+
+    /// Get the bindings of a user agent instance with their address-of-record, for the given
+    /// registration identifier or for all of them.
+    fn instance_bindings(
+        &self,
+        instance_id: &str,
+        reg_id: Option<u32>,
+    ) -> Result<Vec<(String, Binding)>, std::io::Error>;
+
+    /// Replace the bindings of an address-of-record, removing it when there are none.
+    fn set_bindings(&mut self, aor: &str, bindings: Vec<Binding>) -> Result<(), std::io::Error>;
+
+    /// Remove the bindings that have expired at the given time, returning how many have been
+    /// removed.
+    fn remove_expired(&mut self, now: SystemTime) -> Result<usize, std::io::Error>;
+}
+
+/// Get the canonical form of the address-of-record of a SIP or SIPS URI: its scheme, its user
+/// and its host, without its port and its parameters, the host being case-insensitive.
+///
+/// [[RFC3261, Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3)]
+pub(crate) fn address_of_record(uri: &Uri) -> Option<String> {
+    let uri = uri.as_sip_uri()?;
+    let scheme = uri.scheme().to_string().to_ascii_lowercase();
+    let host = uri.host().to_string().to_ascii_lowercase();
+    Some(match uri.userinfo() {
+        Some(userinfo) => format!("{}:{}@{}", scheme, userinfo.user(), host),
+        None => format!("{}:{}", scheme, host),
+    })
+}
+
+/// Convert an expiration interval from seconds.
+pub(crate) fn expires(seconds: u32) -> Duration {
+    Duration::from_secs(u64::from(seconds))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use imersio_sip::{Header, Request};
+
+    pub(crate) fn contacts(value: &str) -> Vec<Contact> {
+        let request = Request::try_from(
+            format!("REGISTER sip:biloxi.com SIP/2.0\r\nContact: {value}\r\n\r\n").as_str(),
+        )
+        .unwrap();
+        match &request.headers()[0] {
+            Header::Contact(header) => header.contacts().to_vec(),
+            header => panic!("Unexpected header: {header}"),
+        }
+    }
+
+    pub(crate) fn binding(contact: &Contact, expires_at: SystemTime) -> Binding {
+        Binding::new(
+            contact.clone(),
+            expires_at,
+            "843817637684230@998sdasdh09".to_string(),
+            1826,
+            vec![],
+            None,
+        )
+    }
+
+    #[test]
+    fn test_address_of_record() {
+        assert_eq!(
+            address_of_record(&Uri::try_from("sip:bob@BILOXI.com:5060;transport=tcp").unwrap()),
+            Some("sip:bob@biloxi.com".to_string())
+        );
+        assert_eq!(
+            address_of_record(&Uri::try_from("sips:biloxi.com").unwrap()),
+            Some("sips:biloxi.com".to_string())
+        );
+        assert_eq!(
+            address_of_record(&Uri::try_from("tel:+1-201-555-0123").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_binding_identity() {
+        let now = SystemTime::now();
+        let contacts = contacts(
+            r#"<sip:bob@192.0.2.4>, <sip:bob@192.0.2.5>;+sip.instance="<urn:uuid:00000000-0000-1000-8000-000a95a0e128>";reg-id=1, <sip:bob@192.0.2.6>;+sip.instance="<urn:uuid:00000000-0000-1000-8000-000a95a0e128>";reg-id=2, <sip:bob@192.0.2.5>"#,
+        );
+        let binding = binding(&contacts[1], now + expires(3600));
+        assert!(!binding.is_for(&contacts[0]));
+        assert!(binding.is_for(&contacts[1]));
+        // Another flow of the same instance.
+        assert!(!binding.is_for(&contacts[2]));
+        // The same URI without instance.
+        assert!(!binding.is_for(&contacts[3]));
+        assert_eq!(binding.remaining(now), 3600);
+        assert!(!binding.is_expired(now));
+        assert!(binding.is_expired(now + expires(3600)));
+    }
+}
@@ -0,0 +1,110 @@
+//! Binding store keeping the bindings in memory, that are lost when the proxy stops.
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use super::location::{Binding, BindingStore};
+
+/// The binding store keeping the bindings in memory.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MemoryStore {
+    bindings: HashMap<String, Vec<Binding>>,
+}
+
+impl MemoryStore {
+    /// Iterate over all the bindings, with their address-of-record.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &Binding)> {
+        self.bindings
+            .iter()
+            .flat_map(|(aor, bindings)| bindings.iter().map(move |binding| (aor.as_str(), binding)))
+    }
+}
+
+impl BindingStore for MemoryStore {
+    fn bindings(&self, aor: &str) -> Result<Vec<Binding>, std::io::Error> {
+        Ok(self.bindings.get(aor).cloned().unwrap_or_default())
+    }
+
+    fn instance_bindings(
+        &self,
+        instance_id: &str,
+        reg_id: Option<u32>,
+    ) -> Result<Vec<(String, Binding)>, std::io::Error> {
+        Ok(self
+            .iter()
+            .filter(|(_, binding)| {
+                binding.instance_id.as_deref() == Some(instance_id)
+                    && (reg_id.is_none() || binding.reg_id == reg_id)
+            })
+            .map(|(aor, binding)| (aor.to_string(), binding.clone()))
+            .collect())
+    }
+
+    fn set_bindings(&mut self, aor: &str, bindings: Vec<Binding>) -> Result<(), std::io::Error> {
+        if bindings.is_empty() {
+            self.bindings.remove(aor);
+        } else {
+            self.bindings.insert(aor.to_string(), bindings);
+        }
+        Ok(())
+    }
+
+    fn remove_expired(&mut self, now: SystemTime) -> Result<usize, std::io::Error> {
+        let mut removed = 0;
+        self.bindings.retain(|_, bindings| {
+            let count = bindings.len();
+            bindings.retain(|binding| !binding.is_expired(now));
+            removed += count - bindings.len();
+            !bindings.is_empty()
+        });
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registrar::location::expires;
+    use crate::registrar::location::tests::{binding, contacts};
+
+    #[test]
+    fn test_memory_store() {
+        let now = SystemTime::now();
+        let contacts = contacts(
+            r#"<sip:bob@192.0.2.4>, <sip:bob@192.0.2.5>;+sip.instance="<urn:uuid:00000000-0000-1000-8000-000a95a0e128>";reg-id=1"#,
+        );
+        let mut store = MemoryStore::default();
+        store
+            .set_bindings(
+                "sip:bob@biloxi.com",
+                vec![
+                    binding(&contacts[0], now + expires(60)),
+                    binding(&contacts[1], now + expires(3600)),
+                ],
+            )
+            .unwrap();
+        store
+            .set_bindings(
+                "sip:bob@atlanta.com",
+                vec![binding(&contacts[1], now + expires(3600))],
+            )
+            .unwrap();
+        assert_eq!(store.bindings("sip:bob@biloxi.com").unwrap().len(), 2);
+        assert!(store.bindings("sip:carol@biloxi.com").unwrap().is_empty());
+
+        let instance = "urn:uuid:00000000-0000-1000-8000-000a95a0e128";
+        assert_eq!(store.instance_bindings(instance, None).unwrap().len(), 2);
+        assert_eq!(store.instance_bindings(instance, Some(1)).unwrap().len(), 2);
+        assert!(
+            store
+                .instance_bindings(instance, Some(2))
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(store.remove_expired(now + expires(120)).unwrap(), 1);
+        assert_eq!(store.bindings("sip:bob@biloxi.com").unwrap().len(), 1);
+        store.set_bindings("sip:bob@biloxi.com", vec![]).unwrap();
+        assert_eq!(store.iter().count(), 1);
+    }
+}
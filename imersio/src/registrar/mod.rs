@@ -0,0 +1,565 @@
+//! Registrar of the proxy, binding the addresses-of-record of its domains to the contacts given
+//! in the REGISTER requests, and finding the contacts of the requests addressed to them.
+//!
+//! [[RFC3261, Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3)]
+
+mod file;
+mod location;
+mod memory;
+
+use chrono::TimeDelta;
+use imersio_sip::{
+    Contact, ContactHeader, Contacts, Header, MinExpiresHeader, PathHeader, Reason, Request, Route,
+    Transport, Uri,
+};
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+use crate::config::{RegistrarConfig, StoreConfig};
+use file::FileStore;
+pub(crate) use location::Binding;
+use location::{BindingStore, address_of_record, expires};
+use memory::MemoryStore;
+
+/// The outcome of the processing of a REGISTER request.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RegistrationOutcome {
+    /// The bindings have been updated.
+    Accepted {
+        /// The current bindings of the address-of-record, with their remaining lifetime.
+        contacts: Vec<Contact>,
+        /// The path vector of the request, echoed in the response.
+        path: Vec<Route>,
+    },
+    /// The REGISTER request has been rejected.
+    Rejected {
+        /// The reason of the rejection.
+        reason: Reason,
+        /// The minimum expiration interval, for a 423 (Interval Too Brief) response.
+        min_expires: Option<Duration>,
+    },
+}
+
+impl RegistrationOutcome {
+    /// Get the reason of the response to send for the REGISTER request.
+    pub(crate) fn reason(&self) -> Reason {
+        match self {
+            Self::Accepted { .. } => Reason::OK,
+            Self::Rejected { reason, .. } => reason.clone(),
+        }
+    }
+
+    /// Get the headers to include in the response to send for the REGISTER request.
+    ///
+    /// A 200 response contains the current bindings in its Contact header and the path vector of
+    /// the request, and a 423 response contains the Min-Expires header.
+    pub(crate) fn headers(&self) -> Vec<Header> {
+        match self {
+            Self::Accepted { contacts, path } => {
+                let mut headers = vec![];
+                if !contacts.is_empty() {
+                    headers.push(Header::Contact(ContactHeader::from(Contacts::from(
+                        contacts.clone(),
+                    ))));
+                }
+                if !path.is_empty() {
+                    headers.push(Header::Path(
+                        PathHeader::builder().routes(path.clone()).build(),
+                    ));
+                }
+                headers
+            }
+            Self::Rejected { min_expires, .. } => min_expires
+                .iter()
+                .filter_map(|min_expires| TimeDelta::from_std(*min_expires).ok())
+                .map(|min_expires| Header::MinExpires(MinExpiresHeader::from(min_expires)))
+                .collect(),
+        }
+    }
+
+    fn rejected(reason: Reason) -> Self {
+        Self::Rejected {
+            reason,
+            min_expires: None,
+        }
+    }
+}
+
+/// The registrar, storing the bindings in its binding store.
+#[derive(Debug)]
+pub(crate) struct Registrar {
+    domains: Vec<String>,
+    default_expires: Duration,
+    min_expires: Duration,
+    max_expires: Duration,
+    store: Box<dyn BindingStore>,
+}
+
+impl Registrar {
+    /// Create the registrar, responsible for the domains of the given configuration, opening the
+    /// binding store it selects.
+    pub(crate) fn new(config: RegistrarConfig) -> Result<Self, std::io::Error> {
+        let store: Box<dyn BindingStore> = match &config.store {
+            StoreConfig::Memory => Box::new(MemoryStore::default()),
+            StoreConfig::File { path } => Box::new(FileStore::open(path)?),
+        };
+        Ok(Self {
+            domains: config.domains,
+            default_expires: Duration::from_secs(config.default_expires),
+            min_expires: Duration::from_secs(config.min_expires),
+            max_expires: Duration::from_secs(config.max_expires),
+            store,
+        })
+    }
+
+    /// Tell whether the domain of a URI is one of the domains of the registrar.
+    pub(crate) fn is_responsible_for(&self, uri: &Uri) -> bool {
+        uri.as_sip_uri().is_some_and(|uri| {
+            let host = uri.host().to_string();
+            self.domains
+                .iter()
+                .any(|domain| domain.eq_ignore_ascii_case(&host))
+        })
+    }
+
+    /// Get the bindings of the address-of-record of a Request-URI that have not expired at the
+    /// given time. A GRUU only gets the bindings of the user agent instance it designates.
+    ///
+    /// [[RFC5627, Section 3.1](https://datatracker.ietf.org/doc/html/rfc5627#section-3.1)]
+    pub(crate) fn lookup(
+        &self,
+        uri: &Uri,
+        now: SystemTime,
+    ) -> Result<Vec<Binding>, std::io::Error> {
+        let Some(aor) = address_of_record(uri) else {
+            return Ok(vec![]);
+        };
+        let instance_id = uri
+            .parameters()
+            .get("gr")
+            .and_then(|parameter| parameter.value());
+        let bindings = match instance_id {
+            Some(instance_id) => self
+                .store
+                .instance_bindings(&instance_id, None)?
+                .into_iter()
+                .filter(|(binding_aor, _)| binding_aor == &aor)
+                .map(|(_, binding)| binding)
+                .collect(),
+            None => self.store.bindings(&aor)?,
+        };
+        Ok(bindings
+            .into_iter()
+            .filter(|binding| !binding.is_expired(now))
+            .collect())
+    }
+
+    /// Remove the bindings that have expired at the given time.
+    pub(crate) fn remove_expired(&mut self, now: SystemTime) {
+        match self.store.remove_expired(now) {
+            Ok(0) => (),
+            Ok(removed) => debug!("Removed {} expired bindings", removed),
+            Err(err) => warn!("Could not remove the expired bindings: {}", err),
+        }
+    }
+
+    /// Process a REGISTER request received at the given time from the given source, and update
+    /// the bindings of its address-of-record accordingly.
+    ///
+    /// The bindings are either all updated or left untouched. The authentication of the request
+    /// is left to the caller.
+    ///
+    /// [[RFC3261, Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3)]
+    pub(crate) fn register(
+        &mut self,
+        request: &Request,
+        source: (Transport, SocketAddr),
+        now: SystemTime,
+    ) -> RegistrationOutcome {
+        match self.update(request, source, now) {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                warn!("Could not update the bindings: {}", err);
+                RegistrationOutcome::rejected(Reason::SERVER_INTERNAL_ERROR)
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        request: &Request,
+        source: (Transport, SocketAddr),
+        now: SystemTime,
+    ) -> Result<RegistrationOutcome, std::io::Error> {
+        let mut to = None;
+        let mut call_id = None;
+        let mut cseq = None;
+        let mut expires_header = None;
+        let mut contacts = vec![];
+        for header in request.headers() {
+            match header {
+                Header::To(header) => to = Some(header.address().uri().clone()),
+                Header::CallId(header) => call_id = Some(header.call_id().to_string()),
+                Header::CSeq(header) => cseq = Some(header.cseq()),
+                Header::Expires(header) => {
+                    expires_header = Some(Duration::from_secs(
+                        u64::try_from(header.expires().num_seconds()).unwrap_or_default(),
+                    ))
+                }
+                Header::Contact(header) => contacts.push(header.contacts()),
+                _ => (),
+            }
+        }
+        let (Some(to), Some(call_id), Some(cseq)) = (to, call_id, cseq) else {
+            return Ok(RegistrationOutcome::rejected(Reason::BAD_REQUEST));
+        };
+
+        // The address-of-record must be in the domain of the Request-URI.
+        let same_domain = match (to.as_sip_uri(), request.uri().as_sip_uri()) {
+            (Some(to), Some(uri)) => to.host() == uri.host(),
+            _ => false,
+        };
+        let aor = match address_of_record(&to) {
+            Some(aor) if same_domain || self.is_responsible_for(&to) => aor,
+            _ => return Ok(RegistrationOutcome::rejected(Reason::NOT_FOUND)),
+        };
+        let mut bindings: Vec<Binding> = self
+            .store
+            .bindings(&aor)?
+            .into_iter()
+            .filter(|binding| !binding.is_expired(now))
+            .collect();
+
+        // A wildcard removes all the bindings, and must be alone with an expiration of 0.
+        if contacts.iter().any(|contacts| contacts.is_any()) {
+            if contacts.len() != 1 || expires_header != Some(Duration::ZERO) {
+                return Ok(RegistrationOutcome::rejected(Reason::BAD_REQUEST));
+            }
+            // An out of order request must not remove any binding.
+            if bindings
+                .iter()
+                .any(|binding| binding.call_id == call_id && binding.cseq >= cseq)
+            {
+                return Ok(RegistrationOutcome::rejected(Reason::SERVER_INTERNAL_ERROR));
+            }
+            self.store.set_bindings(&aor, vec![])?;
+            return Ok(accepted(vec![], request, now));
+        }
+
+        let mut updates = vec![];
+        for contact in contacts.into_iter().flat_map(|contacts| contacts.iter()) {
+            let interval = contact
+                .expires()
+                .map(expires)
+                .or(expires_header)
+                .unwrap_or(self.default_expires);
+            if !interval.is_zero() && interval < self.min_expires {
+                return Ok(RegistrationOutcome::Rejected {
+                    reason: Reason::INTERVAL_TOO_BRIEF,
+                    min_expires: Some(self.min_expires),
+                });
+            }
+            // An out of order request must not update the binding.
+            let existing = bindings.iter().find(|binding| binding.is_for(contact));
+            if let Some(binding) = existing {
+                if binding.call_id == call_id && binding.cseq >= cseq {
+                    return Ok(RegistrationOutcome::rejected(Reason::SERVER_INTERNAL_ERROR));
+                }
+            }
+            updates.push((contact, interval.min(self.max_expires)));
+        }
+        if updates.is_empty() {
+            return Ok(accepted(bindings, request, now));
+        }
+
+        let path: Vec<Route> = request.path().iter().cloned().collect();
+        // The source of a request received directly from the user agent can reach it even when
+        // its contact cannot be reached, eg. behind a NAT.
+        let received = (path.is_empty() && request.vias().len() == 1).then_some(source);
+        for (contact, interval) in updates {
+            bindings.retain(|binding| !binding.is_for(contact));
+            if !interval.is_zero() {
+                bindings.push(Binding::new(
+                    contact.clone(),
+                    now + interval,
+                    call_id.clone(),
+                    cseq,
+                    path.clone(),
+                    received.clone(),
+                ));
+            }
+        }
+        self.store.set_bindings(&aor, bindings.clone())?;
+        Ok(accepted(bindings, request, now))
+    }
+}
+
+fn accepted(bindings: Vec<Binding>, request: &Request, now: SystemTime) -> RegistrationOutcome {
+    RegistrationOutcome::Accepted {
+        contacts: bindings
+            .iter()
+            .map(|binding| binding.contact.with_expires(binding.remaining(now)))
+            .collect(),
+        path: request.path().iter().cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "192.0.2.4:5060";
+
+    fn register(cseq: u32, headers: &str) -> Request {
+        Request::try_from(
+            format!(
+                "REGISTER sip:registrar.biloxi.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP bobspc.biloxi.com:5060;branch=z9hG4bKnashds7\r\n\
+Max-Forwards: 70\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Bob <sip:bob@biloxi.com>;tag=456248\r\n\
+Call-ID: 843817637684230@998sdasdh09\r\n\
+CSeq: {cseq} REGISTER\r\n\
+{headers}\
+Content-Length: 0\r\n\
+\r\n"
+            )
+            .as_str(),
+        )
+        .unwrap()
+    }
+
+    fn registrar() -> Registrar {
+        Registrar::new(RegistrarConfig {
+            domains: vec!["biloxi.com".to_string()],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn process(
+        registrar: &mut Registrar,
+        request: &Request,
+        now: SystemTime,
+    ) -> RegistrationOutcome {
+        registrar.register(request, (Transport::Udp, SOURCE.parse().unwrap()), now)
+    }
+
+    fn contacts(outcome: &RegistrationOutcome) -> Vec<String> {
+        match outcome {
+            RegistrationOutcome::Accepted { contacts, .. } => {
+                contacts.iter().map(ToString::to_string).collect()
+            }
+            outcome => panic!("Unexpected outcome: {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registrar = registrar();
+        let now = SystemTime::now();
+        let outcome = process(
+            &mut registrar,
+            &register(
+                1826,
+                "Contact: <sip:bob@192.0.2.4>\r\nContact: <sip:bob@192.0.2.5>;q=0.5;expires=600\r\n",
+            ),
+            now,
+        );
+        assert_eq!(outcome.reason(), Reason::OK);
+        assert_eq!(
+            contacts(&outcome),
+            vec![
+                "<sip:bob@192.0.2.4>;expires=3600",
+                "<sip:bob@192.0.2.5>;q=0.5;expires=600"
+            ]
+        );
+        assert_eq!(
+            outcome.headers()[0].to_string(),
+            "Contact: <sip:bob@192.0.2.4>;expires=3600, <sip:bob@192.0.2.5>;q=0.5;expires=600"
+        );
+
+        let bindings = registrar
+            .lookup(
+                &Uri::try_from("sip:bob@biloxi.com;user=phone").unwrap(),
+                now,
+            )
+            .unwrap();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(
+            bindings[0].received,
+            Some((Transport::Udp, SOURCE.parse().unwrap()))
+        );
+
+        // A request without contacts fetches the bindings.
+        let later = now + Duration::from_secs(100);
+        let outcome = process(&mut registrar, &register(1827, ""), later);
+        assert_eq!(
+            contacts(&outcome),
+            vec![
+                "<sip:bob@192.0.2.4>;expires=3500",
+                "<sip:bob@192.0.2.5>;q=0.5;expires=500"
+            ]
+        );
+
+        // A binding is removed with an expiration of 0.
+        let outcome = process(
+            &mut registrar,
+            &register(1828, "Contact: <sip:bob@192.0.2.5>\r\nExpires: 0\r\n"),
+            later,
+        );
+        assert_eq!(contacts(&outcome), vec!["<sip:bob@192.0.2.4>;expires=3500"]);
+        assert!(
+            registrar
+                .lookup(
+                    &Uri::try_from("sip:bob@biloxi.com").unwrap(),
+                    now + Duration::from_secs(3600)
+                )
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_register_rejected() {
+        let mut registrar = registrar();
+        let now = SystemTime::now();
+        let outcome = process(
+            &mut registrar,
+            &register(1826, "Contact: <sip:bob@192.0.2.4>;expires=30\r\n"),
+            now,
+        );
+        assert_eq!(outcome.reason(), Reason::INTERVAL_TOO_BRIEF);
+        assert_eq!(outcome.headers()[0].to_string(), "Min-Expires: 60");
+
+        let mut request = register(1826, "Contact: <sip:carol@192.0.2.4>\r\n");
+        request.headers_mut()[2] = Header::try_from("To: <sip:carol@chicago.com>").unwrap();
+        assert_eq!(
+            process(&mut registrar, &request, now).reason(),
+            Reason::NOT_FOUND
+        );
+
+        assert_eq!(
+            process(&mut registrar, &register(1826, "Contact: *\r\n"), now).reason(),
+            Reason::BAD_REQUEST
+        );
+        assert_eq!(
+            process(
+                &mut registrar,
+                &register(
+                    1826,
+                    "Contact: *\r\nContact: <sip:bob@192.0.2.4>\r\nExpires: 0\r\n"
+                ),
+                now
+            )
+            .reason(),
+            Reason::BAD_REQUEST
+        );
+
+        // A request with the same Call-ID must have a higher CSeq to update a binding.
+        let contact = "Contact: <sip:bob@192.0.2.4>\r\n";
+        assert!(process(&mut registrar, &register(1826, contact), now).reason() == Reason::OK);
+        let outcome = process(&mut registrar, &register(1826, contact), now);
+        assert_eq!(outcome.reason(), Reason::SERVER_INTERNAL_ERROR);
+        assert!(process(&mut registrar, &register(1827, contact), now).reason() == Reason::OK);
+    }
+
+    #[test]
+    fn test_register_wildcard() {
+        let mut registrar = registrar();
+        let now = SystemTime::now();
+        process(
+            &mut registrar,
+            &register(1826, "Contact: <sip:bob@192.0.2.4>\r\n"),
+            now,
+        );
+        // An out of order request fails without removing any binding.
+        let outcome = process(
+            &mut registrar,
+            &register(1826, "Contact: *\r\nExpires: 0\r\n"),
+            now,
+        );
+        assert_eq!(outcome.reason(), Reason::SERVER_INTERNAL_ERROR);
+        let bob = Uri::try_from("sip:bob@biloxi.com").unwrap();
+        assert_eq!(registrar.lookup(&bob, now).unwrap().len(), 1);
+
+        let outcome = process(
+            &mut registrar,
+            &register(1827, "Contact: *\r\nExpires: 0\r\n"),
+            now,
+        );
+        assert!(contacts(&outcome).is_empty());
+        assert!(outcome.headers().is_empty());
+        assert!(registrar.lookup(&bob, now).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_register_with_path() {
+        let mut registrar = registrar();
+        let now = SystemTime::now();
+        let request = register(
+            1826,
+            "Via: SIP/2.0/UDP p1.examplehome.com;branch=z9hG4bK34ghi7ab04\r\n\
+             Path: <sip:P1.EXAMPLEHOME.COM;lr>\r\n\
+             Contact: <sip:bob@192.0.2.4>;expires=7200\r\n",
+        );
+        let outcome = process(&mut registrar, &request, now);
+        let headers = outcome.headers();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[1].to_string(), "Path: <sip:P1.EXAMPLEHOME.COM;lr>");
+        let bindings = registrar
+            .lookup(&Uri::try_from("sip:bob@biloxi.com").unwrap(), now)
+            .unwrap();
+        assert_eq!(bindings[0].path.len(), 1);
+        assert_eq!(bindings[0].received, None);
+        assert_eq!(bindings[0].remaining(now), 7200);
+    }
+
+    #[test]
+    fn test_register_instances() {
+        let mut registrar = registrar();
+        let now = SystemTime::now();
+        let instance = "urn:uuid:00000000-0000-1000-8000-000a95a0e128";
+        let outcome = process(
+            &mut registrar,
+            &register(
+                1826,
+                &format!(
+                    "Contact: <sip:bob@192.0.2.4>;+sip.instance=\"<{instance}>\";reg-id=1\r\n\
+                     Contact: <sip:bob@192.0.2.5>\r\n"
+                ),
+            ),
+            now,
+        );
+        assert_eq!(contacts(&outcome).len(), 2);
+
+        // The instance refreshes its binding from another address.
+        let outcome = process(
+            &mut registrar,
+            &register(
+                1827,
+                &format!(
+                    "Contact: <sip:bob@192.0.2.6>;+sip.instance=\"<{instance}>\";reg-id=1\r\n"
+                ),
+            ),
+            now,
+        );
+        assert_eq!(contacts(&outcome).len(), 2);
+
+        // A GRUU only reaches the bindings of its instance.
+        let gruu = Uri::try_from(format!("sip:bob@biloxi.com;gr={instance}").as_str()).unwrap();
+        let bindings = registrar.lookup(&gruu, now).unwrap();
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0].uri().to_string(), "sip:bob@192.0.2.6");
+
+        let later = now + Duration::from_secs(3600);
+        registrar.remove_expired(later);
+        assert!(
+            registrar
+                .lookup(&Uri::try_from("sip:bob@biloxi.com").unwrap(), now)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
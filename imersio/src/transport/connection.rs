@@ -0,0 +1,503 @@
+//! Connections of the stream transports, shared between the received and the sent messages.
+//!
+//! [[RFC3261, Section 18](https://datatracker.ietf.org/doc/html/rfc3261#section-18)]
+
+use imersio_sip::{Host, Message, Transport};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::time::{Instant, sleep_until};
+use tracing::debug;
+
+use crate::transport::{IncomingMessage, received_message};
+
+/// Maximum size of a message received on a connection, bounding the memory used to frame it.
+pub(crate) const MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+/// Number of messages that can be waiting to be written to a connection.
+const OUTGOING_QUEUE_SIZE: usize = 64;
+
+/// Size of the chunks read from a connection.
+const READ_BUFFER_SIZE: usize = 8192;
+
+/// Representation of an element read from a stream of SIP messages.
+#[derive(Debug)]
+pub(crate) enum Frame {
+    /// A keep-alive ping, that is answered with a pong.
+    ///
+    /// [[RFC5626, Section 4.4.1](https://datatracker.ietf.org/doc/html/rfc5626#section-4.4.1)]
+    Ping,
+    /// A complete SIP message.
+    Message(Box<Message>),
+}
+
+/// Extract the next frame from the bytes read on a connection, removing its bytes from the
+/// buffer.
+///
+/// It returns None if the buffer does not contain a complete frame yet, and an error if the
+/// buffer does not contain a valid SIP message, in which case the stream cannot be resynchronized
+/// and the connection must be closed. The body of a message is delimited by its Content-Length
+/// header, a message without one being considered as having an empty body.
+///
+/// [[RFC3261, Section 18.3](https://datatracker.ietf.org/doc/html/rfc3261#section-18.3)]
+pub(crate) fn next_frame(buffer: &mut Vec<u8>) -> Result<Option<Frame>, String> {
+    const PING: &[u8] = b"\r\n\r\n";
+
+    // Skip the CRLFs preceding a message, unless they form a keep-alive ping.
+    while buffer.starts_with(b"\r\n") {
+        if buffer.starts_with(PING) {
+            buffer.drain(..PING.len());
+            return Ok(Some(Frame::Ping));
+        } else if PING.starts_with(buffer) {
+            return Ok(None);
+        }
+        buffer.drain(..2);
+    }
+
+    let Some(head_size) = buffer
+        .windows(PING.len())
+        .position(|window| window == PING)
+        .map(|position| position + PING.len())
+    else {
+        return if buffer.len() > MAX_MESSAGE_SIZE {
+            Err(format!("No message head in {} bytes", buffer.len()))
+        } else {
+            Ok(None)
+        };
+    };
+    let head = Message::try_from(&buffer[..head_size]).map_err(|err| err.to_string())?;
+    let size = head_size + head.content_length().unwrap_or_default();
+    if size > MAX_MESSAGE_SIZE {
+        return Err(format!("Message of {size} bytes is too large"));
+    }
+    if buffer.len() < size {
+        return Ok(None);
+    }
+    let message = Message::try_from(&buffer[..size]).map_err(|err| err.to_string())?;
+    buffer.drain(..size);
+    Ok(Some(Frame::Message(Box::new(message))))
+}
+
+/// Identification of a connection, used to reuse it for the messages to the same destination.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct ConnectionKey {
+    pub(crate) transport: Transport,
+    pub(crate) remote: SocketAddr,
+}
+
+/// A connection registered in the table, with the channel of the messages to write to it.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    pub(crate) id: u64,
+    pub(crate) sender: mpsc::Sender<Vec<u8>>,
+    pub(crate) receiver: mpsc::Receiver<Vec<u8>>,
+}
+
+#[derive(Debug)]
+struct Connection {
+    id: u64,
+    sender: mpsc::Sender<Vec<u8>>,
+}
+
+/// The table of the open connections, keyed by their transport and remote address.
+///
+/// Each connection has a task reading and framing the received messages to hand them to the
+/// core, and a task writing the messages to send. A connection is closed after being idle for
+/// the configured timeout. The peers that cannot be designated by their address, such as the
+/// WebSocket clients, can be designated by aliases while their connection is open.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectionTable {
+    connections: Arc<Mutex<HashMap<ConnectionKey, Connection>>>,
+    aliases: Arc<Mutex<HashMap<Host, (ConnectionKey, u64)>>>,
+    next_id: Arc<AtomicU64>,
+    max_connections: usize,
+    idle_timeout: Duration,
+    core: mpsc::Sender<IncomingMessage>,
+}
+
+impl ConnectionTable {
+    /// Create an empty connection table, handing the received messages to the core.
+    pub(crate) fn new(
+        max_connections: usize,
+        idle_timeout: Duration,
+        core: mpsc::Sender<IncomingMessage>,
+    ) -> Self {
+        Self {
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            aliases: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
+            max_connections,
+            idle_timeout,
+            core,
+        }
+    }
+
+    /// Get the number of open connections.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+
+    /// Get the sender of the messages to write to the connection with the given key, if it is
+    /// open.
+    pub(crate) fn sender(&self, key: &ConnectionKey) -> Option<mpsc::Sender<Vec<u8>>> {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|connection| connection.sender.clone())
+    }
+
+    /// Get the duration after which an idle connection is closed.
+    pub(crate) fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Hand a message received on a connection to the core.
+    ///
+    /// It returns false if the core has stopped, in which case the connection must be closed.
+    pub(crate) async fn deliver(&self, message: IncomingMessage) -> bool {
+        self.core.send(message).await.is_ok()
+    }
+
+    /// Register a new connection in the table.
+    ///
+    /// It returns an error if the maximum number of connections is reached.
+    pub(crate) fn register(
+        &self,
+        key: &ConnectionKey,
+        local: SocketAddr,
+    ) -> Result<Registration, std::io::Error> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel(OUTGOING_QUEUE_SIZE);
+        let mut connections = self.connections.lock().unwrap();
+        if connections.len() >= self.max_connections {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::OutOfMemory,
+                format!("Maximum of {} connections reached", self.max_connections),
+            ));
+        }
+        connections.insert(
+            key.clone(),
+            Connection {
+                id,
+                sender: sender.clone(),
+            },
+        );
+        debug!(
+            "Connection {} open between {} and {}",
+            id, local, key.remote
+        );
+        Ok(Registration {
+            id,
+            sender,
+            receiver,
+        })
+    }
+
+    /// Add an established connection to the table and spawn its reading and writing tasks.
+    ///
+    /// It returns the sender of the messages to write to the connection, or an error if the
+    /// maximum number of connections is reached.
+    pub(crate) fn add<S>(
+        &self,
+        stream: S,
+        transport: Transport,
+        local: SocketAddr,
+        remote: SocketAddr,
+    ) -> Result<mpsc::Sender<Vec<u8>>, std::io::Error>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let key = ConnectionKey { transport, remote };
+        let Registration {
+            id,
+            sender,
+            receiver,
+        } = self.register(&key, local)?;
+        let (reader, writer) = tokio::io::split(stream);
+        let last_activity = Arc::new(Mutex::new(Instant::now()));
+        tokio::spawn(
+            self.clone()
+                .write(writer, receiver, key.clone(), id, last_activity.clone()),
+        );
+        tokio::spawn(
+            self.clone()
+                .read(reader, sender.clone(), key, id, local, last_activity),
+        );
+        Ok(sender)
+    }
+
+    /// Remove a closed connection from the table, with the aliases of its peer.
+    pub(crate) fn remove(&self, key: &ConnectionKey, id: u64) {
+        let mut connections = self.connections.lock().unwrap();
+        if connections
+            .get(key)
+            .is_some_and(|connection| connection.id == id)
+        {
+            connections.remove(key);
+            debug!("Connection {} with {} closed", id, key.remote);
+        }
+        drop(connections);
+        self.aliases
+            .lock()
+            .unwrap()
+            .retain(|_, alias| alias.0 != *key || alias.1 != id);
+    }
+
+    /// Record that the given host designates the peer of a connection, so that the messages for
+    /// this host are sent on this connection while it is open.
+    ///
+    /// The alias is kept by the first connection recording it until it is closed, so that a peer
+    /// cannot take over the alias of another one.
+    pub(crate) fn add_alias(&self, host: Host, key: &ConnectionKey, id: u64) {
+        let mut aliases = self.aliases.lock().unwrap();
+        if let Some((owner, owner_id)) = aliases.get(&host) {
+            if (owner != key || *owner_id != id) && self.is_open(owner, *owner_id) {
+                debug!(
+                    "Ignoring alias {} of {} already used by {}",
+                    host, key.remote, owner.remote
+                );
+                return;
+            }
+        }
+        aliases.insert(host, (key.clone(), id));
+    }
+
+    fn is_open(&self, key: &ConnectionKey, id: u64) -> bool {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|connection| connection.id == id)
+    }
+
+    /// Get the key of the connection with the peer designated by the given host, if there is
+    /// one.
+    pub(crate) fn alias(&self, host: &Host) -> Option<ConnectionKey> {
+        self.aliases
+            .lock()
+            .unwrap()
+            .get(host)
+            .map(|alias| alias.0.clone())
+    }
+
+    async fn read<R>(
+        self,
+        mut reader: R,
+        pong: mpsc::Sender<Vec<u8>>,
+        key: ConnectionKey,
+        id: u64,
+        local: SocketAddr,
+        last_activity: Arc<Mutex<Instant>>,
+    ) where
+        R: AsyncRead + Unpin,
+    {
+        let mut buffer = Vec::new();
+        let mut chunk = vec![0u8; READ_BUFFER_SIZE];
+        'connection: loop {
+            let deadline = *last_activity.lock().unwrap() + self.idle_timeout;
+            select! {
+                result = reader.read(&mut chunk) => match result {
+                    Ok(0) => break,
+                    Ok(size) => {
+                        *last_activity.lock().unwrap() = Instant::now();
+                        buffer.extend_from_slice(&chunk[..size]);
+                        loop {
+                            match next_frame(&mut buffer) {
+                                Ok(Some(Frame::Ping)) => {
+                                    let _ = pong.try_send(b"\r\n".to_vec());
+                                }
+                                Ok(Some(Frame::Message(message))) => {
+                                    let Some(message) = received_message(
+                                        *message,
+                                        key.transport.clone(),
+                                        local,
+                                        key.remote,
+                                    ) else {
+                                        continue;
+                                    };
+                                    if !self.deliver(message).await {
+                                        break 'connection;
+                                    }
+                                }
+                                Ok(None) => break,
+                                Err(err) => {
+                                    debug!("Invalid data on connection {}: {}", id, err);
+                                    break 'connection;
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        debug!("Could not read on connection {}: {}", id, err);
+                        break;
+                    }
+                },
+                _ = sleep_until(deadline) => {
+                    if *last_activity.lock().unwrap() + self.idle_timeout <= Instant::now() {
+                        debug!("Connection {} idle for {:?}", id, self.idle_timeout);
+                        break;
+                    }
+                }
+            }
+        }
+        self.remove(&key, id);
+    }
+
+    async fn write<W>(
+        self,
+        mut writer: W,
+        mut receiver: mpsc::Receiver<Vec<u8>>,
+        key: ConnectionKey,
+        id: u64,
+        last_activity: Arc<Mutex<Instant>>,
+    ) where
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(bytes) = receiver.recv().await {
+            if let Err(err) = writer.write_all(&bytes).await {
+                debug!("Could not write on connection {}: {}", id, err);
+                self.remove(&key, id);
+                break;
+            }
+            *last_activity.lock().unwrap() = Instant::now();
+        }
+        let _ = writer.shutdown().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::duplex;
+
+    const REQUEST: &str = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Content-Length: 3\r\n\
+\r\n\
+v=0";
+
+    #[test]
+    fn test_next_frame() {
+        let mut buffer = format!("\r\n{REQUEST}{REQUEST}\r\n\r\n").into_bytes();
+        assert!(matches!(
+            next_frame(&mut buffer),
+            Ok(Some(Frame::Message(message))) if message.body() == b"v=0"
+        ));
+        assert!(matches!(
+            next_frame(&mut buffer),
+            Ok(Some(Frame::Message(_)))
+        ));
+        assert!(matches!(next_frame(&mut buffer), Ok(Some(Frame::Ping))));
+        assert!(buffer.is_empty());
+        assert!(matches!(next_frame(&mut buffer), Ok(None)));
+    }
+
+    #[test]
+    fn test_next_frame_incomplete() {
+        let mut buffer = REQUEST.as_bytes()[..REQUEST.len() - 1].to_vec();
+        assert!(matches!(next_frame(&mut buffer), Ok(None)));
+        buffer.push(b'0');
+        assert!(matches!(
+            next_frame(&mut buffer),
+            Ok(Some(Frame::Message(_)))
+        ));
+
+        let mut buffer = b"\r\n\r".to_vec();
+        assert!(matches!(next_frame(&mut buffer), Ok(None)));
+        let mut buffer = b"OPTIONS sip:carol@chicago.com SIP/2.0\r\n".to_vec();
+        assert!(matches!(next_frame(&mut buffer), Ok(None)));
+    }
+
+    #[test]
+    fn test_next_frame_invalid() {
+        let mut buffer = b"Hello world!\r\n\r\n".to_vec();
+        assert!(next_frame(&mut buffer).is_err());
+        let mut buffer = vec![b'a'; MAX_MESSAGE_SIZE + 1];
+        assert!(next_frame(&mut buffer).is_err());
+    }
+
+    fn addresses() -> (SocketAddr, SocketAddr) {
+        (
+            "192.0.2.10:5060".parse().unwrap(),
+            "192.0.2.1:49152".parse().unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_connection_table() {
+        let (core, mut incoming) = mpsc::channel(1);
+        let table = ConnectionTable::new(1, Duration::from_secs(30), core);
+        let (local, remote) = addresses();
+        let (stream, mut peer) = duplex(4096);
+        let sender = table.add(stream, Transport::Tcp, local, remote).unwrap();
+        assert_eq!(table.len(), 1);
+        let key = ConnectionKey {
+            transport: Transport::Tcp,
+            remote,
+        };
+        assert!(table.sender(&key).is_some());
+
+        // The maximum number of connections is reached.
+        let (stream, _) = duplex(4096);
+        assert!(
+            table
+                .add(
+                    stream,
+                    Transport::Tcp,
+                    local,
+                    "192.0.2.2:49152".parse().unwrap()
+                )
+                .is_err()
+        );
+
+        // A received message is handed to the core.
+        peer.write_all(format!("\r\n\r\n{REQUEST}").as_bytes())
+            .await
+            .unwrap();
+        let message = incoming.recv().await.unwrap();
+        assert_eq!(message.transport, Transport::Tcp);
+        assert_eq!(message.remote, remote);
+        let mut pong = [0u8; 2];
+        peer.read_exact(&mut pong).await.unwrap();
+        assert_eq!(&pong, b"\r\n");
+
+        // A sent message is written to the connection.
+        sender.send(b"SIP/2.0".to_vec()).await.unwrap();
+        let mut response = [0u8; 7];
+        peer.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response, b"SIP/2.0");
+
+        // The connection is removed once closed by the peer.
+        drop(peer);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while table.len() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_idle_timeout() {
+        let (core, _incoming) = mpsc::channel(1);
+        let table = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let (local, remote) = addresses();
+        let (stream, _peer) = duplex(4096);
+        table.add(stream, Transport::Tcp, local, remote).unwrap();
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        assert_eq!(table.len(), 1);
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        assert_eq!(table.len(), 0);
+    }
+}
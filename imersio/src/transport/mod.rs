@@ -0,0 +1,686 @@
+//! Transport layer of the proxy, receiving the SIP messages from the network and sending them.
+//!
+//! [[RFC3261, Section 18](https://datatracker.ietf.org/doc/html/rfc3261#section-18)]
+
+use imersio_sip::{Host, Message, Transport};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+mod connection;
+#[cfg(target_os = "linux")]
+mod sctp;
+mod tcp;
+mod tls;
+mod udp;
+mod websocket;
+
+pub(crate) use connection::{ConnectionKey, ConnectionTable, MAX_MESSAGE_SIZE, Registration};
+#[cfg(target_os = "linux")]
+pub(crate) use sctp::SctpTransport;
+pub(crate) use tcp::TcpTransport;
+pub(crate) use tls::{TlsContext, TlsTransport};
+pub(crate) use udp::UdpTransport;
+pub(crate) use websocket::{WebSocketTransport, is_websocket, wss};
+
+/// Size above which a request must be sent over a congestion controlled transport, when the path
+/// MTU is unknown.
+///
+/// [[RFC3261, Section 18.1.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.1.1)]
+pub(crate) const MTU_SIZE_LIMIT: usize = 1300;
+
+/// Maximum number of IP addresses whose local addresses are cached, the cache being emptied
+/// when it is full.
+const ADDRESS_CACHE_SIZE: usize = 1024;
+
+/// Representation of a SIP message received by the transport layer, with the addresses it has
+/// been exchanged between.
+#[derive(Debug)]
+pub(crate) struct IncomingMessage {
+    pub(crate) message: Message,
+    pub(crate) transport: Transport,
+    pub(crate) local: SocketAddr,
+    pub(crate) remote: SocketAddr,
+}
+
+/// Build the representation of a message received by a transport, recording the source address
+/// in the top Via of a request.
+///
+/// It returns None if the message is to be discarded.
+///
+/// [[RFC3261, Section 18.2.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.2.1)]
+/// [[RFC3581, Section 4](https://datatracker.ietf.org/doc/html/rfc3581#section-4)]
+pub(crate) fn received_message(
+    mut message: Message,
+    transport: Transport,
+    local: SocketAddr,
+    remote: SocketAddr,
+) -> Option<IncomingMessage> {
+    if let Message::Request(request) = &mut message {
+        if let Err(err) = request.set_received(remote) {
+            debug!("Discarding invalid request from {}: {}", remote, err);
+            return None;
+        }
+    }
+    Some(IncomingMessage {
+        message,
+        transport,
+        local,
+        remote,
+    })
+}
+
+/// Get the transport to use to send a message, given the transport that has been selected for
+/// its destination.
+///
+/// A request larger than `MTU_SIZE_LIMIT` must not be sent over UDP, so TCP is used instead. This
+/// must be called before adding the Via of the request, so that its transport is the one
+/// actually used.
+///
+/// [[RFC3261, Section 18.1.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.1.1)]
+pub(crate) fn select_transport(message: &Message, size: usize, transport: Transport) -> Transport {
+    if matches!(message, Message::Request(_)) && transport.is_udp() && size > MTU_SIZE_LIMIT {
+        Transport::Tcp
+    } else {
+        transport
+    }
+}
+
+/// Check that a message can be sent with the given transport.
+///
+/// A request for a `sips:` URI must be sent over TLS on each hop, secure WebSocket being
+/// layered over TLS.
+///
+/// [[RFC3261, Section 26.2.2](https://datatracker.ietf.org/doc/html/rfc3261#section-26.2.2)]
+/// [[RFC5630, Section 3.1.3](https://datatracker.ietf.org/doc/html/rfc5630#section-3.1.3)]
+/// [[RFC7118, Section 5.3](https://datatracker.ietf.org/doc/html/rfc7118#section-5.3)]
+pub(crate) fn check_secure(message: &Message, transport: &Transport) -> Result<(), std::io::Error> {
+    match message {
+        Message::Request(request)
+            if request.uri().is_secure() && !transport.is_tls() && *transport != wss() =>
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!(
+                    "Request for {} cannot be sent over {}",
+                    request.uri(),
+                    transport
+                ),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Tell whether the given transport can be enabled in the proxy.
+///
+/// SCTP is only supported on Linux.
+pub(crate) fn is_supported(transport: &Transport) -> bool {
+    match transport {
+        Transport::Udp | Transport::Tcp | Transport::Tls => true,
+        Transport::Sctp => cfg!(target_os = "linux"),
+        _ => is_websocket(transport),
+    }
+}
+
+/// Tell whether the given transport is reliable, so that the transactions do not retransmit
+/// their messages over it.
+///
+/// [[RFC3261, Section 17.1.1.2](https://datatracker.ietf.org/doc/html/rfc3261#section-17.1.1.2)]
+pub(crate) fn is_reliable(transport: &Transport) -> bool {
+    transport != &Transport::Udp
+}
+
+/// Normalize an address received on a dual-stack socket, so that an IPv4 peer is seen with its
+/// IPv4 address instead of an IPv4-mapped IPv6 one.
+pub(crate) fn canonical_address(address: SocketAddr) -> SocketAddr {
+    SocketAddr::new(address.ip().to_canonical(), address.port())
+}
+
+/// Convert a destination address to the address family of a local socket, an IPv4 destination
+/// being reachable from a dual-stack IPv6 socket through its IPv4-mapped IPv6 address.
+pub(crate) fn address_for_socket(destination: SocketAddr, local: SocketAddr) -> Option<SocketAddr> {
+    match (destination.ip(), local.ip()) {
+        (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => Some(destination),
+        (IpAddr::V4(ip), IpAddr::V6(local_ip)) if local_ip.is_unspecified() => Some(
+            SocketAddr::new(IpAddr::V6(ip.to_ipv6_mapped()), destination.port()),
+        ),
+        _ => None,
+    }
+}
+
+/// Get the local IP address used to reach the given destination, that is the address to
+/// advertise for a transport bound to an unspecified address.
+///
+/// No packet is sent: connecting a UDP socket only selects its route.
+fn source_ip(destination: SocketAddr) -> Option<IpAddr> {
+    let unspecified = match destination {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(unspecified, 0)).ok()?;
+    socket.connect(destination).ok()?;
+    socket.local_addr().ok().map(|address| address.ip())
+}
+
+/// Tell whether the given IP address is assigned to the host, that is whether a socket can be
+/// bound to it.
+fn is_local_ip(ip: IpAddr) -> bool {
+    UdpSocket::bind(SocketAddr::new(ip, 0)).is_ok()
+}
+
+/// Cache of the local IP addresses, so that a socket is only bound to find them the first time
+/// an IP address is met rather than for each message.
+#[derive(Debug, Default)]
+struct AddressCache {
+    /// The local IP address used to reach a destination IP address.
+    source_ips: HashMap<IpAddr, Option<IpAddr>>,
+    /// Whether an IP address is assigned to the host.
+    local_ips: HashMap<IpAddr, bool>,
+}
+
+/// Get the value cached for a key, or compute it and cache it.
+fn cached<K, V>(
+    cache: &Mutex<AddressCache>,
+    map: fn(&mut AddressCache) -> &mut HashMap<K, V>,
+    key: K,
+    compute: impl FnOnce() -> V,
+) -> V
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    if let Some(value) = map(&mut cache.lock().unwrap()).get(&key) {
+        return value.clone();
+    }
+    let value = compute();
+    let mut cache = cache.lock().unwrap();
+    let entries = map(&mut cache);
+    if entries.len() >= ADDRESS_CACHE_SIZE {
+        entries.clear();
+    }
+    entries.insert(key, value.clone());
+    value
+}
+
+/// The transports enabled in the proxy, used to send the outgoing messages.
+#[derive(Clone, Debug)]
+pub(crate) struct Transports {
+    udp: Vec<UdpTransport>,
+    tcp: Vec<SocketAddr>,
+    tls: Vec<SocketAddr>,
+    #[cfg(target_os = "linux")]
+    sctp: Vec<SctpTransport>,
+    websocket: Vec<(Transport, SocketAddr)>,
+    connections: ConnectionTable,
+    tls_context: Option<TlsContext>,
+    addresses: Arc<Mutex<AddressCache>>,
+}
+
+impl Transports {
+    /// Create the transports, sharing the given table of the connections of the stream
+    /// transports, and the given TLS configuration if there is one.
+    pub(crate) fn new(connections: ConnectionTable, tls_context: Option<TlsContext>) -> Self {
+        Self {
+            udp: Vec::new(),
+            tcp: Vec::new(),
+            tls: Vec::new(),
+            #[cfg(target_os = "linux")]
+            sctp: Vec::new(),
+            websocket: Vec::new(),
+            connections,
+            tls_context,
+            addresses: Arc::new(Mutex::new(AddressCache::default())),
+        }
+    }
+
+    /// Get a reference to the table of the connections of the stream transports.
+    pub(crate) fn connections(&self) -> &ConnectionTable {
+        &self.connections
+    }
+
+    /// Register an enabled UDP transport.
+    pub(crate) fn add_udp(&mut self, transport: UdpTransport) {
+        self.udp.push(transport);
+    }
+
+    /// Register the local address of an enabled TCP transport.
+    pub(crate) fn add_tcp(&mut self, local: SocketAddr) {
+        self.tcp.push(local);
+    }
+
+    /// Get a reference to the TLS configuration, if there is one.
+    pub(crate) fn tls_context(&self) -> Option<&TlsContext> {
+        self.tls_context.as_ref()
+    }
+
+    /// Register the local address of an enabled TLS transport.
+    pub(crate) fn add_tls(&mut self, local: SocketAddr) {
+        self.tls.push(local);
+    }
+
+    /// Register an enabled SCTP transport.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn add_sctp(&mut self, transport: SctpTransport) {
+        self.sctp.push(transport);
+    }
+
+    /// Register the transport and the local address of an enabled WebSocket transport.
+    pub(crate) fn add_websocket(&mut self, transport: Transport, local: SocketAddr) {
+        self.websocket.push((transport, local));
+    }
+
+    /// Tell whether the given transport is enabled.
+    pub(crate) fn is_enabled(&self, transport: &Transport) -> bool {
+        match transport {
+            Transport::Udp => !self.udp.is_empty(),
+            Transport::Tcp => !self.tcp.is_empty(),
+            Transport::Tls => !self.tls.is_empty() && self.tls_context.is_some(),
+            #[cfg(target_os = "linux")]
+            Transport::Sctp => !self.sctp.is_empty(),
+            _ => self
+                .websocket
+                .iter()
+                .any(|(enabled, _)| enabled == transport),
+        }
+    }
+
+    /// Get the local addresses the enabled transports are bound to, with their transport.
+    fn local_addresses(&self) -> impl Iterator<Item = (Transport, SocketAddr)> + '_ {
+        let udp = self
+            .udp
+            .iter()
+            .map(|udp| (Transport::Udp, udp.local_addr()));
+        let tcp = self.tcp.iter().map(|local| (Transport::Tcp, *local));
+        let tls = self.tls.iter().map(|local| (Transport::Tls, *local));
+        #[cfg(target_os = "linux")]
+        let tcp = tcp.chain(
+            self.sctp
+                .iter()
+                .map(|sctp| (Transport::Sctp, sctp.local_addr())),
+        );
+        udp.chain(tcp)
+            .chain(tls)
+            .chain(self.websocket.iter().cloned())
+    }
+
+    /// Get the address to advertise in the Via of a request sent to the given destination with
+    /// the given transport, that is the local address of the transport, with the IP address of
+    /// the interface reaching the destination if the transport is bound to an unspecified
+    /// address.
+    pub(crate) fn local_addr(
+        &self,
+        transport: &Transport,
+        destination: SocketAddr,
+    ) -> Option<SocketAddr> {
+        let local = self
+            .local_addresses()
+            .find(|(enabled, local)| {
+                enabled == transport && address_for_socket(destination, *local).is_some()
+            })
+            .map(|(_, local)| local)?;
+        if local.ip().is_unspecified() {
+            let source_ip = cached(
+                &self.addresses,
+                |cache| &mut cache.source_ips,
+                destination.ip(),
+                || source_ip(destination),
+            )?;
+            Some(SocketAddr::new(source_ip, local.port()))
+        } else {
+            Some(local)
+        }
+    }
+
+    /// Tell whether the given host and port designate the proxy, that is one of the local
+    /// addresses of its transports.
+    pub(crate) fn is_local(&self, host: &Host, port: u16) -> bool {
+        let Some(ip) = host.ip() else {
+            return false;
+        };
+        self.local_addresses().any(|(_, local)| {
+            local.port() == port
+                && (local.ip() == ip.to_canonical()
+                    || (local.ip().is_unspecified()
+                        && (local.is_ipv6() || ip.is_ipv4())
+                        && cached(
+                            &self.addresses,
+                            |cache| &mut cache.local_ips,
+                            *ip,
+                            || is_local_ip(*ip),
+                        )))
+        })
+    }
+
+    /// Send a message to the given destination, with the given transport.
+    ///
+    /// It returns the transport that has actually been used, that is TCP when a request is too
+    /// large to be sent over UDP. A message sent over TCP or TLS reuses the connection open with
+    /// the destination, or a new connection is established. The certificate presented by the
+    /// destination of a new TLS connection is validated against the given host, that is the host
+    /// of the URI the destination has been resolved from.
+    ///
+    /// A message sent over WebSocket can only use a connection opened by the destination. When
+    /// the host is one of the `.invalid` hosts of a WebSocket client, the connection of the client
+    /// is used whatever the destination.
+    ///
+    /// [[RFC7118, Section 5](https://datatracker.ietf.org/doc/html/rfc7118#section-5)]
+    pub(crate) async fn send(
+        &self,
+        message: &Message,
+        transport: Transport,
+        destination: SocketAddr,
+        host: &Host,
+    ) -> Result<Transport, std::io::Error> {
+        check_secure(message, &transport)?;
+        let bytes = message.to_bytes();
+        let selected = select_transport(message, bytes.len(), transport.clone());
+        if selected != transport {
+            debug!(
+                "Request of {} bytes too large for {}, using {}",
+                bytes.len(),
+                transport,
+                selected
+            );
+        }
+        match selected {
+            Transport::Udp => {
+                let (udp, destination) = self
+                    .udp
+                    .iter()
+                    .find_map(|udp| {
+                        address_for_socket(destination, udp.local_addr())
+                            .map(|destination| (udp, destination))
+                    })
+                    .ok_or_else(|| no_transport(&selected, destination))?;
+                udp.send_to(&bytes, destination).await?;
+            }
+            #[cfg(target_os = "linux")]
+            Transport::Sctp => {
+                let (sctp, destination) = self
+                    .sctp
+                    .iter()
+                    .find_map(|sctp| {
+                        address_for_socket(destination, sctp.local_addr())
+                            .map(|destination| (sctp, destination))
+                    })
+                    .ok_or_else(|| no_transport(&selected, destination))?;
+                sctp.send_to(&bytes, destination).await?;
+            }
+            Transport::Tcp | Transport::Tls if self.is_enabled(&selected) => {
+                let key = ConnectionKey {
+                    transport: selected.clone(),
+                    remote: canonical_address(destination),
+                };
+                let sender = match (self.connections.sender(&key), &self.tls_context) {
+                    (Some(sender), _) => sender,
+                    (None, Some(context)) if selected.is_tls() => {
+                        tls::connect(&self.connections, context, destination, host).await?
+                    }
+                    (None, _) => tcp::connect(&self.connections, destination).await?,
+                };
+                sender.send(bytes).await.map_err(|_| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::BrokenPipe,
+                        format!("Connection with {destination} closed"),
+                    )
+                })?;
+            }
+            _ if is_websocket(&selected) && self.is_enabled(&selected) => {
+                let key = self
+                    .connections
+                    .alias(host)
+                    .filter(|key| key.transport == selected)
+                    .unwrap_or_else(|| ConnectionKey {
+                        transport: selected.clone(),
+                        remote: canonical_address(destination),
+                    });
+                let sender = self.connections.sender(&key).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::NotConnected,
+                        format!("No {} connection with {}", selected, host),
+                    )
+                })?;
+                sender.send(bytes).await.map_err(|_| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::BrokenPipe,
+                        format!("Connection with {} closed", key.remote),
+                    )
+                })?;
+            }
+            _ => return Err(no_transport(&selected, destination)),
+        }
+        Ok(selected)
+    }
+}
+
+fn no_transport(transport: &Transport, destination: SocketAddr) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("No {transport} transport available to reach {destination}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body_size: usize) -> Message {
+        let body = "a".repeat(body_size);
+        Message::try_from(
+            format!(
+                "MESSAGE sip:bob@biloxi.com SIP/2.0\r\n\
+                 Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+                 Content-Length: {body_size}\r\n\
+                 \r\n\
+                 {body}"
+            )
+            .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_transport() {
+        let message = request(10);
+        let size = message.to_bytes().len();
+        assert_eq!(
+            select_transport(&message, size, Transport::Udp),
+            Transport::Udp
+        );
+        let message = request(MTU_SIZE_LIMIT);
+        let size = message.to_bytes().len();
+        assert_eq!(
+            select_transport(&message, size, Transport::Udp),
+            Transport::Tcp
+        );
+        assert_eq!(
+            select_transport(&message, size, Transport::Tls),
+            Transport::Tls
+        );
+    }
+
+    #[test]
+    fn test_address_for_socket() {
+        let ipv4: SocketAddr = "192.0.2.1:5060".parse().unwrap();
+        let ipv6: SocketAddr = "[2001:db8::1]:5060".parse().unwrap();
+        assert_eq!(
+            address_for_socket(ipv4, "0.0.0.0:5060".parse().unwrap()),
+            Some(ipv4)
+        );
+        assert_eq!(
+            address_for_socket(ipv4, "[::]:5060".parse().unwrap()),
+            Some("[::ffff:192.0.2.1]:5060".parse().unwrap())
+        );
+        assert_eq!(
+            address_for_socket(ipv4, "[::1]:5060".parse().unwrap()),
+            None
+        );
+        assert_eq!(
+            address_for_socket(ipv6, "0.0.0.0:5060".parse().unwrap()),
+            None
+        );
+        assert_eq!(
+            canonical_address("[::ffff:192.0.2.1]:5060".parse().unwrap()),
+            ipv4
+        );
+    }
+
+    fn host() -> Host {
+        Host::Ip("127.0.0.1".parse().unwrap())
+    }
+
+    #[test]
+    fn test_is_supported() {
+        assert!(is_supported(&Transport::Udp));
+        assert!(is_supported(&wss()));
+        assert_eq!(is_supported(&Transport::Sctp), cfg!(target_os = "linux"));
+        assert!(!is_supported(&Transport::try_from("dccp").unwrap()));
+    }
+
+    #[test]
+    fn test_check_secure() {
+        let message = request(10);
+        assert!(check_secure(&message, &Transport::Udp).is_ok());
+        let message = Message::try_from(
+            "OPTIONS sips:bob@biloxi.com SIP/2.0\r\n\
+             Via: SIP/2.0/TLS pc33.atlanta.com;branch=z9hG4bKnashds8\r\n\
+             Content-Length: 0\r\n\
+             \r\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert!(check_secure(&message, &Transport::Tls).is_ok());
+        assert!(check_secure(&message, &Transport::Tcp).is_err());
+        assert!(check_secure(&message, &wss()).is_ok());
+        assert!(check_secure(&message, &websocket::ws()).is_err());
+    }
+
+    fn transports() -> Transports {
+        let (core, _) = tokio::sync::mpsc::channel(1);
+        Transports::new(
+            ConnectionTable::new(10, std::time::Duration::from_secs(30), core),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_send_without_transport() {
+        let transports = transports();
+        let destination = "127.0.0.1:5060".parse().unwrap();
+        assert!(
+            transports
+                .send(&request(10), Transport::Udp, destination, &host())
+                .await
+                .is_err()
+        );
+        assert!(
+            transports
+                .send(&request(10), Transport::Tcp, destination, &host())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_over_websocket_without_connection() {
+        let mut transports = transports();
+        transports.add_websocket(websocket::ws(), "127.0.0.1:8080".parse().unwrap());
+        assert!(transports.is_enabled(&websocket::ws()));
+        assert!(!transports.is_enabled(&wss()));
+        let err = transports
+            .send(
+                &request(10),
+                websocket::ws(),
+                "127.0.0.1:5060".parse().unwrap(),
+                &host(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotConnected);
+    }
+
+    #[tokio::test]
+    async fn test_send_large_request_over_tcp() {
+        let mut transports = transports();
+        let udp = UdpTransport::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        transports.add_udp(udp);
+        transports.add_tcp("127.0.0.1:5060".parse().unwrap());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let destination = listener.local_addr().unwrap();
+
+        assert_eq!(
+            transports
+                .send(
+                    &request(MTU_SIZE_LIMIT),
+                    Transport::Udp,
+                    destination,
+                    &host()
+                )
+                .await
+                .unwrap(),
+            Transport::Tcp
+        );
+        assert_eq!(transports.connections.len(), 1);
+        // The connection is reused for the next message.
+        assert_eq!(
+            transports
+                .send(&request(10), Transport::Tcp, destination, &host())
+                .await
+                .unwrap(),
+            Transport::Tcp
+        );
+        assert_eq!(transports.connections.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_local_addr() {
+        let mut transports = transports();
+        transports.add_tcp("127.0.0.1:5060".parse().unwrap());
+        transports.add_tls("0.0.0.0:5061".parse().unwrap());
+        let destination = "127.0.0.2:5060".parse().unwrap();
+        assert_eq!(
+            transports.local_addr(&Transport::Tcp, destination),
+            Some("127.0.0.1:5060".parse().unwrap())
+        );
+        assert_eq!(
+            transports.local_addr(&Transport::Tls, "127.0.0.1:5061".parse().unwrap()),
+            Some("127.0.0.1:5061".parse().unwrap())
+        );
+        assert_eq!(transports.local_addr(&Transport::Udp, destination), None);
+        assert_eq!(
+            transports.local_addr(&Transport::Tcp, "[::1]:5060".parse().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_address_cache() {
+        let cache = Mutex::new(AddressCache::default());
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(cached(&cache, |cache| &mut cache.local_ips, ip, || true));
+        assert!(cached(&cache, |cache| &mut cache.local_ips, ip, || false));
+        for index in 0..ADDRESS_CACHE_SIZE as u32 {
+            let ip = IpAddr::V4(Ipv4Addr::from(index));
+            cached(&cache, |cache| &mut cache.local_ips, ip, || false);
+        }
+        assert!(cache.lock().unwrap().local_ips.len() <= ADDRESS_CACHE_SIZE);
+        assert!(!cached(&cache, |cache| &mut cache.local_ips, ip, || false));
+    }
+
+    #[test]
+    fn test_is_local() {
+        let mut transports = transports();
+        transports.add_tcp("127.0.0.1:5060".parse().unwrap());
+        transports.add_tls("0.0.0.0:5061".parse().unwrap());
+        assert!(transports.is_local(&host(), 5060));
+        assert!(!transports.is_local(&host(), 5070));
+        assert!(transports.is_local(&host(), 5061));
+        assert!(!transports.is_local(&Host::Ip("192.0.2.1".parse().unwrap()), 5061));
+        assert!(!transports.is_local(&Host::try_from("localhost").unwrap(), 5060));
+    }
+}
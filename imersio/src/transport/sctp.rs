@@ -0,0 +1,313 @@
+//! SCTP transport, using a one-to-many socket for all the associations.
+//!
+//! Each SIP message is carried in a single SCTP message, so that no framing based on the
+//! Content-Length header is needed.
+//!
+//! [[RFC4168](https://datatracker.ietf.org/doc/html/rfc4168)]
+
+use imersio_sip::Transport;
+use nix::sys::socket::{
+    AddressFamily, Backlog, MsgFlags, SockFlag, SockProtocol, SockType, SockaddrLike,
+    SockaddrStorage, bind, getsockname, listen, recvmsg, sendto, setsockopt, socket, sockopt,
+};
+use std::collections::{HashMap, HashSet};
+use std::io::IoSliceMut;
+use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+use crate::transport::IncomingMessage;
+use crate::transport::udp::process_datagram;
+
+/// Maximum size of a message received on the socket, bounding the memory used to reassemble
+/// the messages delivered in several parts.
+const MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+/// Size of the chunks read from the socket.
+const READ_BUFFER_SIZE: usize = 65536;
+
+/// Number of associations that can be waiting to be established.
+const LISTEN_BACKLOG: i32 = 128;
+
+/// Delay before receiving again after a failure, to avoid spinning on a persistent error.
+const RECEIVE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A one-to-many SCTP socket receiving SIP messages from all its associations and sending them.
+#[derive(Clone, Debug)]
+pub(crate) struct SctpTransport {
+    socket: Arc<AsyncFd<OwnedFd>>,
+    local_addr: SocketAddr,
+}
+
+impl SctpTransport {
+    /// Bind a one-to-many SCTP socket to the given address, accepting the associations of the
+    /// peers.
+    pub(crate) async fn bind(address: SocketAddr) -> Result<Self, std::io::Error> {
+        let family = match address {
+            SocketAddr::V4(_) => AddressFamily::Inet,
+            SocketAddr::V6(_) => AddressFamily::Inet6,
+        };
+        let socket = socket(
+            family,
+            SockType::SeqPacket,
+            SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC,
+            SockProtocol::Sctp,
+        )?;
+        setsockopt(&socket, sockopt::ReuseAddr, &true)?;
+        bind(socket.as_raw_fd(), &SockaddrStorage::from(address))?;
+        listen(&socket, Backlog::new(LISTEN_BACKLOG)?)?;
+        let local_addr = socket_address(&getsockname(socket.as_raw_fd())?)
+            .ok_or_else(|| std::io::Error::other("Unknown local address"))?;
+        Ok(Self {
+            socket: Arc::new(AsyncFd::new(socket)?),
+            local_addr,
+        })
+    }
+
+    /// Get the local address the socket is bound to.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Spawn the task receiving the messages from the socket and handing them to the core.
+    ///
+    /// The task stops when the core stops receiving the messages.
+    pub(crate) fn spawn(&self, core: mpsc::Sender<IncomingMessage>) -> JoinHandle<()> {
+        let transport = self.clone();
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+            let mut reassembly = Reassembly::default();
+            loop {
+                match transport.recv_from(&mut buffer).await {
+                    Ok((size, end_of_record, remote)) => {
+                        let Some(data) = reassembly.push(remote, &buffer[..size], end_of_record)
+                        else {
+                            continue;
+                        };
+                        let Some(message) =
+                            process_datagram(&data, Transport::Sctp, transport.local_addr, remote)
+                        else {
+                            continue;
+                        };
+                        if core.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Could not receive on {}: {}", transport.local_addr, err);
+                        sleep(RECEIVE_RETRY_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Receive a part of a message, telling whether it is the last one.
+    async fn recv_from(
+        &self,
+        buffer: &mut [u8],
+    ) -> Result<(usize, bool, SocketAddr), std::io::Error> {
+        loop {
+            let mut guard = self.socket.readable().await?;
+            let result = guard.try_io(|socket| {
+                let mut iov = [IoSliceMut::new(&mut *buffer)];
+                let message = recvmsg::<SockaddrStorage>(
+                    socket.as_raw_fd(),
+                    &mut iov,
+                    None,
+                    MsgFlags::empty(),
+                )?;
+                let remote = message
+                    .address
+                    .as_ref()
+                    .and_then(socket_address)
+                    .ok_or_else(|| std::io::Error::other("Unknown source address"))?;
+                Ok((
+                    message.bytes,
+                    message.flags.contains(MsgFlags::MSG_EOR),
+                    remote,
+                ))
+            });
+            if let Ok(result) = result {
+                return result;
+            }
+        }
+    }
+
+    /// Send the given bytes to the destination, establishing an association with it if there is
+    /// none.
+    pub(crate) async fn send_to(
+        &self,
+        bytes: &[u8],
+        destination: SocketAddr,
+    ) -> Result<(), std::io::Error> {
+        debug!(
+            "Sending {} bytes from {} to {}",
+            bytes.len(),
+            self.local_addr,
+            destination
+        );
+        let address = SockaddrStorage::from(destination);
+        loop {
+            let mut guard = self.socket.writable().await?;
+            let result = guard.try_io(|socket| {
+                Ok(sendto(
+                    socket.as_raw_fd(),
+                    bytes,
+                    &address,
+                    MsgFlags::empty(),
+                )?)
+            });
+            if let Ok(result) = result {
+                return result.map(|_| ());
+            }
+        }
+    }
+}
+
+/// Reassembly of the messages delivered in several parts, kept apart for each association since
+/// the parts of the messages of different peers can be received interleaved.
+#[derive(Debug, Default)]
+struct Reassembly {
+    /// The parts received so far of the message of each peer.
+    partial: HashMap<SocketAddr, Vec<u8>>,
+    /// The peers whose current message is too large, whose parts are skipped until its end.
+    discarding: HashSet<SocketAddr>,
+}
+
+impl Reassembly {
+    /// Add a part of a message received from a peer, giving the whole message with its last
+    /// part.
+    fn push(&mut self, remote: SocketAddr, data: &[u8], end_of_record: bool) -> Option<Vec<u8>> {
+        if self.discarding.contains(&remote) {
+            if end_of_record {
+                self.discarding.remove(&remote);
+            }
+            return None;
+        }
+        let mut message = self.partial.remove(&remote).unwrap_or_default();
+        message.extend_from_slice(data);
+        if message.len() > MAX_MESSAGE_SIZE {
+            debug!(
+                "Discarding message of more than {} bytes from {}",
+                MAX_MESSAGE_SIZE, remote
+            );
+            if !end_of_record {
+                self.discarding.insert(remote);
+            }
+            return None;
+        }
+        if end_of_record {
+            return Some(message);
+        }
+        self.partial.insert(remote, message);
+        None
+    }
+}
+
+/// Convert a socket address of the system to a standard one, if it is an IP one.
+fn socket_address(address: &SockaddrStorage) -> Option<SocketAddr> {
+    match address.family()? {
+        AddressFamily::Inet => address
+            .as_sockaddr_in()
+            .map(|address| SocketAddr::V4(SocketAddrV4::from(*address))),
+        AddressFamily::Inet6 => address
+            .as_sockaddr_in6()
+            .map(|address| SocketAddr::V6(SocketAddrV6::from(*address))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imersio_sip::Message;
+    use nix::errno::Errno;
+    use std::time::Duration;
+
+    const REQUEST: &str = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/SCTP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    #[test]
+    fn test_socket_address() {
+        let ipv4: SocketAddr = "192.0.2.1:5060".parse().unwrap();
+        let ipv6: SocketAddr = "[2001:db8::1]:5060".parse().unwrap();
+        assert_eq!(socket_address(&SockaddrStorage::from(ipv4)), Some(ipv4));
+        assert_eq!(socket_address(&SockaddrStorage::from(ipv6)), Some(ipv6));
+    }
+
+    #[test]
+    fn test_reassembly() {
+        let alice: SocketAddr = "192.0.2.1:5060".parse().unwrap();
+        let bob: SocketAddr = "192.0.2.2:5060".parse().unwrap();
+        let mut reassembly = Reassembly::default();
+
+        // The parts of the messages of different peers are not mixed.
+        assert_eq!(reassembly.push(alice, b"OPTIONS ", false), None);
+        assert_eq!(reassembly.push(bob, b"INVITE ", false), None);
+        assert_eq!(
+            reassembly.push(alice, b"sip:bob", true),
+            Some(b"OPTIONS sip:bob".to_vec())
+        );
+        assert_eq!(
+            reassembly.push(bob, b"sip:alice", true),
+            Some(b"INVITE sip:alice".to_vec())
+        );
+
+        // The remaining parts of a message too large are skipped.
+        let chunk = vec![b'a'; READ_BUFFER_SIZE];
+        for _ in 0..=MAX_MESSAGE_SIZE / READ_BUFFER_SIZE {
+            assert_eq!(reassembly.push(alice, &chunk, false), None);
+        }
+        assert_eq!(reassembly.push(alice, b"rest", false), None);
+        assert_eq!(reassembly.push(alice, b"end", true), None);
+        assert_eq!(
+            reassembly.push(alice, b"OPTIONS", true),
+            Some(b"OPTIONS".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sctp_transport() {
+        let transport = match SctpTransport::bind("127.0.0.1:0".parse().unwrap()).await {
+            Ok(transport) => transport,
+            Err(err)
+                if err.raw_os_error() == Some(Errno::EPROTONOSUPPORT as i32)
+                    || err.raw_os_error() == Some(Errno::ESOCKTNOSUPPORT as i32) =>
+            {
+                // The kernel does not support SCTP.
+                return;
+            }
+            Err(err) => panic!("{err}"),
+        };
+        let (sender, mut receiver) = mpsc::channel(1);
+        let task = transport.spawn(sender);
+
+        let client = SctpTransport::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        client
+            .send_to(REQUEST.as_bytes(), transport.local_addr())
+            .await
+            .unwrap();
+        let incoming = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(incoming.transport, Transport::Sctp);
+        assert_eq!(incoming.remote, client.local_addr());
+        assert_eq!(incoming.local, transport.local_addr());
+        assert!(matches!(incoming.message, Message::Request(_)));
+        task.abort();
+    }
+}
@@ -0,0 +1,156 @@
+//! TCP transport.
+//!
+//! [[RFC3261, Section 18](https://datatracker.ietf.org/doc/html/rfc3261#section-18)]
+
+use imersio_sip::Transport;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+use tracing::warn;
+
+use crate::transport::{ConnectionTable, canonical_address};
+
+/// Maximum duration of the establishment of an outbound connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before accepting connections again after a failure, eg. when running out of file
+/// descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A TCP listener accepting the connections of the peers.
+#[derive(Debug)]
+pub(crate) struct TcpTransport {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+}
+
+impl TcpTransport {
+    /// Bind a TCP listener to the given address.
+    pub(crate) async fn bind(address: SocketAddr) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind(address).await?;
+        let local_addr = listener.local_addr()?;
+        Ok(Self {
+            listener,
+            local_addr,
+        })
+    }
+
+    /// Get the local address the listener is bound to.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Spawn the task accepting the connections and adding them to the connection table.
+    pub(crate) fn spawn(self, connections: ConnectionTable) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.listener.accept().await {
+                    Ok((stream, remote)) => {
+                        let remote = canonical_address(remote);
+                        let _ = stream.set_nodelay(true);
+                        if let Err(err) =
+                            connections.add(stream, Transport::Tcp, self.local_addr, remote)
+                        {
+                            warn!("Rejecting connection from {}: {}", remote, err);
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Could not accept on {}: {}", self.local_addr, err);
+                        sleep(ACCEPT_RETRY_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Establish an outbound connection to the given destination and add it to the connection
+/// table.
+pub(crate) async fn connect(
+    connections: &ConnectionTable,
+    destination: SocketAddr,
+) -> Result<tokio::sync::mpsc::Sender<Vec<u8>>, std::io::Error> {
+    let stream = timeout(CONNECT_TIMEOUT, TcpStream::connect(destination))
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("Connection to {destination} timed out"),
+            )
+        })??;
+    let _ = stream.set_nodelay(true);
+    let local = stream.local_addr()?;
+    connections.add(
+        stream,
+        Transport::Tcp,
+        local,
+        canonical_address(destination),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imersio_sip::Message;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::mpsc;
+
+    const REQUEST: &str = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/TCP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    #[tokio::test]
+    async fn test_tcp_transport() {
+        let (core, mut incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let transport = TcpTransport::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let local_addr = transport.local_addr();
+        let task = transport.spawn(connections.clone());
+
+        let mut client = TcpStream::connect(local_addr).await.unwrap();
+        client.write_all(REQUEST.as_bytes()).await.unwrap();
+        let message = timeout(Duration::from_secs(5), incoming.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.transport, Transport::Tcp);
+        assert_eq!(message.local, local_addr);
+        assert_eq!(message.remote, client.local_addr().unwrap());
+        assert!(matches!(message.message, Message::Request(_)));
+
+        // The response reuses the connection of the request.
+        let sender = connections
+            .sender(&crate::transport::ConnectionKey {
+                transport: Transport::Tcp,
+                remote: message.remote,
+            })
+            .unwrap();
+        sender.send(b"SIP/2.0 200 OK".to_vec()).await.unwrap();
+        let mut response = [0u8; 14];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response, b"SIP/2.0 200 OK");
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connect() {
+        let (core, _incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let destination = listener.local_addr().unwrap();
+        let sender = connect(&connections, destination).await.unwrap();
+        assert_eq!(connections.len(), 1);
+        let (mut stream, _) = listener.accept().await.unwrap();
+        sender.send(REQUEST.as_bytes().to_vec()).await.unwrap();
+        let mut request = vec![0u8; REQUEST.len()];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request, REQUEST.as_bytes());
+    }
+}
@@ -0,0 +1,361 @@
+//! TLS transport.
+//!
+//! [[RFC3261, Section 26.3.1](https://datatracker.ietf.org/doc/html/rfc3261#section-26.3.1)]
+//! [[RFC5922, Section 7](https://datatracker.ietf.org/doc/html/rfc5922#section-7)]
+
+use imersio_sip::{Host, Transport};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use tracing::{debug, warn};
+
+use crate::config::{ClientAuth, TlsConfig};
+use crate::transport::{ConnectionTable, canonical_address};
+
+/// Maximum duration of the establishment of a connection, including the TLS handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before accepting connections again after a failure, eg. when running out of file
+/// descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// The TLS configuration of the proxy, shared by the listeners and the outbound connections.
+///
+/// The certificate of the proxy is presented both as a server and as a client, so that the
+/// peers can authenticate it in both directions. The certificates of the peers are validated
+/// against the configured CA certificates, or the web PKI roots if there are none.
+#[derive(Clone)]
+pub(crate) struct TlsContext {
+    acceptor: TlsAcceptor,
+    connector: TlsConnector,
+}
+
+impl std::fmt::Debug for TlsContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsContext").finish_non_exhaustive()
+    }
+}
+
+impl TlsContext {
+    /// Load the certificates and the private key from the TLS configuration.
+    pub(crate) fn new(config: &TlsConfig) -> Result<Self, std::io::Error> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let certificates = load_certificates(&config.certificate)?;
+        let private_key = PrivateKeyDer::from_pem_file(&config.private_key)
+            .map_err(|err| invalid_file(&config.private_key, err))?;
+        let mut roots = RootCertStore::empty();
+        match &config.ca_certificates {
+            Some(path) => {
+                for certificate in load_certificates(path)? {
+                    roots
+                        .add(certificate)
+                        .map_err(|err| invalid_file(path, err))?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        let roots = Arc::new(roots);
+
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = match config.client_auth {
+            ClientAuth::None => builder.with_no_client_auth(),
+            ClientAuth::Optional => builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder_with_provider(roots.clone(), provider.clone())
+                    .allow_unauthenticated()
+                    .build()
+                    .map_err(tls_error)?,
+            ),
+            ClientAuth::Required => builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder_with_provider(roots.clone(), provider.clone())
+                    .build()
+                    .map_err(tls_error)?,
+            ),
+        };
+        let server = builder
+            .with_single_cert(certificates.clone(), private_key.clone_key())
+            .map_err(tls_error)?;
+        let client = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .with_root_certificates(roots)
+            .with_client_auth_cert(certificates, private_key)
+            .map_err(tls_error)?;
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(server)),
+            connector: TlsConnector::from(Arc::new(client)),
+        })
+    }
+
+    /// Get the acceptor performing the TLS handshake of the inbound connections.
+    pub(crate) fn acceptor(&self) -> &TlsAcceptor {
+        &self.acceptor
+    }
+
+    /// Get the connector performing the TLS handshake of the outbound connections.
+    #[cfg(test)]
+    pub(crate) fn connector(&self) -> &TlsConnector {
+        &self.connector
+    }
+}
+
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, std::io::Error> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| invalid_file(path, err))
+}
+
+fn invalid_file<E: std::fmt::Display>(path: &Path, err: E) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Could not load '{}': {}", path.display(), err),
+    )
+}
+
+fn tls_error<E: std::fmt::Display>(err: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, err.to_string())
+}
+
+/// Get the name against which the certificate of a peer is validated, that is also sent in the
+/// SNI extension when it is a domain name.
+///
+/// [[RFC5922, Section 7.2](https://datatracker.ietf.org/doc/html/rfc5922#section-7.2)]
+fn server_name(host: &Host) -> Result<ServerName<'static>, std::io::Error> {
+    match host {
+        Host::Name(name) => ServerName::try_from(name.to_string()).map_err(tls_error),
+        Host::Ip(ip) => Ok(ServerName::IpAddress((*ip).into())),
+    }
+}
+
+/// A TLS listener accepting the connections of the peers.
+#[derive(Debug)]
+pub(crate) struct TlsTransport {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    context: TlsContext,
+}
+
+impl TlsTransport {
+    /// Bind a TLS listener to the given address.
+    pub(crate) async fn bind(
+        address: SocketAddr,
+        context: TlsContext,
+    ) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind(address).await?;
+        let local_addr = listener.local_addr()?;
+        Ok(Self {
+            listener,
+            local_addr,
+            context,
+        })
+    }
+
+    /// Get the local address the listener is bound to.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Spawn the task accepting the connections and adding them to the connection table once
+    /// their TLS handshake is done.
+    pub(crate) fn spawn(self, connections: ConnectionTable) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.listener.accept().await {
+                    Ok((stream, remote)) => {
+                        let remote = canonical_address(remote);
+                        let _ = stream.set_nodelay(true);
+                        let acceptor = self.context.acceptor.clone();
+                        let connections = connections.clone();
+                        let local_addr = self.local_addr;
+                        tokio::spawn(async move {
+                            match timeout(CONNECT_TIMEOUT, acceptor.accept(stream)).await {
+                                Ok(Ok(stream)) => {
+                                    if let Err(err) =
+                                        connections.add(stream, Transport::Tls, local_addr, remote)
+                                    {
+                                        warn!("Rejecting connection from {}: {}", remote, err);
+                                    }
+                                }
+                                Ok(Err(err)) => {
+                                    debug!("TLS handshake with {} failed: {}", remote, err)
+                                }
+                                Err(_) => debug!("TLS handshake with {} timed out", remote),
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        warn!("Could not accept on {}: {}", self.local_addr, err);
+                        sleep(ACCEPT_RETRY_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Establish an outbound TLS connection to the given destination and add it to the connection
+/// table.
+///
+/// The certificate of the peer is validated against the host of the target URI.
+pub(crate) async fn connect(
+    connections: &ConnectionTable,
+    context: &TlsContext,
+    destination: SocketAddr,
+    host: &Host,
+) -> Result<mpsc::Sender<Vec<u8>>, std::io::Error> {
+    let server_name = server_name(host)?;
+    let stream = timeout(CONNECT_TIMEOUT, async {
+        let stream = TcpStream::connect(destination).await?;
+        let _ = stream.set_nodelay(true);
+        let local = stream.local_addr()?;
+        Ok::<_, std::io::Error>((context.connector.connect(server_name, stream).await?, local))
+    })
+    .await
+    .map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("Connection to {destination} timed out"),
+        )
+    })?;
+    let (stream, local) = stream?;
+    connections.add(
+        stream,
+        Transport::Tls,
+        local,
+        canonical_address(destination),
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use imersio_sip::{HostnameString, Message};
+    use rcgen::{
+        BasicConstraints, CertificateParams, ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair,
+    };
+    use std::path::PathBuf;
+
+    const REQUEST: &str = "OPTIONS sips:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/TLS pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    /// Generate a CA and a certificate for `localhost` and `127.0.0.1` signed by it, and write
+    /// them in a temporary directory.
+    pub(crate) fn tls_config(name: &str, client_auth: ClientAuth) -> TlsConfig {
+        let directory =
+            std::env::temp_dir().join(format!("imersio-tls-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let issuer = Issuer::new(ca_params, ca_key);
+
+        let key = KeyPair::generate().unwrap();
+        let mut params =
+            CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()]).unwrap();
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        let certificate = params.signed_by(&key, &issuer).unwrap();
+
+        let write = |file: &str, content: String| -> PathBuf {
+            let path = directory.join(file);
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        TlsConfig {
+            certificate: write("cert.pem", certificate.pem()),
+            private_key: write("key.pem", key.serialize_pem()),
+            ca_certificates: Some(write("ca.pem", ca.pem())),
+            client_auth,
+        }
+    }
+
+    #[test]
+    fn test_invalid_tls_config() {
+        let mut config = tls_config("invalid", ClientAuth::None);
+        config.private_key = config.certificate.clone();
+        assert!(TlsContext::new(&config).is_err());
+        config.certificate = PathBuf::from("/nonexistent/cert.pem");
+        assert!(TlsContext::new(&config).is_err());
+    }
+
+    async fn tls_exchange(name: &str, client_auth: ClientAuth, host: Host) -> bool {
+        let context = TlsContext::new(&tls_config(name, client_auth)).unwrap();
+        let (core, mut incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let transport = TlsTransport::bind("127.0.0.1:0".parse().unwrap(), context.clone())
+            .await
+            .unwrap();
+        let local_addr = transport.local_addr();
+        let task = transport.spawn(connections.clone());
+
+        let (client_core, _client_incoming) = mpsc::channel(1);
+        let client_connections = ConnectionTable::new(10, Duration::from_secs(30), client_core);
+        let result = match connect(&client_connections, &context, local_addr, &host).await {
+            Ok(sender) => {
+                sender.send(REQUEST.as_bytes().to_vec()).await.unwrap();
+                let message = timeout(Duration::from_secs(5), incoming.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(message.transport, Transport::Tls);
+                assert_eq!(message.local, local_addr);
+                assert!(matches!(message.message, Message::Request(_)));
+                true
+            }
+            Err(_) => false,
+        };
+        task.abort();
+        result
+    }
+
+    #[tokio::test]
+    async fn test_tls_transport() {
+        assert!(
+            tls_exchange(
+                "name",
+                ClientAuth::None,
+                Host::Name(HostnameString::try_from("localhost").unwrap())
+            )
+            .await
+        );
+        assert!(
+            tls_exchange(
+                "ip",
+                ClientAuth::Required,
+                Host::Ip("127.0.0.1".parse().unwrap())
+            )
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tls_transport_with_invalid_host() {
+        assert!(
+            !tls_exchange(
+                "invalid-host",
+                ClientAuth::Optional,
+                Host::Name(HostnameString::try_from("biloxi.com").unwrap())
+            )
+            .await
+        );
+    }
+}
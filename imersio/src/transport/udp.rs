@@ -0,0 +1,247 @@
+//! UDP transport.
+//!
+//! [[RFC3261, Section 18](https://datatracker.ietf.org/doc/html/rfc3261#section-18)]
+
+use imersio_sip::{Message, Transport};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+use crate::transport::{IncomingMessage, canonical_address, received_message};
+
+/// Maximum size of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
+/// Delay before receiving again after a failure, to avoid spinning on a persistent error.
+const RECEIVE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A UDP socket receiving SIP messages and sending them.
+#[derive(Clone, Debug)]
+pub(crate) struct UdpTransport {
+    socket: Arc<UdpSocket>,
+    local_addr: SocketAddr,
+}
+
+impl UdpTransport {
+    /// Bind a UDP socket to the given address.
+    pub(crate) async fn bind(address: SocketAddr) -> Result<Self, std::io::Error> {
+        let socket = UdpSocket::bind(address).await?;
+        let local_addr = socket.local_addr()?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            local_addr,
+        })
+    }
+
+    /// Get the local address the socket is bound to.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Spawn the task receiving the datagrams from the socket and handing the SIP messages they
+    /// contain to the core.
+    ///
+    /// The task stops when the core stops receiving the messages.
+    pub(crate) fn spawn(&self, core: mpsc::Sender<IncomingMessage>) -> JoinHandle<()> {
+        let socket = self.socket.clone();
+        let local_addr = self.local_addr;
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+            loop {
+                match socket.recv_from(&mut buffer).await {
+                    Ok((size, remote)) => {
+                        let Some(message) =
+                            process_datagram(&buffer[..size], Transport::Udp, local_addr, remote)
+                        else {
+                            continue;
+                        };
+                        if core.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Could not receive on {}: {}", local_addr, err);
+                        sleep(RECEIVE_RETRY_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Send the given bytes to the destination.
+    pub(crate) async fn send_to(
+        &self,
+        bytes: &[u8],
+        destination: SocketAddr,
+    ) -> Result<(), std::io::Error> {
+        debug!(
+            "Sending {} bytes from {} to {}",
+            bytes.len(),
+            self.local_addr,
+            destination
+        );
+        self.socket.send_to(bytes, destination).await?;
+        Ok(())
+    }
+}
+
+/// Parse a received datagram, or a message of another message-oriented transport, into a SIP
+/// message.
+///
+/// The keep-alive datagrams and the datagrams that are not valid SIP messages are discarded. The
+/// bytes following the body of the message, as given by its Content-Length header, are
+/// discarded, whereas a message shorter than its Content-Length is discarded. The source address
+/// is recorded in the top Via of a request.
+///
+/// [[RFC3261, Section 18.2.1](https://datatracker.ietf.org/doc/html/rfc3261#section-18.2.1)]
+/// [[RFC3261, Section 18.3](https://datatracker.ietf.org/doc/html/rfc3261#section-18.3)]
+/// [[RFC3581, Section 4](https://datatracker.ietf.org/doc/html/rfc3581#section-4)]
+pub(crate) fn process_datagram(
+    data: &[u8],
+    transport: Transport,
+    local: SocketAddr,
+    remote: SocketAddr,
+) -> Option<IncomingMessage> {
+    let remote = canonical_address(remote);
+    if data.iter().all(u8::is_ascii_whitespace) {
+        return None;
+    }
+    let mut message = match Message::try_from(data) {
+        Ok(message) => message,
+        Err(err) => {
+            debug!("Discarding invalid message from {}: {}", remote, err);
+            return None;
+        }
+    };
+    if let Some(content_length) = message.content_length() {
+        let body_size = message.body().len();
+        if body_size < content_length {
+            debug!(
+                "Discarding message from {} with a body of {} bytes shorter than its Content-Length {}",
+                remote, body_size, content_length
+            );
+            return None;
+        } else if body_size > content_length {
+            let size = data.len() - body_size + content_length;
+            message = Message::try_from(&data[..size]).ok()?;
+        }
+    }
+    received_message(message, transport, local, remote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imersio_sip::Header;
+    use std::time::Duration;
+
+    const REQUEST: &str = "OPTIONS sip:carol@chicago.com SIP/2.0\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;rport;branch=z9hG4bKhjhs8ass877\r\n\
+Max-Forwards: 70\r\n\
+To: <sip:carol@chicago.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 63104 OPTIONS\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    fn addresses() -> (SocketAddr, SocketAddr) {
+        (
+            "192.0.2.10:5060".parse().unwrap(),
+            "192.0.2.1:9988".parse().unwrap(),
+        )
+    }
+
+    fn top_via(message: &Message) -> String {
+        message
+            .headers()
+            .iter()
+            .find(|header| matches!(header, Header::Via(_)))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_process_request_datagram() {
+        let (local, remote) = addresses();
+        let incoming = process_datagram(REQUEST.as_bytes(), Transport::Udp, local, remote).unwrap();
+        assert_eq!(incoming.transport, Transport::Udp);
+        assert_eq!(incoming.local, local);
+        assert_eq!(incoming.remote, remote);
+        assert_eq!(
+            top_via(&incoming.message),
+            "Via: SIP/2.0/UDP pc33.atlanta.com;rport=9988;branch=z9hG4bKhjhs8ass877;received=192.0.2.1"
+        );
+    }
+
+    #[test]
+    fn test_process_datagram_with_extra_bytes() {
+        let (local, remote) = addresses();
+        let data = format!("{REQUEST}garbage");
+        let incoming = process_datagram(data.as_bytes(), Transport::Udp, local, remote).unwrap();
+        assert!(incoming.message.body().is_empty());
+    }
+
+    #[test]
+    fn test_process_truncated_datagram() {
+        let (local, remote) = addresses();
+        let data = REQUEST.replace("Content-Length: 0", "Content-Length: 10");
+        assert!(process_datagram(data.as_bytes(), Transport::Udp, local, remote).is_none());
+    }
+
+    #[test]
+    fn test_process_invalid_datagrams() {
+        let (local, remote) = addresses();
+        assert!(process_datagram(b"\r\n\r\n", Transport::Udp, local, remote).is_none());
+        assert!(process_datagram(b"Hello world!", Transport::Udp, local, remote).is_none());
+        let data = REQUEST.replace(
+            "Via: SIP/2.0/UDP pc33.atlanta.com;rport;branch=z9hG4bKhjhs8ass877\r\n",
+            "",
+        );
+        assert!(process_datagram(data.as_bytes(), Transport::Udp, local, remote).is_none());
+    }
+
+    #[test]
+    fn test_process_response_datagram() {
+        let (local, remote) = addresses();
+        let data = "SIP/2.0 200 OK\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877\r\n\
+Content-Length: 0\r\n\
+\r\n";
+        let incoming = process_datagram(data.as_bytes(), Transport::Udp, local, remote).unwrap();
+        assert_eq!(
+            top_via(&incoming.message),
+            "Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bKhjhs8ass877"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_udp_transport() {
+        let transport = UdpTransport::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let (sender, mut receiver) = mpsc::channel(1);
+        let task = transport.spawn(sender);
+
+        let client = UdpTransport::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        client
+            .send_to(REQUEST.as_bytes(), transport.local_addr())
+            .await
+            .unwrap();
+        let incoming = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(incoming.remote, client.local_addr());
+        assert_eq!(incoming.local, transport.local_addr());
+        assert!(matches!(incoming.message, Message::Request(_)));
+        task.abort();
+    }
+}
@@ -0,0 +1,628 @@
+//! WebSocket transport, used by the web browsers.
+//!
+//! Each SIP message is carried in a single WebSocket message, so that no framing based on the
+//! Content-Length header is needed. The clients cannot be reached by other means than the
+//! connections they open, so no outbound connection is ever established.
+//!
+//! [[RFC7118](https://datatracker.ietf.org/doc/html/rfc7118)]
+
+use futures_util::{SinkExt, StreamExt};
+use imersio_sip::{Contacts, Header, Host, Message, Transport};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, sleep, sleep_until, timeout};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::{WebSocketStream, accept_hdr_async_with_config, tungstenite};
+use tracing::{debug, warn};
+
+use crate::transport::{
+    ConnectionKey, ConnectionTable, MAX_MESSAGE_SIZE, Registration, TlsContext, canonical_address,
+    received_message,
+};
+
+/// The WebSocket subprotocol carrying SIP messages.
+///
+/// [[RFC7118, Section 4.1](https://datatracker.ietf.org/doc/html/rfc7118#section-4.1)]
+const SIP_SUBPROTOCOL: &str = "sip";
+
+/// Domain of the hosts that the WebSocket clients use in their Contact and Via headers, since
+/// they have no reachable address.
+///
+/// [[RFC7118, Section 5.2](https://datatracker.ietf.org/doc/html/rfc7118#section-5.2)]
+const INVALID_DOMAIN: &str = ".invalid";
+
+/// Methods of the requests whose contacts designate the client they are received from: the
+/// registrations and the dialog-creating requests.
+const ALIASING_METHODS: [&str; 4] = ["REGISTER", "INVITE", "SUBSCRIBE", "REFER"];
+
+/// Maximum duration of the opening of a connection, including the TLS and WebSocket handshakes.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before accepting connections again after a failure, eg. when running out of file
+/// descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Get the transport of the SIP messages sent over WebSocket.
+///
+/// [[RFC7118, Section 5.2.1](https://datatracker.ietf.org/doc/html/rfc7118#section-5.2.1)]
+pub(crate) fn ws() -> Transport {
+    Transport::try_from("WS").unwrap()
+}
+
+/// Get the transport of the SIP messages sent over secure WebSocket.
+///
+/// [[RFC7118, Section 5.2.1](https://datatracker.ietf.org/doc/html/rfc7118#section-5.2.1)]
+pub(crate) fn wss() -> Transport {
+    Transport::try_from("WSS").unwrap()
+}
+
+/// Tell whether the given transport is the WebSocket or the secure WebSocket one.
+pub(crate) fn is_websocket(transport: &Transport) -> bool {
+    *transport == ws() || *transport == wss()
+}
+
+/// A WebSocket listener accepting the connections of the web clients, secured with TLS if it
+/// has a TLS configuration.
+#[derive(Debug)]
+pub(crate) struct WebSocketTransport {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    tls_context: Option<TlsContext>,
+}
+
+impl WebSocketTransport {
+    /// Bind a WebSocket listener to the given address, that is a secure WebSocket one if a TLS
+    /// configuration is given.
+    pub(crate) async fn bind(
+        address: SocketAddr,
+        tls_context: Option<TlsContext>,
+    ) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind(address).await?;
+        let local_addr = listener.local_addr()?;
+        Ok(Self {
+            listener,
+            local_addr,
+            tls_context,
+        })
+    }
+
+    /// Get the local address the listener is bound to.
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Get the transport of the messages exchanged on the connections of the listener.
+    pub(crate) fn transport(&self) -> Transport {
+        if self.tls_context.is_some() {
+            wss()
+        } else {
+            ws()
+        }
+    }
+
+    /// Spawn the task accepting the connections and adding them to the connection table once
+    /// their handshakes are done.
+    pub(crate) fn spawn(self, connections: ConnectionTable) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.listener.accept().await {
+                    Ok((stream, remote)) => {
+                        let remote = canonical_address(remote);
+                        let _ = stream.set_nodelay(true);
+                        let tls_context = self.tls_context.clone();
+                        let connections = connections.clone();
+                        let local_addr = self.local_addr;
+                        tokio::spawn(async move {
+                            let result = timeout(HANDSHAKE_TIMEOUT, async {
+                                match tls_context {
+                                    Some(context) => {
+                                        let stream = context.acceptor().accept(stream).await?;
+                                        let stream = handshake(stream).await?;
+                                        add(&connections, stream, wss(), local_addr, remote)
+                                    }
+                                    None => {
+                                        let stream = handshake(stream).await?;
+                                        add(&connections, stream, ws(), local_addr, remote)
+                                    }
+                                }
+                            })
+                            .await;
+                            match result {
+                                Ok(Ok(())) => (),
+                                Ok(Err(err)) => {
+                                    debug!("Rejecting connection from {}: {}", remote, err)
+                                }
+                                Err(_) => debug!("Handshake with {} timed out", remote),
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        warn!("Could not accept on {}: {}", self.local_addr, err);
+                        sleep(ACCEPT_RETRY_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Perform the WebSocket opening handshake, negotiating the SIP subprotocol.
+///
+/// The size of the received messages is bounded as on the other connection-oriented transports.
+async fn handshake<S>(stream: S) -> Result<WebSocketStream<S>, std::io::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_MESSAGE_SIZE))
+        .max_frame_size(Some(MAX_MESSAGE_SIZE));
+    accept_hdr_async_with_config(stream, negotiate_subprotocol, Some(config))
+        .await
+        .map_err(std::io::Error::other)
+}
+
+/// Accept the WebSocket opening handshake only if the client offers the SIP subprotocol.
+///
+/// [[RFC7118, Section 4.1](https://datatracker.ietf.org/doc/html/rfc7118#section-4.1)]
+#[allow(clippy::result_large_err)] // The signature is the one of the tungstenite callbacks.
+fn negotiate_subprotocol(
+    request: &Request,
+    mut response: Response,
+) -> Result<Response, ErrorResponse> {
+    let offered = request
+        .headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|protocol| protocol.trim().eq_ignore_ascii_case(SIP_SUBPROTOCOL));
+    if !offered {
+        let mut error = ErrorResponse::new(Some("The sip subprotocol is required".to_string()));
+        *error.status_mut() = StatusCode::BAD_REQUEST;
+        return Err(error);
+    }
+    response.headers_mut().insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static(SIP_SUBPROTOCOL),
+    );
+    Ok(response)
+}
+
+/// Add an established WebSocket connection to the connection table and spawn its reading and
+/// writing tasks.
+fn add<S>(
+    connections: &ConnectionTable,
+    stream: WebSocketStream<S>,
+    transport: Transport,
+    local: SocketAddr,
+    remote: SocketAddr,
+) -> Result<(), std::io::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let key = ConnectionKey { transport, remote };
+    let Registration { id, receiver, .. } = connections.register(&key, local)?;
+    let (sink, stream) = stream.split();
+    let last_activity = Arc::new(Mutex::new(Instant::now()));
+    tokio::spawn(write(
+        connections.clone(),
+        sink,
+        receiver,
+        key.clone(),
+        id,
+        last_activity.clone(),
+    ));
+    tokio::spawn(read(
+        connections.clone(),
+        stream,
+        key,
+        id,
+        local,
+        last_activity,
+    ));
+    Ok(())
+}
+
+async fn read<S>(
+    connections: ConnectionTable,
+    mut stream: S,
+    key: ConnectionKey,
+    id: u64,
+    local: SocketAddr,
+    last_activity: Arc<Mutex<Instant>>,
+) where
+    S: futures_util::Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+{
+    loop {
+        let deadline = *last_activity.lock().unwrap() + connections.idle_timeout();
+        select! {
+            frame = stream.next() => match frame {
+                Some(Ok(frame)) if frame.is_text() || frame.is_binary() => {
+                    *last_activity.lock().unwrap() = Instant::now();
+                    let Some(message) = parse_message(&frame.into_data(), key.remote) else {
+                        continue;
+                    };
+                    for host in invalid_hosts(&message) {
+                        connections.add_alias(host, &key, id);
+                    }
+                    let Some(message) =
+                        received_message(message, key.transport.clone(), local, key.remote)
+                    else {
+                        continue;
+                    };
+                    if !connections.deliver(message).await {
+                        break;
+                    }
+                }
+                Some(Ok(frame)) if frame.is_close() => break,
+                Some(Ok(_)) => *last_activity.lock().unwrap() = Instant::now(),
+                Some(Err(err)) => {
+                    debug!("Could not read on connection {}: {}", id, err);
+                    break;
+                }
+                None => break,
+            },
+            _ = sleep_until(deadline) => {
+                if *last_activity.lock().unwrap() + connections.idle_timeout() <= Instant::now() {
+                    debug!("Connection {} idle for {:?}", id, connections.idle_timeout());
+                    break;
+                }
+            }
+        }
+    }
+    connections.remove(&key, id);
+}
+
+async fn write<S>(
+    connections: ConnectionTable,
+    mut sink: S,
+    mut receiver: mpsc::Receiver<Vec<u8>>,
+    key: ConnectionKey,
+    id: u64,
+    last_activity: Arc<Mutex<Instant>>,
+) where
+    S: futures_util::Sink<tungstenite::Message, Error = tungstenite::Error> + Unpin,
+{
+    while let Some(bytes) = receiver.recv().await {
+        let frame = match String::from_utf8(bytes) {
+            Ok(text) => tungstenite::Message::text(text),
+            Err(err) => tungstenite::Message::binary(err.into_bytes()),
+        };
+        if let Err(err) = sink.send(frame).await {
+            debug!("Could not write on connection {}: {}", id, err);
+            connections.remove(&key, id);
+            break;
+        }
+        *last_activity.lock().unwrap() = Instant::now();
+    }
+    let _ = sink.close().await;
+}
+
+/// Parse the SIP message carried by a WebSocket message.
+///
+/// A message whose body does not match its Content-Length header is discarded, but the
+/// connection is kept open since the next messages are still correctly delimited.
+///
+/// [[RFC7118, Section 4.2](https://datatracker.ietf.org/doc/html/rfc7118#section-4.2)]
+fn parse_message(data: &[u8], remote: SocketAddr) -> Option<Message> {
+    let message = match Message::try_from(data) {
+        Ok(message) => message,
+        Err(err) => {
+            debug!("Discarding invalid message from {}: {}", remote, err);
+            return None;
+        }
+    };
+    match message.content_length() {
+        Some(content_length) if content_length != message.body().len() => {
+            debug!(
+                "Discarding message from {} with a body of {} bytes not matching its Content-Length {}",
+                remote,
+                message.body().len(),
+                content_length
+            );
+            None
+        }
+        _ => Some(message),
+    }
+}
+
+/// Get the hosts of the `.invalid` domain in the Contact headers of a registration or of a
+/// dialog-creating request, that designate the client it is received from.
+///
+/// The requests for these contacts can only be sent on the connection the client has opened.
+///
+/// [[RFC7118, Section 5](https://datatracker.ietf.org/doc/html/rfc7118#section-5)]
+fn invalid_hosts(message: &Message) -> Vec<Host> {
+    let Message::Request(request) = message else {
+        return Vec::new();
+    };
+    if !ALIASING_METHODS.contains(&request.method().as_str()) {
+        return Vec::new();
+    }
+    request
+        .headers()
+        .iter()
+        .filter_map(|header| match header {
+            Header::Contact(header) => match header.contacts() {
+                Contacts::Contacts(contacts) => Some(contacts),
+                Contacts::Any => None,
+            },
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|contact| contact.address().uri().host())
+        .filter(|host| {
+            host.name().is_some_and(|name| {
+                name.len() > INVALID_DOMAIN.len()
+                    && name[name.len() - INVALID_DOMAIN.len()..]
+                        .eq_ignore_ascii_case(INVALID_DOMAIN)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientAuth;
+    use crate::transport::tls::tests::tls_config;
+    use imersio_sip::HostnameString;
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::client_async;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    const REGISTER: &str = "REGISTER sip:atlanta.com SIP/2.0\r\n\
+Via: SIP/2.0/WS df7jal23ls0d.invalid;branch=z9hG4bKasudf\r\n\
+From: <sip:alice@atlanta.com>;tag=65bnmj.34asd\r\n\
+To: <sip:alice@atlanta.com>\r\n\
+Call-ID: aiuy7k9njasd\r\n\
+CSeq: 1 REGISTER\r\n\
+Contact: <sip:alice@df7jal23ls0d.invalid;transport=ws>;expires=600\r\n\
+Content-Length: 0\r\n\
+\r\n";
+
+    fn invalid_host() -> Host {
+        Host::Name(HostnameString::try_from("DF7JAL23LS0D.invalid").unwrap())
+    }
+
+    #[test]
+    fn test_transports() {
+        assert!(is_websocket(&ws()));
+        assert!(is_websocket(&Transport::try_from("wss").unwrap()));
+        assert!(!is_websocket(&Transport::Tcp));
+        assert_eq!(ws().to_string(), "WS");
+        assert_eq!(wss().to_string(), "WSS");
+    }
+
+    #[test]
+    fn test_negotiate_subprotocol() {
+        let request = |protocols: Option<&str>| {
+            let mut request = Request::builder().uri("/").body(()).unwrap();
+            if let Some(protocols) = protocols {
+                request.headers_mut().insert(
+                    SEC_WEBSOCKET_PROTOCOL,
+                    HeaderValue::from_str(protocols).unwrap(),
+                );
+            }
+            request
+        };
+        let response =
+            negotiate_subprotocol(&request(Some("chat, SIP")), Response::new(())).unwrap();
+        assert_eq!(
+            response.headers().get(SEC_WEBSOCKET_PROTOCOL).unwrap(),
+            "sip"
+        );
+        let error = negotiate_subprotocol(&request(Some("chat")), Response::new(())).unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert!(negotiate_subprotocol(&request(None), Response::new(())).is_err());
+    }
+
+    #[test]
+    fn test_parse_message() {
+        let remote = "192.0.2.1:49152".parse().unwrap();
+        let message = parse_message(REGISTER.as_bytes(), remote).unwrap();
+        assert_eq!(invalid_hosts(&message), vec![invalid_host()]);
+        let data = REGISTER.replace("Content-Length: 0", "Content-Length: 10");
+        assert!(parse_message(data.as_bytes(), remote).is_none());
+        assert!(parse_message(b"Hello world!", remote).is_none());
+
+        let data = REGISTER.replace("df7jal23ls0d.invalid;transport=ws", "192.0.2.1");
+        let message = parse_message(data.as_bytes(), remote).unwrap();
+        assert!(invalid_hosts(&message).is_empty());
+
+        // Only the registrations and the dialog-creating requests bind their contacts.
+        let data = REGISTER.replace("REGISTER", "MESSAGE");
+        let message = parse_message(data.as_bytes(), remote).unwrap();
+        assert!(invalid_hosts(&message).is_empty());
+        let data = REGISTER.replace("REGISTER", "SUBSCRIBE");
+        let message = parse_message(data.as_bytes(), remote).unwrap();
+        assert_eq!(invalid_hosts(&message), vec![invalid_host()]);
+    }
+
+    async fn client<S>(stream: S, protocol: &str) -> Result<WebSocketStream<S>, tungstenite::Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut request = "ws://localhost/".into_client_request().unwrap();
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_str(protocol).unwrap(),
+        );
+        client_async(request, stream)
+            .await
+            .map(|(stream, _)| stream)
+    }
+
+    #[tokio::test]
+    async fn test_websocket_transport() {
+        let (core, mut incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let transport = WebSocketTransport::bind("127.0.0.1:0".parse().unwrap(), None)
+            .await
+            .unwrap();
+        assert_eq!(transport.transport(), ws());
+        let local_addr = transport.local_addr();
+        let task = transport.spawn(connections.clone());
+
+        let stream = TcpStream::connect(local_addr).await.unwrap();
+        let client_addr = stream.local_addr().unwrap();
+        let mut client = client(stream, "sip").await.unwrap();
+        client
+            .send(tungstenite::Message::text(REGISTER))
+            .await
+            .unwrap();
+        let message = timeout(Duration::from_secs(5), incoming.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.transport, ws());
+        assert_eq!(message.local, local_addr);
+        assert_eq!(message.remote, client_addr);
+        assert!(message.message.to_string().contains(
+            "Via: SIP/2.0/WS df7jal23ls0d.invalid;branch=z9hG4bKasudf;received=127.0.0.1"
+        ));
+
+        // The requests for the contact of the client are sent on its connection.
+        let key = connections.alias(&invalid_host()).unwrap();
+        assert_eq!(
+            key,
+            ConnectionKey {
+                transport: ws(),
+                remote: client_addr
+            }
+        );
+
+        // Another client cannot take over the alias while the connection is open.
+        let stream = TcpStream::connect(local_addr).await.unwrap();
+        let mut other = self::client(stream, "sip").await.unwrap();
+        other
+            .send(tungstenite::Message::text(REGISTER))
+            .await
+            .unwrap();
+        timeout(Duration::from_secs(5), incoming.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(connections.alias(&invalid_host()).unwrap(), key);
+        other.close(None).await.unwrap();
+
+        let sender = connections.sender(&key).unwrap();
+        sender
+            .send(b"SIP/2.0 200 OK\r\n\r\n".to_vec())
+            .await
+            .unwrap();
+        let frame = client.next().await.unwrap().unwrap();
+        assert!(frame.is_text());
+        assert_eq!(frame.into_data().as_ref(), b"SIP/2.0 200 OK\r\n\r\n");
+
+        // The alias is removed with the connection.
+        client.close(None).await.unwrap();
+        timeout(Duration::from_secs(5), async {
+            while connections.alias(&invalid_host()).is_some() || connections.len() > 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(connections.len(), 0);
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_websocket_transport_without_sip_subprotocol() {
+        let (core, _incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let transport = WebSocketTransport::bind("127.0.0.1:0".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let local_addr = transport.local_addr();
+        let task = transport.spawn(connections.clone());
+
+        let stream = TcpStream::connect(local_addr).await.unwrap();
+        assert!(client(stream, "chat").await.is_err());
+        assert_eq!(connections.len(), 0);
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_websocket_transport_with_too_large_message() {
+        let (core, mut incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let transport = WebSocketTransport::bind("127.0.0.1:0".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let local_addr = transport.local_addr();
+        let task = transport.spawn(connections.clone());
+
+        let stream = TcpStream::connect(local_addr).await.unwrap();
+        let mut client = client(stream, "sip").await.unwrap();
+        timeout(Duration::from_secs(5), async {
+            while connections.len() == 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let _ = client
+            .send(tungstenite::Message::binary(vec![
+                b'a';
+                MAX_MESSAGE_SIZE + 1
+            ]))
+            .await;
+        timeout(Duration::from_secs(5), async {
+            while connections.len() > 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(incoming.try_recv().is_err());
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_secure_websocket_transport() {
+        let context = TlsContext::new(&tls_config("wss", ClientAuth::None)).unwrap();
+        let (core, mut incoming) = mpsc::channel(1);
+        let connections = ConnectionTable::new(10, Duration::from_secs(30), core);
+        let transport =
+            WebSocketTransport::bind("127.0.0.1:0".parse().unwrap(), Some(context.clone()))
+                .await
+                .unwrap();
+        assert_eq!(transport.transport(), wss());
+        let local_addr = transport.local_addr();
+        let task = transport.spawn(connections.clone());
+
+        let stream = TcpStream::connect(local_addr).await.unwrap();
+        let stream = context
+            .connector()
+            .connect(
+                rustls::pki_types::ServerName::try_from("localhost").unwrap(),
+                stream,
+            )
+            .await
+            .unwrap();
+        let mut client = client(stream, "sip").await.unwrap();
+        client
+            .send(tungstenite::Message::binary(REGISTER.as_bytes().to_vec()))
+            .await
+            .unwrap();
+        let message = timeout(Duration::from_secs(5), incoming.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.transport, wss());
+        assert!(matches!(message.message, Message::Request(_)));
+        task.abort();
+    }
+}